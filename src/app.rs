@@ -4,77 +4,605 @@ use std::error::Error;
 use std::time::Instant;
 
 use crate::app_structs::{
-    AppState, InputMode, TAB_COUNT, TAB_MEDIA, TAB_SEARCH, TAB_SETTINGS, TAB_VIEWER,
+    AnalysisScope, AppState, ImageAnalysis, InputMode, PageAnalysis, TAB_COUNT, TAB_MEDIA, TAB_SEARCH,
+    TAB_SETTINGS, TAB_VIEWER,
 };
-use crate::file_utils::load_file;
-use crate::pdf_doc::PdfDocument;
+use crate::cli_values::parse_page_range;
+use crate::file_utils::{stage_attachment, LoadedFile};
+use crate::image_stats;
+use crate::loader::{LoadOptions, LoaderRegistry};
+use crate::log_file::{parse_time_range, LogFilter, LogLevel};
+use crate::media::{MediaView, GRID_TILE};
+use crate::outline::{Segment, SegmentKind};
+use crate::pdf_annotations::search_notes;
+use crate::pdf_doc::{ExtractionMode, PasswordError, PdfDocument, PdfOptions};
 use crate::settings::AppSettings;
 use crate::text_analysis::{
     analyze_content, analyze_structure, extract_repeated_lines, search_with_options, SearchOptions,
 };
+use crate::text_compare::ProfileComparison;
+use crate::text_diff::{DiffLayout, LineKind, TextDiff};
 
 /// Lines one wheel notch moves the content. Three is the common terminal step;
 /// one line per notch makes a wheel feel broken.
 const WHEEL_LINES: isize = 3;
 
+/// Share of the visible part of a zoomed image one arrow press pans by.
+const PAN_STEP: f64 = 0.25;
+
+/// Trees with more nodes than this open folded to their top-level keys.
+const TREE_UNFOLDED_NODES: usize = 500;
+
 /// Index of the last row in the Settings list.
-const SETTINGS_LAST: usize = 7;
+const SETTINGS_LAST: usize = 10;
 
 pub struct App {
     pub state: AppState,
     pub last_tick: Instant,
+    /// Password that opened the current file, so re-reading it in another
+    /// extraction mode or with reflow toggled does not ask again. Held in memory only.
+    password: Option<String>,
+    /// The formats files and attachments are opened with.
+    loaders: LoaderRegistry,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
+        Self::with_loaders(LoaderRegistry::default())
+    }
+
+    /// An app that opens files with `loaders` instead of the built-in formats
+    /// alone.
+    pub fn with_loaders(loaders: LoaderRegistry) -> Self {
         let mut state = AppState::default();
         AppSettings::load().apply_to_state(&mut state);
 
         Self {
             state,
             last_tick: Instant::now(),
+            password: None,
+            loaders,
         }
     }
 
     pub fn load_file(&mut self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        let loaded = load_file(file_path)?;
+        self.load_file_with_password(file_path, None)
+    }
+
+    /// Load `file_path`, opening it with `password` if it is an encrypted PDF.
+    ///
+    /// A missing or wrong password is not an error here: it opens the password
+    /// prompt instead, and the file loads once the prompt is answered.
+    pub fn load_file_with_password(
+        &mut self,
+        file_path: &str,
+        password: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        let loaded = match self.loaders.load(file_path, &self.load_options(password)) {
+            Ok(loaded) => loaded,
+            Err(error) => match error.downcast_ref::<PasswordError>() {
+                Some(reason) => {
+                    self.prompt_for_password(file_path, *reason);
+                    return Ok(());
+                }
+                None => return Err(error),
+            },
+        };
 
+        self.password = password.map(str::to_string);
+        self.show_loaded(loaded, file_path);
+        Ok(())
+    }
+
+    /// Compare `old` with `new`, each opened as it would be on its own and
+    /// with `password` if it is an encrypted PDF. The Viewer shows the diff.
+    pub fn load_diff(
+        &mut self,
+        old: &str,
+        new: &str,
+        password: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        let options = self.load_options(password);
+        let open = |path: &str| {
+            self.loaders
+                .load(path, &options)
+                .map_err(|error| -> Box<dyn Error> { format!("{path}: {error}").into() })
+        };
+        let (before, after) = (open(old)?, open(new)?);
+        let diff = TextDiff::new(&before.plain_text(), &after.plain_text());
+
+        self.password = None;
+        self.show_loaded(
+            LoadedFile {
+                kind: "diff",
+                ..LoadedFile::default()
+            }
+            .with(diff),
+            &format!("{old} → {new}"),
+        );
+        Ok(())
+    }
+
+    /// How files are read in this session, opening them with `password`.
+    fn load_options<'a>(&self, password: Option<&'a str>) -> LoadOptions<'a> {
+        LoadOptions {
+            password,
+            ocr: self.state.ocr,
+            ..LoadOptions::default()
+        }
+        .with(PdfOptions {
+            extraction: self.state.extraction_mode,
+            reflow: self.state.reflow,
+        })
+    }
+
+    /// Put what `loaded` holds on screen as `file_name`, and say what it is.
+    /// Extensions the app has no view for are left out.
+    fn show_loaded(&mut self, mut loaded: LoadedFile, file_name: &str) {
+        let extensions = &mut loaded.extensions;
         self.state.file_content = loaded.content;
-        self.state.file_name = file_path.to_string();
-        self.state.document = loaded.document;
+        self.state.file_name = file_name.to_string();
+        self.state.set_document(extensions.take::<PdfDocument>(), loaded.outline);
+        self.state.log = extensions.take();
+        self.state.log_filter = LogFilter::default();
+        self.state.log_lines.clear();
+        self.apply_log_filter();
+        self.state.table = extensions.take();
+        self.state.table_column = None;
+        self.state.table_offset = 0;
+        self.state.tree = extensions.take();
+        self.state.tree_rows.clear();
+        self.state.tree_collapsed = match &self.state.tree {
+            Some(tree) if tree.nodes.len() > TREE_UNFOLDED_NODES => tree
+                .nodes
+                .iter()
+                .map(|node| node.depth > 0 && node.value.is_container())
+                .collect(),
+            Some(tree) => vec![false; tree.nodes.len()],
+            None => Vec::new(),
+        };
+        self.render_tree();
+        self.state.code = extensions.take();
+        self.state.diff = extensions.take();
+        self.state.diff_rows.clear();
+        self.state.diff_hunk = None;
+        self.render_diff();
         self.state.media = loaded.media;
         self.state.selected_media = 0;
+        self.state.media_view = MediaView::default();
+        self.state.selected_attachment = 0;
         self.refresh_analysis();
         self.state.content_scroll = 0;
         self.update_search();
 
-        let summary = match &self.state.document {
-            Some(document) => format!(
+        let state = &self.state;
+        let summary = match (&state.document, &state.log_summary, &state.table, &state.tree) {
+            (Some(document), _, _, _) => format!(
                 "Loaded {} · {} pages · {} images",
-                self.state.file_name,
+                state.file_name,
                 document.page_count(),
-                self.state.media.len()
+                state.media.len()
+            ),
+            (None, Some(log), _, _) => {
+                format!("Loaded {} · log of {} events", state.file_name, log.events)
+            }
+            (None, None, Some(table), _) => format!(
+                "Loaded {} · {} rows in {} columns",
+                state.file_name,
+                table.rows.len(),
+                table.headers.len()
             ),
-            None => format!("Loaded {}", self.state.file_name),
+            (None, None, None, Some(tree)) => format!(
+                "Loaded {} · {} with {} keys and values",
+                state.file_name,
+                tree.format.label(),
+                tree.nodes.len() - 1
+            ),
+            (None, None, None, None) => match (&state.code, &state.diff) {
+                (Some(code), _) => format!(
+                    "Loaded {} · {}, {} lines of code",
+                    state.file_name,
+                    code.language.label(),
+                    code.stats.code_lines
+                ),
+                (None, Some(diff)) => format!(
+                    "Compared {} · {} hunk(s) · {} lines added, {} removed",
+                    state.file_name,
+                    diff.hunks.len(),
+                    diff.stats.added,
+                    diff.stats.removed
+                ),
+                (None, None) => format!("Loaded {}", state.file_name),
+            },
         };
         self.state.status_message = match loaded.notice {
             Some(notice) => format!("{summary} · {notice}"),
             None => summary,
         };
+    }
 
-        Ok(())
+    fn prompt_for_password(&mut self, file_path: &str, reason: PasswordError) {
+        self.state.locked_file = Some(file_path.to_string());
+        self.state.password_buffer.clear();
+        self.state.input_mode = InputMode::Password;
+        self.state.status_message = match reason {
+            PasswordError::Required => format!("{file_path} is encrypted. Enter its password."),
+            PasswordError::Incorrect => format!("Wrong password for {file_path}. Try again."),
+        };
     }
 
     fn refresh_analysis(&mut self) {
-        self.state.structural_analysis = analyze_structure(&self.state.file_content);
+        let text = self.state.analysis_text();
+        let structure = analyze_structure(&text);
+        let content = analyze_content(&text, self.state.keyword_limit);
+        let repeated = extract_repeated_lines(&text, 8);
+        drop(text);
 
-        let content = analyze_content(&self.state.file_content, self.state.keyword_limit);
+        self.state.structural_analysis = structure;
         self.state.keywords = content.keywords;
         self.state.phrases = content.phrases;
         self.state.readability = content.readability;
-
-        self.state.repeated_lines = extract_repeated_lines(&self.state.file_content, 8);
+        self.state.repeated_lines = repeated;
+        self.state.profiles = self.state.diff.as_ref().map(|diff| {
+            ProfileComparison::new(&diff.old.join("\n"), &diff.new.join("\n"), self.state.keyword_limit)
+        });
         self.state.content_scroll = self.state.content_scroll.min(self.max_content_scroll());
+
+        self.state.page_analysis = None;
+        self.follow_page();
+        self.follow_media();
+    }
+
+    /// Keep the per-page analysis on the page at the top of the viewer, or on
+    /// the chosen range. Cheap when neither has changed, so it runs after
+    /// every event.
+    fn follow_page(&mut self) {
+        if self.state.analysis_scope == AnalysisScope::Document {
+            self.state.page_analysis = None;
+            return;
+        }
+        let count = self.state.outline.top_level().len();
+        let (index, last) = match self.state.analysis_scope {
+            // Above the first section, measure the first one.
+            AnalysisScope::Page if count > 0 => {
+                let index = self.state.outline.top_level_at(self.state.content_scroll).unwrap_or(0);
+                (index, index)
+            }
+            AnalysisScope::Pages { first, last } if last <= count => (first - 1, last - 1),
+            // The file was reloaded with fewer pages, or none.
+            _ => {
+                self.state.analysis_scope = AnalysisScope::Document;
+                self.state.page_analysis = None;
+                return;
+            }
+        };
+        let measured = self.state.page_analysis.as_ref().map(|page| (page.index, page.last));
+        if measured == Some((index, last)) {
+            return;
+        }
+        let segments = self.state.outline.top_level()[index..=last].to_vec();
+        let text: String = segments.iter().map(|segment| self.state.segment_text(segment)).collect();
+        let content = analyze_content(&text, self.state.keyword_limit);
+        self.state.page_analysis = Some(PageAnalysis {
+            index,
+            last,
+            structure: analyze_structure(&text),
+            keywords: content.keywords,
+            phrases: content.phrases,
+            readability: content.readability,
+            repeated_lines: extract_repeated_lines(&text, 8),
+        });
+    }
+
+    /// Keep the image measurements on the selected image. Cheap while the
+    /// selection is unchanged, so it runs after every event.
+    fn follow_media(&mut self) {
+        let Some(item) = self.state.selected_media_item() else {
+            self.state.image_analysis = None;
+            return;
+        };
+        if self.state.image_analysis.as_ref().map(|analysis| &analysis.key) == Some(&item.key) {
+            return;
+        }
+        self.state.image_analysis = Some(ImageAnalysis {
+            key: item.key.clone(),
+            title: item.title.clone(),
+            stats: image_stats::analyse(&item.image),
+        });
+    }
+
+    fn toggle_analysis_scope(&mut self) {
+        // A diff has no pages; its other scope is the two files side by side.
+        if self.state.diff.is_some() {
+            self.state.compare_profiles = !self.state.compare_profiles;
+            self.state.status_message = if self.state.compare_profiles {
+                "Analyze compares what the two files are about.".to_string()
+            } else {
+                "Analyze measures the changed lines.".to_string()
+            };
+            return;
+        }
+        if self.state.outline.is_empty() {
+            self.state.status_message =
+                "Per-page analysis needs a paged document such as a PDF.".to_string();
+            return;
+        }
+        self.state.analysis_scope = match self.state.analysis_scope {
+            AnalysisScope::Document => AnalysisScope::Page,
+            AnalysisScope::Page | AnalysisScope::Pages { .. } => AnalysisScope::Document,
+        };
+        self.follow_page();
+        self.state.status_message = match self.state.analysis_scope {
+            AnalysisScope::Page => {
+                "Analyze measures the current page and follows the viewer.".to_string()
+            }
+            _ => "Analyze measures the whole document.".to_string(),
+        };
+    }
+
+    /// Measure the pages typed at the `S` prompt, `3-7` or `4`, as one text.
+    /// An empty prompt goes back to the whole document.
+    fn set_page_range(&mut self, range: &str) {
+        if range.trim().is_empty() {
+            self.state.analysis_scope = AnalysisScope::Document;
+            self.follow_page();
+            self.state.status_message = "Analyze measures the whole document.".to_string();
+            return;
+        }
+        let range = match parse_page_range(range) {
+            Ok(range) => range,
+            Err(error) => {
+                self.state.status_message = error;
+                return;
+            }
+        };
+        let count = self.state.outline.top_level().len();
+        if *range.end() > count {
+            self.state.status_message = format!(
+                "{} has {count} page(s); {}-{} runs past the end.",
+                self.state.file_name,
+                range.start(),
+                range.end()
+            );
+            return;
+        }
+        let (first, last) = range.into_inner();
+        self.state.analysis_scope = AnalysisScope::Pages { first, last };
+        self.follow_page();
+        self.state.status_message =
+            format!("Analyze measures pages {first} to {last} together.");
+    }
+
+    /// Put the log lines the filter keeps into `file_content`, staying on the
+    /// line the viewer was showing or the next one kept. A file that is not a
+    /// log is left alone.
+    fn apply_log_filter(&mut self) {
+        let Some(log) = self.state.log.clone() else {
+            self.state.log_lines.clear();
+            self.state.log_summary = None;
+            return;
+        };
+        let top = self.state.log_lines.get(self.state.content_scroll).copied().unwrap_or(0);
+        let (text, lines) = log.filtered(&self.state.log_filter);
+        self.state.content_scroll = lines.partition_point(|line| *line < top);
+        self.state.log_summary = Some(log.summarize(&lines));
+        self.state.file_content = text;
+        self.state.log_lines = lines;
+    }
+
+    /// Re-filter the log after `log_filter` changed, and say what is shown.
+    fn refilter_log(&mut self) {
+        self.apply_log_filter();
+        self.refresh_analysis();
+        self.update_search();
+        let total = self.state.log.as_ref().map_or(0, |log| log.entries.len());
+        self.state.status_message = format!(
+            "Showing {} of {total} log lines · {}.",
+            self.state.log_lines.len(),
+            self.state.log_filter.describe()
+        );
+    }
+
+    /// Put the tree as folded into `file_content`, keeping the node at the top
+    /// of the viewer there, or the fold that now hides it. A file that is not
+    /// a tree is left alone.
+    fn render_tree(&mut self) {
+        let Some(tree) = self.state.tree.clone() else {
+            self.state.tree_rows.clear();
+            return;
+        };
+        let top = self.state.tree_rows.get(self.state.content_scroll).copied().unwrap_or(0);
+        let (text, rows) = tree.render(&self.state.tree_collapsed);
+        self.state.content_scroll = rows.partition_point(|node| *node <= top).saturating_sub(1);
+        self.state.file_content = text;
+        self.state.tree_rows = rows;
+    }
+
+    /// Fold or unfold the tree node on viewer line `line`.
+    fn toggle_fold(&mut self, line: usize) {
+        let Some(tree) = self.state.tree.clone() else {
+            return;
+        };
+        let Some(&node) = self.state.tree_rows.get(line) else {
+            return;
+        };
+        if !tree.nodes[node].value.is_container() {
+            self.state.status_message = format!("{} has nothing to fold.", tree.path(node));
+            return;
+        }
+        let folded = !self.state.tree_collapsed[node];
+        self.state.tree_collapsed[node] = folded;
+        self.render_tree();
+        self.state.status_message = format!(
+            "{} {}.",
+            if folded { "Folded" } else { "Unfolded" },
+            tree.path(node)
+        );
+    }
+
+    /// Unfold every node, or fold everything down to the top-level keys.
+    fn fold_all(&mut self, folded: bool) {
+        let Some(tree) = self.state.tree.clone() else {
+            self.state.status_message = "Folding needs a JSON, YAML or TOML file.".to_string();
+            return;
+        };
+        self.state.tree_collapsed = tree
+            .nodes
+            .iter()
+            .map(|node| folded && node.depth > 0 && node.value.is_container())
+            .collect();
+        self.render_tree();
+        self.state.status_message = if folded {
+            "Folded to the top-level keys.".to_string()
+        } else {
+            "Unfolded every node.".to_string()
+        };
+    }
+
+    /// Unfold whatever hides tree node `node` and return the line it is on.
+    fn reveal_node(&mut self, node: usize) -> usize {
+        let Some(tree) = self.state.tree.clone() else {
+            return 0;
+        };
+        for ancestor in tree.ancestors(node) {
+            self.state.tree_collapsed[ancestor] = false;
+        }
+        self.render_tree();
+        self.state.tree_rows.partition_point(|row| *row < node)
+    }
+
+    /// Show the tree node at `path` at the top of the viewer.
+    fn goto_path(&mut self, path: &str) {
+        let Some(tree) = self.state.tree.clone() else {
+            return;
+        };
+        match tree.find(path) {
+            Ok(node) => {
+                let line = self.reveal_node(node);
+                self.state.content_scroll = line.min(self.max_content_scroll());
+                self.state.current_tab = TAB_VIEWER;
+                self.state.status_message =
+                    format!("{} · {}", tree.path(node), tree.nodes[node].value.summary());
+            }
+            Err(error) => self.state.status_message = error,
+        }
+    }
+
+    /// Put the diff into `file_content` in the chosen layout, keeping the line
+    /// at the top of the viewer there. A single file is left alone.
+    fn render_diff(&mut self) {
+        let Some(diff) = self.state.diff.clone() else {
+            self.state.diff_rows.clear();
+            return;
+        };
+        let top = self
+            .state
+            .diff_rows
+            .get(self.state.content_scroll)
+            .map_or(0, |row| row.first());
+        let (text, rows) = diff.render(self.state.diff_layout);
+        self.state.content_scroll = rows.partition_point(|row| row.first() < top);
+        self.state.file_content = text;
+        self.state.diff_rows = rows;
+    }
+
+    /// Switch the diff between side by side and unified.
+    fn toggle_diff_layout(&mut self) {
+        if self.state.diff.is_none() {
+            self.state.status_message = "Layouts need two files compared with --diff.".to_string();
+            return;
+        }
+        self.state.diff_layout = match self.state.diff_layout {
+            DiffLayout::SideBySide => DiffLayout::Unified,
+            DiffLayout::Unified => DiffLayout::SideBySide,
+        };
+        self.render_diff();
+        self.update_search();
+        self.state.status_message = format!("Showing the diff {}.", self.state.diff_layout.label());
+    }
+
+    /// Move the viewer to the next or previous hunk, wrapping at both ends
+    /// like [`App::step_match`].
+    fn step_hunk(&mut self, step: isize) {
+        let Some(diff) = self.state.diff.clone() else {
+            return;
+        };
+        if diff.hunks.is_empty() {
+            self.state.status_message = "The two files are the same.".to_string();
+            return;
+        }
+
+        let count = diff.hunks.len() as isize;
+        let next = match self.state.diff_hunk {
+            Some(current) => (current as isize + step).rem_euclid(count) as usize,
+            None if step > 0 => 0,
+            None => diff.hunks.len() - 1,
+        };
+        self.state.diff_hunk = Some(next);
+        let first = diff.first_change(next);
+        let row = self.state.diff_rows.partition_point(|row| row.first() < first);
+        self.state.content_scroll = row.saturating_sub(2).min(self.max_content_scroll());
+        self.state.current_tab = TAB_VIEWER;
+
+        let hunk = &diff.hunks[next];
+        let (removed, added) = hunk.lines.clone().fold((0, 0), |(removed, added), index| {
+            match diff.lines[index].kind {
+                LineKind::Removed => (removed + 1, added),
+                LineKind::Added => (removed, added + 1),
+                LineKind::Same => (removed, added),
+            }
+        });
+        self.state.status_message = format!(
+            "Hunk {} of {count} · old line {}, new line {} · {removed} removed, {added} added",
+            next + 1,
+            hunk.old_start,
+            hunk.new_start
+        );
+    }
+
+    /// Move the column searches are limited to by `step`, through every
+    /// column and back to none.
+    fn select_column(&mut self, step: isize) {
+        let Some(table) = &self.state.table else {
+            return;
+        };
+        // Position 0 stands for every column, 1 for the first, and so on.
+        let positions = table.headers.len() as isize + 1;
+        let current = self.state.table_column.map_or(0, |column| column as isize + 1);
+        let next = (current + step).rem_euclid(positions);
+        self.state.table_column = (next > 0).then(|| next as usize - 1);
+        self.state.status_message = match self.state.table_column {
+            Some(column) => format!(
+                "Searching column {} of {}: {}.",
+                column + 1,
+                table.headers.len(),
+                table.headers[column]
+            ),
+            None => "Searching every column.".to_string(),
+        };
+        self.update_search();
+    }
+
+    /// Raise the lowest level shown one step, wrapping back to every line.
+    fn cycle_log_level(&mut self) {
+        if self.state.log.is_none() {
+            self.state.status_message = "Level filters need a log file.".to_string();
+            return;
+        }
+        self.state.log_filter.min_level = match self.state.log_filter.min_level {
+            None => Some(LogLevel::Debug),
+            Some(LogLevel::Trace | LogLevel::Debug) => Some(LogLevel::Info),
+            Some(LogLevel::Info) => Some(LogLevel::Warn),
+            Some(LogLevel::Warn) => Some(LogLevel::Error),
+            Some(LogLevel::Error | LogLevel::Fatal) => None,
+        };
+        self.refilter_log();
     }
 
     fn update_settings_selection(&mut self, direction: isize) {
@@ -111,6 +639,29 @@ impl App {
             5 => self.state.whole_word = increase,
             6 => self.state.line_numbers = increase,
             7 => self.state.wrap_lines = increase,
+            8 => {
+                let mode = if increase {
+                    ExtractionMode::Layout
+                } else {
+                    ExtractionMode::Flow
+                };
+                if mode != self.state.extraction_mode {
+                    self.state.extraction_mode = mode;
+                    self.persist_settings();
+                    self.reread_document();
+                }
+                return;
+            }
+            9 if self.state.strip_furniture != increase => {
+                self.state.strip_furniture = increase;
+                self.refresh_analysis();
+            }
+            10 => {
+                if self.state.reflow != increase {
+                    self.toggle_reflow();
+                }
+                return;
+            }
             _ => {}
         }
 
@@ -121,6 +672,39 @@ impl App {
         self.persist_settings();
     }
 
+    /// Switch between the PDF's lines as typeset and reflowed paragraphs.
+    fn toggle_reflow(&mut self) {
+        self.state.reflow = !self.state.reflow;
+        self.persist_settings();
+        self.reread_document();
+    }
+
+    /// Read the open PDF again with the current extraction and reflow
+    /// settings, staying on the page the viewer was showing.
+    fn reread_document(&mut self) {
+        if self.state.document.is_none() {
+            return;
+        }
+        let page = self.state.outline.top_level_at(self.state.content_scroll);
+        let file = self.state.file_name.clone();
+        let password = self.password.clone();
+
+        if let Err(error) = self.load_file_with_password(&file, password.as_deref()) {
+            self.state.status_message = format!("Could not re-read {file}: {error}");
+            return;
+        }
+        if let Some(start) = page
+            .and_then(|page| self.state.outline.top_level().get(page).map(|page| page.start_line))
+        {
+            self.state.content_scroll = start.min(self.max_content_scroll());
+        }
+        self.state.status_message = format!(
+            "Re-read {file} with {} extraction, {}.",
+            self.state.extraction_mode.label().to_lowercase(),
+            if self.state.reflow { "lines reflowed" } else { "lines as typeset" }
+        );
+    }
+
     fn persist_settings(&mut self) {
         match AppSettings::from_state(&self.state).save() {
             Ok(()) => {
@@ -140,72 +724,93 @@ impl App {
             .saturating_sub(self.state.viewer_height)
     }
 
-    /// Move the viewer by whole PDF pages. `step` is in pages, not lines.
+    /// Move the viewer by whole pages, or by whatever the outline's top level
+    /// is. `step` is in pages, not lines; only its sign counts.
     fn jump_page(&mut self, step: isize) {
-        let Some(document) = self.state.document.clone() else {
+        if self.state.outline.is_empty() {
             self.state.status_message = "Page jumps need a paged document such as a PDF.".to_string();
             return;
-        };
-        if document.pages.is_empty() {
-            return;
         }
-
-        let current = document.page_of_line(self.state.content_scroll);
-        // Stepping back from mid-page returns to the top of the current page
-        // first, which is what a reader expects from a "previous page" key.
-        let at_page_start = document
-            .pages
-            .get(current)
-            .is_some_and(|page| page.start_line == self.state.content_scroll);
-        let target = if step < 0 && !at_page_start {
-            current
-        } else {
-            (current as isize + step).clamp(0, document.pages.len() as isize - 1) as usize
-        };
-
-        self.show_page(&document, target);
+        if let Some(segment) = self.state.outline.step_from(self.state.content_scroll, step > 0) {
+            self.show_segment(&segment.clone());
+        }
     }
 
     /// Move the viewer to a 1-based page number, as `--page` does.
     pub fn goto_page(&mut self, number: usize) {
-        let Some(document) = self.state.document.clone() else {
+        let pages = self.state.outline.count(SegmentKind::Page);
+        if pages == 0 {
             self.state.status_message =
                 "Page jumps need a paged document such as a PDF.".to_string();
             return;
-        };
+        }
 
-        let Some(index) = document
-            .pages
-            .iter()
-            .position(|page| page.number == number)
-        else {
-            self.state.status_message = format!(
-                "Page {number} is outside this document's {} page(s).",
-                document.page_count()
-            );
+        let Some(page) = self.state.outline.find(SegmentKind::Page, number).cloned() else {
+            self.state.status_message =
+                format!("Page {number} is outside this document's {pages} page(s).");
             return;
         };
 
-        self.show_page(&document, index);
+        self.show_segment(&page);
     }
 
-    /// Park the viewer at the top of `pages[index]` and report where it landed.
-    fn show_page(&mut self, document: &PdfDocument, index: usize) {
-        let Some(page) = document.pages.get(index) else {
-            return;
+    /// Park the viewer at the top of `segment` and report where it landed.
+    fn show_segment(&mut self, segment: &Segment) {
+        // Land on the start verbatim; the viewer clamps to the real pane
+        // height when it draws, which is the only place that height is known.
+        self.state.content_scroll = segment.start_line;
+        self.state.current_tab = TAB_VIEWER;
+
+        let top = self.state.outline.top_level();
+        let position = top.iter().position(|other| *other == segment);
+        let mut message = match (segment.kind, position) {
+            (SegmentKind::Page, _) => format!("Page {} of {}", segment.number, top.len()),
+            (_, Some(position)) => format!("{} · {} of {}", segment.title, position + 1, top.len()),
+            // A bookmark or heading nested inside the pages or chapters.
+            (_, None) => segment.title.clone(),
         };
+        message.push_str(&format!(" · {} lines", segment.line_count));
+        // Only a PDF knows what is on the page besides text.
+        let images = self.state.document.as_ref().and_then(|document| {
+            document
+                .pages
+                .iter()
+                .find(|page| segment.kind == SegmentKind::Page && page.number == segment.number)
+                .map(|page| page.image_count)
+        });
+        if let Some(images) = images {
+            message.push_str(&format!(" · {images} image(s)"));
+        }
+        self.state.status_message = message;
+    }
 
-        // Land on the page start verbatim; the viewer clamps to the real pane
-        // height when it draws, which is the only place that height is known.
-        self.state.content_scroll = page.start_line;
+    /// Show or hide the outline beside the viewer's text.
+    fn toggle_outline_pane(&mut self) {
+        if self.state.outline.is_empty() {
+            self.state.status_message = "This file has no pages or headings to outline.".to_string();
+            return;
+        }
+        self.state.outline_pane = !self.state.outline_pane;
         self.state.current_tab = TAB_VIEWER;
-        self.state.status_message = format!(
-            "Page {} of {} · {} lines · {} image(s)",
-            page.number,
-            document.pages.len(),
-            page.line_count,
-            page.image_count
-        );
+    }
+
+    /// Move the viewer `step` entries along the outline pane from the one it
+    /// highlights.
+    fn step_outline(&mut self, step: isize) {
+        let entries = self.state.outline.contents();
+        let current = entries
+            .iter()
+            .rposition(|segment| segment.contains(self.state.content_scroll));
+        let next = match current {
+            Some(current) => current.saturating_add_signed(step),
+            // Above the first entry only moving down goes anywhere.
+            None if step > 0 => 0,
+            None => return,
+        };
+        if let Some(segment) = entries.get(next.min(entries.len().saturating_sub(1))) {
+            let segment = (*segment).clone();
+            self.show_segment(&segment);
+        }
     }
 
     /// Seed the search box from outside the event loop, as `--search` does.
@@ -221,25 +826,168 @@ impl App {
         }
         let last = self.state.media.len() as isize - 1;
         let next = (self.state.selected_media as isize + step).clamp(0, last) as usize;
-        self.state.selected_media = next;
+        self.show_media(next);
     }
 
-    /// Jump the viewer to the page holding the selected image.
-    fn jump_to_media_page(&mut self) {
+    /// Select image `index`, starting it off fitted and unturned.
+    fn show_media(&mut self, index: usize) {
+        if index != self.state.selected_media {
+            self.state.selected_media = index;
+            self.state.media_view = MediaView::default();
+        }
+    }
+
+    /// Zoom, rotate and pan keys of the Media tab. Arrows only pan while
+    /// zoomed in; otherwise they keep moving through the images. Returns false
+    /// for keys it leaves alone.
+    fn handle_media_view_key(&mut self, key: KeyCode) -> bool {
+        if self.state.selected_media_item().is_none() {
+            return false;
+        }
+        let view = &mut self.state.media_view;
+        let visible = self.state.media_visible;
+        match key {
+            KeyCode::Char('+') | KeyCode::Char('=') => view.zoom_in(),
+            KeyCode::Char('-') => view.zoom_out(),
+            KeyCode::Char('0') => *view = MediaView::default(),
+            KeyCode::Char('1') => {
+                view.actual_size = !view.actual_size;
+                if !view.is_zoomed() {
+                    view.center = (0.5, 0.5);
+                }
+            }
+            KeyCode::Char('R') => view.rotate(),
+            KeyCode::Left if view.is_zoomed() => view.pan((-PAN_STEP, 0.0), visible),
+            KeyCode::Right if view.is_zoomed() => view.pan((PAN_STEP, 0.0), visible),
+            KeyCode::Up if view.is_zoomed() => view.pan((0.0, -PAN_STEP), visible),
+            KeyCode::Down if view.is_zoomed() => view.pan((0.0, PAN_STEP), visible),
+            _ => return false,
+        }
+        let label = self.state.media_view.label();
+        self.state.status_message = if label.is_empty() {
+            "Image fitted to the preview.".to_string()
+        } else {
+            format!("Viewing at {label}.")
+        };
+        true
+    }
+
+    /// Switch the Media tab between the thumbnail grid and the single image.
+    fn toggle_media_grid(&mut self) {
+        self.state.media_grid = !self.state.media_grid;
+        self.state.status_message = if self.state.media_grid {
+            "Thumbnail grid. Enter views the selected image.".to_string()
+        } else {
+            "Single image view. v returns to the grid.".to_string()
+        };
+    }
+
+    /// Arrow keys move through the grid in two dimensions; Enter opens the
+    /// selected image on its own. Returns false for keys it leaves alone.
+    fn handle_media_grid_key(&mut self, key: KeyCode) -> bool {
+        let columns = self.state.media_grid_columns.max(1) as isize;
+        match key {
+            KeyCode::Left => self.select_media(-1),
+            KeyCode::Right => self.select_media(1),
+            KeyCode::Up => self.select_media(-columns),
+            KeyCode::Down => self.select_media(columns),
+            KeyCode::Home => self.select_media(-(self.state.media.len() as isize)),
+            KeyCode::End => self.select_media(self.state.media.len() as isize),
+            KeyCode::Enter if !self.state.media.is_empty() => self.toggle_media_grid(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Pan by however far the mouse moved since the last drag event, so the
+    /// image follows the pointer.
+    fn drag_media(&mut self, position: Position) {
+        let preview = self.state.hit.media_preview;
+        let Some((column, row)) = self.state.media_drag.replace((position.x, position.y)) else {
+            return;
+        };
+        if !self.state.media_view.is_zoomed() || preview.width == 0 || preview.height == 0 {
+            return;
+        }
+        let dx = (f64::from(column) - f64::from(position.x)) / f64::from(preview.width);
+        let dy = (f64::from(row) - f64::from(position.y)) / f64::from(preview.height);
+        let visible = self.state.media_visible;
+        self.state.media_view.pan((dx, dy), visible);
+    }
+
+    /// Step through the PDF's attachments, wrapping at either end.
+    fn select_attachment(&mut self, step: isize) {
+        let Some(document) = &self.state.document else {
+            return;
+        };
+        let count = document.attachments.len() as isize;
+        if count == 0 {
+            self.state.status_message = "This file has no attachments.".to_string();
+            return;
+        }
+        let next = (self.state.selected_attachment as isize + step).rem_euclid(count) as usize;
+        self.state.selected_attachment = next;
+        self.state.status_message = format!(
+            "Attachment {} of {count}: {}. Press o to open it.",
+            next + 1,
+            document.attachments[next].name
+        );
+    }
+
+    /// Open the selected attachment in place of the PDF carrying it.
+    fn open_selected_attachment(&mut self) {
         let Some(document) = self.state.document.clone() else {
             return;
         };
+        let Some(attachment) = document.attachments.get(self.state.selected_attachment) else {
+            self.state.status_message = "This file has no attachments.".to_string();
+            return;
+        };
+
+        let path = match stage_attachment(attachment, &self.loaders) {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(error) => {
+                self.state.status_message = format!("Cannot open {}: {error}", attachment.name);
+                return;
+            }
+        };
+
+        let parent = self.state.file_name.clone();
+        self.state.attachment_parents.push((parent.clone(), self.password.clone()));
+        if let Err(error) = self.load_file(&path) {
+            self.state.attachment_parents.pop();
+            self.state.status_message = format!("Cannot open {}: {error}", attachment.name);
+            return;
+        }
+        // A locked attachment is waiting on the password prompt, which has
+        // already said so.
+        if self.state.input_mode == InputMode::Normal {
+            self.state.status_message = format!(
+                "Opened {} from {parent}. Backspace returns to it.",
+                attachment.name
+            );
+        }
+    }
+
+    /// Go back to the file the current attachment was opened from.
+    fn close_attachment(&mut self) {
+        let Some((parent, password)) = self.state.attachment_parents.pop() else {
+            return;
+        };
+        if let Err(error) = self.load_file_with_password(&parent, password.as_deref()) {
+            self.state.status_message = format!("Could not reopen {parent}: {error}");
+        }
+    }
+
+    /// Jump the viewer to the page holding the selected image.
+    fn jump_to_media_page(&mut self) {
         let Some(item) = self.state.media.get(self.state.selected_media) else {
             return;
         };
         let (title, Some(page_number)) = (item.title.clone(), item.page) else {
             return;
         };
-        let Some(page) = document
-            .pages
-            .iter()
-            .find(|page| page.number == page_number)
-        else {
+        let Some(page) = self.state.outline.find(SegmentKind::Page, page_number) else {
             return;
         };
 
@@ -255,35 +1003,51 @@ impl App {
     }
 
     fn jump_to_selected_result(&mut self) {
-        if let Some(selected) = self.state.search_results.get(self.state.selected_result) {
-            let target = selected.line_number.saturating_sub(3);
-            self.state.content_scroll = target.min(self.max_content_scroll());
-            self.state.current_tab = TAB_VIEWER;
-            self.state.status_message = match self.state.current_page() {
-                Some(page) => format!(
-                    "Jumped to line {} (page {page}) from search results.",
-                    selected.line_number
-                ),
-                None => format!(
-                    "Jumped to line {} from search results.",
-                    selected.line_number
-                ),
+        let Some(selected) = self.state.search_results.get(self.state.selected_result).cloned() else {
+            return;
+        };
+        // A tree match may sit under a fold; open the way to it first.
+        if let Some(tree) = self.state.tree.clone() {
+            let Some(node) = tree.node_of_match(selected.line_number) else {
+                return;
             };
+            let line = self.reveal_node(node);
+            self.state.content_scroll = line.saturating_sub(2).min(self.max_content_scroll());
+            self.state.current_tab = TAB_VIEWER;
+            self.state.status_message =
+                format!("Jumped to {} from search results.", tree.path(node));
+            return;
         }
+
+        let target = selected.line_number.saturating_sub(3);
+        self.state.content_scroll = target.min(self.max_content_scroll());
+        self.state.current_tab = TAB_VIEWER;
+        self.state.status_message = match self.state.current_page() {
+            Some(page) => format!(
+                "Jumped to line {} (page {page}) from search results.",
+                selected.line_number
+            ),
+            None => format!("Jumped to line {} from search results.", selected.line_number),
+        };
     }
 
     pub fn update_search(&mut self) {
         self.state.search_error = None;
+        let options = SearchOptions {
+            case_sensitive: self.state.case_sensitive,
+            regex_mode: self.state.regex_mode,
+            whole_word: self.state.whole_word,
+            column: self.state.table_column,
+        };
 
-        match search_with_options(
-            &self.state.search_query,
-            &self.state.file_content,
-            SearchOptions {
-                case_sensitive: self.state.case_sensitive,
-                regex_mode: self.state.regex_mode,
-                whole_word: self.state.whole_word,
-            },
-        ) {
+        let results = match (&self.state.table, &self.state.tree) {
+            (Some(table), _) => table.search(&self.state.search_query, options),
+            (None, Some(tree)) => tree.search(&self.state.search_query, options),
+            (None, None) => {
+                search_with_options(&self.state.search_query, &self.state.file_content, options)
+            }
+        };
+        match results {
             Ok(results) => {
                 self.state.search_results = results;
             }
@@ -293,6 +1057,19 @@ impl App {
             }
         }
 
+        // The query already parsed above, so a failure here can only repeat
+        // the error that is already on screen.
+        self.state.note_matches = match &self.state.document {
+            Some(document) => search_notes(
+                &document.annotations,
+                &document.form_fields,
+                &self.state.search_query,
+                options,
+            )
+            .unwrap_or_default(),
+            None => Vec::new(),
+        };
+
         self.state.selected_result = 0;
         self.state.result_list_state.select(if self.state.search_results.is_empty() {
             None
@@ -358,7 +1135,7 @@ impl App {
     }
 
     pub fn handle_key_event(&mut self, event: KeyEvent) -> bool {
-        match self.state.input_mode {
+        let keep_running = match self.state.input_mode {
             InputMode::Search => {
                 self.handle_search_key(event.code);
                 true
@@ -367,8 +1144,27 @@ impl App {
                 self.handle_goto_key(event.code);
                 true
             }
+            InputMode::Password => {
+                self.handle_password_key(event.code);
+                true
+            }
+            InputMode::TimeRange => {
+                self.handle_time_range_key(event.code);
+                true
+            }
+            InputMode::PathQuery => {
+                self.handle_path_key(event.code);
+                true
+            }
+            InputMode::PageRange => {
+                self.handle_page_range_key(event.code);
+                true
+            }
             InputMode::Normal => self.handle_command_key(event),
-        }
+        };
+        self.follow_page();
+        self.follow_media();
+        keep_running
     }
 
     fn handle_search_key(&mut self, key: KeyCode) {
@@ -417,11 +1213,123 @@ impl App {
         }
     }
 
+    fn handle_time_range_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => {
+                self.state.input_mode = InputMode::Normal;
+                self.state.time_range_buffer.clear();
+            }
+            KeyCode::Enter => {
+                let range = std::mem::take(&mut self.state.time_range_buffer);
+                self.state.input_mode = InputMode::Normal;
+                // An empty prompt clears the range.
+                let parsed = if range.trim().is_empty() {
+                    Ok((None, None))
+                } else {
+                    parse_time_range(&range)
+                };
+                match parsed {
+                    Ok((since, until)) => {
+                        self.state.log_filter.since = since;
+                        self.state.log_filter.until = until;
+                        self.refilter_log();
+                    }
+                    Err(error) => self.state.status_message = error,
+                }
+            }
+            KeyCode::Backspace => {
+                self.state.time_range_buffer.pop();
+            }
+            KeyCode::Char(c) => self.state.time_range_buffer.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_page_range_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => {
+                self.state.input_mode = InputMode::Normal;
+                self.state.page_range_buffer.clear();
+            }
+            KeyCode::Enter => {
+                let range = std::mem::take(&mut self.state.page_range_buffer);
+                self.state.input_mode = InputMode::Normal;
+                self.set_page_range(&range);
+            }
+            KeyCode::Backspace => {
+                self.state.page_range_buffer.pop();
+            }
+            KeyCode::Char(c) => self.state.page_range_buffer.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_path_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => {
+                self.state.input_mode = InputMode::Normal;
+                self.state.path_buffer.clear();
+            }
+            KeyCode::Enter => {
+                let path = std::mem::take(&mut self.state.path_buffer);
+                self.state.input_mode = InputMode::Normal;
+                self.goto_path(&path);
+            }
+            KeyCode::Backspace => {
+                self.state.path_buffer.pop();
+            }
+            KeyCode::Char(c) => self.state.path_buffer.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_password_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => {
+                self.state.input_mode = InputMode::Normal;
+                self.state.password_buffer.clear();
+                if let Some(file) = self.state.locked_file.take() {
+                    self.state.status_message = format!("{file} stays locked; no password given.");
+                }
+            }
+            KeyCode::Enter => {
+                let password = std::mem::take(&mut self.state.password_buffer);
+                self.state.input_mode = InputMode::Normal;
+                let Some(file) = self.state.locked_file.take() else {
+                    return;
+                };
+                // A wrong password lands back in this prompt through
+                // `load_file_with_password`; anything else is a real failure.
+                if let Err(error) = self.load_file_with_password(&file, Some(&password)) {
+                    self.state.status_message = format!("Could not open {file}: {error}");
+                }
+            }
+            KeyCode::Backspace => {
+                self.state.password_buffer.pop();
+            }
+            KeyCode::Char(c) => self.state.password_buffer.push(c),
+            _ => {}
+        }
+    }
+
     fn handle_command_key(&mut self, event: KeyEvent) -> bool {
         if event.modifiers.contains(KeyModifiers::CONTROL) {
             return self.handle_control_key(event.code);
         }
 
+        if self.state.current_tab == TAB_MEDIA {
+            let handled = if self.state.media_grid {
+                self.handle_media_grid_key(vim_alias(event.code))
+            } else {
+                // Before the vim aliases, so `j`/`k` still step through the
+                // images while the arrows pan a zoomed one.
+                self.handle_media_view_key(event.code)
+            };
+            if handled {
+                return true;
+            }
+        }
+
         match vim_alias(event.code) {
             KeyCode::Char('q') | KeyCode::Esc => return false,
             KeyCode::Char('/') => {
@@ -440,8 +1348,11 @@ impl App {
             }
             KeyCode::Char('[') => self.jump_page(-1),
             KeyCode::Char(']') => self.jump_page(1),
+            KeyCode::Char('n') if self.state.diff.is_some() => self.step_hunk(1),
+            KeyCode::Char('N') if self.state.diff.is_some() => self.step_hunk(-1),
             KeyCode::Char('n') => self.step_match(1),
             KeyCode::Char('N') => self.step_match(-1),
+            KeyCode::Char('u') => self.toggle_diff_layout(),
             KeyCode::Char('c') => {
                 self.state.case_sensitive = !self.state.case_sensitive;
                 self.update_search();
@@ -465,12 +1376,57 @@ impl App {
                 self.state.wrap_lines = !self.state.wrap_lines;
                 self.persist_settings();
             }
+            KeyCode::Char('x') => {
+                self.state.strip_furniture = !self.state.strip_furniture;
+                self.refresh_analysis();
+                self.persist_settings();
+            }
+            KeyCode::Char('p') if self.state.document.is_some() => self.toggle_reflow(),
+            KeyCode::Char('p') => {
+                self.state.status_message = "Reflow needs a PDF.".to_string();
+            }
+            KeyCode::Char('s') => self.toggle_analysis_scope(),
+            KeyCode::Char('S') if self.state.diff.is_none() && !self.state.outline.is_empty() => {
+                self.state.input_mode = InputMode::PageRange;
+                self.state.page_range_buffer.clear();
+            }
+            KeyCode::Char('S') => {
+                self.state.status_message =
+                    "Page ranges need a paged document such as a PDF.".to_string();
+            }
+            KeyCode::Char('t') => self.toggle_outline_pane(),
+            KeyCode::Char('L') => self.cycle_log_level(),
+            KeyCode::Char('T') if self.state.log.is_some() => {
+                self.state.input_mode = InputMode::TimeRange;
+                self.state.time_range_buffer.clear();
+            }
+            KeyCode::Char('T') => {
+                self.state.status_message = "Time filters need a log file.".to_string();
+            }
+            KeyCode::Char('e') => self.fold_all(false),
+            KeyCode::Char('E') => self.fold_all(true),
+            KeyCode::Char('P') if self.state.tree.is_some() => {
+                self.state.input_mode = InputMode::PathQuery;
+                self.state.path_buffer.clear();
+            }
+            KeyCode::Char('P') => {
+                self.state.status_message =
+                    "Path queries need a JSON, YAML or TOML file.".to_string();
+            }
             KeyCode::Enter if self.state.current_tab == TAB_SEARCH => {
                 self.jump_to_selected_result();
             }
             KeyCode::Enter if self.state.current_tab == TAB_MEDIA => {
                 self.jump_to_media_page();
             }
+            KeyCode::Enter if self.state.current_tab == TAB_VIEWER => {
+                self.toggle_fold(self.state.content_scroll);
+            }
+            KeyCode::Char('v') if self.state.current_tab == TAB_MEDIA => self.toggle_media_grid(),
+            KeyCode::Char('a') => self.select_attachment(1),
+            KeyCode::Char('A') => self.select_attachment(-1),
+            KeyCode::Char('o') => self.open_selected_attachment(),
+            KeyCode::Backspace => self.close_attachment(),
             KeyCode::Up => match self.state.current_tab {
                 TAB_SEARCH => self.move_result(-1),
                 TAB_MEDIA => self.select_media(-1),
@@ -495,6 +1451,8 @@ impl App {
             }
             KeyCode::Left if self.state.current_tab == TAB_SETTINGS => self.adjust_setting(false),
             KeyCode::Right if self.state.current_tab == TAB_SETTINGS => self.adjust_setting(true),
+            KeyCode::Left if self.state.table.is_some() => self.select_column(-1),
+            KeyCode::Right if self.state.table.is_some() => self.select_column(1),
             _ => {}
         }
 
@@ -534,8 +1492,14 @@ impl App {
             MouseEventKind::ScrollDown => self.scroll_at(position, 1),
             MouseEventKind::ScrollUp => self.scroll_at(position, -1),
             MouseEventKind::Down(MouseButton::Left) => self.click_at(position),
+            MouseEventKind::Drag(MouseButton::Left) if self.state.media_drag.is_some() => {
+                self.drag_media(position);
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.state.media_drag = None,
             _ => {}
         }
+        self.follow_page();
+        self.follow_media();
 
         true
     }
@@ -546,6 +1510,18 @@ impl App {
 
         if results.contains(position) {
             self.move_result(direction);
+        } else if self.state.hit.outline.contains(position) {
+            self.step_outline(direction);
+        } else if self.state.hit.media_grid.contains(position) {
+            let columns = self.state.media_grid_columns.max(1) as isize;
+            self.select_media(direction * columns);
+        } else if self.state.hit.media_preview.contains(position) {
+            // The wheel zooms the preview, as in most image viewers.
+            if direction < 0 {
+                self.state.media_view.zoom_in();
+            } else {
+                self.state.media_view.zoom_out();
+            }
         } else if media.contains(position) {
             self.select_media(direction);
         } else if settings.contains(position) {
@@ -585,15 +1561,47 @@ impl App {
                 self.state.selected_result = index;
                 self.state.result_list_state.select(Some(index));
             }
+        } else if self.state.hit.outline.contains(position) {
+            let row = (position.y - self.state.hit.outline.y) as usize;
+            let index = self.state.outline_list_state.offset() + row;
+            if let Some(segment) = self.state.outline.contents().get(index) {
+                let segment = (*segment).clone();
+                self.show_segment(&segment);
+            }
         } else if media.contains(position) {
             // Each image occupies two rows: its title and its detail line.
             let index = (position.y - media.y) as usize / 2;
             if index < self.state.media.len() {
-                self.state.selected_media = index;
+                self.show_media(index);
             }
+        } else if self.state.hit.media_grid.contains(position) {
+            self.click_thumbnail(position);
+        } else if self.state.hit.media_preview.contains(position) {
+            self.state.media_drag = Some((position.x, position.y));
         } else if settings.contains(position) {
             let row = (position.y - settings.y) as usize;
             self.state.settings_selection = row.min(SETTINGS_LAST);
+        } else if self.state.tree.is_some() && self.state.hit.viewer.contains(position) {
+            let row = (position.y - self.state.hit.viewer.y) as usize;
+            self.toggle_fold(self.state.content_scroll + row);
+        }
+    }
+
+    /// A click picks the thumbnail under it; a click on the one already
+    /// picked opens it.
+    fn click_thumbnail(&mut self, position: Position) {
+        let grid = self.state.hit.media_grid;
+        let column = usize::from((position.x - grid.x) / GRID_TILE.0);
+        let row = usize::from((position.y - grid.y) / GRID_TILE.1) + self.state.media_grid_scroll;
+        let columns = self.state.media_grid_columns.max(1);
+        if column >= columns {
+            return;
+        }
+        let index = row * columns + column;
+        if index == self.state.selected_media {
+            self.toggle_media_grid();
+        } else if index < self.state.media.len() {
+            self.show_media(index);
         }
     }
 
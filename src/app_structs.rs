@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -6,8 +8,20 @@ use ratatui::layout::Rect;
 use ratatui::widgets::ListState;
 use serde::{Deserialize, Serialize};
 
-use crate::media::{GraphicsMode, MediaItem};
-use crate::pdf_doc::PdfDocument;
+use crate::cli_values::{
+    parse_column, parse_min_size, parse_name_template, parse_page_range, ImageFileFormat,
+};
+use crate::data_tree::DataTree;
+use crate::image_stats::ImageStats;
+use crate::log_file::{LogFile, LogFilter, LogLevel, LogSummary};
+use crate::table::Table;
+use crate::media::{GraphicsMode, MediaItem, MediaView};
+use crate::outline::{Outline, Segment, SegmentKind};
+use crate::pdf_annotations::Note;
+use crate::pdf_doc::{ExtractionMode, PdfDocument};
+use crate::source_code::SourceCode;
+use crate::text_compare::ProfileComparison;
+use crate::text_diff::{DiffLayout, DiffRow, TextDiff};
 use crate::text_analysis::{Keyword, Phrase, Readability, SearchOptions};
 
 /// Workspace modes, in tab order.
@@ -27,6 +41,15 @@ pub struct Cli {
     #[arg(help = "Path to the file to analyze")]
     pub file: Option<String>,
 
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["OLD", "NEW"],
+        conflicts_with_all = ["file", "extract_images", "extract_attachments"],
+        help = "Compare two files of any supported format, line by line and word by word; with --json, print the hunks, with --text, a unified diff"
+    )]
+    pub diff: Option<Vec<String>>,
+
     #[arg(
         long,
         value_enum,
@@ -51,10 +74,59 @@ pub struct Cli {
     #[arg(
         long,
         value_name = "DIR",
-        help = "Write every embedded image to DIR as PNG and exit"
+        help = "Write every embedded image to DIR and exit"
     )]
     pub extract_images: Option<PathBuf>,
 
+    #[arg(
+        long,
+        requires = "extract_images",
+        help = "With --extract-images, write an image repeated across the PDF only once"
+    )]
+    pub unique_images: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t = ImageFileFormat::Png,
+        requires = "extract_images",
+        help = "With --extract-images, the format images are saved in"
+    )]
+    pub image_format: ImageFileFormat,
+
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        value_parser = parse_min_size,
+        requires = "extract_images",
+        help = "With --extract-images, skip images smaller than this, like 64 or 64x32"
+    )]
+    pub min_size: Option<(u32, u32)>,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = parse_name_template,
+        requires = "extract_images",
+        help = "With --extract-images, name files after TEMPLATE using {name} {page} {index} {width} {height} {ext} [default: page-{page}-img-{index}.{ext}]"
+    )]
+    pub image_name: Option<String>,
+
+    #[arg(
+        long,
+        requires = "extract_images",
+        help = "With --extract-images, save JPEGs and image files exactly as stored instead of re-encoding them"
+    )]
+    pub original_bytes: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Write every file embedded in the PDF to DIR and exit"
+    )]
+    pub extract_attachments: Option<PathBuf>,
+
     #[arg(
         short,
         long,
@@ -70,6 +142,47 @@ pub struct Cli {
     )]
     pub page: Option<usize>,
 
+    #[arg(
+        long,
+        value_name = "PASSWORD",
+        help = "Password for an encrypted PDF; the TUI prompts for it when omitted"
+    )]
+    pub password: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        help = "How PDF text is read: flow follows the content stream, layout rebuilds columns and tables from glyph positions [default: the saved setting, else flow]"
+    )]
+    pub extraction: Option<ExtractionMode>,
+
+    #[arg(
+        long,
+        help = "Leave running PDF headers, footers and page numbers out of the headless analysis"
+    )]
+    pub strip_headers: bool,
+
+    #[arg(
+        long,
+        help = "Rejoin PDF lines broken mid-sentence and words hyphenated at line ends [default: the saved setting, else off]"
+    )]
+    pub reflow: bool,
+
+    #[arg(
+        long,
+        help = "Read the text off scanned PDF pages and images with Tesseract while loading (needs the ocr feature)"
+    )]
+    pub ocr: bool,
+
+    #[arg(
+        long,
+        value_name = "RANGE",
+        value_parser = parse_page_range,
+        help = "With --json, also analyse PDF pages RANGE (like 3-7) together; with --text or --extract-images, write only those pages"
+    )]
+    pub page_range: Option<RangeInclusive<usize>>,
+
     #[arg(short = 'i', long, help = "Match the search query case-insensitively")]
     pub ignore_case: bool,
 
@@ -79,6 +192,14 @@ pub struct Cli {
     #[arg(short = 'w', long, help = "Match the search query on whole words only")]
     pub word: bool,
 
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_column,
+        help = "Search only column N of a CSV or TSV file, counting from 1"
+    )]
+    pub column: Option<usize>,
+
     #[arg(
         long,
         value_name = "N",
@@ -94,7 +215,7 @@ impl Cli {
     /// `--search` alone is not enough: on its own it seeds the TUI's search box,
     /// and only turns into printed matches alongside one of these.
     pub fn is_headless(&self) -> bool {
-        self.json || self.text || self.extract_images.is_some()
+        self.json || self.text || self.extract_images.is_some() || self.extract_attachments.is_some()
     }
 
     pub fn search_options(&self) -> SearchOptions {
@@ -102,6 +223,8 @@ impl Cli {
             case_sensitive: !self.ignore_case,
             regex_mode: self.regex,
             whole_word: self.word,
+            // Counted from 1 on the command line.
+            column: self.column.map(|column| column - 1),
         }
     }
 }
@@ -204,6 +327,49 @@ pub enum InputMode {
     Search,
     /// Keys go into the line-number prompt opened with `:`.
     Goto,
+    /// Keys go into the password prompt for an encrypted PDF.
+    Password,
+    /// Keys go into the log time range prompt opened with `T`.
+    TimeRange,
+    /// Keys go into the tree path prompt opened with `P`.
+    PathQuery,
+    /// Keys go into the Analyze page range prompt opened with `S`.
+    PageRange,
+}
+
+/// What the Analyze tab measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisScope {
+    /// The whole file.
+    Document,
+    /// The PDF page at the top of the viewer, following it as it scrolls.
+    Page,
+    /// A run of pages, counted from 1 and inclusive, measured as one text.
+    Pages { first: usize, last: usize },
+}
+
+/// The Analyze tab's numbers for a single page or section.
+#[derive(Debug, Clone)]
+pub struct PageAnalysis {
+    /// Index into the outline's top-level segments.
+    pub index: usize,
+    /// Index of the last segment measured: `index` itself for one page, later
+    /// for a range.
+    pub last: usize,
+    pub structure: StructuralAnalysisResults,
+    pub keywords: Vec<Keyword>,
+    pub phrases: Vec<Phrase>,
+    pub readability: Readability,
+    pub repeated_lines: Vec<(String, usize)>,
+}
+
+/// The Analyze tab's numbers for the selected image.
+#[derive(Debug, Clone)]
+pub struct ImageAnalysis {
+    /// [`MediaItem::key`] of the image measured.
+    pub key: String,
+    pub title: String,
+    pub stats: ImageStats,
 }
 
 /// Where things ended up on screen during the last draw.
@@ -219,8 +385,14 @@ pub struct HitRegions {
     pub tab_row: u16,
     /// Text area of the viewer, excluding its border.
     pub viewer: Rect,
+    /// Entries of the viewer's outline pane, one per row.
+    pub outline: Rect,
     pub search_results: Rect,
     pub media_list: Rect,
+    /// Inside of the Media tab's preview frame, where dragging pans.
+    pub media_preview: Rect,
+    /// Inside of the thumbnail grid, whose tiles start at its top left.
+    pub media_grid: Rect,
     pub settings_list: Rect,
 }
 
@@ -230,21 +402,44 @@ pub struct AppState {
     pub file_name: String,
     pub search_query: String,
     pub search_results: Vec<SearchMatch>,
+    /// PDF annotations and form fields the query matches, or all of them
+    /// while the query is empty.
+    pub note_matches: Vec<Note>,
     pub search_error: Option<String>,
     pub keywords: Vec<Keyword>,
     pub phrases: Vec<Phrase>,
     pub readability: Readability,
     pub repeated_lines: Vec<(String, usize)>,
     pub structural_analysis: StructuralAnalysisResults,
+    pub analysis_scope: AnalysisScope,
+    /// Analysis of the current page, kept while the scope is
+    /// [`AnalysisScope::Page`] and refreshed when the page changes, or of the
+    /// pages an [`AnalysisScope::Pages`] scope names.
+    pub page_analysis: Option<PageAnalysis>,
+    /// Measurements of the selected image, refreshed when the selection
+    /// changes.
+    pub image_analysis: Option<ImageAnalysis>,
     pub current_tab: usize,
     pub input_mode: InputMode,
     /// Digits typed at the `:` prompt, before they become a line number.
     pub goto_buffer: String,
+    /// Characters typed at the password prompt. Never drawn, never persisted.
+    pub password_buffer: String,
+    /// Encrypted file waiting on the password prompt.
+    pub locked_file: Option<String>,
     pub case_sensitive: bool,
     pub regex_mode: bool,
     pub whole_word: bool,
     pub line_numbers: bool,
     pub wrap_lines: bool,
+    /// How PDF page text is read out.
+    pub extraction_mode: ExtractionMode,
+    /// Leave running headers, footers and page numbers out of the analysis.
+    pub strip_furniture: bool,
+    /// Rejoin PDF lines into paragraphs when reading the file.
+    pub reflow: bool,
+    /// Read scanned pages and images with OCR when loading. Set by `--ocr`.
+    pub ocr: bool,
     pub selected_result: usize,
     pub result_list_state: ListState,
     pub theme: Theme,
@@ -253,11 +448,82 @@ pub struct AppState {
     pub preview_line_count: usize,
     pub content_scroll: usize,
     pub status_message: String,
-    /// Page structure, present only for PDFs.
+    /// The PDF itself, with its images, notes and furniture; present only for
+    /// PDFs and formats that build one. Set with [`AppState::set_document`].
     pub document: Option<Arc<PdfDocument>>,
+    /// Pages, chapters or sections of `file_content`, from whichever loader
+    /// read it. Page jumps and per-page analysis go by this. Set with
+    /// [`AppState::set_document`], so it never disagrees with the document.
+    pub outline: Outline,
+    /// Timestamps and levels of the open file, when it is a log.
+    pub log: Option<Arc<LogFile>>,
+    /// Which log lines `file_content` holds.
+    pub log_filter: LogFilter,
+    /// The 0-based log line behind each line of `file_content`.
+    pub log_lines: Vec<usize>,
+    /// Events per level and per minute among the lines shown.
+    pub log_summary: Option<LogSummary>,
+    pub time_range_buffer: String,
+    /// What is typed at the Analyze page range prompt.
+    pub page_range_buffer: String,
+    /// Rows and columns of the open file, when it is a table.
+    pub table: Option<Arc<Table>>,
+    /// The column searches are limited to; `None` searches them all.
+    pub table_column: Option<usize>,
+    /// Characters cut from the left of each table row so the chosen column
+    /// is on screen; set by the viewer as it draws.
+    pub table_offset: usize,
+    /// Keys and values of the open file, when it is JSON, YAML or TOML.
+    /// `file_content` is then the tree as currently folded.
+    pub tree: Option<Arc<DataTree>>,
+    /// Which tree nodes are folded, by node index.
+    pub tree_collapsed: Vec<bool>,
+    /// The tree node behind each line of `file_content`.
+    pub tree_rows: Vec<usize>,
+    pub path_buffer: String,
+    /// Language and syntax of the open file, when it is source code.
+    pub code: Option<Arc<SourceCode>>,
+    /// The changes between two files, when comparing them. `file_content`
+    /// is then the diff as laid out.
+    pub diff: Option<Arc<TextDiff>>,
+    pub diff_layout: DiffLayout,
+    /// The diff lines behind each line of `file_content`.
+    pub diff_rows: Vec<DiffRow>,
+    /// The hunk `n` and `N` last moved to.
+    pub diff_hunk: Option<usize>,
+    /// What the two compared files are about, keyword by keyword.
+    pub profiles: Option<ProfileComparison>,
+    /// Analyze sets the two files' profiles against each other instead of
+    /// measuring the changed lines.
+    pub compare_profiles: bool,
+    /// Show the outline beside the viewer's text.
+    pub outline_pane: bool,
+    /// Which outline entry the pane highlights, and how far it has scrolled.
+    pub outline_list_state: ListState,
     /// Images extracted from the loaded file, in page order.
     pub media: Vec<MediaItem>,
     pub selected_media: usize,
+    /// Show the images as a grid of thumbnails instead of one at a time.
+    pub media_grid: bool,
+    /// Thumbnails per grid row, measured during the last draw. Moving up or
+    /// down a row steps by this many images.
+    pub media_grid_columns: usize,
+    /// First grid row on screen; the grid scrolls to keep the selection in
+    /// view.
+    pub media_grid_scroll: usize,
+    /// Zoom, pan and rotation of the selected image.
+    pub media_view: MediaView,
+    /// Share of the selected image's width and height the preview showed in
+    /// the last draw. Panning steps are measured against it.
+    pub media_visible: (f64, f64),
+    /// Cell the last preview drag event was at, while the mouse button is
+    /// held.
+    pub media_drag: Option<(u16, u16)>,
+    /// Attachment of the loaded PDF that `o` would open.
+    pub selected_attachment: usize,
+    /// Files an opened attachment came out of, innermost last, with the
+    /// password each was opened with. Backspace goes back to the last one.
+    pub attachment_parents: Vec<(String, Option<String>)>,
     /// Rows the viewer actually has room for, measured during the last draw.
     /// Paging keys use this so a page step matches what is on screen.
     pub viewer_height: usize,
@@ -272,6 +538,7 @@ impl Default for AppState {
             file_name: "No file loaded".to_string(),
             search_query: String::new(),
             search_results: Vec::new(),
+            note_matches: Vec::new(),
             search_error: None,
             keywords: Vec::new(),
             phrases: Vec::new(),
@@ -287,14 +554,23 @@ impl Default for AppState {
                 longest_line: 0,
                 average_word_length: 0.0,
             },
+            analysis_scope: AnalysisScope::Document,
+            page_analysis: None,
+            image_analysis: None,
             current_tab: 0,
             input_mode: InputMode::Normal,
             goto_buffer: String::new(),
+            password_buffer: String::new(),
+            locked_file: None,
             case_sensitive: true,
             regex_mode: false,
             whole_word: false,
             line_numbers: true,
             wrap_lines: false,
+            extraction_mode: ExtractionMode::Flow,
+            strip_furniture: false,
+            reflow: false,
+            ocr: false,
             selected_result: 0,
             result_list_state: ListState::default(),
             theme: Theme::TokyoNight,
@@ -304,8 +580,39 @@ impl Default for AppState {
             content_scroll: 0,
             status_message: "Open a file to start searching, viewing, and analyzing text.".to_string(),
             document: None,
+            outline: Outline::default(),
+            log: None,
+            log_filter: LogFilter::default(),
+            log_lines: Vec::new(),
+            log_summary: None,
+            time_range_buffer: String::new(),
+            page_range_buffer: String::new(),
+            table: None,
+            table_column: None,
+            table_offset: 0,
+            tree: None,
+            tree_collapsed: Vec::new(),
+            tree_rows: Vec::new(),
+            path_buffer: String::new(),
+            code: None,
+            diff: None,
+            diff_layout: DiffLayout::default(),
+            diff_rows: Vec::new(),
+            diff_hunk: None,
+            profiles: None,
+            compare_profiles: false,
+            outline_pane: false,
+            outline_list_state: ListState::default(),
             media: Vec::new(),
             selected_media: 0,
+            media_grid: false,
+            media_grid_columns: 1,
+            media_grid_scroll: 0,
+            media_view: MediaView::default(),
+            media_visible: (1.0, 1.0),
+            media_drag: None,
+            selected_attachment: 0,
+            attachment_parents: Vec::new(),
             viewer_height: 50,
             hit: HitRegions::default(),
         }
@@ -329,14 +636,91 @@ impl AppState {
         self.current_tab == TAB_VIEWER || self.current_tab == TAB_DASHBOARD
     }
 
+    /// Show `document`, navigating by `outline`, or by the document's own
+    /// pages when `outline` is empty.
+    pub fn set_document(&mut self, document: Option<Arc<PdfDocument>>, outline: Outline) {
+        self.outline = outline.or_pages_of(document.as_deref());
+        self.document = document;
+    }
+
     /// 1-based page number under the top of the viewer, when the file has pages.
     pub fn current_page(&self) -> Option<usize> {
-        let document = self.document.as_ref()?;
-        let index = document.page_of_line(self.content_scroll);
-        document.pages.get(index).map(|page| page.number)
+        let index = self.outline.top_level_at(self.content_scroll)?;
+        let segment = self.outline.top_level()[index];
+        (segment.kind == SegmentKind::Page).then_some(segment.number)
+    }
+
+    /// The lines of `segment`, less any page furniture when that is being
+    /// skipped.
+    pub fn segment_text(&self, segment: &Segment) -> String {
+        let furniture = |line: usize| {
+            self.strip_furniture
+                && self
+                    .document
+                    .as_ref()
+                    .is_some_and(|document| document.furniture_at(line).is_some())
+        };
+        let mut text = String::new();
+        for (index, line) in self
+            .file_content
+            .lines()
+            .enumerate()
+            .take(segment.end_line())
+            .skip(segment.start_line)
+        {
+            if !furniture(index) {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Severity of line `line` of `file_content`, in a log.
+    pub fn log_level_at(&self, line: usize) -> Option<LogLevel> {
+        let log = self.log.as_ref()?;
+        log.entries.get(*self.log_lines.get(line)?)?.level
+    }
+
+    /// The 0-based line of `file_content` showing `entry`, if it is shown.
+    /// In a tree, a match under a folded node has no line.
+    pub fn match_line(&self, entry: &SearchMatch) -> Option<usize> {
+        match &self.tree {
+            Some(tree) => {
+                let node = tree.node_of_match(entry.line_number)?;
+                self.tree_rows.binary_search(&node).ok()
+            }
+            None => entry.line_number.checked_sub(1),
+        }
     }
 
     pub fn selected_media_item(&self) -> Option<&MediaItem> {
         self.media.get(self.selected_media)
     }
+
+    /// The text the Analyze tab measures: the file content, less any page
+    /// furniture when that is being skipped. A table is measured by the
+    /// cells of its text columns, a tree by its source, and source code by
+    /// its comments and the words its identifiers are made of. A diff is
+    /// measured by the lines it removes and adds.
+    pub fn analysis_text(&self) -> Cow<'_, str> {
+        if let Some(diff) = &self.diff {
+            return Cow::Owned(diff.changed_text());
+        }
+        if let Some(table) = &self.table {
+            return Cow::Owned(table.text_cells());
+        }
+        if let Some(tree) = &self.tree {
+            return Cow::Borrowed(tree.source());
+        }
+        if let Some(code) = &self.code {
+            return Cow::Owned(code.analysis_text(&self.file_content));
+        }
+        match &self.document {
+            Some(document) if self.strip_furniture && !document.furniture.is_empty() => {
+                Cow::Owned(document.text_without_furniture())
+            }
+            _ => Cow::Borrowed(&self.file_content),
+        }
+    }
 }
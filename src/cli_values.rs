@@ -0,0 +1,95 @@
+//! The values command-line flags take, parsed and checked.
+//!
+//! The flag definitions in [`crate::app_structs`], the headless runner and the
+//! TUI's prompts all read these values, so none of them reaches into another
+//! for the parser.
+
+use std::ops::RangeInclusive;
+
+use clap::ValueEnum;
+
+/// What extracted images are re-encoded as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum ImageFileFormat {
+    /// Lossless, and keeps the alpha that `/SMask` transparency produces.
+    #[default]
+    Png,
+    /// Smaller for photos; transparent areas are flattened onto white.
+    Jpeg,
+    /// Lossless WebP, with alpha.
+    Webp,
+}
+
+impl ImageFileFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// Placeholders `--image-name` understands.
+const NAME_PLACEHOLDERS: [&str; 6] = ["{name}", "{page}", "{index}", "{width}", "{height}", "{ext}"];
+
+/// Parse `--image-name`: a file name such as `{name}-p{page}-{index}.{ext}`.
+/// It must contain `{index}`, which is what keeps the names apart, and may
+/// not contain a path separator, so every file lands in the output directory.
+pub fn parse_name_template(value: &str) -> Result<String, String> {
+    if value.contains(['/', '\\']) {
+        return Err(format!("{value:?} is a file name, not a path; drop the separators"));
+    }
+    if !value.contains("{index}") {
+        return Err(format!("{value:?} needs {{index}} so every image gets its own name"));
+    }
+    let mut rest = value;
+    while let Some(start) = rest.find('{') {
+        let placeholder = rest[start..]
+            .find('}')
+            .map(|end| &rest[start..=start + end])
+            .filter(|placeholder| NAME_PLACEHOLDERS.contains(placeholder))
+            .ok_or_else(|| {
+                format!("{value:?} uses an unknown placeholder; use {}", NAME_PLACEHOLDERS.join(" "))
+            })?;
+        rest = &rest[start + placeholder.len()..];
+    }
+    Ok(value.to_string())
+}
+
+/// Parse `--min-size`: `32` for 32x32, or `WIDTHxHEIGHT`.
+pub fn parse_min_size(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value.split_once(['x', 'X']).unwrap_or((value, value));
+    match (width.trim().parse(), height.trim().parse()) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(format!("{value:?} is not a size like 64 or 64x32")),
+    }
+}
+
+/// Parse `--column`: a column number, counting from 1.
+pub fn parse_column(value: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|column| *column > 0)
+        .ok_or_else(|| format!("{value:?} is not a column number; columns count from 1"))
+}
+
+/// Parse `--page-range`: `3-7`, or `4` for a single page. Pages count from 1.
+pub fn parse_page_range(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let (first, last) = value.split_once('-').unwrap_or((value, value));
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|page| *page > 0)
+            .ok_or_else(|| format!("{value:?} is not a page range like 3-7; pages count from 1"))
+    };
+    let (first, last) = (parse(first)?, parse(last)?);
+    if first > last {
+        return Err(format!("{value:?} ends before it starts"));
+    }
+    Ok(first..=last)
+}
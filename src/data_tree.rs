@@ -0,0 +1,712 @@
+//! JSON, YAML and TOML files read as trees.
+//!
+//! A config or data file is nested keys and values, and reading it as lines
+//! leaves the nesting to the eye. Here the file becomes a tree of nodes, kept
+//! in document order, that the Viewer can fold and unfold, that names every
+//! node by its path (`server.tls[0].cert`), and that can be searched by key or
+//! value however deep they sit.
+//!
+//! The nodes are stored flat, in the order they appear, each knowing where
+//! its descendants end. Folding is then a matter of skipping a range, and the
+//! fold state can live with the viewer while the tree itself is shared.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use yaml_rust2::parser::{Event, Parser};
+use yaml_rust2::scanner::TScalarStyle;
+use yaml_rust2::Yaml;
+
+use crate::app_structs::SearchMatch;
+use crate::text_analysis::{Matcher, SearchOptions};
+
+/// Nesting deeper than this is refused rather than risk the stack.
+const MAX_DEPTH: usize = 128;
+/// Most nodes YAML aliases may add to a tree, all told. Each alias copies its
+/// anchor, so a few lines of aliases to aliases could otherwise ask for
+/// billions.
+const MAX_ALIAS_NODES: usize = 100_000;
+/// The key `toml` uses to hand over a date-time through serde.
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+/// The syntaxes a tree is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+        }
+    }
+
+    /// File extensions written in this format.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &["json"],
+            Self::Yaml => &["yaml", "yml"],
+            Self::Toml => &["toml"],
+        }
+    }
+}
+
+/// How a node is reached from its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKey {
+    /// The document itself.
+    Root,
+    Name(String),
+    Index(usize),
+}
+
+/// What a node holds. Containers count their children; scalars carry their
+/// text as the file would write it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeValue {
+    Object(usize),
+    Array(usize),
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    /// A TOML date, time or date-time.
+    DateTime(String),
+}
+
+impl NodeValue {
+    pub fn is_container(&self) -> bool {
+        matches!(self, Self::Object(_) | Self::Array(_))
+    }
+
+    /// `{3 keys}`, `[2 items]`, or the scalar as the file writes it.
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Object(1) => "{1 key}".to_string(),
+            Self::Object(count) => format!("{{{count} keys}}"),
+            Self::Array(1) => "[1 item]".to_string(),
+            Self::Array(count) => format!("[{count} items]"),
+            Self::Null => "null".to_string(),
+            Self::Bool(value) => value.to_string(),
+            Self::Number(text) | Self::DateTime(text) => text.clone(),
+            Self::String(text) => format!("{text:?}"),
+        }
+    }
+
+    /// The text a search looks in: strings without their quotes.
+    fn searchable(&self) -> Option<String> {
+        match self {
+            Self::Object(_) | Self::Array(_) => None,
+            Self::String(text) => Some(text.clone()),
+            other => Some(other.summary()),
+        }
+    }
+}
+
+/// One key or value in the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub key: NodeKey,
+    /// 0 for the root, 1 for its children, and so on.
+    pub depth: usize,
+    pub value: NodeValue,
+    pub parent: Option<usize>,
+    /// One past the last of this node's descendants.
+    pub end: usize,
+}
+
+/// A parsed file, its nodes in document order with the root first.
+#[derive(Debug, Clone)]
+pub struct DataTree {
+    pub format: DataFormat,
+    pub nodes: Vec<TreeNode>,
+    source: String,
+}
+
+impl DataTree {
+    pub fn parse(source: &str, format: DataFormat) -> Result<Self, String> {
+        let root = match format {
+            DataFormat::Json => serde_json::from_str(source).map_err(|error| error.to_string())?,
+            DataFormat::Toml => toml::from_str(source).map_err(|error| error.to_string())?,
+            DataFormat::Yaml => parse_yaml(source)?,
+        };
+        if root.depth() > MAX_DEPTH {
+            return Err(format!("nested more than {MAX_DEPTH} levels deep"));
+        }
+
+        let mut nodes = Vec::new();
+        flatten(root, NodeKey::Root, 0, None, &mut nodes);
+        Ok(Self {
+            format,
+            nodes,
+            source: source.to_string(),
+        })
+    }
+
+    /// The file as it was written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Deepest nesting below the root.
+    pub fn depth(&self) -> usize {
+        self.nodes.iter().map(|node| node.depth).max().unwrap_or(0)
+    }
+
+    /// The direct children of `node`, in order.
+    pub fn children(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let end = self.nodes[node].end;
+        let first = (node + 1 < end).then_some(node + 1);
+        std::iter::successors(first, move |child| {
+            let next = self.nodes[*child].end;
+            (next < end).then_some(next)
+        })
+    }
+
+    /// The nodes holding `node`, innermost first, the root last.
+    pub fn ancestors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.nodes[node].parent, |parent| self.nodes[*parent].parent)
+    }
+
+    /// The nodes shown when those marked in `collapsed` are folded. The root
+    /// is not a row of its own unless it is a lone scalar.
+    pub fn rows(&self, collapsed: &[bool]) -> Vec<usize> {
+        if !self.nodes[0].value.is_container() {
+            return vec![0];
+        }
+        let mut rows = Vec::new();
+        let mut node = 1;
+        while node < self.nodes.len() {
+            rows.push(node);
+            node = if collapsed.get(node).copied().unwrap_or(false) {
+                self.nodes[node].end
+            } else {
+                node + 1
+            };
+        }
+        rows
+    }
+
+    /// `node` as a row: indented by depth, with a fold marker on containers.
+    pub fn line(&self, node: usize, collapsed: bool) -> String {
+        let entry = &self.nodes[node];
+        let indent = "  ".repeat(entry.depth.saturating_sub(1));
+        let marker = match (entry.value.is_container(), collapsed) {
+            (false, _) => "  ",
+            (true, false) => "▾ ",
+            (true, true) => "▸ ",
+        };
+        match &entry.key {
+            NodeKey::Root => format!("{indent}{marker}{}", entry.value.summary()),
+            NodeKey::Name(name) => format!("{indent}{marker}{name}: {}", entry.value.summary()),
+            NodeKey::Index(index) => {
+                format!("{indent}{marker}[{index}] {}", entry.value.summary())
+            }
+        }
+    }
+
+    /// The rows shown with `collapsed` folded, one per line, and the node on
+    /// each.
+    pub fn render(&self, collapsed: &[bool]) -> (String, Vec<usize>) {
+        let rows = self.rows(collapsed);
+        let mut text = String::new();
+        for node in &rows {
+            text.push_str(&self.line(*node, collapsed.get(*node).copied().unwrap_or(false)));
+            text.push('\n');
+        }
+        (text, rows)
+    }
+
+    /// How to reach `node` from the root: `server.ports[2]`, with keys that
+    /// are not plain words quoted as `["two words"]`. The root is `$`.
+    pub fn path(&self, node: usize) -> String {
+        let mut chain: Vec<usize> = self.ancestors(node).collect();
+        chain.reverse();
+        chain.push(node);
+
+        let mut path = String::new();
+        for step in chain {
+            match &self.nodes[step].key {
+                NodeKey::Root => {}
+                NodeKey::Index(index) => path.push_str(&format!("[{index}]")),
+                NodeKey::Name(name) if is_plain_key(name) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(name);
+                }
+                NodeKey::Name(name) => path.push_str(&format!("[{name:?}]")),
+            }
+        }
+        if path.is_empty() {
+            "$".to_string()
+        } else {
+            path
+        }
+    }
+
+    /// The node at `path`, written as [`DataTree::path`] writes them. A
+    /// leading `$` or `.` is allowed.
+    pub fn find(&self, path: &str) -> Result<usize, String> {
+        let mut node = 0;
+        let mut walked = String::new();
+        for step in parse_path(path)? {
+            let child = self
+                .children(node)
+                .find(|child| match (&step, &self.nodes[*child].key) {
+                    (NodeKey::Name(wanted), NodeKey::Name(name)) => wanted == name,
+                    (NodeKey::Index(wanted), NodeKey::Index(index)) => wanted == index,
+                    _ => false,
+                });
+            let shown = match &step {
+                NodeKey::Name(name) if walked.is_empty() && is_plain_key(name) => name.clone(),
+                NodeKey::Name(name) if is_plain_key(name) => format!(".{name}"),
+                NodeKey::Name(name) => format!("[{name:?}]"),
+                NodeKey::Index(index) => format!("[{index}]"),
+                NodeKey::Root => String::new(),
+            };
+            walked.push_str(&shown);
+            node = child.ok_or_else(|| format!("Nothing at {walked} in this file."))?;
+        }
+        Ok(node)
+    }
+
+    /// Nodes whose key or value matches `query`. Each is numbered by the row
+    /// it takes with every node unfolded, and described by its path and value.
+    pub fn search(&self, query: &str, options: SearchOptions) -> Result<Vec<SearchMatch>, String> {
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let matcher = Matcher::new(query, options)?;
+        let first_row = usize::from(self.nodes[0].value.is_container());
+        Ok(self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| {
+                let in_key = match &node.key {
+                    NodeKey::Name(name) => matcher.count(name),
+                    _ => 0,
+                };
+                let in_value = node
+                    .value
+                    .searchable()
+                    .map_or(0, |value| matcher.count(&value));
+                let match_count = in_key + in_value;
+                (match_count > 0).then(|| SearchMatch {
+                    line_number: index + 1 - first_row,
+                    line: format!("{} = {}", self.path(index), node.value.summary()),
+                    match_count,
+                })
+            })
+            .collect())
+    }
+
+    /// The node a search match from [`DataTree::search`] points at.
+    pub fn node_of_match(&self, line_number: usize) -> Option<usize> {
+        let first_row = usize::from(self.nodes[0].value.is_container());
+        let node = (line_number + first_row).checked_sub(1)?;
+        (node < self.nodes.len()).then_some(node)
+    }
+}
+
+/// Whether `name` can follow a `.` in a path as it is.
+fn is_plain_key(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Split a path into the keys and indexes it steps through.
+fn parse_path(path: &str) -> Result<Vec<NodeKey>, String> {
+    let fail = || format!("'{path}' is not a path; try server.ports[0] or [\"two words\"]");
+    let mut rest = path.trim();
+    rest = rest.strip_prefix('$').unwrap_or(rest);
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let close = after.find(']').ok_or_else(fail)?;
+            let inside = after[..close].trim();
+            let quoted = inside
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+                .or_else(|| {
+                    inside
+                        .strip_prefix('\'')
+                        .and_then(|text| text.strip_suffix('\''))
+                });
+            steps.push(match quoted {
+                Some(name) => NodeKey::Name(name.replace("\\\"", "\"")),
+                None => NodeKey::Index(inside.parse().map_err(|_| fail())?),
+            });
+            rest = &after[close + 1..];
+        } else {
+            rest = rest.strip_prefix('.').unwrap_or(rest);
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(fail());
+            }
+            steps.push(NodeKey::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+    Ok(steps)
+}
+
+/// A parsed value before it is laid out flat, keeping keys in file order.
+#[derive(Debug, Clone, PartialEq)]
+enum DataNode {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    DateTime(String),
+    Array(Vec<DataNode>),
+    Object(Vec<(String, DataNode)>),
+}
+
+impl DataNode {
+    /// This node and everything in it.
+    fn size(&self) -> usize {
+        match self {
+            Self::Array(items) => 1 + items.iter().map(Self::size).sum::<usize>(),
+            Self::Object(entries) => 1 + entries.iter().map(|(_, value)| value.size()).sum::<usize>(),
+            _ => 1,
+        }
+    }
+
+    /// The node as a map key. YAML allows numbers and booleans as keys too.
+    fn into_key(self) -> Result<String, &'static str> {
+        match self {
+            Self::String(text) | Self::Number(text) | Self::DateTime(text) => Ok(text),
+            Self::Bool(value) => Ok(value.to_string()),
+            Self::Null => Ok("null".to_string()),
+            Self::Array(_) | Self::Object(_) => Err("keys that are lists or maps are not supported"),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            Self::Array(items) => 1 + items.iter().map(Self::depth).max().unwrap_or(0),
+            Self::Object(entries) => {
+                1 + entries
+                    .iter()
+                    .map(|(_, value)| value.depth())
+                    .max()
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn flatten(
+    value: DataNode,
+    key: NodeKey,
+    depth: usize,
+    parent: Option<usize>,
+    nodes: &mut Vec<TreeNode>,
+) {
+    let index = nodes.len();
+    let (node_value, children): (NodeValue, Vec<(NodeKey, DataNode)>) = match value {
+        DataNode::Null => (NodeValue::Null, Vec::new()),
+        DataNode::Bool(value) => (NodeValue::Bool(value), Vec::new()),
+        DataNode::Number(text) => (NodeValue::Number(text), Vec::new()),
+        DataNode::String(text) => (NodeValue::String(text), Vec::new()),
+        DataNode::DateTime(text) => (NodeValue::DateTime(text), Vec::new()),
+        DataNode::Array(items) => (
+            NodeValue::Array(items.len()),
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| (NodeKey::Index(index), item))
+                .collect(),
+        ),
+        DataNode::Object(entries) => (
+            NodeValue::Object(entries.len()),
+            entries
+                .into_iter()
+                .map(|(name, item)| (NodeKey::Name(name), item))
+                .collect(),
+        ),
+    };
+    nodes.push(TreeNode {
+        key,
+        depth,
+        value: node_value,
+        parent,
+        end: index + 1,
+    });
+    for (child_key, child) in children {
+        flatten(child, child_key, depth + 1, Some(index), nodes);
+    }
+    nodes[index].end = nodes.len();
+}
+
+impl<'de> Deserialize<'de> for DataNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DataNodeVisitor)
+    }
+}
+
+struct DataNodeVisitor;
+
+impl<'de> Visitor<'de> for DataNodeVisitor {
+    type Value = DataNode;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON, YAML or TOML value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<DataNode, E> {
+        Ok(DataNode::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<DataNode, E> {
+        Ok(DataNode::Number(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<DataNode, E> {
+        Ok(DataNode::Number(value.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<DataNode, E> {
+        Ok(DataNode::Number(value.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<DataNode, E> {
+        Ok(DataNode::String(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<DataNode, E> {
+        Ok(DataNode::String(value))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<DataNode, E> {
+        Ok(DataNode::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<DataNode, E> {
+        Ok(DataNode::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<DataNode, D::Error> {
+        DataNode::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DataNode, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(DataNode::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DataNode, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key::<KeyText>()? {
+            if key.0 == TOML_DATETIME {
+                let text: String = map.next_value()?;
+                return Ok(DataNode::DateTime(text));
+            }
+            entries.push((key.0, map.next_value()?));
+        }
+        Ok(DataNode::Object(entries))
+    }
+}
+
+/// A map key as text.
+struct KeyText(String);
+
+impl<'de> Deserialize<'de> for KeyText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DataNode::deserialize(deserializer)?
+            .into_key()
+            .map(Self)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// A sequence or mapping the YAML parser has opened and not yet closed.
+enum OpenNode {
+    Sequence(Vec<DataNode>),
+    /// Entries so far, and the key waiting for its value.
+    Mapping(Vec<(String, DataNode)>, Option<String>),
+}
+
+/// A YAML stream as one node: a lone document as itself, several as a list
+/// of them.
+///
+/// The tree is built from the parser's events with a stack rather than by
+/// recursion, so nesting is refused as soon as it goes too deep. Aliases are
+/// copies of their anchor, up to [`MAX_ALIAS_NODES`] in all.
+fn parse_yaml(source: &str) -> Result<DataNode, String> {
+    let mut parser = Parser::new_from_str(source);
+    let mut documents = Vec::new();
+    // Open containers, innermost last, with their anchor ids; 0 is none.
+    let mut open: Vec<(OpenNode, usize)> = Vec::new();
+    let mut anchors: HashMap<usize, DataNode> = HashMap::new();
+    let mut alias_nodes = 0;
+
+    loop {
+        let (event, _) = parser.next_token().map_err(|error| error.to_string())?;
+        let (node, anchor) = match event {
+            Event::StreamEnd => break,
+            Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => {
+                if open.len() >= MAX_DEPTH {
+                    return Err(format!("nested more than {MAX_DEPTH} levels deep"));
+                }
+                let node = match event {
+                    Event::SequenceStart(..) => OpenNode::Sequence(Vec::new()),
+                    _ => OpenNode::Mapping(Vec::new(), None),
+                };
+                open.push((node, anchor));
+                continue;
+            }
+            Event::SequenceEnd | Event::MappingEnd => match open.pop() {
+                Some((OpenNode::Sequence(items), anchor)) => (DataNode::Array(items), anchor),
+                Some((OpenNode::Mapping(entries, _), anchor)) => (DataNode::Object(entries), anchor),
+                None => return Err("a list or map closes that never opened".to_string()),
+            },
+            Event::Scalar(text, style, anchor, tag) => {
+                let quoted = style != TScalarStyle::Plain;
+                let string = tag.is_some_and(|tag| tag.suffix == "str");
+                (yaml_scalar(text, quoted || string), anchor)
+            }
+            Event::Alias(anchor) => {
+                let node = anchors
+                    .get(&anchor)
+                    .ok_or("an alias names an anchor that is not defined before it")?;
+                alias_nodes += node.size();
+                if alias_nodes > MAX_ALIAS_NODES {
+                    return Err(format!("aliases expand to more than {MAX_ALIAS_NODES} nodes"));
+                }
+                (node.clone(), 0)
+            }
+            Event::Nothing | Event::StreamStart | Event::DocumentStart | Event::DocumentEnd => {
+                continue
+            }
+        };
+        if anchor != 0 {
+            anchors.insert(anchor, node.clone());
+        }
+        match open.last_mut() {
+            None => documents.push(node),
+            Some((OpenNode::Sequence(items), _)) => items.push(node),
+            Some((OpenNode::Mapping(entries, key), _)) => match key.take() {
+                None => *key = Some(node.into_key()?),
+                Some(name) => entries.push((name, node)),
+            },
+        }
+    }
+
+    Ok(match documents.len() {
+        0 => DataNode::Null,
+        1 => documents.remove(0),
+        _ => DataNode::Array(documents),
+    })
+}
+
+/// A YAML scalar typed by the core schema: `null`, `~`, `true`, numbers as
+/// written. Quoted scalars, block scalars and `!!str` are always strings.
+fn yaml_scalar(text: String, string: bool) -> DataNode {
+    if string {
+        return DataNode::String(text);
+    }
+    match Yaml::from_str(&text) {
+        Yaml::Null => DataNode::Null,
+        Yaml::Boolean(value) => DataNode::Bool(value),
+        Yaml::Integer(_) | Yaml::Real(_) => DataNode::Number(text),
+        _ => DataNode::String(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+name = "flerp"
+released = 2024-03-05
+
+[server]
+ports = [80, 443]
+"two words" = true
+"#;
+
+    #[test]
+    fn keys_keep_their_order_and_paths_name_them() {
+        let tree = DataTree::parse(CONFIG, DataFormat::Toml).unwrap();
+        let keys: Vec<String> = tree.children(0).map(|node| tree.path(node)).collect();
+        assert_eq!(keys, ["name", "released", "server"]);
+        let port = tree.find("server.ports[1]").unwrap();
+        assert_eq!(tree.nodes[port].value, NodeValue::Number("443".into()));
+        assert_eq!(tree.path(port), "server.ports[1]");
+        let quoted = tree.find("$.server[\"two words\"]").unwrap();
+        assert_eq!(tree.path(quoted), "server[\"two words\"]");
+        assert_eq!(
+            tree.nodes[tree.find("released").unwrap()].value,
+            NodeValue::DateTime("2024-03-05".into())
+        );
+        assert_eq!(
+            tree.find("server.ports[2]").unwrap_err(),
+            "Nothing at server.ports[2] in this file."
+        );
+    }
+
+    #[test]
+    fn folding_hides_descendants() {
+        let tree = DataTree::parse(r#"{"a": {"b": [1, 2]}, "c": null}"#, DataFormat::Json).unwrap();
+        let mut collapsed = vec![false; tree.nodes.len()];
+        let (text, rows) = tree.render(&collapsed);
+        assert_eq!(
+            text,
+            "▾ a: {1 key}\n  ▾ b: [2 items]\n      [0] 1\n      [1] 2\n  c: null\n"
+        );
+        assert_eq!(rows, [1, 2, 3, 4, 5]);
+        collapsed[2] = true;
+        assert_eq!(tree.rows(&collapsed), [1, 2, 5]);
+        assert_eq!(tree.line(2, true), "  ▸ b: [2 items]");
+    }
+
+    #[test]
+    fn yaml_streams_become_a_list_of_documents() {
+        let tree = DataTree::parse("a: 1\n---\nb: [x, y]\n", DataFormat::Yaml).unwrap();
+        assert_eq!(tree.nodes[0].value, NodeValue::Array(2));
+        assert_eq!(tree.path(tree.find("[1].b[0]").unwrap()), "[1].b[0]");
+    }
+
+    #[test]
+    fn yaml_scalars_are_typed_and_aliases_copy_their_anchor() {
+        let source = "base: &base {port: 80, on: true}\nlive: *base\nquoted: '80'\nnone: ~\n";
+        let tree = DataTree::parse(source, DataFormat::Yaml).unwrap();
+        let value = |path: &str| tree.nodes[tree.find(path).unwrap()].value.clone();
+        assert_eq!(value("live.port"), NodeValue::Number("80".into()));
+        assert_eq!(value("live.on"), NodeValue::Bool(true));
+        assert_eq!(value("quoted"), NodeValue::String("80".into()));
+        assert_eq!(value("none"), NodeValue::Null);
+    }
+
+    #[test]
+    fn yaml_alias_bombs_and_deep_nesting_are_refused() {
+        let mut bomb = String::from("a0: &a0 [x, x, x, x, x, x, x, x, x, x]\n");
+        for level in 1..8 {
+            let previous = format!("*a{}", level - 1);
+            bomb += &format!("a{level}: &a{level} [{}]\n", vec![previous; 10].join(", "));
+        }
+        let error = DataTree::parse(&bomb, DataFormat::Yaml).unwrap_err();
+        assert!(error.contains("aliases expand"), "{error}");
+        let deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        let error = DataTree::parse(&deep, DataFormat::Yaml).unwrap_err();
+        assert!(error.contains("levels deep"), "{error}");
+    }
+}
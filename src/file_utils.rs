@@ -1,74 +1,457 @@
+use std::any::Any;
+use std::collections::hash_map::RandomState;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
-use std::sync::Arc;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
+use image::{DynamicImage, ImageFormat};
+
+use crate::data_tree::{DataFormat, DataTree};
+use crate::image_meta::ImageMetadata;
+use crate::loader::{Extensions, LoadOptions, Loader, LoaderRegistry, SNIFF_BYTES};
+use crate::log_file::LogFile;
 use crate::media::MediaItem;
-use crate::pdf_doc::{self, PdfDocument};
+use crate::outline::Outline;
+use crate::pdf_attachments::{safe_file_name, write_new_file, Attachment};
+use crate::pdf_doc::{self, PdfImageAsset};
+use crate::source_code::{Language, SourceCode};
+use crate::table::{self, Table};
+use crate::text_headings;
 
 /// Everything flerp learned from a file in one load.
+#[derive(Default)]
 pub struct LoadedFile {
+    /// Which [`Loader`] read the file; set by the registry.
+    pub kind: &'static str,
     pub content: String,
-    pub document: Option<Arc<PdfDocument>>,
+    /// Pages, chapters or sections in `content`. When a loader leaves it empty
+    /// but adds a [`PdfDocument`](pdf_doc::PdfDocument), the registry fills it with the document's
+    /// pages.
+    pub outline: Outline,
     pub media: Vec<MediaItem>,
     pub notice: Option<String>,
+    /// Some of `content` was recognised from pixels by OCR rather than read
+    /// from the file. For a PDF, the pages involved are flagged too.
+    pub ocr: bool,
+    /// What the loader knows about its format, one value per type. The
+    /// built-in loaders add one of these, which the app and `--json` show:
+    ///
+    /// - a [`PdfDocument`](pdf_doc::PdfDocument), with its images, notes and furniture
+    /// - a [`LogFile`], with timestamps and levels
+    /// - a [`Table`]; `content` is then the rows lined up, without the header
+    /// - a [`DataTree`] of keys and values; `content` is then the source as
+    ///   written
+    /// - a [`SourceCode`], with language, syntax and line counts
+    /// - a [`TextDiff`](crate::text_diff::TextDiff), when two files are compared rather than one opened;
+    ///   `content` is then empty, and the app lays the diff out
+    pub extensions: Extensions,
+}
+
+impl LoadedFile {
+    /// This file with `value` among its extensions.
+    pub fn with<T: Any + Send + Sync>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// The extension of type `T`, if the loader added one.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.extensions.get()
+    }
+
+    /// The file as plain text, as `--text` prints it: the content, with a
+    /// table's header line above its rows.
+    pub fn plain_text(&self) -> String {
+        match self.get::<Table>() {
+            Some(table) => format!("{}\n{}", table.header_line(), self.content),
+            None => self.content.clone(),
+        }
+    }
 }
 
 pub fn load_file(file_path: &str) -> Result<LoadedFile, Box<dyn Error>> {
-    let path = Path::new(file_path);
-    let extension = path
-        .extension()
+    load_file_with_password(file_path, None)
+}
+
+/// Load a file, using `password` to open it if it turns out to be an encrypted
+/// PDF. Formats that cannot be encrypted ignore it.
+pub fn load_file_with_password(
+    file_path: &str,
+    password: Option<&str>,
+) -> Result<LoadedFile, Box<dyn Error>> {
+    load_file_with_options(
+        file_path,
+        &LoadOptions {
+            password,
+            ..LoadOptions::default()
+        },
+    )
+}
+
+/// Load a file with `options`, using the built-in loaders.
+pub fn load_file_with_options(
+    file_path: &str,
+    options: &LoadOptions,
+) -> Result<LoadedFile, Box<dyn Error>> {
+    LoaderRegistry::default().load(file_path, options)
+}
+
+/// The last char boundary of `text` at or before byte `index`.
+fn char_boundary_at_most(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
         .and_then(|value| value.to_str())
-        .map(|value| value.to_ascii_lowercase());
+        .is_some_and(|value| extensions.iter().any(|extension| value.eq_ignore_ascii_case(extension)))
+}
+
+/// PDFs, by name or by their `%PDF-` header.
+pub struct PdfLoader;
+
+impl Loader for PdfLoader {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn sniff(&self, path: &Path, head: &[u8]) -> bool {
+        has_extension(path, &["pdf"]) || head.starts_with(b"%PDF-")
+    }
+
+    fn load(&self, path: &Path, options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        load_pdf(&path.to_string_lossy(), options)
+    }
+}
+
+/// PNG, JPEG and WebP pictures, by name or by their signature.
+pub struct ImageLoader;
+
+impl Loader for ImageLoader {
+    fn name(&self) -> &'static str {
+        "image"
+    }
+
+    fn sniff(&self, path: &Path, head: &[u8]) -> bool {
+        has_extension(path, &["png", "jpg", "jpeg", "webp"])
+            || matches!(
+                image::guess_format(head),
+                Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP)
+            )
+    }
+
+    fn load(&self, path: &Path, options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        load_image(path, options.ocr)
+    }
+}
+
+/// Anything that reads as UTF-8. A multi-byte character cut off by the end of
+/// the sniffed bytes does not count against it.
+pub struct TextLoader;
+
+impl Loader for TextLoader {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn sniff(&self, _path: &Path, head: &[u8]) -> bool {
+        match std::str::from_utf8(head) {
+            Ok(_) => true,
+            Err(error) => error.error_len().is_none(),
+        }
+    }
+
+    /// Headings found in the text become its outline.
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        Ok(LoadedFile {
+            outline: text_headings::detect(&content),
+            content,
+            ..LoadedFile::default()
+        })
+    }
+}
+
+/// Application logs: text whose lines mostly start with a timestamp.
+pub struct LogLoader;
+
+impl Loader for LogLoader {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn sniff(&self, _path: &Path, head: &[u8]) -> bool {
+        let text = match std::str::from_utf8(head) {
+            Ok(text) => text,
+            Err(error) if error.error_len().is_none() => {
+                std::str::from_utf8(&head[..error.valid_up_to()]).unwrap_or_default()
+            }
+            Err(_) => return false,
+        };
+        // The sniff window can cut the last line short; leave it out so half
+        // a timestamp does not count against the file.
+        let complete = match text.rfind('\n') {
+            Some(end) if head.len() >= SNIFF_BYTES => &text[..end],
+            _ => text,
+        };
+        LogFile::looks_like_log(complete)
+    }
+
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let log = LogFile::parse(&content);
+        Ok(LoadedFile {
+            content,
+            ..LoadedFile::default()
+        }
+        .with(log))
+    }
+}
+
+/// CSV and TSV files, by name. Delimited text is too common a shape to guess
+/// from contents alone.
+pub struct TableLoader;
+
+impl Loader for TableLoader {
+    fn name(&self) -> &'static str {
+        "table"
+    }
+
+    fn sniff(&self, path: &Path, _head: &[u8]) -> bool {
+        has_extension(path, &["csv", "tsv", "tab"])
+    }
 
-    match extension.as_deref() {
-        Some("pdf") => load_pdf(file_path),
-        Some("png") | Some("jpg") | Some("jpeg") => load_image(path),
-        _ => Ok(LoadedFile {
-            content: fs::read_to_string(file_path)?,
-            document: None,
-            media: Vec::new(),
-            notice: None,
-        }),
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let head = &text[..char_boundary_at_most(&text, SNIFF_BYTES)];
+        let table = Table::parse(&text, table::delimiter_for(path, head))?;
+        Ok(LoadedFile {
+            content: table.text(),
+            ..LoadedFile::default()
+        }
+        .with(table))
     }
 }
 
-fn load_pdf(file_path: &str) -> Result<LoadedFile, Box<dyn Error>> {
-    let document = pdf_doc::load(file_path)?;
+/// Source files, by name, in the languages [`Language`] knows.
+pub struct CodeLoader;
 
+impl Loader for CodeLoader {
+    fn name(&self) -> &'static str {
+        "code"
+    }
+
+    fn sniff(&self, path: &Path, _head: &[u8]) -> bool {
+        Language::from_path(path).is_some()
+    }
+
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let language = Language::from_path(path).ok_or("not a source file")?;
+        let code = SourceCode::parse(&content, language);
+        Ok(LoadedFile {
+            content,
+            ..LoadedFile::default()
+        }
+        .with(code))
+    }
+}
+
+/// JSON, YAML and TOML files, by name. One that does not parse is still
+/// shown, as text, with the parser's complaint as a notice.
+pub struct StructuredLoader(pub DataFormat);
+
+impl Loader for StructuredLoader {
+    fn name(&self) -> &'static str {
+        match self.0 {
+            DataFormat::Json => "json",
+            DataFormat::Yaml => "yaml",
+            DataFormat::Toml => "toml",
+        }
+    }
+
+    fn sniff(&self, path: &Path, _head: &[u8]) -> bool {
+        has_extension(path, self.0.extensions())
+    }
+
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        match DataTree::parse(&content, self.0) {
+            Ok(tree) => Ok(LoadedFile {
+                content,
+                ..LoadedFile::default()
+            }
+            .with(tree)),
+            Err(error) => Ok(LoadedFile {
+                notice: Some(format!(
+                    "Could not read as {}: {error}; showing it as text.",
+                    self.0.label()
+                )),
+                content,
+                ..LoadedFile::default()
+            }),
+        }
+    }
+}
+
+/// Put a file embedded in a PDF on disk so it can be opened with [`load_file`]
+/// like any other, and return where it went.
+///
+/// Attachments land in a private directory under the system temp directory,
+/// made once per process. Formats none of `loaders` recognises are refused
+/// here, before anything is written.
+pub fn stage_attachment(
+    attachment: &Attachment,
+    loaders: &LoaderRegistry,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let name = safe_file_name(&attachment.name, 0);
+    if loaders.find(Path::new(&name), &attachment.data).is_none() {
+        return Err(format!(
+            "{} ({}) is not a format flerp can show; extract it with --extract-attachments",
+            attachment.name,
+            attachment.mime_type.as_deref().unwrap_or("unknown type")
+        )
+        .into());
+    }
+
+    let directory = staging_directory()?;
+    Ok(write_new_file(&directory, &name, &attachment.data)?)
+}
+
+/// Remove everything [`stage_attachment`] put on disk during this process.
+pub fn remove_staged_attachments() {
+    if let Some(directory) = staging().take() {
+        let _ = fs::remove_dir_all(directory);
+    }
+}
+
+/// Names tried for a new temporary directory before giving up.
+const MAX_DIRECTORY_ATTEMPTS: usize = 16;
+
+/// Where this process stages attachments, once one has been.
+static STAGING: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn staging() -> MutexGuard<'static, Option<PathBuf>> {
+    STAGING.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn staging_directory() -> io::Result<PathBuf> {
+    let mut staging = staging();
+    if let Some(directory) = staging.as_ref() {
+        return Ok(directory.clone());
+    }
+    let directory = create_private_directory("flerp-attachments")?;
+    *staging = Some(directory.clone());
+    Ok(directory)
+}
+
+/// A new directory in the system temp directory that only this user can
+/// enter. The temp directory may be shared, so the name is hard to guess and
+/// an existing directory or symlink at it is never reused: someone else could
+/// have put it there to read the files or redirect them.
+fn create_private_directory(prefix: &str) -> io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+    for _ in 0..MAX_DIRECTORY_ATTEMPTS {
+        // Each `RandomState` is freshly keyed, so the hash is unpredictable.
+        let salt = RandomState::new().hash_one(std::process::id());
+        let name = format!("{prefix}-{}-{salt:016x}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        match builder.create(&path) {
+            Ok(()) => return Ok(path),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free name for a temporary directory",
+    ))
+}
+
+fn load_pdf(file_path: &str, options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+    let document = pdf_doc::load_with_options(file_path, options)?;
+
+    // One entry per distinct picture: a logo embedded again on every page is
+    // listed once, with the pages it appears on.
     let media = document
         .images
         .iter()
         .enumerate()
+        .filter(|(_, asset)| asset.duplicate_of.is_none())
         .map(|(index, asset)| MediaItem {
             key: format!("{file_path}#{index}"),
             title: format!("Page {} · image {}", asset.page, index + 1),
-            detail: format!(
-                "{}x{} · {} · {}",
-                asset.width, asset.height, asset.encoding, asset.color_space
-            ),
+            detail: image_detail(asset),
             page: Some(asset.page),
             image: asset.image.clone(),
+            copies: match document.image_copy_pages(index) {
+                pages if pages.len() > 1 => pages,
+                _ => Vec::new(),
+            },
+            source: Some(index),
+            metadata: asset.metadata.clone(),
         })
         .collect();
 
-    let notice = match (&document.image_error, document.skipped.len()) {
-        (Some(error), _) => Some(format!("Embedded images unavailable: {error}")),
-        (None, 0) => None,
-        (None, count) => Some(format!(
+    let mut notices = Vec::new();
+    if !document.unreadable_pages.is_empty() {
+        let pages: Vec<String> = document.unreadable_pages.iter().map(usize::to_string).collect();
+        notices.push(format!("Text on page(s) {} could not be read.", pages.join(", ")));
+    }
+    match (&document.image_error, document.skipped.len()) {
+        (Some(error), _) => notices.push(format!("Embedded images unavailable: {error}")),
+        (None, 0) => {}
+        (None, count) => notices.push(format!(
             "{count} embedded image(s) could not be decoded; see the Media tab."
         )),
-    };
+    }
+    let ocr_pages = document.pages.iter().filter(|page| page.ocr).count();
+    if ocr_pages > 0 {
+        notices.push(format!("{ocr_pages} scanned page(s) read by OCR."));
+    }
+    if let Some(error) = &document.ocr_error {
+        notices.push(format!("OCR unavailable: {error}"));
+    }
 
     Ok(LoadedFile {
+        kind: "pdf",
         content: document.text.clone(),
-        document: Some(Arc::new(document)),
+        outline: document.outline(),
         media,
-        notice,
-    })
+        notice: (!notices.is_empty()).then(|| notices.join(" ")),
+        ocr: ocr_pages > 0,
+        extensions: Extensions::default(),
+    }
+    .with(document))
 }
 
-fn load_image(path: &Path) -> Result<LoadedFile, Box<dyn Error>> {
+/// Pixel size, encoding and colour space, then where the page draws it.
+fn image_detail(asset: &PdfImageAsset) -> String {
+    let mut detail = format!(
+        "{}x{} · {} · {}",
+        asset.width, asset.height, asset.encoding, asset.color_space
+    );
+    if asset.inline {
+        detail.push_str(" · inline");
+    }
+    if let Some(placement) = &asset.placement {
+        detail.push_str(" · ");
+        detail.push_str(&placement.label(asset.width, asset.height));
+    }
+    detail
+}
+
+fn load_image(path: &Path, ocr: bool) -> Result<LoadedFile, Box<dyn Error>> {
     let metadata = fs::metadata(path)?;
     let format = path
         .extension()
@@ -80,24 +463,70 @@ fn load_image(path: &Path) -> Result<LoadedFile, Box<dyn Error>> {
         .and_then(|value| value.to_str())
         .unwrap_or("unknown");
 
-    let image = image::ImageReader::open(path)?.decode()?;
+    // Metadata has to be read before decoding consumes the decoder. Photos are
+    // turned upright here so everything downstream sees them the right way up.
+    let mut decoder = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let image_metadata = ImageMetadata::read(&mut decoder);
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    if let Some(orientation) = image_metadata.upright() {
+        image.apply_orientation(orientation);
+    }
     let (width, height) = (image.width(), image.height());
 
-    let content = format!(
-        "Image file loaded.\n\nName: {name}\nFormat: {format}\nDimensions: {width}x{height}\nSize: {} bytes\n\nOpen the Media tab to view the image itself.",
+    let mut placeholder = format!(
+        "Image file loaded.\n\nName: {name}\nFormat: {format}\nDimensions: {width}x{height}\nSize: {} bytes\n",
         metadata.len()
     );
+    for (label, value) in image_metadata.rows() {
+        placeholder.push_str(&format!("{label}: {value}\n"));
+    }
+    placeholder.push_str("\nOpen the Media tab to view the image itself.");
+
+    // Text found in the image goes under its file details, so search and
+    // analysis see what the image says and the details stay on screen.
+    let text = if ocr { image_text(&image) } else { Ok(None) };
+    let (content, notice, ocr) = match text {
+        Ok(Some(text)) => (
+            format!("{placeholder}\n\nText read by OCR:\n\n{text}\n"),
+            Some(format!("Text read from {name} by OCR; the image is in the Media tab.")),
+            true,
+        ),
+        Ok(None) => (placeholder, None, false),
+        Err(error) => (placeholder, Some(format!("OCR unavailable: {error}")), false),
+    };
 
     Ok(LoadedFile {
+        kind: "image",
         content,
-        document: None,
+        outline: Outline::default(),
         media: vec![MediaItem {
             key: path.to_string_lossy().to_string(),
             title: name.to_string(),
             detail: format!("{width}x{height} · {format}"),
             page: None,
             image,
+            copies: Vec::new(),
+            source: None,
+            metadata: (!image_metadata.is_empty()).then_some(image_metadata),
         }],
-        notice: None,
+        notice,
+        ocr,
+        extensions: Extensions::default(),
     })
 }
+
+/// The text OCR finds in `image`, if any.
+#[cfg(feature = "ocr")]
+fn image_text(image: &DynamicImage) -> Result<Option<String>, String> {
+    crate::ocr::recognize(image)
+        .map(|text| (!text.is_empty()).then_some(text))
+        .map_err(|error| error.to_string())
+}
+
+/// Without the `ocr` feature an image has no text to offer.
+#[cfg(not(feature = "ocr"))]
+fn image_text(_image: &DynamicImage) -> Result<Option<String>, String> {
+    Ok(None)
+}
@@ -6,67 +6,252 @@
 
 use std::error::Error;
 use std::fs;
-use std::io::Write;
+use std::io::{BufWriter, Write};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, Rgb, RgbImage};
 use serde_json::{json, Value};
 
-use crate::file_utils::{load_file, LoadedFile};
-use crate::media::MediaItem;
+use crate::app_structs::SearchMatch;
+use crate::cli_values::ImageFileFormat;
+use crate::data_tree::DataTree;
+use crate::file_utils::LoadedFile;
+use crate::image_stats;
+use crate::loader::{LoadOptions, LoaderRegistry};
+use crate::log_file::LogFile;
+use crate::outline::{Outline, SegmentKind};
+use crate::pdf_annotations::search_notes;
+use crate::pdf_attachments::write_attachments;
+use crate::pdf_doc::{ExtractionMode, PdfDocument, PdfOptions};
+use crate::source_code::SourceCode;
+use crate::table::Table;
 use crate::text_analysis::{
     analyze_content, analyze_structure, extract_repeated_lines, search_with_options, SearchOptions,
 };
+use crate::text_compare::ProfileComparison;
+use crate::text_diff::{Hunk, TextDiff};
 
 /// One non-interactive invocation, assembled from the CLI flags.
 pub struct HeadlessRequest {
     pub file: String,
+    /// Password for an encrypted PDF. There is no one to prompt here, so a
+    /// missing or wrong one is reported as an error.
+    pub password: Option<String>,
     /// Emit a single JSON object describing the file.
     pub json: bool,
     /// Emit the file's extracted plain text verbatim.
     pub text: bool,
-    /// Write every embedded image into this directory as PNG.
+    /// Write every embedded image into this directory.
     pub extract_images: Option<PathBuf>,
+    /// Which images `extract_images` writes, and how.
+    pub image_export: ImageExport,
+    /// Write every file embedded in a PDF into this directory, as-is.
+    pub extract_attachments: Option<PathBuf>,
+    /// How PDF text is read. Never taken from saved settings here.
+    pub extraction: ExtractionMode,
+    /// Analyse the PDF text without its running headers, footers and page
+    /// numbers. They are still listed under `furniture`.
+    pub strip_furniture: bool,
+    /// Rejoin PDF lines into paragraphs. Never taken from saved settings here.
+    pub reflow: bool,
+    /// Read scanned pages and images with OCR.
+    pub ocr: bool,
+    /// 1-based pages to analyse together, reported as `page_range`. `--text`
+    /// writes only these pages, and `extract_images` only their images.
+    pub page_range: Option<RangeInclusive<usize>>,
     pub search: Option<String>,
     pub search_options: SearchOptions,
     pub keyword_limit: usize,
 }
 
+/// A plain analysis of no file in particular, with the CLI's defaults; set
+/// `file` and whatever else the invocation asks for.
+impl Default for HeadlessRequest {
+    fn default() -> Self {
+        Self {
+            file: String::new(),
+            password: None,
+            json: false,
+            text: false,
+            extract_images: None,
+            image_export: ImageExport::default(),
+            extract_attachments: None,
+            extraction: ExtractionMode::default(),
+            strip_furniture: false,
+            reflow: false,
+            ocr: false,
+            page_range: None,
+            search: None,
+            search_options: SearchOptions::default(),
+            keyword_limit: 10,
+        }
+    }
+}
+
+/// A headless `--diff`: two files compared, printed as a unified diff or,
+/// with `json`, as hunks.
+pub struct DiffRequest {
+    pub old: String,
+    pub new: String,
+    /// Password for whichever of the two is an encrypted PDF.
+    pub password: Option<String>,
+    pub json: bool,
+    pub extraction: ExtractionMode,
+    pub reflow: bool,
+    pub ocr: bool,
+    /// Keywords per list when comparing what the two files are about.
+    pub keyword_limit: usize,
+}
+
+/// How `--extract-images` writes what it finds.
+#[derive(Debug, Clone, Default)]
+pub struct ImageExport {
+    /// Write one file per distinct image instead of one per copy, when a PDF
+    /// embeds the same picture more than once.
+    pub unique: bool,
+    pub format: ImageFileFormat,
+    /// Leave out images narrower or shorter than this, in pixels, such as
+    /// icons and bullets.
+    pub min_size: (u32, u32),
+    /// File name pattern checked by [`crate::cli_values::parse_name_template`];
+    /// `None` names files `page-003-img-02.png`.
+    pub name_template: Option<String>,
+    /// Save JPEGs, and image files, exactly as stored instead of re-encoding
+    /// their pixels. Images stored as raw samples are still written in
+    /// `format`.
+    pub original: bool,
+}
+
+/// JPEG quality for re-encoded images: well above the encoder's default of
+/// 75, since the pixels were usually compressed once already.
+const JPEG_QUALITY: u8 = 90;
+
+impl ImageFileFormat {
+    fn save(self, image: &DynamicImage, path: &Path) -> Result<(), Box<dyn Error>> {
+        let file = BufWriter::new(fs::File::create(path)?);
+        match self {
+            Self::Png => image.write_with_encoder(PngEncoder::new(file))?,
+            Self::Jpeg => DynamicImage::ImageRgb8(on_white(image))
+                .write_with_encoder(JpegEncoder::new_with_quality(file, JPEG_QUALITY))?,
+            // The WebP encoder takes 8-bit samples only.
+            Self::Webp if image.color().has_alpha() => DynamicImage::ImageRgba8(image.to_rgba8())
+                .write_with_encoder(WebPEncoder::new_lossless(file))?,
+            Self::Webp => DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(WebPEncoder::new_lossless(file))?,
+        }
+        Ok(())
+    }
+}
+
+/// `image` composited onto a white page, for formats without alpha. Dropping
+/// the alpha instead would bring back whatever colour the hidden pixels had.
+fn on_white(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [red, green, blue, alpha] = rgba.get_pixel(x, y).0;
+        let blend = |channel: u8| {
+            let (channel, alpha) = (u32::from(channel), u32::from(alpha));
+            ((channel * alpha + 255 * (255 - alpha) + 127) / 255) as u8
+        };
+        Rgb([blend(red), blend(green), blend(blue)])
+    })
+}
+
 /// An image written to disk, reported back so the caller can say what it did.
 struct WrittenImage {
+    /// The image's number in the document, as in `images[].index`.
+    index: usize,
     path: PathBuf,
     page: Option<usize>,
     width: u32,
     height: u32,
+    /// File extension of what was written.
+    format: String,
+    /// Written as stored rather than re-encoded.
+    original: bool,
 }
 
 pub fn run(request: &HeadlessRequest, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
-    let loaded = load_file(&request.file)?;
+    run_with_loaders(request, &LoaderRegistry::default(), out)
+}
+
+/// [`run`], opening the file with `loaders` instead of the built-in formats.
+pub fn run_with_loaders(
+    request: &HeadlessRequest,
+    loaders: &LoaderRegistry,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let loaded = loaders.load(
+        &request.file,
+        &LoadOptions {
+            password: request.password.as_deref(),
+            ocr: request.ocr,
+            ..LoadOptions::default()
+        }
+        .with(PdfOptions {
+            extraction: request.extraction,
+            reflow: request.reflow,
+        }),
+    )?;
+
+    if let Some(range) = &request.page_range {
+        let pages = loaded.get::<PdfDocument>().map(|pdf| pdf.page_count());
+        match pages {
+            None => return Err("--page-range needs a PDF".into()),
+            Some(count) if *range.end() > count => {
+                return Err(format!(
+                    "{} has {count} page(s); --page-range {}-{} runs past the end",
+                    request.file,
+                    range.start(),
+                    range.end()
+                )
+                .into())
+            }
+            Some(_) => {}
+        }
+    }
 
     // Extraction runs first so its result can be folded into the JSON object
     // rather than printed alongside it, which would leave stdout unparseable.
     let written = match &request.extract_images {
-        Some(directory) => write_images(&loaded.media, directory)?,
+        Some(directory) => write_images(&images_to_write(&loaded, request), request, directory)?,
         None => Vec::new(),
     };
+    let attachments = match (&request.extract_attachments, loaded.get::<PdfDocument>()) {
+        (Some(directory), Some(pdf)) => write_attachments(&pdf.attachments, directory)?,
+        (Some(_), None) => return Err("--extract-attachments needs a PDF".into()),
+        (None, _) => Vec::new(),
+    };
 
     if request.text {
-        write!(out, "{}", loaded.content)?;
-        if !loaded.content.ends_with('\n') {
+        let text = match (&request.page_range, loaded.get::<PdfDocument>()) {
+            (Some(range), Some(pdf)) => pdf.pages_text(page_indices(range), false),
+            _ => loaded.plain_text(),
+        };
+        write!(out, "{text}")?;
+        if !text.ends_with('\n') {
             writeln!(out)?;
         }
         return Ok(());
     }
 
     if request.json {
-        let document = analysis_json(request, &loaded, &written)?;
+        let document = analysis_json(request, &loaded, &written, &attachments)?;
         writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
         return Ok(());
     }
 
-    // Plain-text mode: grep-style match lines, then a line per written image.
+    // Plain-text mode: grep-style match lines, then a line per written image
+    // and attachment.
     if let Some(query) = &request.search {
-        let matches = search_with_options(query, &loaded.content, request.search_options)
-            .map_err(|error| -> Box<dyn Error> { error.into() })?;
+        let matches = search_file(&loaded, query, request.search_options)?;
         for entry in &matches {
             writeln!(out, "{}:{}:{}", request.file, entry.line_number, entry.line)?;
         }
@@ -91,83 +276,396 @@ pub fn run(request: &HeadlessRequest, out: &mut impl Write) -> Result<(), Box<dy
         }
     }
 
+    for path in &attachments {
+        writeln!(out, "{}", path.display())?;
+    }
+
     Ok(())
 }
 
+pub fn run_diff(request: &DiffRequest, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let loaders = LoaderRegistry::default();
+    let options = LoadOptions {
+        password: request.password.as_deref(),
+        ocr: request.ocr,
+        ..LoadOptions::default()
+    }
+    .with(PdfOptions {
+        extraction: request.extraction,
+        reflow: request.reflow,
+    });
+    let open = |path: &str| {
+        loaders
+            .load(path, &options)
+            .map_err(|error| -> Box<dyn Error> { format!("{path}: {error}").into() })
+    };
+    let (old, new) = (open(&request.old)?, open(&request.new)?);
+    let diff = TextDiff::new(&old.plain_text(), &new.plain_text());
+
+    if !request.json {
+        write!(out, "{}", diff.unified(&request.old, &request.new))?;
+        return Ok(());
+    }
+
+    let side = |file: &str, loaded: &LoadedFile, lines: usize| {
+        json!({ "file": file, "kind": loaded.kind, "lines": lines })
+    };
+    let mut stats = json!(diff.stats);
+    stats["hunks"] = json!(diff.hunks.len());
+    stats["similarity"] = json!(diff.stats.similarity());
+    let document = json!({
+        "old": side(&request.old, &old, diff.old.len()),
+        "new": side(&request.new, &new, diff.new.len()),
+        "stats": stats,
+        "hunks": diff.hunks.iter().map(|hunk| hunk_json(&diff, hunk)).collect::<Vec<_>>(),
+        "profiles": profiles_json(&ProfileComparison::new(
+            &old.plain_text(),
+            &new.plain_text(),
+            request.keyword_limit,
+        )),
+    });
+    writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
+    Ok(())
+}
+
+/// Readability, vocabulary and keywords of the two files, set side by side.
+/// Weights are shares of each file's total keyword weight.
+fn profiles_json(profiles: &ProfileComparison) -> Value {
+    let shared: Vec<Value> = profiles
+        .shared
+        .iter()
+        .map(|keyword| {
+            let mut value = json!(keyword);
+            value["delta"] = json!(keyword.delta());
+            value
+        })
+        .collect();
+    json!({
+        "readability": {
+            "old": profiles.old_readability,
+            "new": profiles.new_readability,
+            "lix_delta": profiles.lix_delta(),
+        },
+        "vocabulary": {
+            "old": profiles.old_vocabulary,
+            "new": profiles.new_vocabulary,
+            "shared": profiles.shared_vocabulary,
+            "jaccard": profiles.jaccard(),
+        },
+        "keywords": {
+            "only_old": profiles.only_old,
+            "only_new": profiles.only_new,
+            "shared": shared,
+        },
+    })
+}
+
+/// A hunk with its lines, numbered from 1 on each side. The words that
+/// changed in a line are given as character offsets into its text.
+fn hunk_json(diff: &TextDiff, hunk: &Hunk) -> Value {
+    let lines: Vec<Value> = hunk
+        .lines
+        .clone()
+        .map(|index| {
+            let line = &diff.lines[index];
+            let text = diff.text(index);
+            let column = |byte: usize| text[..byte].chars().count();
+            let changed: Vec<Value> = line
+                .changed
+                .iter()
+                .map(|range| {
+                    json!({
+                        "start": column(range.start),
+                        "end": column(range.end),
+                        "text": &text[range.clone()],
+                    })
+                })
+                .collect();
+            json!({
+                "kind": line.kind,
+                "old_line": line.old.map(|line| line + 1),
+                "new_line": line.new.map(|line| line + 1),
+                "text": text,
+                "changed": changed,
+            })
+        })
+        .collect();
+    json!({
+        "old_start": hunk.old_start,
+        "old_lines": hunk.old_lines,
+        "new_start": hunk.new_start,
+        "new_lines": hunk.new_lines,
+        "lines": lines,
+    })
+}
+
+/// An image due to be written, numbered as in the document so a file name
+/// means the same thing whether or not copies were left out.
+struct ImageToWrite<'a> {
+    index: usize,
+    page: Option<usize>,
+    image: &'a DynamicImage,
+    original: Option<Original<'a>>,
+}
+
+/// An image's own encoded form, for `--original-bytes`.
+enum Original<'a> {
+    /// A JPEG stream from inside a PDF.
+    Jpeg(&'a [u8]),
+    /// The image file that was opened.
+    File(&'a Path),
+}
+
+impl Original<'_> {
+    fn extension(&self) -> String {
+        match self {
+            Original::Jpeg(_) => "jpg".to_string(),
+            Original::File(path) => path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "img".to_string()),
+        }
+    }
+}
+
+/// Every copy of every PDF image, since the Media list folds copies together,
+/// or only the first of each when `unique` is set; then only those on the
+/// requested pages and at least the minimum size.
+fn images_to_write<'a>(loaded: &'a LoadedFile, request: &'a HeadlessRequest) -> Vec<ImageToWrite<'a>> {
+    let export = &request.image_export;
+    let images: Vec<ImageToWrite> = match loaded.get::<PdfDocument>() {
+        Some(pdf) => pdf
+            .images
+            .iter()
+            .enumerate()
+            .filter(|(_, asset)| !export.unique || asset.duplicate_of.is_none())
+            .map(|(index, asset)| ImageToWrite {
+                index,
+                page: Some(asset.page),
+                image: &asset.image,
+                original: asset.original.as_deref().map(Original::Jpeg),
+            })
+            .collect(),
+        None => loaded
+            .media
+            .iter()
+            .enumerate()
+            .map(|(index, item)| ImageToWrite {
+                index,
+                page: item.page,
+                image: &item.image,
+                original: Some(Original::File(Path::new(&request.file))),
+            })
+            .collect(),
+    };
+
+    let (min_width, min_height) = export.min_size;
+    images
+        .into_iter()
+        .filter(|item| match (&request.page_range, item.page) {
+            (Some(range), Some(page)) => range.contains(&page),
+            _ => true,
+        })
+        .filter(|item| item.image.width() >= min_width && item.image.height() >= min_height)
+        .collect()
+}
+
 fn write_images(
-    media: &[MediaItem],
+    images: &[ImageToWrite],
+    request: &HeadlessRequest,
     directory: &Path,
 ) -> Result<Vec<WrittenImage>, Box<dyn Error>> {
     fs::create_dir_all(directory)?;
+    let export = &request.image_export;
 
-    media
+    images
         .iter()
-        .enumerate()
-        .map(|(index, item)| {
-            let path = directory.join(image_file_name(item, index));
-            // Always PNG: the images arrive already decoded to pixels, so
-            // re-encoding as JPEG would lose data for no gain, and PNG keeps
-            // the alpha that /SMask transparency produced.
-            item.image.save(&path)?;
+        .map(|item| {
+            let original = item.original.as_ref().filter(|_| export.original);
+            let extension = match original {
+                Some(original) => original.extension(),
+                None => export.format.extension().to_string(),
+            };
+            let path = directory.join(image_file_name(item, request, &extension));
+            match original {
+                Some(Original::Jpeg(bytes)) => fs::write(&path, bytes)?,
+                Some(Original::File(source)) => {
+                    fs::copy(source, &path)?;
+                }
+                None => export.format.save(item.image, &path)?,
+            }
 
             Ok(WrittenImage {
+                index: item.index,
                 path,
                 page: item.page,
                 width: item.image.width(),
                 height: item.image.height(),
+                format: extension,
+                original: original.is_some(),
             })
         })
         .collect()
 }
 
-fn image_file_name(item: &MediaItem, index: usize) -> String {
-    match item.page {
-        Some(page) => format!("page-{page:03}-img-{:02}.png", index + 1),
-        None => format!("image-{:02}.png", index + 1),
-    }
+fn image_file_name(item: &ImageToWrite, request: &HeadlessRequest, extension: &str) -> String {
+    let template = match (&request.image_export.name_template, item.page) {
+        (Some(template), _) => template.as_str(),
+        (None, Some(_)) => "page-{page}-img-{index}.{ext}",
+        (None, None) => "image-{index}.{ext}",
+    };
+    let name = Path::new(&request.file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    template
+        .replace("{name}", &name)
+        .replace("{page}", &format!("{:03}", item.page.unwrap_or(0)))
+        .replace("{index}", &format!("{:02}", item.index + 1))
+        .replace("{width}", &item.image.width().to_string())
+        .replace("{height}", &item.image.height().to_string())
+        .replace("{ext}", extension)
+}
+
+/// Where each copy of PDF image `source` is drawn, with the resolution it
+/// prints at there. Copies that are never painted are left out.
+fn placements_json(pdf: &PdfDocument, source: usize) -> Value {
+    Value::Array(
+        pdf.images
+            .iter()
+            .enumerate()
+            .filter(|(index, asset)| *index == source || asset.duplicate_of == Some(source))
+            .filter_map(|(_, asset)| {
+                let placement = asset.placement?;
+                let (x, y) = placement.dpi(asset.width, asset.height);
+                let mut value = json!(placement);
+                value["page"] = json!(asset.page);
+                value["dpi"] = json!({ "x": x, "y": y });
+                Some(value)
+            })
+            .collect(),
+    )
+}
+
+/// Matches for `query` in the loaded file: in its cells for a table, where
+/// `options.column` can narrow them to one column, otherwise in its lines.
+fn search_file(
+    loaded: &LoadedFile,
+    query: &str,
+    options: SearchOptions,
+) -> Result<Vec<SearchMatch>, Box<dyn Error>> {
+    let matches = match loaded.get::<Table>() {
+        Some(table) => table.search(query, options),
+        None => search_with_options(query, &loaded.content, options),
+    };
+    matches.map_err(|error| error.into())
 }
 
 fn analysis_json(
     request: &HeadlessRequest,
     loaded: &LoadedFile,
     written: &[WrittenImage],
+    attachments: &[PathBuf],
 ) -> Result<Value, Box<dyn Error>> {
-    let structure = analyze_structure(&loaded.content);
-    let content = analyze_content(&loaded.content, request.keyword_limit);
-    let repeated: Vec<Value> = extract_repeated_lines(&loaded.content, 8)
-        .into_iter()
-        .map(|(line, count)| json!({ "line": line, "count": count }))
-        .collect();
+    // A table is measured by what its text columns say, and source code by
+    // its comments and identifiers, as in the TUI.
+    let text = match (
+        loaded.get::<PdfDocument>(),
+        loaded.get::<Table>(),
+        loaded.get::<SourceCode>(),
+    ) {
+        (Some(pdf), _, _) if request.strip_furniture => pdf.text_without_furniture(),
+        (_, Some(table), _) => table.text_cells(),
+        (_, _, Some(code)) => code.analysis_text(&loaded.content),
+        _ => loaded.content.clone(),
+    };
 
-    let mut document = json!({
-        "file": request.file,
-        "kind": kind(loaded),
-        "language": content.language,
-        "stats": structure,
-        "readability": content.readability,
-        "keywords": content.keywords,
-        "phrases": content.phrases,
-        "repeated_lines": repeated,
-    });
+    let mut document = text_analysis(&text, request.keyword_limit);
+    document["file"] = json!(request.file);
+    document["kind"] = json!(loaded.kind);
+    document["ocr"] = json!(loaded.ocr);
 
     if let Some(notice) = &loaded.notice {
         document["notice"] = json!(notice);
     }
+    if !loaded.outline.is_empty() {
+        document["outline"] = json!(loaded.outline.segments());
+    }
+    if let Some(table) = loaded.get::<Table>() {
+        document["table"] = json!({
+            "delimiter": table.delimiter.to_string(),
+            "rows": table.rows.len(),
+            "columns": table.columns,
+        });
+    }
+    if let Some(tree) = loaded.get::<DataTree>() {
+        document["tree"] = json!({
+            "format": tree.format,
+            "nodes": tree.nodes.len() - 1,
+            "depth": tree.depth(),
+            "keys": tree.children(0).map(|node| tree.path(node)).collect::<Vec<_>>(),
+        });
+    }
+    if let Some(code) = loaded.get::<SourceCode>() {
+        let stats = &code.stats;
+        document["code"] = json!({
+            "language": code.language,
+            "lines": stats.lines,
+            "code_lines": stats.code_lines,
+            "comment_lines": stats.comment_lines,
+            "blank_lines": stats.blank_lines,
+            "comment_ratio": stats.comment_ratio(),
+            "functions": stats.functions.len(),
+            "longest_function": stats.longest_function(),
+        });
+    }
+    if let Some(log) = loaded.get::<LogFile>() {
+        let every_line: Vec<usize> = (0..log.entries.len()).collect();
+        let summary = log.summarize(&every_line);
+        let levels: serde_json::Map<String, Value> = summary
+            .levels
+            .iter()
+            .map(|(level, count)| (level.label().to_lowercase(), json!(count)))
+            .collect();
+        document["log"] = json!({
+            "events": summary.events,
+            "levels": levels,
+            "events_per_minute": summary
+                .per_minute
+                .iter()
+                .map(|(minute, count)| json!({ "minute": minute.to_string(), "count": count }))
+                .collect::<Vec<_>>(),
+        });
+    }
 
-    if let Some(pdf) = &loaded.document {
+    if let Some(pdf) = loaded.get::<PdfDocument>() {
+        document["extraction"] = json!(request.extraction);
+        document["reflow"] = json!(request.reflow);
         document["pages"] = Value::Array(
             pdf.pages
                 .iter()
-                .map(|page| {
+                .enumerate()
+                .map(|(index, page)| {
+                    let text = pdf.pages_text(index..=index, request.strip_furniture);
                     json!({
                         "number": page.number,
                         "start_line": page.start_line,
                         "line_count": page.line_count,
                         "image_count": page.image_count,
+                        "ocr": page.ocr,
+                        "analysis": text_analysis(&text, request.keyword_limit),
                     })
                 })
                 .collect(),
         );
+        if let Some(range) = &request.page_range {
+            let text = pdf.pages_text(page_indices(range), request.strip_furniture);
+            document["page_range"] = json!({
+                "first": range.start(),
+                "last": range.end(),
+                "analysis": text_analysis(&text, request.keyword_limit),
+            });
+        }
         document["skipped_images"] = Value::Array(
             pdf.skipped
                 .iter()
@@ -184,6 +682,30 @@ fn analysis_json(
         if let Some(error) = &pdf.image_error {
             document["image_error"] = json!(error);
         }
+        if let Some(error) = &pdf.ocr_error {
+            document["ocr_error"] = json!(error);
+        }
+        if !pdf.unreadable_pages.is_empty() {
+            document["unreadable_pages"] = json!(pdf.unreadable_pages);
+        }
+        document["annotations"] = json!(pdf.annotations);
+        document["form_fields"] = json!(pdf.form_fields);
+        document["attachments"] = json!(pdf.attachments);
+        let lines: Vec<&str> = pdf.text.lines().collect();
+        document["furniture_excluded"] = json!(request.strip_furniture);
+        document["furniture"] = Value::Array(
+            pdf.furniture
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "line_number": entry.line + 1,
+                        "page": pdf.pages.get(pdf.page_of_line(entry.line)).map(|page| page.number),
+                        "kind": entry.kind,
+                        "text": lines.get(entry.line).copied().unwrap_or_default(),
+                    })
+                })
+                .collect(),
+        );
     }
 
     document["images"] = Value::Array(
@@ -191,15 +713,33 @@ fn analysis_json(
             .media
             .iter()
             .enumerate()
-            .map(|(index, item)| {
-                json!({
+            .map(|(position, item)| {
+                // Numbered as the written files are: by the document's own
+                // image list, copies included, rather than the Media list.
+                let index = item.source.unwrap_or(position);
+                // Every page the picture is on, this copy's included.
+                let pages: Vec<usize> = if item.copies.is_empty() {
+                    item.page.into_iter().collect()
+                } else {
+                    item.copies.clone()
+                };
+                let mut image = json!({
                     "index": index,
                     "title": item.title,
                     "detail": item.detail,
                     "page": item.page,
                     "width": item.image.width(),
                     "height": item.image.height(),
-                })
+                    "copies": item.copies.len().max(1),
+                    "pages": pages,
+                    "metadata": item.metadata,
+                    "analysis": image_stats::analyse(&item.image),
+                });
+                if let (Some(pdf), Some(source)) = (loaded.get::<PdfDocument>(), item.source) {
+                    image["inline"] = json!(pdf.images[source].inline);
+                    image["placements"] = placements_json(pdf, source);
+                }
+                image
             })
             .collect(),
     );
@@ -208,48 +748,96 @@ fn analysis_json(
         document["extracted_images"] = Value::Array(
             written
                 .iter()
-                .map(|image| json!({ "path": image.path, "page": image.page }))
+                .map(|image| {
+                    json!({
+                        "index": image.index,
+                        "path": image.path,
+                        "page": image.page,
+                        "format": image.format,
+                        "original": image.original,
+                    })
+                })
                 .collect(),
         );
     }
+    if !attachments.is_empty() {
+        document["extracted_attachments"] = json!(attachments);
+    }
 
     if let Some(query) = &request.search {
-        let matches = search_with_options(query, &loaded.content, request.search_options)
-            .map_err(|error| -> Box<dyn Error> { error.into() })?;
+        let matches = search_file(loaded, query, request.search_options)?;
 
         document["search"] = json!({
             "query": query,
             "case_sensitive": request.search_options.case_sensitive,
             "regex": request.search_options.regex_mode,
             "whole_word": request.search_options.whole_word,
+            "column": request.search_options.column.map(|column| column + 1),
             "match_count": matches.iter().map(|entry| entry.match_count).sum::<usize>(),
             "matches": matches
                 .iter()
                 .map(|entry| {
                     json!({
                         "line_number": entry.line_number,
-                        // Line numbers are 1-based; page lookup is not.
-                        "page": loaded.document.as_ref().and_then(|pdf| {
-                            let index = pdf.page_of_line(entry.line_number.saturating_sub(1));
-                            pdf.pages.get(index).map(|page| page.number)
-                        }),
+                        // Line numbers are 1-based; outline lookups are not.
+                        "page": page_of(&loaded.outline, entry.line_number.saturating_sub(1)),
+                        "section": loaded
+                            .outline
+                            .innermost_at(entry.line_number.saturating_sub(1))
+                            .filter(|segment| segment.kind != SegmentKind::Page)
+                            .map(|segment| &segment.title),
                         "match_count": entry.match_count,
                         "line": entry.line,
                     })
                 })
                 .collect::<Vec<Value>>(),
         });
+
+        // Notes live outside the text, so their matches are listed apart
+        // rather than being given line numbers they do not have.
+        if let Some(pdf) = loaded.get::<PdfDocument>() {
+            let notes =
+                search_notes(&pdf.annotations, &pdf.form_fields, query, request.search_options)
+                    .map_err(|error| -> Box<dyn Error> { error.into() })?;
+            document["search"]["note_matches"] = json!(notes);
+        }
     }
 
     Ok(document)
 }
 
-fn kind(loaded: &LoadedFile) -> &'static str {
-    if loaded.document.is_some() {
-        "pdf"
-    } else if !loaded.media.is_empty() {
-        "image"
-    } else {
-        "text"
-    }
+/// Stats, language, readability, keywords, phrases and repeated lines: the
+/// same fields for the whole file, each page and a page range.
+fn text_analysis(text: &str, keyword_limit: usize) -> Value {
+    let structure = analyze_structure(text);
+    let content = analyze_content(text, keyword_limit);
+    let repeated: Vec<Value> = extract_repeated_lines(text, 8)
+        .into_iter()
+        .map(|(line, count)| json!({ "line": line, "count": count }))
+        .collect();
+
+    json!({
+        "language": content.language,
+        "stats": structure,
+        "readability": content.readability,
+        "keywords": content.keywords,
+        "phrases": content.phrases,
+        "repeated_lines": repeated,
+    })
 }
+
+/// The page number of 0-based `line`, when the outline has pages.
+fn page_of(outline: &Outline, line: usize) -> Option<usize> {
+    outline
+        .segments()
+        .iter()
+        .filter(|segment| segment.kind == SegmentKind::Page)
+        .rfind(|segment| segment.start_line <= line)
+        .map(|page| page.number)
+}
+
+/// 1-based page numbers to indices into `PdfDocument::pages`.
+fn page_indices(range: &RangeInclusive<usize>) -> RangeInclusive<usize> {
+    range.start() - 1..=range.end() - 1
+}
+
@@ -0,0 +1,199 @@
+//! Perceptual fingerprints for spotting the same picture twice.
+//!
+//! A PDF often embeds its logo once per page as separate objects, so object
+//! identity cannot tell the copies apart from genuinely different images. The
+//! pixels can: two images are taken to be copies when their difference hashes
+//! nearly agree and their shape and overall colour match. Hashing alone is not
+//! enough for flat images, whose gradients all hash to zero whatever colour
+//! they are, nor for scans of one form, whose layout and lighting decide a 9x8
+//! thumbnail whatever was written on them. A match on the small hash is
+//! therefore only a candidate, confirmed by a 16x16 hash and a pixel-by-pixel
+//! comparison of thumbnails.
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+/// Most differing hash bits two copies may have, out of 64. Re-encoding and
+/// rescaling flip a few; a different picture flips dozens.
+const MAX_HASH_DISTANCE: u32 = 6;
+/// Most differing bits in the 16x16 hash, out of 256. A small image redrawn
+/// at twice the resolution flips a couple of dozen along its edges.
+const MAX_FINE_DISTANCE: u32 = 32;
+/// Side of the greyscale thumbnails compared pixel by pixel. At 64 a word
+/// still moves the pixels it covers; at 16 a page of text is a grey wash.
+const THUMBNAIL_SIDE: u32 = 64;
+/// Largest mean per-pixel difference between copies' thumbnails, out of 255.
+/// Copies at other resolutions stay under three and a half; two scans of one
+/// form with different text on them are six or more apart.
+const MAX_PIXEL_DIFFERENCE: f64 = 4.5;
+/// Largest per-channel difference in mean colour between copies.
+const MAX_MEAN_DIFFERENCE: u8 = 24;
+/// Largest relative difference in aspect ratio between copies.
+const MAX_ASPECT_DIFFERENCE: f32 = 0.05;
+
+/// What an image is compared by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint {
+    /// Difference hash: one bit per horizontal neighbour pair in a 9x8
+    /// greyscale thumbnail, set where brightness rises to the right.
+    pub hash: u64,
+    /// The same over a 17x16 thumbnail, row by row.
+    pub fine: [u64; 4],
+    /// Greyscale thumbnail, `THUMBNAIL_SIDE` square.
+    pub thumbnail: Vec<u8>,
+    /// The image's shorter side, up to `THUMBNAIL_SIDE`: how much of the
+    /// thumbnail is picture rather than interpolation.
+    pub detail: u32,
+    pub mean: [u8; 3],
+    pub aspect: f32,
+}
+
+impl Fingerprint {
+    pub fn of(image: &DynamicImage) -> Self {
+        let hash = difference_hash::<1>(image, 8)[0];
+        let fine = difference_hash::<4>(image, 16);
+        let thumbnail = image
+            .resize_exact(THUMBNAIL_SIDE, THUMBNAIL_SIDE, FilterType::Triangle)
+            .to_luma8()
+            .into_raw();
+
+        let rgb = image.to_rgb8();
+        let pixels = u64::from(rgb.width()) * u64::from(rgb.height());
+        let mut sums = [0u64; 3];
+        for pixel in rgb.pixels() {
+            for (sum, channel) in sums.iter_mut().zip(pixel.0) {
+                *sum += u64::from(channel);
+            }
+        }
+        let mean = sums.map(|sum| (sum / pixels.max(1)) as u8);
+
+        Self {
+            hash,
+            fine,
+            thumbnail,
+            detail: image.width().min(image.height()).clamp(1, THUMBNAIL_SIDE),
+            mean,
+            aspect: image.width() as f32 / image.height().max(1) as f32,
+        }
+    }
+
+    /// Whether `other` looks like the same picture. The cheap checks run
+    /// first; most pairs of different pictures fail the 64-bit hash.
+    pub fn matches(&self, other: &Fingerprint) -> bool {
+        (self.hash ^ other.hash).count_ones() <= MAX_HASH_DISTANCE
+            && self
+                .mean
+                .iter()
+                .zip(other.mean)
+                .all(|(a, b)| a.abs_diff(b) <= MAX_MEAN_DIFFERENCE)
+            && (self.aspect - other.aspect).abs()
+                <= MAX_ASPECT_DIFFERENCE * self.aspect.max(other.aspect)
+            && self
+                .fine
+                .iter()
+                .zip(other.fine)
+                .map(|(a, b)| (a ^ b).count_ones())
+                .sum::<u32>()
+                <= MAX_FINE_DISTANCE
+            && self.pixel_difference(other) <= MAX_PIXEL_DIFFERENCE
+    }
+
+    /// Mean absolute difference between the two thumbnails, out of 255.
+    ///
+    /// Compared in blocks no finer than the smaller image has pixels for: a
+    /// thumbnail blown up from a small image is interpolation, and two sizes
+    /// of one picture interpolate differently along every edge.
+    fn pixel_difference(&self, other: &Fingerprint) -> f64 {
+        let detail = self.detail.min(other.detail);
+        let block = THUMBNAIL_SIDE.div_ceil(detail).next_power_of_two();
+        let blocks = THUMBNAIL_SIDE / block;
+        let sum = |thumbnail: &[u8], x: u32, y: u32| -> i64 {
+            (0..block * block)
+                .map(|offset| {
+                    let (dx, dy) = (offset % block, offset / block);
+                    i64::from(thumbnail[((y * block + dy) * THUMBNAIL_SIDE + x * block + dx) as usize])
+                })
+                .sum()
+        };
+        let total: i64 = (0..blocks * blocks)
+            .map(|index| {
+                let (x, y) = (index % blocks, index / blocks);
+                (sum(&self.thumbnail, x, y) - sum(&other.thumbnail, x, y)).abs()
+            })
+            .sum();
+        total as f64 / f64::from(block * block) / f64::from(blocks * blocks)
+    }
+}
+
+/// Difference hash over a `(side + 1) x side` greyscale thumbnail, packed
+/// into `WORDS` 64-bit words; `side * side` must be `64 * WORDS`.
+fn difference_hash<const WORDS: usize>(image: &DynamicImage, side: u32) -> [u64; WORDS] {
+    let grey = image.resize_exact(side + 1, side, FilterType::Triangle).to_luma8();
+    let mut words = [0u64; WORDS];
+    for y in 0..side {
+        for x in 0..side {
+            let bit = (y * side + x) as usize;
+            let rising = grey.get_pixel(x + 1, y)[0] > grey.get_pixel(x, y)[0];
+            words[bit / 64] = (words[bit / 64] << 1) | u64::from(rising);
+        }
+    }
+    words
+}
+
+/// For each fingerprint, the index of the first earlier one it matches, or
+/// `None` for the first of its kind. Copies always point at a first copy, never
+/// at another copy, so a group is everything pointing at the same index.
+pub fn duplicates(fingerprints: &[Fingerprint]) -> Vec<Option<usize>> {
+    let mut firsts: Vec<usize> = Vec::new();
+    fingerprints
+        .iter()
+        .enumerate()
+        .map(|(index, fingerprint)| {
+            let original = firsts
+                .iter()
+                .copied()
+                .find(|first| fingerprints[*first].matches(fingerprint));
+            if original.is_none() {
+                firsts.push(index);
+            }
+            original
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn logo(width: u32, height: u32, tint: u8) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let band = (x * 4 / width + y * 3 / height) as u8;
+            Rgb([band * 40, 200 - band * 30, tint])
+        }))
+    }
+
+    #[test]
+    fn rescaled_copies_match_and_other_pictures_do_not() {
+        let original = Fingerprint::of(&logo(64, 32, 10));
+        let rescaled = Fingerprint::of(&logo(128, 64, 12));
+        let stretched = Fingerprint::of(&logo(64, 64, 10));
+        let recoloured = Fingerprint::of(&logo(64, 32, 120));
+
+        assert!(original.matches(&rescaled));
+        assert!(!original.matches(&stretched), "a different shape");
+        assert!(!original.matches(&recoloured), "a different colour");
+    }
+
+    #[test]
+    fn flat_images_are_told_apart_by_colour() {
+        let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([255, 0, 0])));
+        let blue = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([0, 0, 255])));
+        let fingerprints = [
+            Fingerprint::of(&red),
+            Fingerprint::of(&blue),
+            Fingerprint::of(&red),
+        ];
+        assert_eq!(duplicates(&fingerprints), [None, None, Some(0)]);
+    }
+}
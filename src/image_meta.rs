@@ -0,0 +1,496 @@
+//! What a photo says about itself: camera, exposure, when and where it was
+//! taken, which way up it belongs and the colour profile it was saved in.
+//!
+//! The `image` crate hands over the raw EXIF, XMP and ICC blocks but does not
+//! read them. EXIF is a small TIFF directory and is walked here directly; XMP
+//! is XML, of which only a handful of well-known properties are picked out; an
+//! ICC profile is reduced to the description it carries.
+
+use image::metadata::Orientation;
+use image::ImageDecoder;
+use regex::Regex;
+use serde::Serialize;
+
+const TAG_MAKE: u16 = 0x010f;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_SOFTWARE: u16 = 0x0131;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_EXPOSURE_TIME: u16 = 0x829a;
+const TAG_F_NUMBER: u16 = 0x829d;
+const TAG_ISO: u16 = 0x8827;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_FOCAL_LENGTH: u16 = 0x920a;
+const TAG_LENS_MODEL: u16 = 0xa434;
+const TAG_GPS_LATITUDE_REF: u16 = 1;
+const TAG_GPS_LATITUDE: u16 = 2;
+const TAG_GPS_LONGITUDE_REF: u16 = 3;
+const TAG_GPS_LONGITUDE: u16 = 4;
+const TAG_GPS_ALTITUDE_REF: u16 = 5;
+const TAG_GPS_ALTITUDE: u16 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Gps {
+    /// Degrees, north positive.
+    pub latitude: f64,
+    /// Degrees, east positive.
+    pub longitude: f64,
+    /// Metres above sea level.
+    pub altitude: Option<f64>,
+}
+
+/// Everything worth showing from a photo's embedded metadata. Fields the file
+/// does not carry stay `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImageMetadata {
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    /// "1/125 s · f/2.8 · ISO 200 · 35 mm", from whichever of those are set.
+    pub exposure: Option<String>,
+    /// When the picture was taken, as `YYYY-MM-DD HH:MM:SS`, or the file's
+    /// own timestamp when the capture time is missing.
+    pub taken: Option<String>,
+    pub gps: Option<Gps>,
+    /// EXIF orientation, 1 to 8; 1 is upright.
+    pub orientation: Option<u8>,
+    pub software: Option<String>,
+    pub title: Option<String>,
+    pub creator: Option<String>,
+    /// The embedded ICC profile's description, such as "Display P3".
+    pub color_profile: Option<String>,
+}
+
+impl ImageMetadata {
+    /// Read every block `decoder` exposes. Call before decoding the pixels,
+    /// which consumes the decoder.
+    pub fn read(decoder: &mut impl ImageDecoder) -> Self {
+        let mut metadata = decoder
+            .exif_metadata()
+            .ok()
+            .flatten()
+            .map(|exif| Self::from_exif(&exif))
+            .unwrap_or_default();
+        if let Some(xmp) = decoder.xmp_metadata().ok().flatten() {
+            metadata.fill_from_xmp(&String::from_utf8_lossy(&xmp));
+        }
+        metadata.color_profile = decoder.icc_profile().ok().flatten().map(|profile| {
+            icc_description(&profile).unwrap_or_else(|| "embedded ICC profile".to_string())
+        });
+        metadata
+    }
+
+    /// The fields of a raw EXIF block: a TIFF header and its directories.
+    pub fn from_exif(chunk: &[u8]) -> Self {
+        let Some(tiff) = Tiff::new(chunk) else {
+            return Self::default();
+        };
+        let ifd0 = tiff.first_directory();
+        let exif = tiff.sub_directory(&ifd0, TAG_EXIF_IFD);
+        let gps = tiff.sub_directory(&ifd0, TAG_GPS_IFD);
+        let ascii = |entries: &[Entry], tag| find(entries, tag).and_then(|entry| tiff.ascii(entry));
+        let rational = |entries: &[Entry], tag| {
+            find(entries, tag).and_then(|entry| tiff.rationals(entry).first().copied())
+        };
+
+        let camera = match (ascii(&ifd0, TAG_MAKE), ascii(&ifd0, TAG_MODEL)) {
+            // Models usually repeat the make: "Canon" + "Canon EOS R6".
+            (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+            (Some(make), Some(model)) => Some(format!("{make} {model}")),
+            (make, model) => make.or(model),
+        };
+
+        let mut exposure = Vec::new();
+        if let Some(seconds) = rational(&exif, TAG_EXPOSURE_TIME).filter(|value| *value > 0.0) {
+            exposure.push(if seconds < 1.0 {
+                format!("1/{:.0} s", 1.0 / seconds)
+            } else {
+                format!("{seconds} s")
+            });
+        }
+        if let Some(aperture) = rational(&exif, TAG_F_NUMBER) {
+            exposure.push(format!("f/{aperture:.1}").replace(".0", ""));
+        }
+        if let Some(iso) = find(&exif, TAG_ISO).and_then(|entry| tiff.unsigned(entry)) {
+            exposure.push(format!("ISO {iso}"));
+        }
+        if let Some(focal) = rational(&exif, TAG_FOCAL_LENGTH) {
+            exposure.push(format!("{focal:.0} mm"));
+        }
+
+        let taken = ascii(&exif, TAG_DATE_TIME_ORIGINAL)
+            .or_else(|| ascii(&ifd0, TAG_DATE_TIME))
+            .map(|stamp| exif_date(&stamp));
+
+        Self {
+            camera,
+            lens: ascii(&exif, TAG_LENS_MODEL),
+            exposure: (!exposure.is_empty()).then(|| exposure.join(" · ")),
+            taken,
+            gps: tiff.gps(&gps),
+            orientation: find(&ifd0, TAG_ORIENTATION)
+                .and_then(|entry| tiff.unsigned(entry))
+                .and_then(|value| u8::try_from(value).ok())
+                .filter(|value| (1..=8).contains(value)),
+            software: ascii(&ifd0, TAG_SOFTWARE),
+            ..Self::default()
+        }
+    }
+
+    /// Take from XMP what EXIF left unset.
+    fn fill_from_xmp(&mut self, xmp: &str) {
+        let property = |names: &[&str]| names.iter().find_map(|name| xmp_property(xmp, name));
+        if self.taken.is_none() {
+            self.taken = property(&[
+                "exif:DateTimeOriginal",
+                "photoshop:DateCreated",
+                "xmp:CreateDate",
+            ])
+            .map(|stamp| stamp.replacen('T', " ", 1));
+        }
+        if self.software.is_none() {
+            self.software = property(&["xmp:CreatorTool"]);
+        }
+        self.title = property(&["dc:title"]);
+        self.creator = property(&["dc:creator"]);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// How to turn the stored pixels upright, when they are not already.
+    pub fn upright(&self) -> Option<Orientation> {
+        self.orientation
+            .filter(|value| *value != 1)
+            .and_then(Orientation::from_exif)
+    }
+
+    /// Label and value for every field that is set, in display order.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = Vec::new();
+        let mut add = |label, value: &Option<String>| {
+            if let Some(value) = value {
+                rows.push((label, value.clone()));
+            }
+        };
+        add("Title", &self.title);
+        add("Creator", &self.creator);
+        add("Camera", &self.camera);
+        add("Lens", &self.lens);
+        add("Exposure", &self.exposure);
+        add("Taken", &self.taken);
+        add("Location", &self.gps.map(|gps| gps.label()));
+        add("Orientation", &self.orientation.map(orientation_label));
+        add("Software", &self.software);
+        add("Colour", &self.color_profile);
+        rows
+    }
+}
+
+impl Gps {
+    /// "52.37303° N, 4.89245° E, 12 m".
+    pub fn label(&self) -> String {
+        let mut label = format!(
+            "{:.5}° {}, {:.5}° {}",
+            self.latitude.abs(),
+            if self.latitude < 0.0 { 'S' } else { 'N' },
+            self.longitude.abs(),
+            if self.longitude < 0.0 { 'W' } else { 'E' },
+        );
+        if let Some(altitude) = self.altitude {
+            label.push_str(&format!(", {altitude:.0} m"));
+        }
+        label
+    }
+}
+
+fn orientation_label(value: u8) -> String {
+    match value {
+        1 => "upright",
+        2 => "mirrored",
+        3 => "upside down",
+        4 => "flipped vertically",
+        5 => "mirrored, turned left",
+        6 => "turned right",
+        7 => "mirrored, turned right",
+        8 => "turned left",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+/// EXIF writes dates as `2024:05:01 10:22:03`.
+fn exif_date(stamp: &str) -> String {
+    match stamp.split_once(' ') {
+        Some((date, time)) => format!("{} {time}", date.replace(':', "-")),
+        None => stamp.replace(':', "-"),
+    }
+}
+
+/// The text of an XMP property, written either as an attribute or as an
+/// element, looking inside `rdf:Alt`/`rdf:Seq` lists for their first item.
+fn xmp_property(xmp: &str, name: &str) -> Option<String> {
+    let name = regex::escape(name);
+    let attribute = Regex::new(&format!(r#"{name}\s*=\s*"([^"]*)""#)).ok()?;
+    let element = Regex::new(&format!(r"(?s)<{name}[^>]*>(.*?)</{name}>")).ok()?;
+    let item = Regex::new(r"(?s)<rdf:li[^>]*>(.*?)</rdf:li>").expect("static regex");
+
+    let raw = match attribute.captures(xmp) {
+        Some(captures) => captures[1].to_string(),
+        None => {
+            let body = element.captures(xmp)?[1].to_string();
+            match item.captures(&body) {
+                Some(captures) => captures[1].to_string(),
+                None => body,
+            }
+        }
+    };
+    let value = raw
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// The `desc` tag of an ICC profile, in either the version 2 `desc` or the
+/// version 4 `mluc` encoding.
+fn icc_description(profile: &[u8]) -> Option<String> {
+    let be_u32 = |offset: usize| {
+        profile
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    let tags = be_u32(128)?;
+    let (offset, size) = (0..tags.min(256)).find_map(|index| {
+        let entry = 132 + index * 12;
+        (profile.get(entry..entry + 4)? == b"desc")
+            .then(|| Some((be_u32(entry + 4)?, be_u32(entry + 8)?)))?
+    })?;
+    let tag = profile.get(offset..offset.checked_add(size)?)?;
+
+    let text = match tag.get(0..4)? {
+        b"desc" => {
+            let length = u32::from_be_bytes(tag.get(8..12)?.try_into().ok()?) as usize;
+            String::from_utf8_lossy(tag.get(12..12 + length)?).to_string()
+        }
+        b"mluc" => {
+            let record_length = u32::from_be_bytes(tag.get(20..24)?.try_into().ok()?) as usize;
+            let record_offset = u32::from_be_bytes(tag.get(24..28)?.try_into().ok()?) as usize;
+            let units: Vec<u16> = tag
+                .get(record_offset..record_offset + record_length)?
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// One directory entry: what it is and where its value sits.
+#[derive(Clone, Copy)]
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    /// Offset of the value in the block: inside the entry when it fits in four
+    /// bytes, elsewhere otherwise.
+    value: usize,
+}
+
+fn find(entries: &[Entry], tag: u16) -> Option<&Entry> {
+    entries.iter().find(|entry| entry.tag == tag)
+}
+
+/// Read-only view of a TIFF-structured block, bounds-checked throughout since
+/// the offsets come from the file.
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(chunk: &'a [u8]) -> Option<Self> {
+        // Some writers keep the JPEG APP1 "Exif\0\0" prefix.
+        let data = chunk.strip_prefix(b"Exif\0\0").unwrap_or(chunk);
+        let big_endian = match data.get(0..4)? {
+            [b'I', b'I', 42, 0] => false,
+            [b'M', b'M', 0, 42] => true,
+            _ => return None,
+        };
+        Some(Self { data, big_endian })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn first_directory(&self) -> Vec<Entry> {
+        self.u32(4)
+            .map(|offset| self.directory(offset as usize))
+            .unwrap_or_default()
+    }
+
+    fn sub_directory(&self, entries: &[Entry], tag: u16) -> Vec<Entry> {
+        find(entries, tag)
+            .and_then(|entry| self.unsigned(entry))
+            .map(|offset| self.directory(offset as usize))
+            .unwrap_or_default()
+    }
+
+    fn directory(&self, offset: usize) -> Vec<Entry> {
+        let Some(count) = self.u16(offset) else {
+            return Vec::new();
+        };
+        (0..usize::from(count))
+            .map_while(|index| {
+                let at = offset + 2 + index * 12;
+                let kind = self.u16(at + 2)?;
+                let count = self.u32(at + 4)? as usize;
+                let size = count.checked_mul(type_size(kind))?;
+                let value = if size <= 4 {
+                    at + 8
+                } else {
+                    self.u32(at + 8)? as usize
+                };
+                Some(Entry {
+                    tag: self.u16(at)?,
+                    kind,
+                    count,
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    fn ascii(&self, entry: &Entry) -> Option<String> {
+        let bytes = self
+            .data
+            .get(entry.value..entry.value.checked_add(entry.count)?)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// A BYTE, SHORT or LONG value.
+    fn unsigned(&self, entry: &Entry) -> Option<u32> {
+        match entry.kind {
+            1 => self.data.get(entry.value).map(|byte| u32::from(*byte)),
+            3 => self.u16(entry.value).map(u32::from),
+            4 => self.u32(entry.value),
+            _ => None,
+        }
+    }
+
+    fn rationals(&self, entry: &Entry) -> Vec<f64> {
+        if entry.kind != 5 && entry.kind != 10 {
+            return Vec::new();
+        }
+        (0..entry.count.min(16))
+            .map_while(|index| {
+                let at = entry.value + index * 8;
+                let (numerator, denominator) = (self.u32(at)?, self.u32(at + 4)?);
+                let value = if entry.kind == 10 {
+                    f64::from(numerator as i32) / f64::from(denominator as i32)
+                } else {
+                    f64::from(numerator) / f64::from(denominator)
+                };
+                value.is_finite().then_some(value)
+            })
+            .collect()
+    }
+
+    fn gps(&self, entries: &[Entry]) -> Option<Gps> {
+        let coordinate = |tag, reference, negative: char| {
+            let parts = self.rationals(find(entries, tag)?);
+            let [degrees, minutes, seconds] = parts.get(0..3)?.try_into().ok()?;
+            let value: f64 = degrees + minutes / 60.0 + seconds / 3600.0;
+            let sign = find(entries, reference)
+                .and_then(|entry| self.ascii(entry))
+                .is_some_and(|text| text.starts_with(negative));
+            Some(if sign { -value } else { value })
+        };
+        let altitude = find(entries, TAG_GPS_ALTITUDE)
+            .and_then(|entry| self.rationals(entry).first().copied())
+            .map(|metres| {
+                let below = find(entries, TAG_GPS_ALTITUDE_REF)
+                    .and_then(|entry| self.unsigned(entry))
+                    == Some(1);
+                if below {
+                    -metres
+                } else {
+                    metres
+                }
+            });
+        Some(Gps {
+            latitude: coordinate(TAG_GPS_LATITUDE, TAG_GPS_LATITUDE_REF, 'S')?,
+            longitude: coordinate(TAG_GPS_LONGITUDE, TAG_GPS_LONGITUDE_REF, 'W')?,
+            altitude,
+        })
+    }
+}
+
+/// Bytes per value of a TIFF field type.
+fn type_size(kind: u16) -> usize {
+    match kind {
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xmp_fills_what_exif_left_out() {
+        let xmp = r#"<x:xmpmeta><rdf:RDF><rdf:Description xmp:CreatorTool="Darktable 4.6"
+            xmp:CreateDate="2023-08-14T18:02:11">
+            <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Harbour at dusk</rdf:li></rdf:Alt></dc:title>
+            <dc:creator><rdf:Seq><rdf:li>A. Fotograaf &amp; Co</rdf:li></rdf:Seq></dc:creator>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#;
+        let mut metadata = ImageMetadata {
+            software: Some("Camera firmware 1.2".to_string()),
+            ..ImageMetadata::default()
+        };
+        metadata.fill_from_xmp(xmp);
+
+        assert_eq!(metadata.title.as_deref(), Some("Harbour at dusk"));
+        assert_eq!(metadata.creator.as_deref(), Some("A. Fotograaf & Co"));
+        assert_eq!(metadata.taken.as_deref(), Some("2023-08-14 18:02:11"));
+        assert_eq!(
+            metadata.software.as_deref(),
+            Some("Camera firmware 1.2"),
+            "EXIF wins"
+        );
+    }
+
+    #[test]
+    fn truncated_exif_reads_as_nothing_rather_than_panicking() {
+        assert!(ImageMetadata::from_exif(b"II*\0\x08\0\0\0\x05\0\x0f\x01\x02\0").is_empty());
+        assert!(ImageMetadata::from_exif(b"not exif").is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+//! Numbers that describe a picture rather than a text: how its channels are
+//! distributed, which few colours make it up, how bright it is, how much of it
+//! is see-through and whether it is really a greyscale image stored as colour.
+
+use image::DynamicImage;
+use serde::Serialize;
+
+/// Buckets per channel histogram; eight levels each.
+pub const HISTOGRAM_BINS: usize = 32;
+/// Colours the palette is reduced to before small ones are dropped.
+const PALETTE_SIZE: usize = 5;
+/// Share of the picture below which a palette colour is left out.
+const MIN_PALETTE_SHARE: f64 = 0.01;
+/// Pixels k-means looks at; evenly spread, so large scans cost no more.
+const PALETTE_SAMPLES: usize = 4096;
+const KMEANS_ROUNDS: usize = 12;
+/// Largest difference between a pixel's channels that still reads as grey.
+/// JPEG noise alone puts a few levels between them.
+const GREY_TOLERANCE: u8 = 12;
+/// Share of pixels that must be grey for the image to count as greyscale.
+const GREY_SHARE: f64 = 0.99;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Histogram {
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PaletteColor {
+    pub rgb: [u8; 3],
+    /// `#rrggbb`.
+    pub hex: String,
+    /// Share of the visible pixels nearest this colour, 0 to 1.
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageStats {
+    pub histogram: Histogram,
+    /// Mean luma, 0 (black) to 255 (white).
+    pub mean_brightness: f64,
+    /// Standard deviation of luma: low for flat images, high for contrasty ones.
+    pub contrast: f64,
+    pub grayscale: bool,
+    /// Share of pixels that are not fully opaque, for images with an alpha
+    /// channel (a PNG's own, or a PDF image's `/SMask`); `None` otherwise.
+    pub transparency: Option<f64>,
+    /// Dominant colours, largest share first.
+    pub palette: Vec<PaletteColor>,
+}
+
+/// Measure `image`. Fully transparent pixels count towards transparency only;
+/// their colour is whatever the encoder left there and says nothing.
+pub fn analyse(image: &DynamicImage) -> ImageStats {
+    let rgba = image.to_rgba8();
+    let mut histogram = [[0u32; HISTOGRAM_BINS]; 3];
+    let (mut luma_sum, mut luma_squares) = (0f64, 0f64);
+    let (mut visible, mut translucent, mut grey) = (0u64, 0u64, 0u64);
+
+    for pixel in rgba.pixels() {
+        let [red, green, blue, alpha] = pixel.0;
+        if alpha < u8::MAX {
+            translucent += 1;
+        }
+        if alpha == 0 {
+            continue;
+        }
+        visible += 1;
+        for (channel, value) in histogram.iter_mut().zip([red, green, blue]) {
+            channel[usize::from(value) * HISTOGRAM_BINS / 256] += 1;
+        }
+        let luma = luma([red, green, blue]);
+        luma_sum += luma;
+        luma_squares += luma * luma;
+        let (low, high) = (red.min(green).min(blue), red.max(green).max(blue));
+        if high - low <= GREY_TOLERANCE {
+            grey += 1;
+        }
+    }
+
+    let pixels = u64::from(rgba.width()) * u64::from(rgba.height());
+    let count = visible.max(1) as f64;
+    let mean = luma_sum / count;
+    let [red, green, blue] = histogram.map(|channel| channel.to_vec());
+
+    ImageStats {
+        histogram: Histogram { red, green, blue },
+        mean_brightness: mean,
+        contrast: (luma_squares / count - mean * mean).max(0.0).sqrt(),
+        grayscale: visible > 0 && grey as f64 >= visible as f64 * GREY_SHARE,
+        transparency: image
+            .color()
+            .has_alpha()
+            .then(|| translucent as f64 / pixels.max(1) as f64),
+        palette: palette(&rgba),
+    }
+}
+
+fn luma([red, green, blue]: [u8; 3]) -> f64 {
+    0.299 * f64::from(red) + 0.587 * f64::from(green) + 0.114 * f64::from(blue)
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// The dominant colours by k-means over a spread-out sample of the visible
+/// pixels. Seeding is deterministic, so the same image always gives the same
+/// palette: the first centre is the sample nearest the mean colour, each next
+/// one the sample farthest from every centre so far.
+fn palette(rgba: &image::RgbaImage) -> Vec<PaletteColor> {
+    let stride = (rgba.pixels().len() / PALETTE_SAMPLES).max(1);
+    let opaque: Vec<[f64; 3]> = rgba
+        .pixels()
+        .step_by(stride)
+        .filter(|pixel| pixel.0[3] > 0)
+        .map(|pixel| {
+            let [red, green, blue, _] = pixel.0;
+            [f64::from(red), f64::from(green), f64::from(blue)]
+        })
+        .collect();
+    if opaque.is_empty() {
+        return Vec::new();
+    }
+
+    let mean = opaque.iter().fold([0.0; 3], |sum, sample| {
+        [sum[0] + sample[0], sum[1] + sample[1], sum[2] + sample[2]]
+    });
+    let mean = mean.map(|channel| channel / opaque.len() as f64);
+    let nearest_to = |target: [f64; 3]| {
+        opaque
+            .iter()
+            .copied()
+            .min_by(|a, b| distance(*a, target).total_cmp(&distance(*b, target)))
+    };
+    let mut centres = vec![nearest_to(mean).unwrap_or(mean)];
+    while centres.len() < PALETTE_SIZE {
+        let farthest = opaque.iter().copied().max_by(|a, b| {
+            let gap = |sample: &[f64; 3]| {
+                centres
+                    .iter()
+                    .map(|centre| distance(*sample, *centre))
+                    .fold(f64::INFINITY, f64::min)
+            };
+            gap(a).total_cmp(&gap(b))
+        });
+        match farthest {
+            // Fewer distinct colours than centres: stop at what there is.
+            Some(sample) if !centres.contains(&sample) => centres.push(sample),
+            _ => break,
+        }
+    }
+
+    let closest = |sample: &[f64; 3], centres: &[[f64; 3]]| {
+        (0..centres.len())
+            .min_by(|a, b| {
+                distance(*sample, centres[*a]).total_cmp(&distance(*sample, centres[*b]))
+            })
+            .unwrap_or(0)
+    };
+    let mut members = vec![0usize; centres.len()];
+    for _ in 0..KMEANS_ROUNDS {
+        let mut sums = vec![[0f64; 3]; centres.len()];
+        members = vec![0; centres.len()];
+        for sample in &opaque {
+            let index = closest(sample, &centres);
+            members[index] += 1;
+            for (sum, value) in sums[index].iter_mut().zip(sample) {
+                *sum += value;
+            }
+        }
+        let moved: Vec<[f64; 3]> = sums
+            .iter()
+            .zip(&members)
+            .zip(&centres)
+            .map(|((sum, count), centre)| match count {
+                0 => *centre,
+                count => sum.map(|channel| channel / *count as f64),
+            })
+            .collect();
+        let settled = moved == centres;
+        centres = moved;
+        if settled {
+            break;
+        }
+    }
+
+    let mut colors: Vec<PaletteColor> = centres
+        .iter()
+        .zip(&members)
+        .map(|(centre, count)| {
+            let rgb = centre.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+            PaletteColor {
+                rgb,
+                hex: format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]),
+                share: *count as f64 / opaque.len() as f64,
+            }
+        })
+        .filter(|color| color.share >= MIN_PALETTE_SHARE)
+        .collect();
+    colors.sort_by(|a, b| b.share.total_cmp(&a.share));
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    #[test]
+    fn a_flag_reduces_to_its_colours_in_proportion() {
+        // Three quarters blue, one quarter yellow.
+        let flag = DynamicImage::ImageRgb8(RgbImage::from_fn(40, 20, |x, _| {
+            if x < 30 {
+                Rgb([0, 87, 183])
+            } else {
+                Rgb([255, 215, 0])
+            }
+        }));
+        let stats = analyse(&flag);
+
+        assert_eq!(stats.palette.len(), 2);
+        assert_eq!(stats.palette[0].hex, "#0057b7");
+        assert!((stats.palette[0].share - 0.75).abs() < 0.01);
+        assert_eq!(stats.palette[1].rgb, [255, 215, 0]);
+        assert!(!stats.grayscale);
+        assert_eq!(stats.transparency, None);
+        assert_eq!(stats.histogram.red.iter().sum::<u32>(), 800);
+        assert_eq!(stats.histogram.red[HISTOGRAM_BINS - 1], 200);
+    }
+
+    #[test]
+    fn grey_pixels_stored_as_colour_read_as_greyscale() {
+        let scan = DynamicImage::ImageRgba8(RgbaImage::from_fn(10, 10, |x, y| {
+            let level = (x * 25) as u8;
+            Rgba([
+                level,
+                level.saturating_add(3),
+                level,
+                if y < 5 { 255 } else { 0 },
+            ])
+        }));
+        let stats = analyse(&scan);
+
+        assert!(stats.grayscale);
+        assert_eq!(stats.transparency, Some(0.5));
+        assert_eq!(
+            stats.histogram.green.iter().sum::<u32>(),
+            50,
+            "only visible pixels"
+        );
+    }
+}
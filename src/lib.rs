@@ -2,11 +2,34 @@
 // and is compiled exactly once.
 pub mod app;
 pub mod app_structs;
+pub mod cli_values;
+pub mod data_tree;
 pub mod file_utils;
 pub mod headless;
+pub mod image_hash;
+pub mod image_meta;
+pub mod image_stats;
+pub mod loader;
+pub mod log_file;
 pub mod media;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+pub mod outline;
+pub mod pdf_annotations;
+pub mod pdf_attachments;
+pub mod pdf_bookmarks;
 pub mod pdf_doc;
+pub mod pdf_furniture;
+pub mod pdf_glyphs;
+pub mod pdf_layout;
+pub mod pdf_placement;
+pub mod pdf_reflow;
 pub mod settings;
+pub mod source_code;
 pub mod stopwords;
+pub mod table;
 pub mod text_analysis;
+pub mod text_compare;
+pub mod text_diff;
+pub mod text_headings;
 pub mod ui_components;
@@ -0,0 +1,171 @@
+//! File formats as plug-ins.
+//!
+//! Every format flerp opens is a [`Loader`]: it recognises files it can read
+//! and turns one into a [`LoadedFile`]. A [`LoaderRegistry`] asks each in turn,
+//! so a crate that depends on flerp can add a format of its own without
+//! touching this one, and the built-in PDF, image and text loaders are
+//! registered the same way.
+//!
+//! Neither side has to know every format: options for one format, and what a
+//! loader learned about its file beyond text, travel as [`Extensions`], found
+//! by their type.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::data_tree::DataFormat;
+use crate::file_utils::{
+    CodeLoader, ImageLoader, LoadedFile, LogLoader, PdfLoader, StructuredLoader, TableLoader,
+    TextLoader,
+};
+use crate::pdf_doc::PdfDocument;
+
+/// How much of a file [`Loader::sniff`] is shown.
+pub const SNIFF_BYTES: usize = 512;
+
+/// Values of any type, at most one of each, looked up by their type.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Keep `value`, in place of any earlier value of its type.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.values.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    /// Remove the value of type `T`, still shared with any clone of these
+    /// extensions.
+    pub fn take<T: Any + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        self.values.remove(&TypeId::of::<T>())?.downcast().ok()
+    }
+}
+
+/// What the user asked for about reading a file, beyond which file.
+#[derive(Clone, Default)]
+pub struct LoadOptions<'a> {
+    /// Opens an encrypted file. Formats that cannot be encrypted ignore it.
+    pub password: Option<&'a str>,
+    /// Read the text off scanned pages and images with OCR, where the loader
+    /// can. Off unless asked for: Tesseract takes seconds a page, and the file
+    /// waits on it.
+    pub ocr: bool,
+    /// Settings for one format, such as [`crate::pdf_doc::PdfOptions`].
+    pub extensions: Extensions,
+}
+
+impl LoadOptions<'_> {
+    /// These options with `value` among the format settings.
+    pub fn with<T: Any + Send + Sync>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+}
+
+/// One file format.
+///
+/// A loader that divides its files into pages, chapters or sections describes
+/// them in [`LoadedFile::outline`]; one that holds pictures fills
+/// [`LoadedFile::media`]. Anything else it learns about the file, such as a
+/// [`PdfDocument`] or a parsed table, goes in [`LoadedFile::extensions`]. All
+/// are optional: plain `content` is enough.
+pub trait Loader: Send + Sync {
+    /// Short lowercase name, reported as the file's `kind` by `--json`.
+    fn name(&self) -> &'static str;
+
+    /// Whether this loader reads `path`, judged by its name or by `head`, the
+    /// first [`SNIFF_BYTES`] of it or fewer.
+    fn sniff(&self, path: &Path, head: &[u8]) -> bool;
+
+    /// Read the whole file. A loader takes the settings for its own format
+    /// from `options.extensions` and ignores the rest.
+    fn load(&self, path: &Path, options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>>;
+}
+
+/// The loaders flerp knows, asked most recently registered first.
+#[derive(Clone)]
+pub struct LoaderRegistry {
+    loaders: Vec<Arc<dyn Loader>>,
+}
+
+impl Default for LoaderRegistry {
+    /// The built-in formats. Text comes last, as it takes any file that
+    /// reads as UTF-8, with logs, tables, source and data files, which are
+    /// text too, just ahead of it.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(TextLoader);
+        registry.register(LogLoader);
+        registry.register(TableLoader);
+        registry.register(CodeLoader);
+        registry.register(StructuredLoader(DataFormat::Toml));
+        registry.register(StructuredLoader(DataFormat::Yaml));
+        registry.register(StructuredLoader(DataFormat::Json));
+        registry.register(ImageLoader);
+        registry.register(PdfLoader);
+        registry
+    }
+}
+
+impl LoaderRegistry {
+    /// A registry that opens nothing until loaders are added.
+    pub fn empty() -> Self {
+        Self {
+            loaders: Vec::new(),
+        }
+    }
+
+    /// Add `loader` ahead of every loader already registered, so it can take
+    /// over files a built-in one would otherwise read.
+    pub fn register(&mut self, loader: impl Loader + 'static) {
+        self.loaders.insert(0, Arc::new(loader));
+    }
+
+    /// Names of the registered loaders, in the order they are asked.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.loaders.iter().map(|loader| loader.name()).collect()
+    }
+
+    /// The first loader that recognises a file called `path` starting with
+    /// `head`.
+    pub fn find(&self, path: &Path, head: &[u8]) -> Option<&dyn Loader> {
+        let head = &head[..head.len().min(SNIFF_BYTES)];
+        self.loaders
+            .iter()
+            .find(|loader| loader.sniff(path, head))
+            .map(Arc::as_ref)
+    }
+
+    /// Load `file_path` with whichever loader recognises it.
+    pub fn load(
+        &self,
+        file_path: &str,
+        options: &LoadOptions,
+    ) -> Result<LoadedFile, Box<dyn Error>> {
+        let path = Path::new(file_path);
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        fs::File::open(path)?
+            .take(SNIFF_BYTES as u64)
+            .read_to_end(&mut head)?;
+
+        let loader = self
+            .find(path, &head)
+            .ok_or_else(|| format!("{file_path} is not in a format flerp can open"))?;
+        let mut loaded = loader.load(path, options)?;
+        loaded.kind = loader.name();
+        let document = loaded.extensions.get::<PdfDocument>();
+        loaded.outline = mem::take(&mut loaded.outline).or_pages_of(document);
+        Ok(loaded)
+    }
+}
@@ -0,0 +1,574 @@
+//! Application logs: when each line was written and how severe it is.
+//!
+//! A line that starts with a timestamp starts an event. Lines without one,
+//! such as the frames of a stack trace, belong to the event above them and
+//! share its time and level, so filtering never strands half a trace. Times
+//! are kept as written: offsets such as `+02:00` are not applied, which
+//! keeps a log's own clock readable.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+/// Share of non-empty lines that must start with a timestamp for a file to
+/// read as a log.
+const MIN_STAMPED_SHARE: f64 = 0.6;
+/// Words after the timestamp searched for a level: `app[42]: ERROR …`.
+const LEVEL_WORDS: usize = 3;
+
+/// Severity, least severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 6] = [
+        Self::Trace,
+        Self::Debug,
+        Self::Info,
+        Self::Warn,
+        Self::Error,
+        Self::Fatal,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+            Self::Fatal => "FATAL",
+        }
+    }
+
+    /// The level a word such as `WARNING`, `[error]` or `level=info` names.
+    fn parse(word: &str) -> Option<Self> {
+        let word = word.strip_prefix("level=").unwrap_or(word);
+        let word = word.trim_matches(|c: char| !c.is_ascii_alphabetic());
+        Some(match word.to_ascii_uppercase().as_str() {
+            "TRACE" | "TRC" => Self::Trace,
+            "DEBUG" | "DBG" => Self::Debug,
+            "INFO" | "INF" | "NOTICE" => Self::Info,
+            "WARN" | "WARNING" | "WRN" => Self::Warn,
+            "ERROR" | "ERR" => Self::Error,
+            "FATAL" | "CRITICAL" | "CRIT" | "PANIC" | "EMERG" | "ALERT" => Self::Fatal,
+            _ => return None,
+        })
+    }
+}
+
+/// A moment as a log writes it. Logs that give only the time of day have no
+/// date, and compare by time of day alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogTime {
+    /// Since 1970-01-01 00:00 for dated times, since midnight otherwise.
+    pub seconds: i64,
+    pub dated: bool,
+}
+
+impl LogTime {
+    fn of_day(seconds: i64) -> Self {
+        Self {
+            seconds,
+            dated: false,
+        }
+    }
+
+    fn on(year: i64, month: i64, day: i64, seconds: i64) -> Self {
+        Self {
+            seconds: days_from_civil(year, month, day) * SECONDS_PER_DAY + seconds,
+            dated: true,
+        }
+    }
+
+    /// The start of the minute this falls in.
+    pub fn minute(self) -> Self {
+        Self {
+            seconds: self.seconds - self.seconds.rem_euclid(60),
+            ..self
+        }
+    }
+
+    /// Seconds to compare against `bound`: the time of day when `bound` has
+    /// no date, so `12:30` picks out half past twelve on any day.
+    fn against(self, bound: LogTime) -> i64 {
+        if bound.dated {
+            self.seconds
+        } else {
+            self.seconds.rem_euclid(SECONDS_PER_DAY)
+        }
+    }
+}
+
+impl fmt::Display for LogTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clock = self.seconds.rem_euclid(SECONDS_PER_DAY);
+        let (hours, minutes, seconds) = (clock / 3600, clock % 3600 / 60, clock % 60);
+        if self.dated {
+            let (year, month, day) = civil_from_days(self.seconds.div_euclid(SECONDS_PER_DAY));
+            write!(f, "{year:04}-{month:02}-{day:02} ")?;
+        }
+        write!(f, "{hours:02}:{minutes:02}:{seconds:02}")
+    }
+}
+
+impl Serialize for LogTime {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The timestamp formats flerp recognises, compiled once per use.
+pub struct Timestamps {
+    /// `2024-03-05T12:34:56.789Z`, `2024-03-05 12:34:56,789`, `2024/03/05 12:34`.
+    iso: Regex,
+    /// syslog's `Mar  5 12:34:56`, which has no year.
+    syslog: Regex,
+    /// The common log format's `[05/Mar/2024:12:34:56 +0000]`, which follows
+    /// the client address rather than leading the line.
+    common: Regex,
+    /// A bare `12:34:56.789`.
+    clock: Regex,
+}
+
+impl Default for Timestamps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timestamps {
+    pub fn new() -> Self {
+        Self {
+            iso: Regex::new(
+                r"^\[?(\d{4})[-/](\d{2})[-/](\d{2})[T ](\d{2}):(\d{2})(?::(\d{2}))?(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?\]?",
+            )
+            .expect("static regex"),
+            syslog: Regex::new(r"^([A-Z][a-z]{2}) {1,2}(\d{1,2}) (\d{2}):(\d{2}):(\d{2})")
+                .expect("static regex"),
+            common: Regex::new(
+                r"\[(\d{2})/([A-Z][a-z]{2})/(\d{4}):(\d{2}):(\d{2}):(\d{2}) [+-]\d{4}\]",
+            )
+            .expect("static regex"),
+            clock: Regex::new(r"^\[?(\d{2}):(\d{2}):(\d{2})(?:[.,]\d+)?\]?").expect("static regex"),
+        }
+    }
+
+    /// The timestamp `line` was written at and where it sits in the line.
+    pub fn find(&self, line: &str) -> Option<(LogTime, Range<usize>)> {
+        let number = |captures: &Captures, index: usize| -> i64 {
+            captures
+                .get(index)
+                .map_or(0, |group| group.as_str().parse().unwrap_or(0))
+        };
+        let clock = |captures: &Captures, first: usize| {
+            number(captures, first) * 3600
+                + number(captures, first + 1) * 60
+                + number(captures, first + 2)
+        };
+
+        if let Some(captures) = self.iso.captures(line) {
+            let (month, day) = (number(&captures, 2), number(&captures, 3));
+            if (1..=12).contains(&month) && (1..=31).contains(&day) {
+                let time = LogTime::on(number(&captures, 1), month, day, clock(&captures, 4));
+                return Some((time, captures.get(0)?.range()));
+            }
+        }
+        if let Some(captures) = self.syslog.captures(line) {
+            if let Some(month) = month_number(&captures[1]) {
+                // No year is given; 1970 keeps the months in order.
+                let time = LogTime::on(1970, month, number(&captures, 2), clock(&captures, 3));
+                return Some((time, captures.get(0)?.range()));
+            }
+        }
+        if let Some(captures) = self.common.captures(line) {
+            if let Some(month) = month_number(&captures[2]) {
+                let time = LogTime::on(
+                    number(&captures, 3),
+                    month,
+                    number(&captures, 1),
+                    clock(&captures, 4),
+                );
+                return Some((time, captures.get(0)?.range()));
+            }
+        }
+        let captures = self.clock.captures(line)?;
+        Some((
+            LogTime::of_day(clock(&captures, 1)),
+            captures.get(0)?.range(),
+        ))
+    }
+
+    /// `line` without its timestamp, so events that differ only in when they
+    /// happened compare equal.
+    pub fn strip<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        match self.find(line) {
+            Some((_, range)) if range.start == 0 => Cow::Borrowed(line[range.end..].trim_start()),
+            Some((_, range)) => {
+                Cow::Owned(format!("{}{}", &line[..range.start], &line[range.end..]))
+            }
+            None => Cow::Borrowed(line),
+        }
+    }
+}
+
+/// One line of the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogEntry {
+    pub time: Option<LogTime>,
+    pub level: Option<LogLevel>,
+    /// Carries no timestamp of its own, and takes the time and level of the
+    /// event above it.
+    pub continuation: bool,
+}
+
+/// Which lines of a log to show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Hide events less severe than this, and those with no level.
+    pub min_level: Option<LogLevel>,
+    pub since: Option<LogTime>,
+    /// Inclusive.
+    pub until: Option<LogTime>,
+}
+
+impl LogFilter {
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    pub fn keeps(&self, entry: &LogEntry) -> bool {
+        let level = match self.min_level {
+            Some(min_level) => entry.level.is_some_and(|level| level >= min_level),
+            None => true,
+        };
+        let since = self.since.is_none_or(|since| {
+            entry
+                .time
+                .is_some_and(|time| time.against(since) >= since.seconds)
+        });
+        let until = self.until.is_none_or(|until| {
+            entry
+                .time
+                .is_some_and(|time| time.against(until) <= until.seconds)
+        });
+        level && since && until
+    }
+
+    /// `WARN and above · 12:00:00 to 12:30:59`, or `all lines`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(level) = self.min_level {
+            parts.push(format!("{} and above", level.label()));
+        }
+        match (self.since, self.until) {
+            (Some(since), Some(until)) => parts.push(format!("{since} to {until}")),
+            (Some(since), None) => parts.push(format!("from {since}")),
+            (None, Some(until)) => parts.push(format!("until {until}")),
+            (None, None) => {}
+        }
+        if parts.is_empty() {
+            "all lines".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
+}
+
+/// Events per level and per minute among the lines shown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogSummary {
+    pub events: usize,
+    /// Every level, least severe first, with how many events have it.
+    pub levels: Vec<(LogLevel, usize)>,
+    /// Minutes that had any event, in order.
+    pub per_minute: Vec<(LogTime, usize)>,
+}
+
+impl LogSummary {
+    /// `per_minute` spread over at most `bins` bars, with gaps as zeroes, and
+    /// the minutes each bar covers.
+    pub fn binned(&self, bins: usize) -> (Vec<u64>, i64) {
+        let (Some((first, _)), Some((last, _))) = (self.per_minute.first(), self.per_minute.last())
+        else {
+            return (Vec::new(), 1);
+        };
+        let span = (last.seconds - first.seconds) / 60 + 1;
+        let bins = bins.max(1) as i64;
+        let width = ((span + bins - 1) / bins).max(1);
+        let mut counts = vec![0u64; ((span + width - 1) / width) as usize];
+        for (minute, count) in &self.per_minute {
+            let index = ((minute.seconds - first.seconds) / 60 / width) as usize;
+            if let Some(bar) = counts.get_mut(index) {
+                *bar += *count as u64;
+            }
+        }
+        (counts, width)
+    }
+}
+
+/// A log read line by line.
+#[derive(Debug, Clone, Default)]
+pub struct LogFile {
+    text: String,
+    /// One per line of the text.
+    pub entries: Vec<LogEntry>,
+}
+
+impl LogFile {
+    pub fn parse(text: &str) -> Self {
+        let timestamps = Timestamps::new();
+        let mut current: Option<LogEntry> = None;
+        let entries = text
+            .lines()
+            .map(|line| match timestamps.find(line) {
+                Some((time, range)) => {
+                    let rest = format!("{} {}", &line[..range.start], &line[range.end..]);
+                    let level = rest
+                        .split_whitespace()
+                        .take(LEVEL_WORDS)
+                        .find_map(LogLevel::parse);
+                    let entry = LogEntry {
+                        time: Some(time),
+                        level,
+                        continuation: false,
+                    };
+                    current = Some(entry);
+                    entry
+                }
+                None => LogEntry {
+                    continuation: true,
+                    ..current.unwrap_or(LogEntry {
+                        time: None,
+                        level: None,
+                        continuation: true,
+                    })
+                },
+            })
+            .collect();
+        Self {
+            text: text.to_string(),
+            entries,
+        }
+    }
+
+    /// Whether most of the non-empty lines of `text` start with a timestamp.
+    /// Two stamped lines are the least that says so.
+    pub fn looks_like_log(text: &str) -> bool {
+        let timestamps = Timestamps::new();
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let stamped = lines
+            .iter()
+            .filter(|line| timestamps.find(line).is_some())
+            .count();
+        stamped >= 2 && stamped as f64 >= lines.len() as f64 * MIN_STAMPED_SHARE
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The lines `filter` keeps, joined, and the 0-based line of the log each
+    /// one came from.
+    pub fn filtered(&self, filter: &LogFilter) -> (String, Vec<usize>) {
+        let mut text = String::new();
+        let mut lines = Vec::new();
+        for (index, (line, entry)) in self.text.lines().zip(&self.entries).enumerate() {
+            if filter.keeps(entry) {
+                text.push_str(line);
+                text.push('\n');
+                lines.push(index);
+            }
+        }
+        (text, lines)
+    }
+
+    /// Count the events among `lines`, 0-based lines of the log.
+    pub fn summarize(&self, lines: &[usize]) -> LogSummary {
+        let mut levels: BTreeMap<LogLevel, usize> =
+            LogLevel::ALL.iter().map(|level| (*level, 0)).collect();
+        let mut per_minute: BTreeMap<LogTime, usize> = BTreeMap::new();
+        let mut events = 0;
+        for entry in lines.iter().filter_map(|line| self.entries.get(*line)) {
+            if entry.continuation {
+                continue;
+            }
+            events += 1;
+            if let Some(level) = entry.level {
+                *levels.entry(level).or_default() += 1;
+            }
+            if let Some(time) = entry.time {
+                *per_minute.entry(time.minute()).or_default() += 1;
+            }
+        }
+        LogSummary {
+            events,
+            levels: levels.into_iter().collect(),
+            per_minute: per_minute.into_iter().collect(),
+        }
+    }
+}
+
+/// Read a `--since`-style bound or one side of a `T` range: `12:30`,
+/// `12:30:15`, `2024-03-05`, `2024-03-05 12:30`. An `until` bound given to
+/// the minute or day covers all of it.
+pub fn parse_time(text: &str, until: bool) -> Result<LogTime, String> {
+    let text = text.trim();
+    let fail = || format!("'{text}' is not a time; try 12:30 or 2024-03-05 12:30");
+    let (date, clock) = match text.split_once(['T', ' ']) {
+        Some((date, clock)) => (Some(date), Some(clock)),
+        None if text.contains('-') => (Some(text), None),
+        None => (None, Some(text)),
+    };
+
+    // What an `until` bound adds to cover the whole minute or day it names.
+    let (seconds, rest) = match clock {
+        Some(clock) => {
+            let parts: Vec<i64> = clock
+                .split(':')
+                .map(|part| part.parse::<i64>().map_err(|_| fail()))
+                .collect::<Result<_, _>>()?;
+            let (hours, minutes, seconds) = match parts.as_slice() {
+                [hours, minutes] => (*hours, *minutes, None),
+                [hours, minutes, seconds] => (*hours, *minutes, Some(*seconds)),
+                _ => return Err(fail()),
+            };
+            if hours > 23 || minutes > 59 || seconds.is_some_and(|seconds| seconds > 59) {
+                return Err(fail());
+            }
+            let rest = if seconds.is_some() { 0 } else { 59 };
+            (hours * 3600 + minutes * 60 + seconds.unwrap_or(0), rest)
+        }
+        None => (0, SECONDS_PER_DAY - 1),
+    };
+
+    let mut time = match date {
+        Some(date) => {
+            let parts: Vec<i64> = date
+                .split('-')
+                .map(|part| part.parse::<i64>().map_err(|_| fail()))
+                .collect::<Result<_, _>>()?;
+            let [year, month, day] = parts.as_slice() else {
+                return Err(fail());
+            };
+            if !(1..=12).contains(month) || !(1..=31).contains(day) {
+                return Err(fail());
+            }
+            LogTime::on(*year, *month, *day, seconds)
+        }
+        None => LogTime::of_day(seconds),
+    };
+    if until {
+        time.seconds += rest;
+    }
+    Ok(time)
+}
+
+/// A `since..until` range, either side optional: `12:00..12:30`, `..09:15`.
+pub fn parse_time_range(text: &str) -> Result<(Option<LogTime>, Option<LogTime>), String> {
+    let Some((since, until)) = text.split_once("..") else {
+        return Err("a time range is written since..until, as in 12:00..12:30".to_string());
+    };
+    let bound = |side: &str, until: bool| {
+        (!side.trim().is_empty())
+            .then(|| parse_time(side, until))
+            .transpose()
+    };
+    Ok((bound(since, false)?, bound(until, true)?))
+}
+
+fn month_number(name: &str) -> Option<i64> {
+    MONTHS
+        .iter()
+        .position(|month| *month == name)
+        .map(|index| index as i64 + 1)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_timestamp_formats_are_read() {
+        let timestamps = Timestamps::new();
+        let time = |line: &str| timestamps.find(line).map(|(time, _)| time.to_string());
+        assert_eq!(
+            time("2024-03-05T12:34:56.789Z INFO started").as_deref(),
+            Some("2024-03-05 12:34:56")
+        );
+        assert_eq!(
+            time("[2024-03-05 12:34:56,001] WARN slow").as_deref(),
+            Some("2024-03-05 12:34:56")
+        );
+        assert_eq!(
+            time("Mar  5 12:34:56 host sshd[42]: ok").as_deref(),
+            Some("1970-03-05 12:34:56")
+        );
+        assert_eq!(
+            time(r#"10.0.0.1 - - [05/Mar/2024:12:34:56 +0000] "GET / HTTP/1.1" 200"#).as_deref(),
+            Some("2024-03-05 12:34:56")
+        );
+        assert_eq!(time("12:34:56.5 tick").as_deref(), Some("12:34:56"));
+        assert_eq!(time("version 2024-13-40 12:00"), None);
+        assert_eq!(civil_from_days(days_from_civil(2000, 2, 29)), (2000, 2, 29));
+    }
+
+    #[test]
+    fn ranges_cover_what_they_name() {
+        let (since, until) = parse_time_range("12:00..12:30").unwrap();
+        assert_eq!(since.unwrap().to_string(), "12:00:00");
+        assert_eq!(until.unwrap().to_string(), "12:30:59");
+        let (since, until) = parse_time_range("2024-03-05..").unwrap();
+        assert_eq!(since.unwrap().to_string(), "2024-03-05 00:00:00");
+        assert_eq!(until, None);
+        assert!(parse_time_range("noon").is_err());
+        assert!(parse_time_range("25:00..").is_err());
+    }
+}
@@ -14,7 +14,8 @@ use std::{
 use clap::Parser;
 use flerp::app::App;
 use flerp::app_structs::Cli;
-use flerp::headless::{self, HeadlessRequest};
+use flerp::file_utils;
+use flerp::headless::{self, DiffRequest, HeadlessRequest, ImageExport};
 use flerp::media::{GraphicsMode, MediaRenderer};
 use flerp::ui_components::ui;
 
@@ -35,14 +36,27 @@ pub fn run_tui(cli: &Cli, graphics: GraphicsMode) -> Result<(), Box<dyn Error>>
     let mut terminal = Terminal::new(backend)?;
 
     let mut app_instance = App::new();
+    // The flag wins over the saved setting for this session only.
+    if let Some(mode) = cli.extraction {
+        app_instance.state.extraction_mode = mode;
+    }
+    if cli.reflow {
+        app_instance.state.reflow = true;
+    }
+    app_instance.state.ocr = cli.ocr;
 
     // Dosya yükleme
     if let Some(path) = cli.file.as_deref() {
-        if let Err(e) = app_instance.load_file(path) {
+        if let Err(e) = app_instance.load_file_with_password(path, cli.password.as_deref()) {
             eprintln!("Error loading file: {}", e);
             // Consider how to handle this error: maybe exit or continue without a file
         }
     }
+    if let Some([old, new]) = cli.diff.as_deref() {
+        if let Err(e) = app_instance.load_diff(old, new, cli.password.as_deref()) {
+            eprintln!("Error comparing files: {}", e);
+        }
+    }
 
     // Flags that place the session somewhere other than the top of the file.
     if let Some(query) = cli.search.as_deref() {
@@ -96,15 +110,43 @@ fn dispatch(event: Event, app: &mut App) -> bool {
 }
 
 fn run_headless(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    if let Some([old, new]) = cli.diff.as_deref() {
+        let request = DiffRequest {
+            old: old.clone(),
+            new: new.clone(),
+            password: cli.password.clone(),
+            json: cli.json,
+            extraction: cli.extraction.unwrap_or_default(),
+            reflow: cli.reflow,
+            ocr: cli.ocr,
+            keyword_limit: cli.keywords,
+        };
+        return headless::run_diff(&request, &mut stdout().lock());
+    }
+
     let Some(file) = cli.file.clone() else {
-        return Err("--json, --text and --extract-images each need a file path".into());
+        return Err("--json, --text, --extract-images and --extract-attachments each need a file path".into());
     };
 
     let request = HeadlessRequest {
         file,
+        password: cli.password.clone(),
         json: cli.json,
         text: cli.text,
         extract_images: cli.extract_images.clone(),
+        image_export: ImageExport {
+            unique: cli.unique_images,
+            format: cli.image_format,
+            min_size: cli.min_size.unwrap_or_default(),
+            name_template: cli.image_name.clone(),
+            original: cli.original_bytes,
+        },
+        extract_attachments: cli.extract_attachments.clone(),
+        extraction: cli.extraction.unwrap_or_default(),
+        strip_furniture: cli.strip_headers,
+        reflow: cli.reflow,
+        ocr: cli.ocr,
+        page_range: cli.page_range.clone(),
         search: cli.search.clone(),
         search_options: cli.search_options(),
         keyword_limit: cli.keywords,
@@ -116,11 +158,19 @@ fn run_headless(cli: &Cli) -> Result<(), Box<dyn Error>> {
 
 fn main() -> ExitCode {
     let cli = Cli::parse();
+    if cli.ocr && !cfg!(feature = "ocr") {
+        eprintln!("flerp: --ocr needs a flerp built with `--features ocr`");
+        return ExitCode::FAILURE;
+    }
 
     let outcome = if cli.is_headless() {
         run_headless(&cli)
     } else {
-        run_tui(&cli, cli.graphics)
+        let outcome = run_tui(&cli, cli.graphics);
+        // Attachments opened during the session were copied out to open them;
+        // nothing needs them once the TUI is gone, however it ended.
+        file_utils::remove_staged_attachments();
+        outcome
     };
 
     match outcome {
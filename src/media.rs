@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use clap::ValueEnum;
+use image::imageops::FilterType;
 use image::DynamicImage;
 use ratatui::layout::Rect;
 use ratatui::Frame;
@@ -6,6 +9,8 @@ use ratatui_image::picker::{Picker, ProtocolType};
 use ratatui_image::protocol::StatefulProtocol;
 use ratatui_image::{Resize, StatefulImage};
 
+use crate::image_meta::ImageMetadata;
+
 /// Which terminal graphics protocol to use.
 ///
 /// Detection asks the terminal and trusts the answer, but the query is swallowed
@@ -46,6 +51,191 @@ pub struct MediaItem {
     /// 1-based page this image sits on, for paged documents.
     pub page: Option<usize>,
     pub image: DynamicImage,
+    /// Pages showing this image, this one included, when the file embeds it
+    /// more than once; the other copies are not listed separately.
+    pub copies: Vec<usize>,
+    /// Index into [`PdfDocument::images`](crate::pdf_doc::PdfDocument::images)
+    /// for an image lifted from a PDF.
+    pub source: Option<usize>,
+    /// EXIF, XMP and colour profile details, for photos that carry them.
+    pub metadata: Option<ImageMetadata>,
+}
+
+impl MediaItem {
+    /// "×3 on pages 1, 4, 9" for a repeated image, nothing otherwise.
+    pub fn copies_label(&self) -> Option<String> {
+        if self.copies.len() < 2 {
+            return None;
+        }
+        let pages: Vec<String> = self.copies.iter().map(usize::to_string).collect();
+        Some(format!("×{} on pages {}", self.copies.len(), pages.join(", ")))
+    }
+}
+
+/// Size of one thumbnail in the grid, in cells, border and caption included.
+pub const GRID_TILE: (u16, u16) = (22, 11);
+
+/// Zoom factors `+` and `-` step through, relative to the fitted size.
+pub const ZOOM_LEVELS: [f64; 9] = [1.0, 1.5, 2.0, 3.0, 4.0, 6.0, 8.0, 12.0, 16.0];
+
+/// How the selected image is being looked at: zoom, pan and rotation. Reset
+/// whenever another image is selected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MediaView {
+    /// Index into [`ZOOM_LEVELS`]; 0 fits the whole image.
+    pub zoom: usize,
+    /// One image pixel per screen pixel, whatever the zoom says.
+    pub actual_size: bool,
+    /// Point of the turned image at the middle of the preview, as fractions of
+    /// its width and height.
+    pub center: (f64, f64),
+    /// Clockwise quarter turns, 0 to 3.
+    pub quarter_turns: u8,
+}
+
+impl Default for MediaView {
+    fn default() -> Self {
+        Self {
+            zoom: 0,
+            actual_size: false,
+            center: (0.5, 0.5),
+            quarter_turns: 0,
+        }
+    }
+}
+
+impl MediaView {
+    /// Showing less than the whole image, so there is something to pan to.
+    pub fn is_zoomed(&self) -> bool {
+        self.zoom > 0 || self.actual_size
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.actual_size = false;
+        self.zoom = (self.zoom + 1).min(ZOOM_LEVELS.len() - 1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.actual_size = false;
+        self.zoom = self.zoom.saturating_sub(1);
+        if self.zoom == 0 {
+            self.center = (0.5, 0.5);
+        }
+    }
+
+    /// Turn a quarter clockwise, keeping the same spot in the middle.
+    pub fn rotate(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+        let (x, y) = self.center;
+        self.center = (1.0 - y, x);
+    }
+
+    /// Move the middle of the preview by a share of what is visible, given the
+    /// `visible` fractions of the image the last frame showed.
+    pub fn pan(&mut self, (dx, dy): (f64, f64), visible: (f64, f64)) {
+        let clamp = |center: f64, delta: f64, visible: f64| {
+            let half = (visible / 2.0).min(0.5);
+            (center + delta * visible).clamp(half, 1.0 - half)
+        };
+        self.center = (
+            clamp(self.center.0, dx, visible.0),
+            clamp(self.center.1, dy, visible.1),
+        );
+    }
+
+    /// "200% · 1:1 · 90°" style summary; empty for the default view.
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.actual_size {
+            parts.push("1:1 pixels".to_string());
+        } else if self.zoom > 0 {
+            parts.push(format!("{:.0}%", ZOOM_LEVELS[self.zoom] * 100.0));
+        }
+        if self.quarter_turns > 0 {
+            parts.push(format!("{}°", u16::from(self.quarter_turns) * 90));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// The part of an image that fits the preview under a [`MediaView`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Screen pixels per image pixel.
+    pub scale: f64,
+}
+
+impl Region {
+    /// The region's share of an image `width` by `height`.
+    pub fn fraction(&self, (width, height): (u32, u32)) -> (f64, f64) {
+        (
+            f64::from(self.width) / f64::from(width.max(1)),
+            f64::from(self.height) / f64::from(height.max(1)),
+        )
+    }
+}
+
+/// What of an image `width` by `height` (already turned) a preview of
+/// `area` screen pixels shows. Fitting never enlarges, matching how unzoomed
+/// images have always been drawn; zoom levels multiply the fitted scale.
+pub fn visible_region((width, height): (u32, u32), view: &MediaView, area: (u32, u32)) -> Region {
+    let (width, height) = (width.max(1), height.max(1));
+    let fit = (f64::from(area.0) / f64::from(width))
+        .min(f64::from(area.1) / f64::from(height))
+        .min(1.0);
+    let scale = if view.actual_size {
+        1.0
+    } else {
+        fit * ZOOM_LEVELS[view.zoom.min(ZOOM_LEVELS.len() - 1)]
+    };
+
+    let span = |extent: u32, room: u32| ((f64::from(room) / scale).ceil() as u32).clamp(1, extent);
+    let (visible_width, visible_height) = (span(width, area.0), span(height, area.1));
+    let start = |extent: u32, visible: u32, center: f64| {
+        let start = (center * f64::from(extent) - f64::from(visible) / 2.0).round();
+        start.clamp(0.0, f64::from(extent - visible)) as u32
+    };
+
+    Region {
+        x: start(width, visible_width, view.center.0),
+        y: start(height, visible_height, view.center.1),
+        width: visible_width,
+        height: visible_height,
+        scale,
+    }
+}
+
+/// Turn `image` by `quarter_turns` clockwise quarters.
+pub fn rotated(image: &DynamicImage, quarter_turns: u8) -> DynamicImage {
+    match quarter_turns % 4 {
+        1 => image.rotate90(),
+        2 => image.rotate180(),
+        3 => image.rotate270(),
+        _ => image.clone(),
+    }
+}
+
+/// The pixels to hand the terminal: the visible region, enlarged when zoomed
+/// past the image's own resolution so the protocol only has to place it.
+pub fn visible_pixels(image: &DynamicImage, view: &MediaView, area: (u32, u32)) -> DynamicImage {
+    let turned = rotated(image, view.quarter_turns);
+    let region = visible_region((turned.width(), turned.height()), view, area);
+    let crop = turned.crop_imm(region.x, region.y, region.width, region.height);
+    if region.scale <= 1.0 {
+        return crop;
+    }
+    // Nearest keeps individual pixels sharp, which is the point of zooming.
+    let size =
+        |extent: u32, room: u32| ((f64::from(extent) * region.scale) as u32).clamp(1, room.max(1));
+    crop.resize_exact(
+        size(region.width, area.0),
+        size(region.height, area.1),
+        FilterType::Nearest,
+    )
 }
 
 /// Holds the terminal graphics protocol and the currently encoded image.
@@ -56,7 +246,12 @@ pub struct MediaItem {
 pub struct MediaRenderer {
     picker: Option<Picker>,
     protocol: Option<StatefulProtocol>,
-    current_key: Option<String>,
+    /// What the encoded protocol state shows: the image, how it is viewed, and
+    /// the preview size the crop was computed for.
+    current_key: Option<(String, MediaView, Rect)>,
+    /// Encoded thumbnails for the grid, by item key, with the cell size each
+    /// was made for.
+    thumbnails: HashMap<String, ((u16, u16), StatefulProtocol)>,
     error: Option<String>,
 }
 
@@ -80,6 +275,7 @@ impl MediaRenderer {
                 picker: Some(picker),
                 protocol: None,
                 current_key: None,
+                thumbnails: HashMap::new(),
                 error: None,
             },
             // A terminal that will not answer the query can still show
@@ -105,6 +301,7 @@ impl MediaRenderer {
             picker: Some(Picker::halfblocks()),
             protocol: None,
             current_key: None,
+            thumbnails: HashMap::new(),
             error: None,
         }
     }
@@ -132,24 +329,86 @@ impl MediaRenderer {
         self.error.as_deref()
     }
 
-    /// Prepare `item` for display, reusing the existing protocol state when the
-    /// same image is still selected.
-    pub fn select(&mut self, item: &MediaItem) {
-        if self.current_key.as_deref() == Some(item.key.as_str()) {
+    /// Screen pixels in `area`, from the terminal's cell size.
+    pub fn area_pixels(&self, area: Rect) -> (u32, u32) {
+        let (cell_width, cell_height) = self
+            .picker
+            .as_ref()
+            .map(Picker::font_size)
+            .unwrap_or((10, 20));
+        (
+            u32::from(area.width) * u32::from(cell_width),
+            u32::from(area.height) * u32::from(cell_height),
+        )
+    }
+
+    /// Prepare `item` for display in `area` as `view` frames it, reusing the
+    /// existing protocol state while none of those change.
+    ///
+    /// The crop happens here, before encoding, so a zoomed-in view only sends
+    /// the visible region through Kitty or Sixel rather than the whole image.
+    pub fn select(&mut self, item: &MediaItem, view: &MediaView, area: Rect) {
+        // Only a zoomed view's crop depends on the preview size; otherwise the
+        // protocol state fits the image to whatever area it is given.
+        let area = if view.is_zoomed() { area } else { Rect::ZERO };
+        let key = (item.key.clone(), *view, area);
+        if self.current_key.as_ref() == Some(&key) {
             return;
         }
 
+        let pixels = if view.is_zoomed() || view.quarter_turns > 0 {
+            visible_pixels(&item.image, view, self.area_pixels(key.2))
+        } else {
+            item.image.clone()
+        };
         let Some(picker) = self.picker.as_mut() else {
             return;
         };
 
-        self.protocol = Some(picker.new_resize_protocol(item.image.clone()));
-        self.current_key = Some(item.key.clone());
+        self.protocol = Some(picker.new_resize_protocol(pixels));
+        self.current_key = Some(key);
     }
 
     pub fn clear(&mut self) {
         self.protocol = None;
         self.current_key = None;
+        self.thumbnails.clear();
+    }
+
+    /// Draw a small copy of `item` into `area`, encoding it only the first
+    /// time it is shown at this size. The image is shrunk before encoding so
+    /// a grid of large scans costs no more to send than the thumbnails are.
+    pub fn render_thumbnail(&mut self, frame: &mut Frame, item: &MediaItem, area: Rect) -> bool {
+        let size = (area.width, area.height);
+        let (width, height) = self.area_pixels(area);
+        let Some(picker) = self.picker.as_mut() else {
+            return false;
+        };
+        if self.thumbnails.get(&item.key).is_none_or(|(made_for, _)| *made_for != size) {
+            let thumbnail = item.image.thumbnail(width.max(1), height.max(1));
+            self.thumbnails
+                .insert(item.key.clone(), (size, picker.new_resize_protocol(thumbnail)));
+        }
+        let Some((_, protocol)) = self.thumbnails.get_mut(&item.key) else {
+            return false;
+        };
+
+        frame.render_stateful_widget(
+            StatefulImage::default().resize(Resize::Fit(None)),
+            area,
+            protocol,
+        );
+        if let Err(error) = protocol.last_encoding_result().unwrap_or(Ok(())) {
+            self.error = Some(error.to_string());
+        }
+        true
+    }
+
+    /// Drop thumbnails of images no longer in `items`, such as those of a file
+    /// that has since been replaced.
+    pub fn retain_thumbnails(&mut self, items: &[MediaItem]) {
+        self.thumbnails
+            .retain(|key, _| items.iter().any(|item| &item.key == key));
     }
 
     /// Draw the selected image into `area`. Returns false when there is nothing
@@ -0,0 +1,117 @@
+//! Optical character recognition for pixels with no text layer behind them:
+//! scanned PDF pages and standalone images.
+//!
+//! Only built with the `ocr` cargo feature. Recognition is done by a locally
+//! installed Tesseract run as a child process, so nothing leaves the machine and
+//! the models are whichever `*.traineddata` files that Tesseract already has.
+//! `FLERP_TESSERACT` names a binary other than `tesseract` on the `PATH`, and
+//! `FLERP_OCR_LANG` picks the models: `eng` by default, `eng+tur` for both.
+//!
+//! Recognition takes seconds a page, and the TUI re-reads a document every
+//! time reflow or the extraction mode changes, so what Tesseract returns is
+//! kept for the rest of the process and handed back for the same pixels.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{Cursor, ErrorKind, Write};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+
+use image::{DynamicImage, ImageFormat};
+
+const DEFAULT_BINARY: &str = "tesseract";
+const DEFAULT_LANGUAGE: &str = "eng";
+
+/// Text already recognised, by engine, language and image.
+static RECOGNIZED: Mutex<BTreeMap<u64, String>> = Mutex::new(BTreeMap::new());
+
+/// The text Tesseract reads off `image`, tidied; empty when it finds none.
+///
+/// Errors mean the engine itself could not be used (not installed, missing
+/// language data), not that the image had no text in it.
+pub fn recognize(image: &DynamicImage) -> Result<String, Box<dyn Error>> {
+    let binary = std::env::var("FLERP_TESSERACT").unwrap_or_else(|_| DEFAULT_BINARY.to_string());
+    let language = std::env::var("FLERP_OCR_LANG").unwrap_or_else(|_| DEFAULT_LANGUAGE.to_string());
+
+    // Colour only gets in Tesseract's way, and grey PNG is the smallest
+    // lossless thing to hand it.
+    let grey = image.to_luma8();
+    let mut hasher = DefaultHasher::new();
+    (&binary, &language, grey.dimensions(), grey.as_raw()).hash(&mut hasher);
+    let key = hasher.finish();
+    if let Some(text) = recognized().get(&key) {
+        return Ok(text.clone());
+    }
+
+    let mut png = Vec::new();
+    DynamicImage::ImageLuma8(grey).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+    let mut child = Command::new(&binary)
+        .args(["stdin", "stdout", "-l", &language])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| -> Box<dyn Error> {
+            if error.kind() == ErrorKind::NotFound {
+                format!("{binary} is not installed; OCR needs Tesseract and its language data").into()
+            } else {
+                format!("could not run {binary}: {error}").into()
+            }
+        })?;
+
+    // Feed stdin from its own thread so a chatty engine cannot fill the stdout
+    // pipe while we are still writing. A write error only means the engine
+    // quit early, which its exit status reports better.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(&png);
+    });
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("no error message");
+        return Err(format!("{binary} failed ({}): {reason}", output.status).into());
+    }
+
+    let text = clean(&String::from_utf8_lossy(&output.stdout));
+    recognized().insert(key, text.clone());
+    Ok(text)
+}
+
+/// The cache, still usable if a thread panicked while holding it: the worst
+/// a half-finished insert leaves behind is a missing entry.
+fn recognized() -> MutexGuard<'static, BTreeMap<u64, String>> {
+    RECOGNIZED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Tesseract ends every page with a form feed and pads lines with spaces;
+/// neither belongs in the document text.
+fn clean(raw: &str) -> String {
+    let lines: Vec<&str> = raw
+        .split(['\n', '\x0c'])
+        .map(str::trim_end)
+        .collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clean;
+
+    #[test]
+    fn strips_page_feeds_and_trailing_padding() {
+        let raw = "\nINVOICE 42  \n\nPaid in full \n\x0c";
+        assert_eq!(clean(raw), "INVOICE 42\n\nPaid in full");
+    }
+}
@@ -0,0 +1,249 @@
+//! Where a document divides: pages, chapters, headings, log sections.
+//!
+//! Every loader can describe its file as an [`Outline`] of line ranges, so
+//! page jumps, the viewer's position and per-section analysis work the same
+//! whether the divisions came from a PDF's pages or from headings in text.
+
+use serde::Serialize;
+
+use crate::pdf_doc::PdfDocument;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentKind {
+    Page,
+    Chapter,
+    Heading,
+    Section,
+}
+
+impl SegmentKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Page => "Page",
+            Self::Chapter => "Chapter",
+            Self::Heading => "Heading",
+            Self::Section => "Section",
+        }
+    }
+}
+
+/// A run of lines in the file's content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    /// What the outline calls it: `Page 3`, or the heading's own text.
+    pub title: String,
+    /// Nesting depth, 0 outermost. A heading inside a chapter is one deeper.
+    pub level: usize,
+    /// 1-based position among the segments of the same kind and level, which
+    /// for a page is its page number.
+    pub number: usize,
+    /// 0-based first line.
+    pub start_line: usize,
+    pub line_count: usize,
+}
+
+impl Segment {
+    pub fn end_line(&self) -> usize {
+        self.start_line + self.line_count
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start_line..self.end_line()).contains(&line)
+    }
+}
+
+/// A file's segments in line order. Empty for files with no structure.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outline {
+    segments: Vec<Segment>,
+}
+
+impl Outline {
+    /// Order `segments` by first line, outer ones before those they contain.
+    pub fn new(mut segments: Vec<Segment>) -> Self {
+        segments.sort_by_key(|segment| (segment.start_line, segment.level));
+        Self { segments }
+    }
+
+    /// This outline, or `document`'s pages and bookmarks when it is empty. A
+    /// loader that reads a PDF need not work out its outline itself.
+    pub fn or_pages_of(self, document: Option<&PdfDocument>) -> Self {
+        match document {
+            Some(document) if self.is_empty() => document.outline(),
+            _ => self,
+        }
+    }
+
+    /// One page segment per entry of `line_counts`, back to back from line 0.
+    pub fn pages(line_counts: impl IntoIterator<Item = usize>) -> Self {
+        let mut start_line = 0;
+        let segments = line_counts
+            .into_iter()
+            .enumerate()
+            .map(|(index, line_count)| {
+                let segment = Segment {
+                    kind: SegmentKind::Page,
+                    title: format!("Page {}", index + 1),
+                    level: 0,
+                    number: index + 1,
+                    start_line,
+                    line_count,
+                };
+                start_line += line_count;
+                segment
+            })
+            .collect();
+        Self { segments }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The outermost segments, which `[` and `]` step between and the viewer
+    /// counts its position in: the pages of a PDF, the chapters of a book.
+    pub fn top_level(&self) -> Vec<&Segment> {
+        let Some(level) = self.segments.iter().map(|segment| segment.level).min() else {
+            return Vec::new();
+        };
+        self.segments
+            .iter()
+            .filter(|segment| segment.level == level)
+            .collect()
+    }
+
+    /// Index into [`top_level`](Self::top_level) of the segment holding
+    /// `line`, or of the last one starting before it. `None` above the first.
+    pub fn top_level_at(&self, line: usize) -> Option<usize> {
+        self.top_level()
+            .iter()
+            .rposition(|segment| segment.start_line <= line)
+    }
+
+    /// The most deeply nested segment holding `line`.
+    pub fn innermost_at(&self, line: usize) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.contains(line))
+            .max_by_key(|segment| (segment.level, segment.start_line))
+    }
+
+    /// What a table of contents lists: the chapters, headings and sections
+    /// when there are any, and the pages when there is nothing else.
+    pub fn contents(&self) -> Vec<&Segment> {
+        let headings: Vec<&Segment> = self
+            .segments
+            .iter()
+            .filter(|segment| segment.kind != SegmentKind::Page)
+            .collect();
+        if headings.is_empty() {
+            self.segments.iter().collect()
+        } else {
+            headings
+        }
+    }
+
+    /// The segment of `kind` numbered `number`, such as page 12.
+    pub fn find(&self, kind: SegmentKind, number: usize) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|segment| segment.kind == kind && segment.number == number)
+    }
+
+    pub fn count(&self, kind: SegmentKind) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.kind == kind)
+            .count()
+    }
+
+    /// Where `]` (`forward`) or `[` lands from `line`: the next or previous
+    /// top-level start. Going back from partway through a segment lands on its
+    /// own start first, as a "previous page" key does. Past either end it
+    /// stays on the first or last segment.
+    pub fn step_from(&self, line: usize, forward: bool) -> Option<&Segment> {
+        let top = self.top_level();
+        let found = if forward {
+            top.iter().find(|segment| segment.start_line > line)
+        } else {
+            top.iter().rev().find(|segment| segment.start_line < line)
+        };
+        found
+            .or(if forward { top.last() } else { top.first() })
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading(title: &str, level: usize, start_line: usize, line_count: usize) -> Segment {
+        Segment {
+            kind: if level == 0 {
+                SegmentKind::Chapter
+            } else {
+                SegmentKind::Heading
+            },
+            title: title.to_string(),
+            level,
+            number: 1,
+            start_line,
+            line_count,
+        }
+    }
+
+    #[test]
+    fn steps_move_between_top_level_starts() {
+        let outline = Outline::pages([10, 5, 8]);
+        assert_eq!(outline.top_level_at(12), Some(1));
+        assert_eq!(outline.step_from(12, true).map(|page| page.number), Some(3));
+        assert_eq!(
+            outline.step_from(12, false).map(|page| page.number),
+            Some(2)
+        );
+        assert_eq!(
+            outline.step_from(10, false).map(|page| page.number),
+            Some(1)
+        );
+        assert_eq!(outline.step_from(20, true).map(|page| page.number), Some(3));
+        assert_eq!(
+            outline
+                .find(SegmentKind::Page, 2)
+                .map(|page| page.start_line),
+            Some(10)
+        );
+        assert_eq!(outline.contents().len(), 3, "pages when nothing else");
+    }
+
+    #[test]
+    fn nested_segments_report_the_innermost() {
+        let outline = Outline::new(vec![
+            heading("Usage", 1, 4, 3),
+            heading("Guide", 0, 2, 10),
+            heading("Reference", 0, 12, 6),
+        ]);
+        assert_eq!(outline.segments()[0].title, "Guide", "sorted by start");
+        assert_eq!(
+            outline.innermost_at(5).map(|s| s.title.as_str()),
+            Some("Usage")
+        );
+        assert_eq!(
+            outline.innermost_at(8).map(|s| s.title.as_str()),
+            Some("Guide")
+        );
+        assert_eq!(outline.top_level().len(), 2);
+        assert_eq!(outline.contents().len(), 3);
+        assert_eq!(outline.top_level_at(1), None, "before the first chapter");
+        assert_eq!(
+            outline.step_from(0, true).map(|s| s.title.as_str()),
+            Some("Guide")
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Review marks and form data a PDF carries beside its page text.
+//!
+//! Sticky notes, highlights and filled-in AcroForm fields never reach the text
+//! layer, so a reviewed contract reads exactly like the unreviewed one. These
+//! are collected separately and kept apart from `PdfDocument::text`, which stays
+//! the document's own words.
+
+use std::collections::{BTreeMap, HashSet};
+
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+
+use crate::pdf_glyphs::{self, Glyph};
+use crate::text_analysis::{search_with_options, SearchOptions};
+
+/// Annotation subtypes that are page furniture rather than review content.
+/// Widgets are reported as form fields instead; popups only repeat their
+/// parent's text.
+const IGNORED_SUBTYPES: [&str; 3] = ["Link", "Widget", "Popup"];
+
+/// Subtypes whose `/QuadPoints` mark a run of page text.
+const MARKUP_SUBTYPES: [&str; 4] = ["Highlight", "Underline", "StrikeOut", "Squiggly"];
+
+/// A deepest sensible AcroForm tree. Guards against a `/Kids` cycle.
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// A note, highlight or other markup attached to a page.
+#[derive(Debug, Clone, Serialize)]
+pub struct Annotation {
+    /// 1-based page the annotation sits on.
+    pub page: usize,
+    /// The PDF `/Subtype`: `Text` for sticky notes, `Highlight`, `FreeText`...
+    pub kind: String,
+    pub author: Option<String>,
+    pub contents: Option<String>,
+    /// Page text covered by a text-markup annotation's `/QuadPoints`.
+    pub highlighted: Option<String>,
+}
+
+/// One terminal AcroForm field and whatever was typed or ticked into it.
+#[derive(Debug, Clone, Serialize)]
+pub struct FormField {
+    /// Fully qualified name, parents joined with dots as the spec defines.
+    pub name: String,
+    /// The `/FT` field type: `Tx` text, `Btn` button, `Ch` choice, `Sig`.
+    pub kind: String,
+    pub value: Option<String>,
+    /// 1-based page of the field's first widget, when it has one.
+    pub page: Option<usize>,
+}
+
+impl Annotation {
+    /// Everything a search should look at, one item per line.
+    pub fn searchable_text(&self) -> String {
+        [&self.author, &self.contents, &self.highlighted]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl FormField {
+    pub fn searchable_text(&self) -> String {
+        match &self.value {
+            Some(value) => format!("{}\n{value}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// An annotation or a form field, flattened to what a list row shows.
+#[derive(Debug, Clone, Serialize)]
+pub struct Note {
+    pub page: Option<usize>,
+    /// What the note is and who left it: `Highlight · Alice`, `Field name`.
+    pub label: String,
+    /// The words themselves, one line per part.
+    pub text: String,
+}
+
+/// Annotations first, then form fields, the order the panel lists them in.
+pub fn notes(annotations: &[Annotation], fields: &[FormField]) -> Vec<Note> {
+    let annotations = annotations.iter().map(|annotation| Note {
+        page: Some(annotation.page),
+        label: match &annotation.author {
+            Some(author) => format!("{} · {author}", annotation.kind),
+            None => annotation.kind.clone(),
+        },
+        text: [&annotation.highlighted, &annotation.contents]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n"),
+    });
+    let fields = fields.iter().map(|field| Note {
+        page: field.page,
+        label: format!("Field {}", field.name),
+        text: field.value.clone().unwrap_or_default(),
+    });
+
+    annotations.chain(fields).collect()
+}
+
+/// The notes whose text matches `query`, under the same rules as the page
+/// text. An empty query keeps them all, so the panel is never blank for a
+/// document that has notes.
+pub fn search_notes(
+    annotations: &[Annotation],
+    fields: &[FormField],
+    query: &str,
+    options: SearchOptions,
+) -> Result<Vec<Note>, String> {
+    let all = notes(annotations, fields);
+    if query.is_empty() {
+        return Ok(all);
+    }
+
+    let searchable = annotations
+        .iter()
+        .map(Annotation::searchable_text)
+        .chain(fields.iter().map(FormField::searchable_text));
+
+    let mut matched = Vec::new();
+    for (note, text) in all.into_iter().zip(searchable) {
+        if !search_with_options(query, &text, options)?.is_empty() {
+            matched.push(note);
+        }
+    }
+    Ok(matched)
+}
+
+/// Every review annotation in page order.
+pub fn collect_annotations(pdf: &Document) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+
+    for (number, page_id) in pdf.get_pages() {
+        let Ok(page) = pdf.get_dictionary(page_id) else {
+            continue;
+        };
+        let Some(annots) = page
+            .get(b"Annots")
+            .ok()
+            .and_then(|object| resolve(pdf, object).as_array().ok())
+        else {
+            continue;
+        };
+
+        // Glyphs are only worth collecting for a page that has a highlight.
+        let mut glyphs: Option<Vec<Glyph>> = None;
+
+        for annot in annots {
+            let Ok(dict) = resolve(pdf, annot).as_dict() else {
+                continue;
+            };
+            let kind = name_of(dict, b"Subtype").unwrap_or_else(|| "Unknown".to_string());
+            if IGNORED_SUBTYPES.contains(&kind.as_str()) {
+                continue;
+            }
+
+            let highlighted = if MARKUP_SUBTYPES.contains(&kind.as_str()) {
+                let glyphs = glyphs.get_or_insert_with(|| {
+                    pdf_glyphs::page_glyphs(pdf, number).unwrap_or_default()
+                });
+                quad_points(pdf, dict).and_then(|quads| text_under(glyphs, &quads))
+            } else {
+                None
+            };
+
+            annotations.push(Annotation {
+                page: number as usize,
+                kind,
+                author: text_of(pdf, dict, b"T"),
+                contents: text_of(pdf, dict, b"Contents"),
+                highlighted,
+            });
+        }
+    }
+
+    annotations
+}
+
+/// Every terminal form field in the document's `/AcroForm`, in tree order.
+pub fn collect_form_fields(pdf: &Document) -> Vec<FormField> {
+    let Some(fields) = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| resolve(pdf, form).as_dict().ok())
+        .and_then(|form| form.get(b"Fields").ok())
+        .and_then(|fields| resolve(pdf, fields).as_array().ok())
+    else {
+        return Vec::new();
+    };
+
+    let pages: BTreeMap<ObjectId, usize> = pdf
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number as usize))
+        .collect();
+
+    let mut collected = Vec::new();
+    let mut visited = HashSet::new();
+    for field in fields {
+        walk_field(pdf, field, None, None, &pages, &mut visited, &mut collected, 0);
+    }
+    collected
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk_field(
+    pdf: &Document,
+    object: &Object,
+    parent_name: Option<&str>,
+    parent_kind: Option<&str>,
+    pages: &BTreeMap<ObjectId, usize>,
+    visited: &mut HashSet<ObjectId>,
+    collected: &mut Vec<FormField>,
+    depth: usize,
+) {
+    if depth > MAX_FIELD_DEPTH {
+        return;
+    }
+    if let Object::Reference(id) = object {
+        if !visited.insert(*id) {
+            return;
+        }
+    }
+    let Ok(dict) = resolve(pdf, object).as_dict() else {
+        return;
+    };
+
+    // A kid with no `/T` of its own is a widget of its parent, not a field.
+    let name = match (text_of(pdf, dict, b"T"), parent_name) {
+        (Some(own), Some(parent)) => format!("{parent}.{own}"),
+        (Some(own), None) => own,
+        (None, Some(parent)) => parent.to_string(),
+        (None, None) => String::new(),
+    };
+    // `/FT` is inheritable, so a kid without one takes its parent's.
+    let kind = name_of(dict, b"FT").or_else(|| parent_kind.map(str::to_string));
+
+    let kids: Vec<&Object> = dict
+        .get(b"Kids")
+        .ok()
+        .and_then(|kids| resolve(pdf, kids).as_array().ok())
+        .map(|kids| kids.iter().collect())
+        .unwrap_or_default();
+    let named_kids = kids.iter().any(|kid| {
+        resolve(pdf, kid)
+            .as_dict()
+            .is_ok_and(|kid| kid.has(b"T"))
+    });
+
+    if named_kids {
+        for kid in kids {
+            walk_field(
+                pdf,
+                kid,
+                Some(&name),
+                kind.as_deref(),
+                pages,
+                visited,
+                collected,
+                depth + 1,
+            );
+        }
+        return;
+    }
+
+    // A terminal field: its own widget, or its first widget kid, says where it is.
+    let page = std::iter::once(dict)
+        .chain(kids.iter().filter_map(|kid| resolve(pdf, kid).as_dict().ok()))
+        .find_map(|widget| widget.get(b"P").ok()?.as_reference().ok())
+        .and_then(|id| pages.get(&id).copied());
+
+    collected.push(FormField {
+        name,
+        kind: kind.unwrap_or_else(|| "unknown".to_string()),
+        value: dict.get(b"V").ok().and_then(|value| value_text(pdf, value)),
+        page,
+    });
+}
+
+fn resolve<'a>(pdf: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+fn name_of(dict: &Dictionary, key: &[u8]) -> Option<String> {
+    dict.get(key)
+        .ok()?
+        .as_name()
+        .ok()
+        .map(|name| String::from_utf8_lossy(name).to_string())
+}
+
+/// A text-string entry, decoded from PDFDocEncoding or UTF-16 and trimmed.
+/// Blank strings count as absent; an empty note says nothing.
+fn text_of(pdf: &Document, dict: &Dictionary, key: &[u8]) -> Option<String> {
+    let object = resolve(pdf, dict.get(key).ok()?);
+    let text = decode_text_string(object).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A field value as a reader would see it: text as-is, a checkbox's export
+/// name (`/Yes`, `/Off`) without its slash, several choices comma-joined.
+fn value_text(pdf: &Document, value: &Object) -> Option<String> {
+    match resolve(pdf, value) {
+        Object::Name(name) => Some(String::from_utf8_lossy(name).to_string()),
+        Object::Array(items) => {
+            let values: Vec<String> = items
+                .iter()
+                .filter_map(|item| value_text(pdf, item))
+                .collect();
+            (!values.is_empty()).then(|| values.join(", "))
+        }
+        object => {
+            let text = decode_text_string(object).ok()?;
+            (!text.trim().is_empty()).then_some(text)
+        }
+    }
+}
+
+/// The `/QuadPoints` of a markup annotation, as one bounding box per quad:
+/// `(left, bottom, right, top)`.
+fn quad_points(pdf: &Document, dict: &Dictionary) -> Option<Vec<(f64, f64, f64, f64)>> {
+    let numbers: Vec<f64> = resolve(pdf, dict.get(b"QuadPoints").ok()?)
+        .as_array()
+        .ok()?
+        .iter()
+        .filter_map(|value| value.as_float().ok().map(f64::from))
+        .collect();
+
+    let boxes: Vec<(f64, f64, f64, f64)> = numbers
+        .chunks_exact(8)
+        .map(|quad| {
+            let xs = [quad[0], quad[2], quad[4], quad[6]];
+            let ys = [quad[1], quad[3], quad[5], quad[7]];
+            (
+                xs.iter().copied().fold(f64::INFINITY, f64::min),
+                ys.iter().copied().fold(f64::INFINITY, f64::min),
+                xs.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                ys.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            )
+        })
+        .collect();
+
+    (!boxes.is_empty()).then_some(boxes)
+}
+
+/// The page text whose glyphs sit inside the given boxes, one box per line of
+/// the highlight, read top to bottom.
+pub fn text_under(glyphs: &[Glyph], boxes: &[(f64, f64, f64, f64)]) -> Option<String> {
+    let mut lines = Vec::new();
+
+    for &(left, bottom, right, top) in boxes {
+        let mut line = String::new();
+        let mut last_end: Option<f64> = None;
+        for glyph in glyphs {
+            let (x, y) = glyph.center();
+            if x < left || x > right || y < bottom || y > top {
+                continue;
+            }
+            // A visible gap is a space the content stream left implicit.
+            if let Some(end) = last_end {
+                if glyph.x - end > glyph.size * 0.15 && !line.ends_with(' ') {
+                    line.push(' ');
+                }
+            }
+            line.push_str(&glyph.text);
+            last_end = Some(glyph.x + glyph.width);
+        }
+
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+
+    (!lines.is_empty()).then(|| lines.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(x: f64, y: f64, text: &str) -> Glyph {
+        Glyph {
+            x,
+            y,
+            width: 6.0,
+            size: 12.0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn picks_the_glyphs_inside_each_box_and_restores_gaps() {
+        let glyphs = vec![
+            glyph(0.0, 700.0, "n"),
+            glyph(6.0, 700.0, "o"),
+            glyph(20.0, 700.0, "y"),
+            glyph(26.0, 700.0, "e"),
+            glyph(32.0, 700.0, "s"),
+            glyph(0.0, 680.0, "t"),
+            glyph(6.0, 680.0, "w"),
+            glyph(12.0, 680.0, "o"),
+        ];
+
+        // Covers "yes" on the first line and all of the second.
+        let boxes = [(18.0, 696.0, 40.0, 712.0), (0.0, 676.0, 20.0, 692.0)];
+        assert_eq!(text_under(&glyphs, &boxes).as_deref(), Some("yes two"));
+
+        // A box over both words of the first line keeps them apart.
+        let wide = [(0.0, 696.0, 40.0, 712.0)];
+        assert_eq!(text_under(&glyphs, &wide).as_deref(), Some("no yes"));
+
+        // A box over nothing yields nothing, not an empty string.
+        assert_eq!(text_under(&glyphs, &[(100.0, 0.0, 200.0, 10.0)]), None);
+    }
+}
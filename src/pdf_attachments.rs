@@ -0,0 +1,281 @@
+//! Files embedded in a PDF.
+//!
+//! Portfolios and e-invoices (ZUGFeRD, Factur-X) carry whole files inside the
+//! PDF: the machine-readable XML invoice, the spreadsheet behind a report. They
+//! live in the catalog's `/EmbeddedFiles` name tree or hang off a
+//! `FileAttachment` annotation, and never show up in the page text.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+
+/// A deepest sensible name tree. Guards against a `/Kids` cycle.
+const MAX_TREE_DEPTH: usize = 32;
+/// Most numbered names [`write_new_file`] tries before giving up.
+const MAX_RENAMES: usize = 999;
+
+/// One embedded file and its bytes.
+#[derive(Debug, Clone, Serialize)]
+pub struct Attachment {
+    /// The file name the author gave it, which is not a safe path: see
+    /// [`safe_file_name`].
+    pub name: String,
+    pub description: Option<String>,
+    /// Declared by the PDF's `/Subtype`, or guessed from the extension.
+    pub mime_type: Option<String>,
+    pub size: usize,
+    /// 1-based page of a `FileAttachment` annotation. Files in the document's
+    /// name tree belong to no page.
+    pub page: Option<usize>,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Lower-cased extension of the file name, if it has one.
+    pub fn extension(&self) -> Option<String> {
+        Path::new(&self.name)
+            .extension()
+            .and_then(|value| value.to_str())
+            .map(|value| value.to_ascii_lowercase())
+    }
+}
+
+/// Every embedded file, document-level ones first, then those attached to
+/// pages. A file reachable both ways is listed once.
+pub fn collect_attachments(pdf: &Document) -> Vec<Attachment> {
+    let mut collected = Vec::new();
+    let mut seen = HashSet::new();
+
+    if let Some(tree) = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| resolve(pdf, names).as_dict().ok())
+        .and_then(|names| names.get(b"EmbeddedFiles").ok())
+    {
+        let mut visited = HashSet::new();
+        walk_name_tree(pdf, tree, &mut visited, &mut seen, &mut collected, 0);
+    }
+
+    for (number, page_id) in pdf.get_pages() {
+        let Some(annots) = pdf
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|page| page.get(b"Annots").ok())
+            .and_then(|annots| resolve(pdf, annots).as_array().ok())
+        else {
+            continue;
+        };
+        for annot in annots {
+            let Ok(dict) = resolve(pdf, annot).as_dict() else {
+                continue;
+            };
+            if dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"FileAttachment") {
+                continue;
+            }
+            let Ok(spec) = dict.get(b"FS") else {
+                continue;
+            };
+            if let Some(mut attachment) = read_file_spec(pdf, spec, None, &mut seen) {
+                attachment.page = Some(number as usize);
+                collected.push(attachment);
+            }
+        }
+    }
+
+    collected
+}
+
+fn walk_name_tree(
+    pdf: &Document,
+    node: &Object,
+    visited: &mut HashSet<ObjectId>,
+    seen: &mut HashSet<ObjectId>,
+    collected: &mut Vec<Attachment>,
+    depth: usize,
+) {
+    if depth > MAX_TREE_DEPTH {
+        return;
+    }
+    if let Object::Reference(id) = node {
+        if !visited.insert(*id) {
+            return;
+        }
+    }
+    let Ok(dict) = resolve(pdf, node).as_dict() else {
+        return;
+    };
+
+    // Leaves hold `[key value key value ...]`; the key is the fallback name.
+    if let Ok(names) = dict.get(b"Names").and_then(Object::as_array) {
+        for pair in names.chunks_exact(2) {
+            let key = decode_text_string(resolve(pdf, &pair[0])).ok();
+            if let Some(attachment) = read_file_spec(pdf, &pair[1], key, seen) {
+                collected.push(attachment);
+            }
+        }
+    }
+    if let Ok(kids) = dict.get(b"Kids").and_then(Object::as_array) {
+        for kid in kids {
+            walk_name_tree(pdf, kid, visited, seen, collected, depth + 1);
+        }
+    }
+}
+
+/// Read a file specification whose `/EF` holds the embedded stream.
+/// `seen` records embedded streams already read, so a file that is both in the
+/// name tree and on a page is not listed twice.
+fn read_file_spec(
+    pdf: &Document,
+    spec: &Object,
+    fallback_name: Option<String>,
+    seen: &mut HashSet<ObjectId>,
+) -> Option<Attachment> {
+    let spec = resolve(pdf, spec).as_dict().ok()?;
+    let embedded = spec.get(b"EF").ok()?;
+    let embedded = resolve(pdf, embedded).as_dict().ok()?;
+    // `/UF` is the Unicode name; `/F` the older byte-string one.
+    let stream_ref = embedded.get(b"UF").or_else(|_| embedded.get(b"F")).ok()?;
+    if let Object::Reference(id) = stream_ref {
+        if !seen.insert(*id) {
+            return None;
+        }
+    }
+    let stream = resolve(pdf, stream_ref).as_stream().ok()?;
+    let data = stream.get_plain_content().ok()?;
+
+    let name = text_of(pdf, spec, b"UF")
+        .or_else(|| text_of(pdf, spec, b"F"))
+        .or(fallback_name)
+        .unwrap_or_else(|| "attachment".to_string());
+    let mime_type = stream
+        .dict
+        .get(b"Subtype")
+        .and_then(Object::as_name)
+        .ok()
+        .map(|subtype| String::from_utf8_lossy(subtype).to_string())
+        .or_else(|| guess_mime_type(&name).map(str::to_string));
+
+    Some(Attachment {
+        description: text_of(pdf, spec, b"Desc"),
+        mime_type,
+        size: data.len(),
+        page: None,
+        name,
+        data,
+    })
+}
+
+fn guess_mime_type(name: &str) -> Option<&'static str> {
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "pdf" => "application/pdf",
+        "xml" => "text/xml",
+        "json" => "application/json",
+        "txt" | "text" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "zip" => "application/zip",
+        _ => return None,
+    })
+}
+
+fn resolve<'a>(pdf: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+fn text_of(pdf: &Document, dict: &Dictionary, key: &[u8]) -> Option<String> {
+    let text = decode_text_string(resolve(pdf, dict.get(key).ok()?)).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The attachment's name reduced to a single path component.
+///
+/// The name comes from the document, and a document can claim to be
+/// `../../.bashrc`. Only the last component survives, and one that is empty or
+/// a dot-name falls back to `attachment-N`.
+pub fn safe_file_name(name: &str, index: usize) -> String {
+    let last = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    let last = last.trim();
+
+    if last.is_empty() || last.chars().all(|c| c == '.') {
+        format!("attachment-{:02}", index + 1)
+    } else {
+        last.to_string()
+    }
+}
+
+/// Write every attachment into `directory`, returning the paths in order.
+/// Nothing already in the directory is overwritten, including attachments
+/// written a moment earlier: see [`write_new_file`].
+pub fn write_attachments(
+    attachments: &[Attachment],
+    directory: &Path,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    fs::create_dir_all(directory)?;
+    attachments
+        .iter()
+        .enumerate()
+        .map(|(index, attachment)| {
+            let name = safe_file_name(&attachment.name, index);
+            Ok(write_new_file(directory, &name, &attachment.data)?)
+        })
+        .collect()
+}
+
+/// Write `data` to a new file called `name` in `directory` and return its
+/// path. When the name is taken the file becomes `02-name`, then `03-name`
+/// and so on: an existing file, or a symlink someone left there, is never
+/// opened.
+pub fn write_new_file(directory: &Path, name: &str, data: &[u8]) -> io::Result<PathBuf> {
+    for attempt in 1..=MAX_RENAMES {
+        let path = match attempt {
+            1 => directory.join(name),
+            _ => directory.join(format!("{attempt:02}-{name}")),
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(data)?;
+                return Ok(path);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already holds {MAX_RENAMES} files called {name}", directory.display()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostile_names_stay_inside_the_target_directory() {
+        assert_eq!(safe_file_name("invoice.xml", 0), "invoice.xml");
+        assert_eq!(safe_file_name("../../.bashrc", 0), ".bashrc");
+        assert_eq!(safe_file_name("C:\\Users\\x\\data.csv", 0), "data.csv");
+        assert_eq!(safe_file_name("..", 2), "attachment-03");
+        assert_eq!(safe_file_name("dir/", 0), "attachment-01");
+    }
+}
@@ -0,0 +1,199 @@
+//! A PDF's own table of contents.
+//!
+//! Bookmarks live in the catalog's `/Outlines` tree: each item has a title,
+//! a `/First` child and a `/Next` sibling, and points at a page either
+//! directly, through a `GoTo` action, or by a name looked up in the
+//! document's named destinations.
+
+use std::collections::{HashMap, HashSet};
+
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId};
+
+/// A deepest sensible bookmark or name tree. Guards against runaway nesting.
+const MAX_TREE_DEPTH: usize = 32;
+
+/// One entry of the bookmarks a PDF reader shows in its sidebar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub title: String,
+    /// Nesting depth, 0 for the outermost entries.
+    pub level: usize,
+    /// 1-based page it points at.
+    pub page: usize,
+}
+
+/// Every bookmark that points at a page, in reading order. Bookmarks that
+/// point elsewhere, such as at a web address, are left out, but their
+/// children are kept.
+pub fn collect_bookmarks(pdf: &Document) -> Vec<Bookmark> {
+    let Some(first) = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|outlines| resolve(pdf, outlines).as_dict().ok())
+        .and_then(|outlines| outlines.get(b"First").ok())
+    else {
+        return Vec::new();
+    };
+
+    let pages = pdf
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number as usize))
+        .collect();
+    let mut walk = Walk {
+        pdf,
+        pages,
+        visited: HashSet::new(),
+        bookmarks: Vec::new(),
+    };
+    walk.siblings(first, 0);
+    walk.bookmarks
+}
+
+struct Walk<'a> {
+    pdf: &'a Document,
+    pages: HashMap<ObjectId, usize>,
+    /// Items already read, so a `/Next` or `/First` loop ends.
+    visited: HashSet<ObjectId>,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Walk<'_> {
+    fn siblings(&mut self, first: &Object, level: usize) {
+        if level >= MAX_TREE_DEPTH {
+            return;
+        }
+        let mut next = Some(first);
+        while let Some(object) = next {
+            if let Object::Reference(id) = object {
+                if !self.visited.insert(*id) {
+                    return;
+                }
+            }
+            let Ok(item) = resolve(self.pdf, object).as_dict() else {
+                return;
+            };
+
+            if let (Some(title), Some(page)) =
+                (text_of(self.pdf, item, b"Title"), self.target(item))
+            {
+                self.bookmarks.push(Bookmark { title, level, page });
+            }
+            if let Ok(child) = item.get(b"First") {
+                self.siblings(child, level + 1);
+            }
+            next = item.get(b"Next").ok();
+        }
+    }
+
+    /// The page an item's `/Dest` or `GoTo` action leads to.
+    fn target(&self, item: &Dictionary) -> Option<usize> {
+        let destination = match item.get(b"Dest") {
+            Ok(destination) => destination,
+            Err(_) => {
+                let action = resolve(self.pdf, item.get(b"A").ok()?).as_dict().ok()?;
+                if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+                    return None;
+                }
+                action.get(b"D").ok()?
+            }
+        };
+        self.page_of(destination)
+    }
+
+    /// A destination is `[page /Fit …]`, a dictionary holding one under
+    /// `/D`, or a name standing for one.
+    fn page_of(&self, destination: &Object) -> Option<usize> {
+        match resolve(self.pdf, destination) {
+            Object::Array(array) => match array.first()? {
+                Object::Reference(id) => self.pages.get(id).copied(),
+                // Remote-document style: a 0-based page index.
+                Object::Integer(index) => usize::try_from(*index).ok().map(|index| index + 1),
+                _ => None,
+            },
+            // Only an array is followed out of a dictionary, so one pointing
+            // back at itself cannot recurse.
+            Object::Dictionary(dict) => match resolve(self.pdf, dict.get(b"D").ok()?) {
+                array @ Object::Array(_) => self.page_of(array),
+                _ => None,
+            },
+            Object::Name(name) | Object::String(name, _) => {
+                let target = self.named(name)?;
+                // A name resolving to another name is not followed, which
+                // keeps a self-referencing one from recursing.
+                match resolve(self.pdf, &target) {
+                    Object::Name(_) | Object::String(..) => None,
+                    _ => self.page_of(&target),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The destination `name` stands for, from the catalog's `/Dests`
+    /// dictionary or its `/Names` → `/Dests` name tree.
+    fn named(&self, name: &[u8]) -> Option<Object> {
+        let catalog = self.pdf.catalog().ok()?;
+        if let Some(found) = catalog
+            .get(b"Dests")
+            .ok()
+            .and_then(|dests| resolve(self.pdf, dests).as_dict().ok())
+            .and_then(|dests| dests.get(name).ok())
+        {
+            return Some(found.clone());
+        }
+
+        let tree = catalog
+            .get(b"Names")
+            .ok()
+            .and_then(|names| resolve(self.pdf, names).as_dict().ok())
+            .and_then(|names| names.get(b"Dests").ok())?;
+        let mut visited = HashSet::new();
+        find_in_name_tree(self.pdf, tree, name, &mut visited, 0)
+    }
+}
+
+fn find_in_name_tree(
+    pdf: &Document,
+    node: &Object,
+    name: &[u8],
+    visited: &mut HashSet<ObjectId>,
+    depth: usize,
+) -> Option<Object> {
+    if depth >= MAX_TREE_DEPTH {
+        return None;
+    }
+    if let Object::Reference(id) = node {
+        if !visited.insert(*id) {
+            return None;
+        }
+    }
+    let node = resolve(pdf, node).as_dict().ok()?;
+
+    if let Ok(pairs) = node.get(b"Names").and_then(Object::as_array) {
+        for pair in pairs.chunks(2) {
+            if let [Object::String(key, _), value] = pair {
+                if key == name {
+                    return Some(value.clone());
+                }
+            }
+        }
+    }
+    let kids = node.get(b"Kids").and_then(Object::as_array).ok()?;
+    kids.iter()
+        .find_map(|kid| find_in_name_tree(pdf, kid, name, visited, depth + 1))
+}
+
+fn resolve<'a>(pdf: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+fn text_of(pdf: &Document, dict: &Dictionary, key: &[u8]) -> Option<String> {
+    let text = decode_text_string(resolve(pdf, dict.get(key).ok()?)).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
@@ -1,8 +1,28 @@
 use std::error::Error;
+use std::fmt;
+use std::io::Cursor;
+use std::ops::RangeInclusive;
 use std::panic::{self, AssertUnwindSafe};
 
+use clap::ValueEnum;
+use image::codecs::jpeg::JpegDecoder;
 use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+use lopdf::encryption::DecryptionError;
 use lopdf::{Dictionary, Document, Object, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::image_hash::{self, Fingerprint};
+use crate::image_meta::ImageMetadata;
+use crate::loader::LoadOptions;
+use crate::outline::{Outline, Segment, SegmentKind};
+use crate::pdf_annotations::{self, Annotation, FormField};
+use crate::pdf_attachments::{self, Attachment};
+use crate::pdf_bookmarks::{self, Bookmark};
+use crate::pdf_furniture::{self, FurnitureKind, FurnitureLine};
+use crate::pdf_glyphs;
+use crate::pdf_layout;
+use crate::pdf_placement::{self, Drawn, InlineImage, Placement};
+use crate::pdf_reflow;
 
 /// Largest image flerp will decode, in pixels. Keeps a malformed `/Width` from
 /// turning into a multi-gigabyte allocation.
@@ -17,6 +37,33 @@ pub struct PdfImageAsset {
     pub encoding: String,
     pub color_space: String,
     pub image: DynamicImage,
+    /// Perceptual hash of the pixels; see [`image_hash`](crate::image_hash).
+    pub hash: u64,
+    /// Index into [`PdfDocument::images`] of the first image this one is a
+    /// near-identical copy of, such as a logo embedded again on every page.
+    pub duplicate_of: Option<usize>,
+    /// Where the page first draws it; `None` when it is listed in the page's
+    /// resources but never painted.
+    pub placement: Option<Placement>,
+    /// Painted from inside the content stream (`BI … EI`) rather than an
+    /// XObject.
+    pub inline: bool,
+    /// EXIF and colour profile details carried inside a JPEG image. Its
+    /// orientation is not applied: the page's transform decides which way up
+    /// it is drawn.
+    pub metadata: Option<ImageMetadata>,
+    /// The JPEG file as stored in the PDF, once any compression wrapped
+    /// around it is undone, so it can be saved without re-encoding. `None`
+    /// for images stored as raw samples.
+    pub original: Option<Vec<u8>>,
+}
+
+/// What [`decode_image`] makes of one image stream.
+struct DecodedImage {
+    image: DynamicImage,
+    encoding: String,
+    metadata: Option<ImageMetadata>,
+    original: Option<Vec<u8>>,
 }
 
 /// An image flerp found but could not turn into pixels.
@@ -35,9 +82,11 @@ pub struct PdfPage {
     pub start_line: usize,
     pub line_count: usize,
     pub image_count: usize,
+    /// The page had no text layer and its text was recognised from its images.
+    pub ocr: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct PdfDocument {
     /// Every page's text, joined in page order. Page boundaries are tracked in
     /// `pages` rather than injected as marker lines, so search results and the
@@ -46,8 +95,22 @@ pub struct PdfDocument {
     pub pages: Vec<PdfPage>,
     pub images: Vec<PdfImageAsset>,
     pub skipped: Vec<SkippedImage>,
-    /// Set when the page tree could be read for text but not for images.
+    /// Set when the text could be read but the image streams could not.
     pub image_error: Option<String>,
+    /// Notes, highlights and other review marks, in page order.
+    pub annotations: Vec<Annotation>,
+    /// Filled-in AcroForm fields.
+    pub form_fields: Vec<FormField>,
+    /// Files embedded in the document or attached to its pages.
+    pub attachments: Vec<Attachment>,
+    /// Running headers, footers and page numbers in `text`, in line order.
+    pub furniture: Vec<FurnitureLine>,
+    /// Set when a page needed OCR and the engine could not be run.
+    pub ocr_error: Option<String>,
+    /// 1-based pages whose text layer could not be parsed; they read as empty.
+    pub unreadable_pages: Vec<usize>,
+    /// The document's bookmarks, in the order it lists them.
+    pub bookmarks: Vec<Bookmark>,
 }
 
 impl PdfDocument {
@@ -55,6 +118,74 @@ impl PdfDocument {
         self.pages.len()
     }
 
+    /// The pages as an [`Outline`], for the format-neutral page navigation,
+    /// with the bookmarks as headings nested inside them.
+    pub fn outline(&self) -> Outline {
+        let total_lines = self
+            .pages
+            .last()
+            .map_or(0, |page| page.start_line + page.line_count);
+        let start_of = |number: usize| {
+            self.pages
+                .get(number.saturating_sub(1))
+                .map_or(total_lines, |page| page.start_line)
+        };
+
+        let mut segments: Vec<Segment> = self
+            .pages
+            .iter()
+            .map(|page| Segment {
+                kind: SegmentKind::Page,
+                title: format!("Page {}", page.number),
+                level: 0,
+                number: page.number,
+                start_line: page.start_line,
+                line_count: page.line_count,
+            })
+            .collect();
+
+        // A bookmark runs until the next one at its own or an outer level.
+        // Bookmarks only point at pages, so one sharing its page with the
+        // next covers that whole page rather than nothing.
+        let mut numbers: Vec<usize> = Vec::new();
+        for (index, bookmark) in self.bookmarks.iter().enumerate() {
+            let start_line = start_of(bookmark.page);
+            let end_line = self.bookmarks[index + 1..]
+                .iter()
+                .find(|next| next.level <= bookmark.level)
+                .map_or(total_lines, |next| start_of(next.page));
+            let end_line = end_line.max(start_of(bookmark.page + 1)).min(total_lines);
+
+            if numbers.len() <= bookmark.level {
+                numbers.resize(bookmark.level + 1, 0);
+            }
+            numbers[bookmark.level] += 1;
+            segments.push(Segment {
+                kind: SegmentKind::Heading,
+                title: bookmark.title.clone(),
+                level: bookmark.level + 1,
+                number: numbers[bookmark.level],
+                start_line,
+                line_count: end_line.saturating_sub(start_line),
+            });
+        }
+        Outline::new(segments)
+    }
+
+    /// Pages showing image `index` or a copy of it, in document order.
+    pub fn image_copy_pages(&self, index: usize) -> Vec<usize> {
+        let first = self
+            .images
+            .get(index)
+            .map_or(index, |image| image.duplicate_of.unwrap_or(index));
+        self.images
+            .iter()
+            .enumerate()
+            .filter(|(other, image)| *other == first || image.duplicate_of == Some(first))
+            .map(|(_, image)| image.page)
+            .collect()
+    }
+
     /// Page containing `line` (0-based), as an index into `pages`.
     pub fn page_of_line(&self, line: usize) -> usize {
         match self
@@ -65,17 +196,224 @@ impl PdfDocument {
             Err(index) => index.saturating_sub(1),
         }
     }
+
+    /// What kind of page furniture `line` (0-based) is, if any.
+    pub fn furniture_at(&self, line: usize) -> Option<FurnitureKind> {
+        self.furniture
+            .binary_search_by_key(&line, |entry| entry.line)
+            .ok()
+            .map(|index| self.furniture[index].kind)
+    }
+
+    /// The text with every furniture line removed, for analysis that should
+    /// only see the document's content.
+    pub fn text_without_furniture(&self) -> String {
+        let mut text = String::with_capacity(self.text.len());
+        for (index, line) in self.text.lines().enumerate() {
+            if self.furniture_at(index).is_none() {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// The text of `pages` (indices into [`Self::pages`], clamped to the
+    /// document), less its furniture when `skip_furniture` is set.
+    pub fn pages_text(&self, pages: RangeInclusive<usize>, skip_furniture: bool) -> String {
+        let (Some(first), Some(last)) = (
+            self.pages.get(*pages.start()),
+            self.pages.get((*pages.end()).min(self.pages.len().saturating_sub(1))),
+        ) else {
+            return String::new();
+        };
+        let start = first.start_line;
+        let end = last.start_line + last.line_count;
+
+        let mut text = String::new();
+        for (index, line) in self.text.lines().enumerate().take(end).skip(start) {
+            if !(skip_furniture && self.furniture_at(index).is_some()) {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        text
+    }
 }
 
+/// How page text is read out of the content stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[value(rename_all = "lower")]
+pub enum ExtractionMode {
+    /// Content-stream order, as `pdf-extract` reads it. Fast, and right for
+    /// single-column documents.
+    #[default]
+    Flow,
+    /// Rebuilt from glyph positions: columns read one at a time, table cells
+    /// kept on their row.
+    Layout,
+}
+
+impl ExtractionMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExtractionMode::Flow => "Flow",
+            ExtractionMode::Layout => "Layout",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            ExtractionMode::Flow => ExtractionMode::Layout,
+            ExtractionMode::Layout => ExtractionMode::Flow,
+        }
+    }
+}
+
+/// How a PDF's text is read out, passed in [`LoadOptions::extensions`]. A PDF
+/// loaded without one is read with the defaults.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfOptions {
+    pub extraction: ExtractionMode,
+    /// Rejoin soft-wrapped lines and hyphenated words into paragraphs.
+    pub reflow: bool,
+}
+
+/// Why an encrypted PDF would not open.
+///
+/// Kept apart from the other load failures so the TUI can answer it with a
+/// password prompt instead of an error message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordError {
+    /// The document needs a password and none was given.
+    Required,
+    /// A password was given and the document rejected it.
+    Incorrect,
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::Required => {
+                write!(f, "the PDF is encrypted; supply its password with --password")
+            }
+            PasswordError::Incorrect => write!(f, "the password does not open this PDF"),
+        }
+    }
+}
+
+impl Error for PasswordError {}
+
 pub fn load(file_path: &str) -> Result<PdfDocument, Box<dyn Error>> {
-    let page_texts = extract_page_texts(file_path)?;
+    load_with_password(file_path, None)
+}
+
+/// Load a PDF, decrypting it with `password` when it is encrypted.
+///
+/// The password is ignored for documents that open without one, so passing it
+/// unconditionally is safe.
+pub fn load_with_password(
+    file_path: &str,
+    password: Option<&str>,
+) -> Result<PdfDocument, Box<dyn Error>> {
+    load_with_options(
+        file_path,
+        &LoadOptions {
+            password,
+            ..LoadOptions::default()
+        },
+    )
+}
+
+pub fn load_with_options(
+    file_path: &str,
+    options: &LoadOptions,
+) -> Result<PdfDocument, Box<dyn Error>> {
+    let reading = options.extensions.get::<PdfOptions>().copied().unwrap_or_default();
+    let pdf = open(file_path, options.password)?;
+    let (mut page_texts, unreadable_pages) = extract_page_texts(&pdf, reading.extraction)?;
+
+    // Image extraction is best-effort: a document whose text we can read is
+    // still worth opening even if its image streams defeat us.
+    let collected = contain_panics(|| collect_images(&pdf));
+    let image_error = collected
+        .is_none()
+        .then(|| "the PDF image streams could not be parsed".to_string());
+    let (images, skipped) = collected.unwrap_or_default();
+
+    // With no page readable and no image to show there is nothing to open.
+    if !page_texts.is_empty() && unreadable_pages.len() == page_texts.len() && images.is_empty() {
+        return Err("the PDF text layer could not be parsed".into());
+    }
+
+    let (ocr_pages, ocr_error) = if options.ocr {
+        recognize_scanned_pages(&mut page_texts, &images)
+    } else {
+        (vec![false; page_texts.len()], None)
+    };
+
+    let (mut text, mut pages) = join_pages(&page_texts);
+    for (page, ocr) in pages.iter_mut().zip(ocr_pages) {
+        page.image_count = images.iter().filter(|asset| asset.page == page.number).count();
+        page.ocr = ocr;
+    }
 
+    // Furniture is found on the lines as typeset, before reflow can run a
+    // footer into the paragraph above it.
+    let mut furniture = pdf_furniture::detect(&pages, &text);
+    if reading.reflow {
+        let reflowed = pdf_reflow::reflow(&pages, &text, &furniture);
+        text = reflowed.text;
+        pages = reflowed.pages;
+        furniture = reflowed.furniture;
+    }
+
+    let mut document = PdfDocument {
+        text,
+        pages,
+        images,
+        skipped,
+        image_error,
+        annotations: Vec::new(),
+        form_fields: Vec::new(),
+        attachments: Vec::new(),
+        furniture,
+        ocr_error,
+        unreadable_pages,
+        bookmarks: Vec::new(),
+    };
+
+    // Review marks are extras too: a malformed `/Annots` array should cost the
+    // notes, not the document.
+    if let Some((annotations, form_fields)) = contain_panics(|| {
+        (
+            pdf_annotations::collect_annotations(&pdf),
+            pdf_annotations::collect_form_fields(&pdf),
+        )
+    }) {
+        document.annotations = annotations;
+        document.form_fields = form_fields;
+    }
+    if let Some(attachments) = contain_panics(|| pdf_attachments::collect_attachments(&pdf)) {
+        document.attachments = attachments;
+    }
+    if let Some(bookmarks) = contain_panics(|| pdf_bookmarks::collect_bookmarks(&pdf)) {
+        document.bookmarks = bookmarks;
+    }
+
+    Ok(document)
+}
+
+/// One text made of `page_texts` in order, and where each page sits in it.
+/// Pages come back without images or OCR; the caller fills those in.
+fn join_pages(page_texts: &[impl AsRef<str>]) -> (String, Vec<PdfPage>) {
     let mut text = String::new();
     let mut pages = Vec::with_capacity(page_texts.len());
     let mut line_cursor = 0usize;
 
     for (index, page_text) in page_texts.iter().enumerate() {
-        let normalized = page_text.replace("\r\n", "\n");
+        let normalized = page_text.as_ref().replace("\r\n", "\n");
         let body = normalized.trim_matches('\n');
         // An empty page still occupies the single newline we push below.
         let line_count = body.lines().count().max(1);
@@ -85,104 +423,265 @@ pub fn load(file_path: &str) -> Result<PdfDocument, Box<dyn Error>> {
             start_line: line_cursor,
             line_count,
             image_count: 0,
+            ocr: false,
         });
 
         text.push_str(body);
         text.push('\n');
         line_cursor += line_count;
     }
+    (text, pages)
+}
 
-    let mut document = PdfDocument {
-        text,
-        pages,
-        images: Vec::new(),
-        skipped: Vec::new(),
-        image_error: None,
+/// Give every page that has images but no text the text OCR reads off those
+/// images. Returns which pages were recognised, and why OCR could not run if it
+/// could not; the first failure stops it, since the next page would fail the
+/// same way.
+#[cfg(feature = "ocr")]
+fn recognize_scanned_pages(
+    page_texts: &mut [String],
+    images: &[PdfImageAsset],
+) -> (Vec<bool>, Option<String>) {
+    let mut ocr_pages = vec![false; page_texts.len()];
+    for (index, page_text) in page_texts.iter_mut().enumerate() {
+        if !page_text.trim().is_empty() {
+            continue;
+        }
+        let mut recognized = Vec::new();
+        for asset in images.iter().filter(|asset| asset.page == index + 1) {
+            match crate::ocr::recognize(&asset.image) {
+                Ok(text) if !text.is_empty() => recognized.push(text),
+                Ok(_) => {}
+                Err(error) => return (ocr_pages, Some(error.to_string())),
+            }
+        }
+        if !recognized.is_empty() {
+            *page_text = recognized.join("\n\n");
+            ocr_pages[index] = true;
+        }
+    }
+    (ocr_pages, None)
+}
+
+/// Without the `ocr` feature a scanned page stays empty.
+#[cfg(not(feature = "ocr"))]
+fn recognize_scanned_pages(
+    page_texts: &mut [String],
+    _images: &[PdfImageAsset],
+) -> (Vec<bool>, Option<String>) {
+    (vec![false; page_texts.len()], None)
+}
+
+/// Parse the file once and decrypt it, so text and images read the same
+/// plaintext objects.
+fn open(file_path: &str, password: Option<&str>) -> Result<Document, Box<dyn Error>> {
+    let mut pdf = Document::load(file_path)?;
+
+    // lopdf already tries the empty user password while loading, which opens
+    // the common "permissions only" documents. One still carrying `/Encrypt`
+    // needs a real password, for RC4 and AES alike.
+    if !pdf.is_encrypted() {
+        return Ok(pdf);
+    }
+
+    let Some(password) = password else {
+        return Err(PasswordError::Required.into());
     };
+    match pdf.decrypt(password) {
+        Ok(()) => Ok(pdf),
+        Err(lopdf::Error::Decryption(DecryptionError::IncorrectPassword)) => {
+            Err(PasswordError::Incorrect.into())
+        }
+        Err(error) => Err(error.into()),
+    }
+}
 
-    // Image extraction is best-effort: a document whose text we can read is
-    // still worth opening even if its page tree defeats us.
-    match Document::load(file_path) {
-        Ok(pdf) => collect_images(&pdf, &mut document),
-        Err(error) => document.image_error = Some(error.to_string()),
+/// `pdf-extract` panics on some malformed documents, and on any page whose
+/// content stream `lopdf` cannot parse, such as one with a compressed inline
+/// image. Contain that per page, so a bad page reads as empty instead of
+/// taking the document or the terminal down with it. The numbers of those
+/// pages come back alongside the texts.
+fn extract_page_texts(
+    pdf: &Document,
+    mode: ExtractionMode,
+) -> Result<(Vec<String>, Vec<usize>), Box<dyn Error>> {
+    let numbers: Vec<u32> = pdf.get_pages().keys().copied().collect();
+    let mut texts = Vec::with_capacity(numbers.len());
+    let mut unreadable = Vec::new();
+
+    for number in &numbers {
+        let outcome = contain_panics(|| match mode {
+            ExtractionMode::Flow => {
+                let mut text = String::new();
+                let mut output = pdf_extract::PlainTextOutput::new(&mut text);
+                pdf_extract::output_doc_page(pdf, &mut output, *number).map(|_| text)
+            }
+            ExtractionMode::Layout => pdf_glyphs::page_glyphs(pdf, *number)
+                .map(|glyphs| pdf_layout::layout_text(&glyphs)),
+        });
+        match outcome {
+            Some(result) => texts.push(result?),
+            None => {
+                texts.push(String::new());
+                unreadable.push(*number as usize);
+            }
+        }
     }
 
-    Ok(document)
+    Ok((texts, unreadable))
 }
 
-/// `pdf-extract` panics on some malformed documents. Contain that so a bad file
-/// surfaces as an error instead of taking the terminal down mid-render.
-fn extract_page_texts(file_path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+/// Run `work` with panics caught and the default hook silenced, so a panic deep
+/// in a PDF library does not scribble over the TUI on its way out.
+fn contain_panics<T>(work: impl FnOnce() -> T) -> Option<T> {
     let previous_hook = panic::take_hook();
     panic::set_hook(Box::new(|_| {}));
-    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
-        pdf_extract::extract_text_by_pages(file_path)
-    }));
+    let outcome = panic::catch_unwind(AssertUnwindSafe(work));
     panic::set_hook(previous_hook);
-
-    match outcome {
-        Ok(result) => Ok(result?),
-        Err(_) => Err("the PDF text layer could not be parsed".into()),
-    }
+    outcome.ok()
 }
 
-fn collect_images(pdf: &Document, document: &mut PdfDocument) {
+fn collect_images(pdf: &Document) -> (Vec<PdfImageAsset>, Vec<SkippedImage>) {
+    let mut images = Vec::new();
+    let mut skipped = Vec::new();
     let mut seen = Vec::new();
 
     for (page_number, page_id) in pdf.get_pages() {
         let page_number = page_number as usize;
-        let Ok(raw_images) = pdf.get_page_images(page_id) else {
-            continue;
-        };
+        let drawings = pdf_placement::page_drawings(pdf, page_id);
+        // A page with no /XObject resources can still paint inline images.
+        let raw_images = pdf.get_page_images(page_id).unwrap_or_default();
 
         for raw in raw_images {
             // The same XObject is usually shared by every page that shows it
-            // (headers, logos, watermarks). Keep the first occurrence only.
+            // (headers, logos, watermarks). Keep the first occurrence only;
+            // copies embedded as separate objects are matched by their pixels
+            // below.
             if seen.contains(&raw.id) {
                 continue;
             }
             seen.push(raw.id);
 
-            let width = raw.width.max(0) as u32;
-            let height = raw.height.max(0) as u32;
-
-            match decode_image(pdf, &raw) {
-                Ok((image, encoding)) => {
-                    document.images.push(PdfImageAsset {
-                        page: page_number,
-                        width: image.width(),
-                        height: image.height(),
-                        encoding,
-                        color_space: raw
-                            .color_space
-                            .clone()
-                            .unwrap_or_else(|| "unspecified".to_string()),
-                        image,
-                    });
-                }
-                Err(reason) => document.skipped.push(SkippedImage {
-                    page: page_number,
-                    width,
-                    height,
-                    reason,
-                }),
-            }
+            // Where it is first drawn on this page; an image listed in the
+            // resources but never painted has no placement.
+            let placement = drawings.iter().find_map(|drawn| match drawn {
+                Drawn::XObject { id, placement } if *id == raw.id => Some(*placement),
+                _ => None,
+            });
+            let (asset, skip) = decode_asset(pdf, &raw, page_number, placement, false);
+            images.extend(asset);
+            skipped.extend(skip);
+        }
+
+        for drawn in &drawings {
+            let Drawn::Inline { image, placement } = drawn else {
+                continue;
+            };
+            let raw = inline_raw(image);
+            let (asset, skip) = decode_asset(pdf, &raw, page_number, Some(*placement), true);
+            images.extend(asset);
+            skipped.extend(skip);
         }
     }
 
-    for page in &mut document.pages {
-        page.image_count = document
-            .images
+    let fingerprints: Vec<Fingerprint> = images.iter().map(|asset| Fingerprint::of(&asset.image)).collect();
+    for ((asset, fingerprint), duplicate_of) in images
+        .iter_mut()
+        .zip(&fingerprints)
+        .zip(image_hash::duplicates(&fingerprints))
+    {
+        asset.hash = fingerprint.hash;
+        asset.duplicate_of = duplicate_of;
+    }
+
+    (images, skipped)
+}
+
+/// Decode one image into an asset, or say why it was skipped.
+fn decode_asset(
+    pdf: &Document,
+    raw: &lopdf::xobject::PdfImage<'_>,
+    page: usize,
+    placement: Option<Placement>,
+    inline: bool,
+) -> (Option<PdfImageAsset>, Option<SkippedImage>) {
+    match decode_image(pdf, raw) {
+        Ok(DecodedImage {
+            image,
+            encoding,
+            metadata,
+            original,
+        }) => (
+            Some(PdfImageAsset {
+                page,
+                width: image.width(),
+                height: image.height(),
+                encoding,
+                color_space: raw
+                    .color_space
+                    .clone()
+                    .unwrap_or_else(|| "unspecified".to_string()),
+                image,
+                hash: 0,
+                duplicate_of: None,
+                placement,
+                inline,
+                metadata,
+                original,
+            }),
+            None,
+        ),
+        Err(reason) => (
+            None,
+            Some(SkippedImage {
+                page,
+                width: raw.width.max(0) as u32,
+                height: raw.height.max(0) as u32,
+                reason,
+            }),
+        ),
+    }
+}
+
+/// An inline image in the shape `lopdf` gives XObject images, so the same
+/// decoder handles both.
+fn inline_raw(image: &InlineImage) -> lopdf::xobject::PdfImage<'_> {
+    let dict = &image.dict;
+    let integer = |key: &[u8]| dict.get(key).and_then(Object::as_i64).ok();
+    let color_space = match dict.get(b"ColorSpace") {
+        Ok(Object::Name(name)) => Some(String::from_utf8_lossy(name).to_string()),
+        Ok(Object::Array(items)) => items
+            .first()
+            .and_then(|head| head.as_name().ok())
+            .map(|name| String::from_utf8_lossy(name).to_string()),
+        _ => None,
+    };
+    let filters = match dict.get(b"Filter") {
+        Ok(Object::Name(name)) => vec![String::from_utf8_lossy(name).to_string()],
+        Ok(Object::Array(names)) => names
             .iter()
-            .filter(|asset| asset.page == page.number)
-            .count();
+            .filter_map(|name| name.as_name().ok())
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .collect(),
+        _ => Vec::new(),
+    };
+    lopdf::xobject::PdfImage {
+        // Inline images have no object of their own.
+        id: (0, 0),
+        width: integer(b"Width").unwrap_or(0),
+        height: integer(b"Height").unwrap_or(0),
+        color_space,
+        filters: Some(filters),
+        bits_per_component: integer(b"BitsPerComponent"),
+        content: &image.data,
+        origin_dict: dict,
     }
 }
 
 fn decode_image(
     pdf: &Document,
     raw: &lopdf::xobject::PdfImage<'_>,
-) -> Result<(DynamicImage, String), String> {
+) -> Result<DecodedImage, String> {
     let width = u32::try_from(raw.width).map_err(|_| "invalid width".to_string())?;
     let height = u32::try_from(raw.height).map_err(|_| "invalid height".to_string())?;
     if width == 0 || height == 0 {
@@ -194,12 +693,16 @@ fn decode_image(
 
     let filters = raw.filters.clone().unwrap_or_default();
 
-    let (image, encoding) = match filters.last().map(String::as_str) {
+    let (image, encoding, metadata, original) = match filters.last().map(String::as_str) {
         Some("DCTDecode") => {
             let bytes = predecode(raw.origin_dict, raw.content, &filters[..filters.len() - 1])?;
-            let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::Jpeg)
+            let mut decoder = JpegDecoder::new(Cursor::new(&bytes))
+                .map_err(|error| format!("JPEG decode failed: {error}"))?;
+            let metadata = ImageMetadata::read(&mut decoder);
+            let image = DynamicImage::from_decoder(decoder)
                 .map_err(|error| format!("JPEG decode failed: {error}"))?;
-            (image, "JPEG (DCTDecode)".to_string())
+            let metadata = (!metadata.is_empty()).then_some(metadata);
+            (image, "JPEG (DCTDecode)".to_string(), metadata, Some(bytes))
         }
         Some("JPXDecode") => return Err("JPEG 2000 (JPXDecode) is not supported".to_string()),
         Some("CCITTFaxDecode") => {
@@ -214,11 +717,16 @@ fn decode_image(
             } else {
                 format!("{} samples", filters.join(" + "))
             };
-            (image, encoding)
+            (image, encoding, None, None)
         }
     };
 
-    Ok((apply_soft_mask(pdf, raw.origin_dict, image), encoding))
+    Ok(DecodedImage {
+        image: apply_soft_mask(pdf, raw.origin_dict, image),
+        encoding,
+        metadata,
+        original,
+    })
 }
 
 /// Run the decompression filters that sit in front of the pixel data.
@@ -593,9 +1101,11 @@ fn decode_soft_mask(pdf: &Document, dict: &Dictionary) -> Option<GrayImage> {
         return None;
     }
 
+    // An uncompressed mask has no /Filter at all, which lopdf reports as an
+    // error rather than an empty list.
     let filters: Vec<String> = mask
         .filters()
-        .ok()?
+        .unwrap_or_default()
         .into_iter()
         .map(|filter| String::from_utf8_lossy(filter).to_string())
         .collect();
@@ -692,13 +1202,11 @@ mod tests {
         let document = PdfDocument {
             text: String::new(),
             pages: vec![
-                PdfPage { number: 1, start_line: 0, line_count: 10, image_count: 0 },
-                PdfPage { number: 2, start_line: 10, line_count: 5, image_count: 0 },
-                PdfPage { number: 3, start_line: 15, line_count: 7, image_count: 0 },
+                PdfPage { number: 1, start_line: 0, line_count: 10, image_count: 0, ocr: false },
+                PdfPage { number: 2, start_line: 10, line_count: 5, image_count: 0, ocr: false },
+                PdfPage { number: 3, start_line: 15, line_count: 7, image_count: 0, ocr: false },
             ],
-            images: Vec::new(),
-            skipped: Vec::new(),
-            image_error: None,
+            ..PdfDocument::default()
         };
 
         assert_eq!(document.page_of_line(0), 0);
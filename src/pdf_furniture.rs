@@ -0,0 +1,212 @@
+//! Running headers, footers and page numbers.
+//!
+//! A report that prints its title at the top of every page and "Confidential"
+//! at the bottom repeats those lines once per page, which makes them the most
+//! repeated lines and pushes their words up the keyword ranking. They are found
+//! here by where they sit and how often they recur, so analysis can leave them
+//! out and the viewer can show them for what they are.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::pdf_doc::PdfPage;
+
+/// How many non-blank lines at each end of a page are looked at.
+const ZONE_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FurnitureKind {
+    Header,
+    Footer,
+    PageNumber,
+}
+
+impl FurnitureKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            FurnitureKind::Header => "header",
+            FurnitureKind::Footer => "footer",
+            FurnitureKind::PageNumber => "page number",
+        }
+    }
+}
+
+/// One line of [`PdfDocument::text`](crate::pdf_doc::PdfDocument::text) that is
+/// page furniture rather than content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FurnitureLine {
+    /// 0-based line index into the document text.
+    pub line: usize,
+    pub kind: FurnitureKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Zone {
+    Top,
+    Bottom,
+}
+
+/// Every furniture line in the document, in line order.
+///
+/// A line is a header or footer when the same text, digits aside, sits near
+/// the top or bottom of more than half the pages; "Page 3 of 12" and "Page 4 of
+/// 12" are the same footer. A bare page number near either edge counts even
+/// when nothing else repeats. Single-page documents have nothing to compare,
+/// so only their page numbers are found.
+pub fn detect(pages: &[PdfPage], text: &str) -> Vec<FurnitureLine> {
+    let lines: Vec<&str> = text.lines().collect();
+    let page_number = page_number_pattern();
+
+    // The candidate lines of every page, by zone.
+    let zones: Vec<Vec<(usize, Zone)>> = pages
+        .iter()
+        .map(|page| {
+            let end = (page.start_line + page.line_count).min(lines.len());
+            let filled: Vec<usize> = (page.start_line..end)
+                .filter(|index| !lines[*index].trim().is_empty())
+                .collect();
+            let top = filled.iter().take(ZONE_LINES).map(|index| (*index, Zone::Top));
+            let bottom = filled
+                .iter()
+                .rev()
+                .take(ZONE_LINES)
+                .map(|index| (*index, Zone::Bottom));
+            top.chain(bottom).collect()
+        })
+        .collect();
+
+    // On how many pages each normalised line appears, per zone.
+    let mut counts: HashMap<(Zone, String), usize> = HashMap::new();
+    for candidates in &zones {
+        let unique: HashSet<(Zone, String)> = candidates
+            .iter()
+            .map(|(index, zone)| (*zone, normalize(lines[*index])))
+            .collect();
+        for key in unique {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let threshold = (pages.len() / 2 + 1).max(2);
+
+    let mut found: HashMap<usize, FurnitureKind> = HashMap::new();
+    for candidates in &zones {
+        for (index, zone) in candidates {
+            if found.contains_key(index) {
+                continue;
+            }
+            let line = lines[*index];
+            let kind = if page_number.is_match(strip_decoration(line)) {
+                Some(FurnitureKind::PageNumber)
+            } else if counts.get(&(*zone, normalize(line))).copied().unwrap_or(0) >= threshold {
+                Some(match zone {
+                    Zone::Top => FurnitureKind::Header,
+                    Zone::Bottom => FurnitureKind::Footer,
+                })
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                found.insert(*index, kind);
+            }
+        }
+    }
+
+    let mut furniture: Vec<FurnitureLine> = found
+        .into_iter()
+        .map(|(line, kind)| FurnitureLine { line, kind })
+        .collect();
+    furniture.sort_by_key(|entry| entry.line);
+    furniture
+}
+
+/// `12`, `Page 12`, `12 of 40`, `12 / 40`, case aside.
+fn page_number_pattern() -> Regex {
+    Regex::new(r"(?i)^(page\s+)?\d{1,4}(\s*(of|/)\s*\d{1,4})?$").expect("static regex")
+}
+
+/// Drop the dashes and bars page numbers are often dressed in: `- 3 -`.
+fn strip_decoration(line: &str) -> &str {
+    line.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '–' | '—' | '|' | '·'))
+}
+
+/// The line with digits and spacing made irrelevant, so running headers that
+/// carry a page or chapter number still compare equal.
+fn normalize(line: &str) -> String {
+    line.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .map(|c| if c.is_ascii_digit() { '#' } else { c.to_ascii_lowercase() })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages_of(texts: &[&[&str]]) -> (Vec<PdfPage>, String) {
+        let mut pages = Vec::new();
+        let mut text = String::new();
+        let mut start = 0;
+        for (index, lines) in texts.iter().enumerate() {
+            pages.push(PdfPage {
+                number: index + 1,
+                start_line: start,
+                line_count: lines.len(),
+                image_count: 0,
+                ocr: false,
+            });
+            for line in *lines {
+                text.push_str(line);
+                text.push('\n');
+            }
+            start += lines.len();
+        }
+        (pages, text)
+    }
+
+    #[test]
+    fn repeated_edges_and_bare_numbers_are_furniture_and_body_text_is_not() {
+        let (pages, text) = pages_of(&[
+            &["Quarterly Review", "Revenue grew.", "Costs fell.", "Confidential", "- 1 -"],
+            &["Quarterly Review", "Margins held.", "Confidential", "- 2 -"],
+            &["Chapter 3 notes", "Quarterly Review", "Outlook is calm.", "Confidential", "3"],
+        ]);
+
+        let found: Vec<(usize, FurnitureKind)> = detect(&pages, &text)
+            .into_iter()
+            .map(|entry| (entry.line, entry.kind))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                (0, FurnitureKind::Header),
+                (3, FurnitureKind::Footer),
+                (4, FurnitureKind::PageNumber),
+                (5, FurnitureKind::Header),
+                (7, FurnitureKind::Footer),
+                (8, FurnitureKind::PageNumber),
+                (10, FurnitureKind::Header),
+                (12, FurnitureKind::Footer),
+                (13, FurnitureKind::PageNumber),
+            ]
+        );
+    }
+
+    #[test]
+    fn running_heads_that_carry_a_page_number_still_match() {
+        let (pages, text) = pages_of(&[
+            &["Page 1 of 2 · Acme", "First page body."],
+            &["Page 2 of 2 · Acme", "Second page body."],
+        ]);
+
+        let lines: Vec<usize> = detect(&pages, &text).iter().map(|entry| entry.line).collect();
+        assert_eq!(lines, [0, 2]);
+    }
+}
@@ -0,0 +1,94 @@
+//! Positioned glyphs from a PDF page.
+//!
+//! `pdf-extract` flattens a page to a string, which throws away where each
+//! character was drawn. Anything that has to relate text to a place on the page
+//! -- the words under a highlight, say -- needs the positions back, so this
+//! listens to the same content-stream walk and keeps them.
+
+use lopdf::Document;
+use pdf_extract::{MediaBox, OutputDev, OutputError, Transform};
+
+/// One drawn character, in the page's user space: points, origin bottom-left,
+/// `y` growing upwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    /// Left edge of the glyph, on its baseline.
+    pub x: f64,
+    /// Baseline.
+    pub y: f64,
+    pub width: f64,
+    /// Rendered font size, after every transform is applied.
+    pub size: f64,
+    pub text: String,
+}
+
+impl Glyph {
+    /// Centre of the glyph box, which is what containment tests should use: a
+    /// highlight drawn a point short of the baseline still covers the glyph.
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.size / 3.0)
+    }
+}
+
+/// Every glyph on the 1-based page `number`, in content-stream order.
+pub fn page_glyphs(pdf: &Document, number: u32) -> Result<Vec<Glyph>, OutputError> {
+    let mut collector = GlyphCollector::default();
+    pdf_extract::output_doc_page(pdf, &mut collector, number)?;
+    Ok(collector.glyphs)
+}
+
+#[derive(Default)]
+struct GlyphCollector {
+    glyphs: Vec<Glyph>,
+}
+
+impl OutputDev for GlyphCollector {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        _media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        text: &str,
+    ) -> Result<(), OutputError> {
+        // Same measure `pdf-extract` uses for its own line breaking: the side
+        // of a square with the area the transformed em box covers.
+        let scaled_x = font_size * (trm.m11 + trm.m21);
+        let scaled_y = font_size * (trm.m12 + trm.m22);
+        let size = (scaled_x * scaled_y).abs().sqrt();
+
+        self.glyphs.push(Glyph {
+            x: trm.m31,
+            y: trm.m32,
+            width: width * size,
+            size,
+            text: text.to_string(),
+        });
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
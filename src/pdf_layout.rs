@@ -0,0 +1,405 @@
+//! Page text rebuilt from where each glyph was drawn.
+//!
+//! `pdf-extract` reads a page in content-stream order and breaks lines on
+//! baseline changes, so a two-column paper comes out with its columns
+//! interleaved line by line and a table loses the space between its cells.
+//! This reading groups glyphs into rows by baseline, splits rows into segments
+//! at wide gaps, finds the gutters that run down the whole page, and then reads
+//! each column to the bottom before starting the next. Segments that share a
+//! row inside one column are table cells and keep their horizontal positions.
+
+use crate::pdf_glyphs::Glyph;
+
+/// A gap wider than this many ems ends a segment: a column gutter or the space
+/// between table cells, not a word space.
+const SEGMENT_GAP_EMS: f64 = 1.0;
+
+/// A gap wider than this many ems is a word space the content stream left
+/// implicit.
+const WORD_GAP_EMS: f64 = 0.15;
+
+/// Glyphs whose baselines are closer than this many ems share a row.
+const ROW_TOLERANCE_EMS: f64 = 0.4;
+
+/// Vertical distance, in ems, that counts as a paragraph break.
+const PARAGRAPH_GAP_EMS: f64 = 1.8;
+
+/// A column whose segments fill less than this share of its width is a stack
+/// of table cells, not running text.
+const MIN_COLUMN_FILL: f64 = 0.5;
+
+/// Narrowest text column, in ems. Anything narrower is a table column.
+const MIN_COLUMN_EMS: f64 = 12.0;
+
+/// Most buckets the gutter search divides the text's width into. A page is
+/// far narrower than this many points, so buckets are a point wide; a glyph
+/// placed far off the page widens the buckets instead of the allocation.
+const MAX_BUCKETS: usize = 4096;
+
+/// Widest a row may be padded to line up its cells, in characters. Beyond it
+/// a cell is set off by the usual two spaces, so a stray glyph far to the
+/// right does not pull a line out to millions of characters.
+const MAX_ROW_CHARS: usize = 1000;
+
+/// A run of text on one row, with no wide gap inside it.
+#[derive(Debug, Clone)]
+struct Segment {
+    left: f64,
+    right: f64,
+    text: String,
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    /// Baseline, in user space.
+    y: f64,
+    size: f64,
+    segments: Vec<Segment>,
+}
+
+/// The page's text in reading order, with columns read one after another and
+/// table rows kept on one line.
+pub fn layout_text(glyphs: &[Glyph]) -> String {
+    let rows = rows(glyphs);
+    if rows.is_empty() {
+        return String::new();
+    }
+
+    let char_width = median(glyphs.iter().map(|glyph| glyph.width).filter(|w| *w > 0.0))
+        .unwrap_or(6.0);
+    let columns = columns(&rows);
+
+    let mut out = Lines::default();
+    // Rows of the current band, split by column. A row that crosses a gutter
+    // closes the band, so a title above two columns is read before both.
+    let mut band: Vec<Vec<Row>> = vec![Vec::new(); columns.len()];
+
+    for row in rows {
+        let placed: Option<Vec<usize>> = row
+            .segments
+            .iter()
+            .map(|segment| column_of(&columns, segment))
+            .collect();
+
+        match placed {
+            Some(indices) => {
+                for (column, segment) in indices.into_iter().zip(row.segments) {
+                    let rows = &mut band[column];
+                    match rows.last_mut() {
+                        Some(last) if last.y == row.y => last.segments.push(segment),
+                        _ => rows.push(Row {
+                            y: row.y,
+                            size: row.size,
+                            segments: vec![segment],
+                        }),
+                    }
+                }
+            }
+            None => {
+                flush_band(&mut band, &columns, char_width, &mut out);
+                out.push_row(&row, columns[0].0, char_width);
+            }
+        }
+    }
+    flush_band(&mut band, &columns, char_width, &mut out);
+
+    out.lines.join("\n")
+}
+
+/// Output lines, plus the last row written so a paragraph gap can be told
+/// from ordinary leading.
+#[derive(Default)]
+struct Lines {
+    lines: Vec<String>,
+    last: Option<(f64, f64)>,
+}
+
+impl Lines {
+    fn push_row(&mut self, row: &Row, left: f64, char_width: f64) {
+        if let Some((y, size)) = self.last {
+            if y - row.y > PARAGRAPH_GAP_EMS * size.max(row.size) {
+                self.blank();
+            }
+        }
+        self.lines.push(row_text(row, left, char_width));
+        self.last = Some((row.y, row.size));
+    }
+
+    /// A blank line, never two in a row and never at the top.
+    fn blank(&mut self) {
+        if self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+    }
+}
+
+/// Read out each column of the band in turn, a blank line between them.
+fn flush_band(band: &mut [Vec<Row>], columns: &[(f64, f64)], char_width: f64, out: &mut Lines) {
+    for (index, rows) in band.iter_mut().enumerate() {
+        if rows.is_empty() {
+            continue;
+        }
+        if index > 0 {
+            out.blank();
+            // The next column starts back at the top of the band.
+            out.last = None;
+        }
+        for row in rows.iter() {
+            out.push_row(row, columns[index].0, char_width);
+        }
+        rows.clear();
+    }
+}
+
+/// A row as one line. Segments after the first keep their distance from the
+/// column's left edge, so the cells of a table line up from row to row.
+fn row_text(row: &Row, left: f64, char_width: f64) -> String {
+    let mut line = String::new();
+    for (index, segment) in row.segments.iter().enumerate() {
+        if index > 0 {
+            let target = ((segment.left - left) / char_width)
+                .round()
+                .clamp(0.0, MAX_ROW_CHARS as f64) as usize;
+            let width = line.chars().count();
+            let pad = target.saturating_sub(width).max(2);
+            line.extend(std::iter::repeat_n(' ', pad));
+        }
+        line.push_str(&segment.text);
+    }
+    line
+}
+
+/// The column a segment reads in, or `None` when it runs across a whole
+/// gutter. A line that only pokes into a gutter still belongs to its column:
+/// the gutter is where almost nothing is, not where nothing is.
+fn column_of(columns: &[(f64, f64)], segment: &Segment) -> Option<usize> {
+    let crosses = columns
+        .windows(2)
+        .any(|pair| segment.left < pair[0].1 && segment.right > pair[1].0);
+    if crosses {
+        return None;
+    }
+    columns
+        .iter()
+        .rposition(|(left, _)| segment.left >= *left - 0.5)
+        .or(Some(0))
+}
+
+/// Glyphs grouped into rows, top of the page first, each row split into
+/// segments at wide gaps.
+fn rows(glyphs: &[Glyph]) -> Vec<Row> {
+    let mut sorted: Vec<&Glyph> = glyphs
+        .iter()
+        .filter(|glyph| !glyph.text.trim().is_empty() && glyph.size > 0.0)
+        .collect();
+    sorted.sort_by(|a, b| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+
+    let mut grouped: Vec<Vec<&Glyph>> = Vec::new();
+    for glyph in sorted {
+        match grouped.last_mut() {
+            Some(row) if (row[0].y - glyph.y).abs() <= ROW_TOLERANCE_EMS * row[0].size => {
+                row.push(glyph)
+            }
+            _ => grouped.push(vec![glyph]),
+        }
+    }
+
+    grouped
+        .into_iter()
+        .map(|mut glyphs| {
+            glyphs.sort_by(|a, b| a.x.total_cmp(&b.x));
+            let size = glyphs.iter().map(|glyph| glyph.size).fold(0.0, f64::max);
+            let mut segments: Vec<Segment> = Vec::new();
+            for glyph in &glyphs {
+                match segments.last_mut() {
+                    Some(segment) if glyph.x - segment.right <= SEGMENT_GAP_EMS * glyph.size => {
+                        if glyph.x - segment.right > WORD_GAP_EMS * glyph.size {
+                            segment.text.push(' ');
+                        }
+                        segment.text.push_str(&glyph.text);
+                        segment.right = segment.right.max(glyph.x + glyph.width);
+                    }
+                    _ => segments.push(Segment {
+                        left: glyph.x,
+                        right: glyph.x + glyph.width,
+                        text: glyph.text.clone(),
+                    }),
+                }
+            }
+            Row {
+                y: glyphs[0].y,
+                size,
+                segments,
+            }
+        })
+        .collect()
+}
+
+/// The page's text columns as `(left, right)` spans, left to right.
+///
+/// A gutter is a vertical strip that almost no segment crosses; a heading
+/// spanning both columns is allowed to. Candidate columns that are mostly
+/// short segments are table cells rather than text, and then the page is
+/// treated as a single column.
+fn columns(rows: &[Row]) -> Vec<(f64, f64)> {
+    let segments: Vec<&Segment> = rows.iter().flat_map(|row| &row.segments).collect();
+    let left = segments.iter().map(|s| s.left).fold(f64::INFINITY, f64::min);
+    let right = segments.iter().map(|s| s.right).fold(f64::NEG_INFINITY, f64::max);
+    let whole = vec![(left, right)];
+    if !(right - left).is_finite() || right - left < 1.0 {
+        return whole;
+    }
+
+    // Coverage in buckets across the text's horizontal extent, a point wide
+    // unless that would take more than `MAX_BUCKETS`.
+    let step = ((right - left) / MAX_BUCKETS as f64).max(1.0);
+    let buckets = ((right - left) / step).ceil() as usize + 1;
+    let mut coverage = vec![0usize; buckets];
+    for segment in &segments {
+        let start = ((segment.left - left) / step).floor().max(0.0) as usize;
+        let end = (((segment.right - left) / step).ceil() as usize).min(buckets - 1);
+        for count in &mut coverage[start..=end] {
+            *count += 1;
+        }
+    }
+
+    let allowance = (rows.len() / 8).max(1);
+    let min_gutter = median(rows.iter().map(|row| row.size)).unwrap_or(10.0);
+    let mut gutters = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for (x, count) in coverage.iter().enumerate() {
+        match (run_start, *count <= allowance) {
+            (None, true) => run_start = Some(x),
+            (Some(start), false) => {
+                if (x - start) as f64 * step >= min_gutter && start > 0 {
+                    gutters.push((left + start as f64 * step, left + x as f64 * step));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if gutters.is_empty() {
+        return whole;
+    }
+
+    let mut spans = Vec::new();
+    let mut edge = left;
+    for (start, end) in &gutters {
+        spans.push((edge, *start));
+        edge = *end;
+    }
+    spans.push((edge, right));
+
+    let text_like = spans.iter().all(|(span_left, span_right)| {
+        let width = span_right - span_left;
+        if width < MIN_COLUMN_EMS * min_gutter {
+            return false;
+        }
+        let fills = segments
+            .iter()
+            .filter(|s| s.left >= span_left - 0.5 && s.right <= span_right + 0.5)
+            .map(|s| (s.right - s.left) / width);
+        median(fills).is_some_and(|fill| fill >= MIN_COLUMN_FILL)
+    });
+
+    if text_like {
+        spans
+    } else {
+        whole
+    }
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(values[values.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glyphs for `text` set in a 10pt font with 5pt advance, starting at `x`.
+    fn word(x: f64, y: f64, text: &str) -> Vec<Glyph> {
+        text.chars()
+            .enumerate()
+            .map(|(index, c)| Glyph {
+                x: x + index as f64 * 5.0,
+                y,
+                width: 5.0,
+                size: 10.0,
+                text: c.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn two_columns_are_read_one_after_the_other_under_their_title() {
+        let mut glyphs = word(72.0, 760.0, "A title that spans both columns of the page");
+        for (row, (left, right)) in [
+            ("the left column starts here and", "the right column picks up here"),
+            ("carries on down the page until", "and it also runs to the bottom"),
+            ("it reaches the bottom margin", "before the page ends"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let y = 730.0 - row as f64 * 12.0;
+            // Content-stream order interleaves the columns, as a typesetter
+            // writing row by row would.
+            glyphs.extend(word(72.0, y, left));
+            glyphs.extend(word(240.0, y, right));
+        }
+
+        assert_eq!(
+            layout_text(&glyphs),
+            "A title that spans both columns of the page\n\n\
+             the left column starts here and\n\
+             carries on down the page until\n\
+             it reaches the bottom margin\n\n\
+             the right column picks up here\n\
+             and it also runs to the bottom\n\
+             before the page ends"
+        );
+    }
+
+    #[test]
+    fn a_glyph_far_off_the_page_does_not_size_the_output() {
+        let mut glyphs = word(72.0, 700.0, "a row of ordinary text");
+        glyphs.extend(word(1e9, 700.0, "stray"));
+        glyphs.extend(word(72.0, 688.0, "and another under it"));
+
+        let text = layout_text(&glyphs);
+        assert!(text.len() < 2 * MAX_ROW_CHARS, "{} bytes", text.len());
+        assert!(text.starts_with("a row of ordinary text"), "{text}");
+        assert!(text.contains("stray"), "{text}");
+        assert!(text.ends_with("and another under it"), "{text}");
+    }
+
+    #[test]
+    fn table_cells_stay_on_their_row_and_line_up() {
+        let mut glyphs = Vec::new();
+        for (row, cells) in [["Item", "Qty", "Price"], ["Apples", "3", "1.20"], ["Pears", "12", "0.80"]]
+            .iter()
+            .enumerate()
+        {
+            let y = 700.0 - row as f64 * 12.0;
+            for (column, cell) in cells.iter().enumerate() {
+                glyphs.extend(word(72.0 + column as f64 * 60.0, y, cell));
+            }
+        }
+
+        let text = layout_text(&glyphs);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3, "{text}");
+        assert_eq!(lines[1].split_whitespace().collect::<Vec<_>>(), ["Apples", "3", "1.20"]);
+        // Every cell starts in the same text column on every row.
+        let starts = |line: &str, cell: &str| line.find(cell).unwrap();
+        assert_eq!(starts(lines[0], "Qty"), starts(lines[1], "3"));
+        assert_eq!(starts(lines[1], "1.20"), starts(lines[2], "0.80"));
+    }
+}
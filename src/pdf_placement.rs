@@ -0,0 +1,828 @@
+//! Where each image is drawn on its page.
+//!
+//! An image XObject is a grid of pixels with no size or position of its own;
+//! the content stream places it by setting the current transformation matrix
+//! (CTM) and painting the image into the unit square. Following `q`, `Q` and
+//! `cm` up to each `Do` therefore gives the image's box on the page, the
+//! resolution it prints at and any rotation. Form XObjects are followed too,
+//! since a logo is often drawn from inside one.
+//!
+//! The same walk picks up inline images, the `BI … ID … EI` blocks that live in
+//! the content stream itself and never appear among the page's resources.
+//! `lopdf`'s content parser gives up on the whole page when an inline image is
+//! compressed, so the stream is tokenised here instead, keeping only what the
+//! walk needs.
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+
+/// Form XObjects nested deeper than this are not followed, which also keeps a
+/// form that draws itself from recursing forever.
+const MAX_FORM_DEPTH: usize = 12;
+
+/// Arrays and dictionaries nested deeper than this in a content stream are
+/// skipped rather than parsed. Real operands nest two or three deep; the
+/// limit keeps a hostile stream of brackets from overflowing the stack.
+const MAX_OBJECT_DEPTH: usize = 32;
+
+/// Points per inch, the unit PDF page coordinates are in.
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// A PDF transformation matrix `[a b c d e f]`, mapping `(x, y)` to
+/// `(a·x + c·y + e, b·x + d·y + f)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Matrix([f64; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    /// `self` applied first, then `then`, as `cm` composes with the CTM.
+    fn then(self, then: Matrix) -> Matrix {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = then.0;
+        Matrix([
+            a * a2 + b * c2,
+            a * b2 + b * d2,
+            c * a2 + d * c2,
+            c * b2 + d * d2,
+            e * a2 + f * c2 + e2,
+            e * b2 + f * d2 + f2,
+        ])
+    }
+
+    fn apply(self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    fn from_numbers(numbers: &[Object]) -> Option<Matrix> {
+        let values: Vec<f64> = numbers.iter().filter_map(number).collect();
+        let values: [f64; 6] = values.try_into().ok()?;
+        values
+            .iter()
+            .all(|value| value.is_finite())
+            .then_some(Matrix(values))
+    }
+}
+
+/// Where an image landed on its page.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Placement {
+    /// The upright box the image covers, in points from the page's bottom-left
+    /// corner.
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+    pub top: f64,
+    /// The image's own width and height in points, measured along its edges,
+    /// so a rotated image keeps its real size.
+    pub drawn_width: f64,
+    pub drawn_height: f64,
+    /// Counter-clockwise rotation in whole degrees, 0 to 359.
+    pub rotation: u16,
+}
+
+impl Placement {
+    fn of(ctm: Matrix) -> Placement {
+        let corners =
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| ctm.apply(x, y));
+        let xs = corners.map(|(x, _)| x);
+        let ys = corners.map(|(_, y)| y);
+        let [a, b, c, d, _, _] = ctm.0;
+        let degrees = b.atan2(a).to_degrees().round().rem_euclid(360.0);
+        Placement {
+            left: xs.into_iter().fold(f64::INFINITY, f64::min),
+            bottom: ys.into_iter().fold(f64::INFINITY, f64::min),
+            right: xs.into_iter().fold(f64::NEG_INFINITY, f64::max),
+            top: ys.into_iter().fold(f64::NEG_INFINITY, f64::max),
+            drawn_width: a.hypot(b),
+            drawn_height: c.hypot(d),
+            rotation: degrees as u16 % 360,
+        }
+    }
+
+    /// Horizontal and vertical resolution an image of `width` by `height`
+    /// pixels prints at here. Zero when it is drawn with no size.
+    pub fn dpi(&self, width: u32, height: u32) -> (f64, f64) {
+        let per_inch = |pixels: u32, points: f64| {
+            if points > 0.0 {
+                f64::from(pixels) * POINTS_PER_INCH / points
+            } else {
+                0.0
+            }
+        };
+        (
+            per_inch(width, self.drawn_width),
+            per_inch(height, self.drawn_height),
+        )
+    }
+
+    /// "144x72 pt at 72,600 · 150 dpi · rotated 90°", for the Media list.
+    pub fn label(&self, width: u32, height: u32) -> String {
+        let (x_dpi, y_dpi) = self.dpi(width, height);
+        let dpi = if (x_dpi - y_dpi).abs() < 1.0 {
+            format!("{x_dpi:.0} dpi")
+        } else {
+            format!("{x_dpi:.0}x{y_dpi:.0} dpi")
+        };
+        let mut label = format!(
+            "{:.0}x{:.0} pt at {:.0},{:.0} · {dpi}",
+            self.drawn_width, self.drawn_height, self.left, self.bottom
+        );
+        if self.rotation != 0 {
+            label.push_str(&format!(" · rotated {}°", self.rotation));
+        }
+        label
+    }
+}
+
+/// An image painted inside the content stream rather than referenced from the
+/// page's resources.
+pub struct InlineImage {
+    /// The image's parameters with abbreviations spelled out (`/W` becomes
+    /// `/Width`, `/CS /RGB` becomes `/ColorSpace /DeviceRGB`) and named colour
+    /// spaces looked up in the page's resources, so it reads like an XObject
+    /// dictionary.
+    pub dict: Dictionary,
+    pub data: Vec<u8>,
+}
+
+/// One image painted on a page.
+pub enum Drawn {
+    XObject {
+        id: ObjectId,
+        placement: Placement,
+    },
+    Inline {
+        image: InlineImage,
+        placement: Placement,
+    },
+}
+
+/// Every image a page paints, in painting order. A broken content stream
+/// yields what was read before the break.
+pub fn page_drawings(pdf: &Document, page_id: ObjectId) -> Vec<Drawn> {
+    let Ok(content) = pdf.get_page_content(page_id) else {
+        return Vec::new();
+    };
+    let resources = page_resources(pdf, page_id);
+    let mut drawn = Vec::new();
+    walk(pdf, &content, resources, Matrix::IDENTITY, 0, &mut drawn);
+    drawn
+}
+
+fn page_resources(pdf: &Document, page_id: ObjectId) -> Option<&Dictionary> {
+    let (direct, inherited) = pdf.get_page_resources(page_id).ok()?;
+    direct.or_else(|| {
+        inherited
+            .into_iter()
+            .find_map(|id| pdf.get_dictionary(id).ok())
+    })
+}
+
+fn walk(
+    pdf: &Document,
+    content: &[u8],
+    resources: Option<&Dictionary>,
+    base: Matrix,
+    depth: usize,
+    drawn: &mut Vec<Drawn>,
+) {
+    let mut lexer = Lexer::new(content);
+    let mut ctm = base;
+    let mut saved: Vec<Matrix> = Vec::new();
+    let mut operands: Vec<Object> = Vec::new();
+
+    while let Some(token) = lexer.next_token() {
+        let Token::Keyword(operator) = token else {
+            if let Some(object) = lexer.object(token) {
+                operands.push(object);
+            }
+            continue;
+        };
+
+        match operator.as_slice() {
+            b"q" => saved.push(ctm),
+            b"Q" => ctm = saved.pop().unwrap_or(base),
+            b"cm" => {
+                if let Some(matrix) = operands
+                    .get(operands.len().saturating_sub(6)..)
+                    .and_then(Matrix::from_numbers)
+                {
+                    ctm = matrix.then(ctm);
+                }
+            }
+            b"Do" => {
+                if let Some(Object::Name(name)) = operands.last() {
+                    draw_xobject(pdf, name, resources, ctm, depth, drawn);
+                }
+            }
+            b"BI" => match lexer.inline_image(pdf, resources) {
+                Some(image) => drawn.push(Drawn::Inline {
+                    image,
+                    placement: Placement::of(ctm),
+                }),
+                None => break,
+            },
+            _ => {}
+        }
+        operands.clear();
+    }
+}
+
+fn draw_xobject(
+    pdf: &Document,
+    name: &[u8],
+    resources: Option<&Dictionary>,
+    ctm: Matrix,
+    depth: usize,
+    drawn: &mut Vec<Drawn>,
+) {
+    let Some(Object::Reference(id)) = resources
+        .and_then(|resources| resources.get(b"XObject").ok())
+        .map(|xobjects| resolve(pdf, xobjects))
+        .and_then(|xobjects| xobjects.as_dict().ok())
+        .and_then(|xobjects| xobjects.get(name).ok())
+    else {
+        return;
+    };
+    let Ok(stream) = pdf.get_object(*id).and_then(Object::as_stream) else {
+        return;
+    };
+
+    match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+        Ok(b"Image") => drawn.push(Drawn::XObject {
+            id: *id,
+            placement: Placement::of(ctm),
+        }),
+        Ok(b"Form") if depth < MAX_FORM_DEPTH => {
+            let matrix = stream
+                .dict
+                .get(b"Matrix")
+                .ok()
+                .and_then(|matrix| resolve(pdf, matrix).as_array().ok())
+                .and_then(|numbers| Matrix::from_numbers(numbers))
+                .unwrap_or(Matrix::IDENTITY);
+            // A form without resources of its own uses the ones it was drawn
+            // with.
+            let form_resources = stream
+                .dict
+                .get(b"Resources")
+                .ok()
+                .and_then(|resources| resolve(pdf, resources).as_dict().ok())
+                .or(resources);
+            let content = stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone());
+            walk(
+                pdf,
+                &content,
+                form_resources,
+                matrix.then(ctm),
+                depth + 1,
+                drawn,
+            );
+        }
+        _ => {}
+    }
+}
+
+fn resolve<'a>(pdf: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+fn number(object: &Object) -> Option<f64> {
+    match object {
+        Object::Integer(value) => Some(*value as f64),
+        Object::Real(value) => Some(f64::from(*value)),
+        _ => None,
+    }
+}
+
+enum Token {
+    Integer(i64),
+    Real(f64),
+    Name(Vec<u8>),
+    String(Vec<u8>),
+    ArrayStart,
+    ArrayEnd,
+    DictStart,
+    DictEnd,
+    Keyword(Vec<u8>),
+}
+
+/// Just enough of a content-stream tokenizer to follow the graphics state and
+/// find inline images.
+struct Lexer<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\0' | b'\t' | b'\n' | b'\x0c' | b'\r' | b' ')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+impl<'a> Lexer<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) {
+                self.position += 1;
+            } else if byte == b'%' {
+                while self
+                    .peek()
+                    .is_some_and(|byte| byte != b'\n' && byte != b'\r')
+                {
+                    self.position += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// A run of regular characters: a number, keyword or name body.
+    fn regular(&mut self) -> &'a [u8] {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|byte| !is_whitespace(byte) && !is_delimiter(byte))
+        {
+            self.position += 1;
+        }
+        &self.bytes[start..self.position]
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        self.skip_space();
+        let byte = self.peek()?;
+        let token = match byte {
+            b'/' => {
+                self.position += 1;
+                Token::Name(decode_name(self.regular()))
+            }
+            b'(' => Token::String(self.literal_string()),
+            b'<' if self.bytes.get(self.position + 1) == Some(&b'<') => {
+                self.position += 2;
+                Token::DictStart
+            }
+            b'>' if self.bytes.get(self.position + 1) == Some(&b'>') => {
+                self.position += 2;
+                Token::DictEnd
+            }
+            b'<' => Token::String(self.hex_string()),
+            b'[' => {
+                self.position += 1;
+                Token::ArrayStart
+            }
+            b']' => {
+                self.position += 1;
+                Token::ArrayEnd
+            }
+            _ if is_delimiter(byte) => {
+                // A stray `)`, `>` or brace: nothing to make of it.
+                self.position += 1;
+                Token::Keyword(vec![byte])
+            }
+            _ => {
+                let word = self.regular();
+                let text = std::str::from_utf8(word).unwrap_or_default();
+                if let Ok(value) = text.parse::<i64>() {
+                    Token::Integer(value)
+                } else if let Some(value) = text.parse::<f64>().ok().filter(|_| {
+                    text.bytes()
+                        .all(|byte| byte.is_ascii_digit() || matches!(byte, b'.' | b'-' | b'+'))
+                }) {
+                    Token::Real(value)
+                } else {
+                    Token::Keyword(word.to_vec())
+                }
+            }
+        };
+        Some(token)
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        self.position += 1;
+        let mut depth = 1;
+        let mut out = Vec::new();
+        while let Some(byte) = self.peek() {
+            self.position += 1;
+            match byte {
+                b'\\' => {
+                    if let Some(escaped) = self.peek() {
+                        self.position += 1;
+                        out.push(escaped);
+                    }
+                }
+                b'(' => {
+                    depth += 1;
+                    out.push(byte);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    out.push(byte);
+                }
+                _ => out.push(byte),
+            }
+        }
+        out
+    }
+
+    fn hex_string(&mut self) -> Vec<u8> {
+        self.position += 1;
+        let mut digits = Vec::new();
+        while let Some(byte) = self.peek() {
+            self.position += 1;
+            if byte == b'>' {
+                break;
+            }
+            if let Some(digit) = (byte as char).to_digit(16) {
+                digits.push(digit as u8);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    /// The object that starts with `token`, reading on for arrays and
+    /// dictionaries. Keywords other than `true`, `false` and `null` are not
+    /// objects.
+    fn object(&mut self, token: Token) -> Option<Object> {
+        self.nested_object(token, 0)
+    }
+
+    /// [`Lexer::object`] inside `depth` arrays and dictionaries. Past
+    /// `MAX_OBJECT_DEPTH` the object is read over and dropped.
+    fn nested_object(&mut self, token: Token, depth: usize) -> Option<Object> {
+        if matches!(token, Token::ArrayStart | Token::DictStart) && depth >= MAX_OBJECT_DEPTH {
+            self.skip_nested();
+            return None;
+        }
+        Some(match token {
+            Token::Integer(value) => Object::Integer(value),
+            Token::Real(value) => Object::Real(value as f32),
+            Token::Name(name) => Object::Name(name),
+            Token::String(bytes) => Object::String(bytes, lopdf::StringFormat::Literal),
+            Token::ArrayStart => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next_token()? {
+                        Token::ArrayEnd => break,
+                        token => items.extend(self.nested_object(token, depth + 1)),
+                    }
+                }
+                Object::Array(items)
+            }
+            Token::DictStart => {
+                let mut dict = Dictionary::new();
+                loop {
+                    match self.next_token()? {
+                        Token::DictEnd => break,
+                        Token::Name(key) => {
+                            let token = self.next_token()?;
+                            if let Some(value) = self.nested_object(token, depth + 1) {
+                                dict.set(key, value);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Object::Dictionary(dict)
+            }
+            Token::Keyword(word) => match word.as_slice() {
+                b"true" => Object::Boolean(true),
+                b"false" => Object::Boolean(false),
+                b"null" => Object::Null,
+                _ => return None,
+            },
+            Token::ArrayEnd | Token::DictEnd => return None,
+        })
+    }
+
+    /// Read past the end of the array or dictionary just opened, however
+    /// deep it goes, without recursing.
+    fn skip_nested(&mut self) {
+        let mut open = 1usize;
+        while open > 0 {
+            match self.next_token() {
+                Some(Token::ArrayStart | Token::DictStart) => open += 1,
+                Some(Token::ArrayEnd | Token::DictEnd) => open -= 1,
+                Some(_) => {}
+                None => return,
+            }
+        }
+    }
+
+    /// Read an inline image, with the lexer just past `BI`, and leave it past
+    /// `EI`. `None` when the stream ends first.
+    fn inline_image(
+        &mut self,
+        pdf: &Document,
+        resources: Option<&Dictionary>,
+    ) -> Option<InlineImage> {
+        let mut dict = Dictionary::new();
+        loop {
+            match self.next_token()? {
+                Token::Keyword(word) if word == b"ID" => break,
+                Token::Name(key) => {
+                    let token = self.next_token()?;
+                    if let Some(value) = self.object(token) {
+                        let key = expand_key(&key);
+                        let value = expand_value(pdf, key, value, resources);
+                        dict.set(key, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // One whitespace byte separates `ID` from the data.
+        self.position += 1;
+        let start = self.position.min(self.bytes.len());
+        let end = self.inline_data_end(start, unfiltered_length(&dict));
+        let data = self.bytes[start..end].to_vec();
+        self.position = end;
+        self.skip_space();
+        if self.bytes[self.position..].starts_with(b"EI") {
+            self.position += 2;
+        }
+        Some(InlineImage { dict, data })
+    }
+
+    /// Where the data that starts at `start` ends. An uncompressed image's
+    /// length is known from its size; a compressed one runs until the first
+    /// `EI` standing on its own.
+    fn inline_data_end(&self, start: usize, known: Option<usize>) -> usize {
+        let ends_at = |end: usize| {
+            let rest = &self.bytes[end..];
+            let after_space = rest
+                .iter()
+                .position(|byte| !is_whitespace(*byte))
+                .unwrap_or(rest.len());
+            rest[after_space..].starts_with(b"EI")
+                && rest
+                    .get(after_space + 2)
+                    .is_none_or(|byte| is_whitespace(*byte) || is_delimiter(*byte))
+        };
+        if let Some(end) = known.map(|length| start + length) {
+            if end <= self.bytes.len() && ends_at(end) {
+                return end;
+            }
+        }
+        (start..self.bytes.len())
+            .find(|end| {
+                self.bytes
+                    .get(*end)
+                    .is_some_and(|byte| is_whitespace(*byte))
+                    && ends_at(*end)
+            })
+            .unwrap_or(self.bytes.len())
+    }
+}
+
+/// `#20` style escapes in a name.
+fn decode_name(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut index = 0;
+    while index < raw.len() {
+        let escaped = (raw[index] == b'#')
+            .then(|| raw.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                index += 3;
+            }
+            None => {
+                out.push(raw[index]);
+                index += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Inline image keys may be abbreviated; their XObject spelling.
+fn expand_key(key: &[u8]) -> &[u8] {
+    match key {
+        b"BPC" => b"BitsPerComponent",
+        b"CS" => b"ColorSpace",
+        b"D" => b"Decode",
+        b"DP" => b"DecodeParms",
+        b"F" => b"Filter",
+        b"H" => b"Height",
+        b"IM" => b"ImageMask",
+        b"I" => b"Interpolate",
+        b"L" => b"Length",
+        b"W" => b"Width",
+        other => other,
+    }
+}
+
+fn expand_value(
+    pdf: &Document,
+    key: &[u8],
+    value: Object,
+    resources: Option<&Dictionary>,
+) -> Object {
+    match (key, value) {
+        (b"Filter", Object::Name(name)) => Object::Name(expand_filter(&name)),
+        (b"Filter", Object::Array(names)) => Object::Array(
+            names
+                .into_iter()
+                .map(|name| match name {
+                    Object::Name(name) => Object::Name(expand_filter(&name)),
+                    other => other,
+                })
+                .collect(),
+        ),
+        (b"ColorSpace", Object::Name(name)) => match name.as_slice() {
+            b"G" => Object::Name(b"DeviceGray".to_vec()),
+            b"RGB" => Object::Name(b"DeviceRGB".to_vec()),
+            b"CMYK" => Object::Name(b"DeviceCMYK".to_vec()),
+            b"DeviceGray" | b"DeviceRGB" | b"DeviceCMYK" => Object::Name(name),
+            // Anything else names an entry in the page's /ColorSpace.
+            _ => resources
+                .and_then(|resources| resources.get(b"ColorSpace").ok())
+                .and_then(|spaces| resolve(pdf, spaces).as_dict().ok())
+                .and_then(|spaces| spaces.get(&name).ok())
+                .map(|space| resolve(pdf, space).clone())
+                .unwrap_or(Object::Name(name)),
+        },
+        (b"ColorSpace", Object::Array(mut items)) => {
+            if let Some(Object::Name(head)) = items.first_mut() {
+                if head == b"I" {
+                    *head = b"Indexed".to_vec();
+                }
+            }
+            if let Some(Object::Name(base)) = items.get(1) {
+                let base = expand_value(pdf, b"ColorSpace", Object::Name(base.clone()), resources);
+                items[1] = base;
+            }
+            Object::Array(items)
+        }
+        (_, value) => value,
+    }
+}
+
+fn expand_filter(name: &[u8]) -> Vec<u8> {
+    match name {
+        b"AHx" => b"ASCIIHexDecode".to_vec(),
+        b"A85" => b"ASCII85Decode".to_vec(),
+        b"LZW" => b"LZWDecode".to_vec(),
+        b"Fl" => b"FlateDecode".to_vec(),
+        b"RL" => b"RunLengthDecode".to_vec(),
+        b"CCF" => b"CCITTFaxDecode".to_vec(),
+        b"DCT" => b"DCTDecode".to_vec(),
+        other => other.to_vec(),
+    }
+}
+
+/// Bytes in an uncompressed inline image, when its parameters say.
+fn unfiltered_length(dict: &Dictionary) -> Option<usize> {
+    if dict.has(b"Filter") {
+        return None;
+    }
+    let integer = |key: &[u8]| {
+        dict.get(key)
+            .ok()?
+            .as_i64()
+            .ok()
+            .and_then(|value| usize::try_from(value).ok())
+    };
+    let mask = dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let components = match dict.get(b"ColorSpace").ok()? {
+        _ if mask => 1,
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" => 1,
+            b"DeviceRGB" => 3,
+            b"DeviceCMYK" => 4,
+            _ => return None,
+        },
+        Object::Array(items) if matches!(items.first(), Some(Object::Name(head)) if head == b"Indexed") => {
+            1
+        }
+        _ => return None,
+    };
+    let bits = if mask {
+        1
+    } else {
+        integer(b"BitsPerComponent")?
+    };
+    let row = (integer(b"Width")? * components * bits).div_ceil(8);
+    Some(row * integer(b"Height")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_transforms_place_and_rotate_the_unit_square() {
+        // Move to (100, 200), then turn a quarter and scale to 50x20 points.
+        let ctm = Matrix([0.0, 50.0, -20.0, 0.0, 0.0, 0.0])
+            .then(Matrix([1.0, 0.0, 0.0, 1.0, 100.0, 200.0]));
+        let placement = Placement::of(ctm);
+
+        assert_eq!((placement.left, placement.bottom), (80.0, 200.0));
+        assert_eq!((placement.right, placement.top), (100.0, 250.0));
+        assert_eq!(
+            (placement.drawn_width, placement.drawn_height),
+            (50.0, 20.0)
+        );
+        assert_eq!(placement.rotation, 90);
+        assert_eq!(placement.dpi(100, 40), (144.0, 144.0));
+        assert_eq!(
+            placement.label(100, 40),
+            "50x20 pt at 80,200 · 144 dpi · rotated 90°"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_operands_are_skipped_without_recursing() {
+        // Far deeper than the stack could take one frame per bracket.
+        let depth = 1_000_000;
+        let mut content = "[".repeat(depth).into_bytes();
+        content.push(b'1');
+        content.extend("]".repeat(depth).into_bytes());
+        content.extend(b" [[2] /Name] 3");
+
+        let mut lexer = Lexer::new(&content);
+        let mut objects = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            objects.push(lexer.object(token));
+        }
+
+        assert_eq!(objects.len(), 3);
+        // Kept down to the limit, and the rest dropped.
+        let mut levels = 0;
+        let mut object = objects[0].as_ref();
+        while let Some(Object::Array(items)) = object {
+            levels += 1;
+            object = items.first();
+        }
+        assert_eq!(levels, MAX_OBJECT_DEPTH);
+        assert_eq!(object, None);
+        assert_eq!(
+            objects[1],
+            Some(Object::Array(vec![
+                Object::Array(vec![Object::Integer(2)]),
+                Object::Name(b"Name".to_vec()),
+            ]))
+        );
+        assert_eq!(objects[2], Some(Object::Integer(3)));
+    }
+
+    #[test]
+    fn inline_image_abbreviations_are_spelled_out_and_binary_data_kept_whole() {
+        // The data holds the bytes "EI" itself; its known length decides.
+        let content = b"q 8 0 0 4 10 20 cm BI /W 2 /H 1 /CS /G /BPC 8 ID E\x49 EI Q";
+        let mut lexer = Lexer::new(content);
+        while let Some(token) = lexer.next_token() {
+            if matches!(&token, Token::Keyword(word) if word == b"BI") {
+                break;
+            }
+        }
+        let image = lexer
+            .inline_image(&Document::new(), None)
+            .expect("inline image");
+
+        assert_eq!(image.data, b"EI");
+        assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 2);
+        assert_eq!(
+            image.dict.get(b"ColorSpace").unwrap().as_name().unwrap(),
+            b"DeviceGray"
+        );
+        assert!(matches!(lexer.next_token(), Some(Token::Keyword(word)) if word == b"Q"));
+    }
+}
@@ -0,0 +1,280 @@
+//! Rejoining the lines a PDF was typeset into.
+//!
+//! Extracted text keeps every line break the typesetter chose, so a sentence
+//! arrives in pieces and a long word as "informa-" and "tion". Phrase ranking,
+//! sentence counting and whole-word search all see those pieces. Reflow puts
+//! paragraphs back together one page at a time, so page boundaries hold, and
+//! leaves headings, list items, table rows and page furniture on lines of their
+//! own.
+
+use std::collections::HashSet;
+
+use regex::Regex;
+
+use crate::pdf_doc::PdfPage;
+use crate::pdf_furniture::FurnitureLine;
+
+/// A line at least this share of the document's typical line length is taken
+/// to run to the right margin, so the next line continues it.
+const FULL_LINE_RATIO: f64 = 0.7;
+
+/// Below this typical length there is no body text to measure against: the
+/// document is a form, a list or verse, and only unmistakable breaks (hyphens,
+/// lowercase continuations) are joined.
+const MIN_MEASURE: usize = 30;
+
+/// Text, page table and furniture after reflow, all in the same line numbering.
+pub struct Reflowed {
+    pub text: String,
+    pub pages: Vec<PdfPage>,
+    pub furniture: Vec<FurnitureLine>,
+}
+
+/// Rejoin soft-wrapped lines and hyphenated words in `text`, page by page.
+///
+/// `furniture` is given in the numbering of `text` and comes back in the new
+/// one; furniture lines are never joined to anything.
+pub fn reflow(pages: &[PdfPage], text: &str, furniture: &[FurnitureLine]) -> Reflowed {
+    let lines: Vec<&str> = text.lines().collect();
+    let pinned: HashSet<usize> = furniture.iter().map(|entry| entry.line).collect();
+    let context = Context::new(&lines, &pinned);
+
+    let mut out = Reflowed {
+        text: String::with_capacity(text.len()),
+        pages: Vec::with_capacity(pages.len()),
+        furniture: Vec::with_capacity(furniture.len()),
+    };
+    // Where each source line ended up, for moving the furniture across.
+    let mut moved = vec![0usize; lines.len()];
+    let mut cursor = 0usize;
+
+    for page in pages {
+        let end = (page.start_line + page.line_count).min(lines.len());
+        let mut page_lines: Vec<String> = Vec::new();
+        let mut previous: Option<usize> = None;
+
+        for index in page.start_line..end {
+            let line = lines[index];
+            match (previous, page_lines.last_mut()) {
+                (Some(before), Some(current)) if context.joins(before, index) => {
+                    context.append(current, line);
+                }
+                _ => page_lines.push(line.trim_end().to_string()),
+            }
+            moved[index] = cursor + page_lines.len() - 1;
+            previous = Some(index);
+        }
+
+        // An empty page still occupies one line, as it does unreflowed.
+        if page_lines.is_empty() {
+            page_lines.push(String::new());
+        }
+        out.pages.push(PdfPage {
+            start_line: cursor,
+            line_count: page_lines.len(),
+            ..page.clone()
+        });
+        for line in &page_lines {
+            out.text.push_str(line);
+            out.text.push('\n');
+        }
+        cursor += page_lines.len();
+    }
+
+    out.furniture = furniture
+        .iter()
+        .filter(|entry| entry.line < moved.len())
+        .map(|entry| FurnitureLine {
+            line: moved[entry.line],
+            kind: entry.kind,
+        })
+        .collect();
+    out
+}
+
+struct Context<'a> {
+    lines: &'a [&'a str],
+    pinned: &'a HashSet<usize>,
+    /// Typical body line length in characters, or 0 when there is none.
+    measure: usize,
+    /// Words the document hyphenates mid-line, like "well-known": the
+    /// hyphen stays when one of them is split at a line end.
+    compounds: HashSet<String>,
+    list_item: Regex,
+}
+
+impl<'a> Context<'a> {
+    fn new(lines: &'a [&'a str], pinned: &'a HashSet<usize>) -> Self {
+        let mut lengths: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(index, line)| !pinned.contains(index) && !line.trim().is_empty())
+            .map(|(_, line)| line.trim().chars().count())
+            .collect();
+        lengths.sort_unstable();
+        // The upper quartile: most body lines run to the margin, while short
+        // headings and paragraph ends pull a median down.
+        let measure = lengths
+            .get(lengths.len() * 3 / 4)
+            .copied()
+            .filter(|measure| *measure >= MIN_MEASURE)
+            .unwrap_or(0);
+
+        let compounds = lines
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|word| word.contains('-'))
+            .map(str::to_lowercase)
+            .collect();
+
+        Self {
+            lines,
+            pinned,
+            measure,
+            compounds,
+            list_item: Regex::new(r"^([•◦▪‣*–-]|(\d{1,3}|[a-zA-Z])[.)])\s").expect("static regex"),
+        }
+    }
+
+    /// Whether line `next` continues line `before` rather than starting a new
+    /// one.
+    fn joins(&self, before: usize, next: usize) -> bool {
+        if self.pinned.contains(&before) || self.pinned.contains(&next) {
+            return false;
+        }
+        let (a, b) = (self.lines[before].trim(), self.lines[next].trim());
+        if a.is_empty() || b.is_empty() || is_table_row(a) || is_table_row(b) {
+            return false;
+        }
+        if self.list_item.is_match(b) {
+            return false;
+        }
+        if hyphen_break(a, b).is_some() {
+            return true;
+        }
+
+        let full = self.measure > 0
+            && a.chars().count() as f64 >= self.measure as f64 * FULL_LINE_RATIO;
+        full || (!ends_sentence(a) && starts_lowercase(b))
+    }
+
+    /// Append `line` to the paragraph built so far.
+    fn append(&self, paragraph: &mut String, line: &str) {
+        let line = line.trim();
+        match hyphen_break(paragraph, line) {
+            Some(hyphen) => {
+                let stem_end = paragraph.len() - hyphen.len_utf8();
+                let stem = paragraph[..stem_end]
+                    .rsplit(char::is_whitespace)
+                    .next()
+                    .unwrap_or_default();
+                let head = line.split_whitespace().next().unwrap_or_default();
+                let compound = format!("{stem}-{head}")
+                    .trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase();
+                let keep = hyphen != '\u{ad}'
+                    && (starts_uppercase(line) || self.compounds.contains(&compound));
+                if !keep {
+                    paragraph.truncate(stem_end);
+                }
+            }
+            None => paragraph.push(' '),
+        }
+        paragraph.push_str(line);
+    }
+}
+
+/// The hyphen `a` ends on when it splits a word across the break into `b`.
+fn hyphen_break(a: &str, b: &str) -> Option<char> {
+    let mut tail = a.trim_end().chars().rev();
+    let hyphen = tail.next().filter(|c| matches!(c, '-' | '\u{ad}' | '\u{2010}'))?;
+    let letter_before = tail.next().is_some_and(char::is_alphabetic);
+    let letter_after = b.trim_start().chars().next().is_some_and(char::is_alphabetic);
+    (letter_before && letter_after).then_some(hyphen)
+}
+
+/// Layout extraction pads table cells apart with runs of spaces.
+fn is_table_row(line: &str) -> bool {
+    line.contains("  ")
+}
+
+fn ends_sentence(line: &str) -> bool {
+    line.trim_end_matches(['"', '\'', '”', '’', ')', ']'])
+        .ends_with(['.', '!', '?', ':'])
+}
+
+fn starts_lowercase(line: &str) -> bool {
+    line.chars().next().is_some_and(char::is_lowercase)
+}
+
+fn starts_uppercase(line: &str) -> bool {
+    line.chars().next().is_some_and(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_furniture::FurnitureKind;
+
+    fn page(number: usize, start_line: usize, line_count: usize) -> PdfPage {
+        PdfPage {
+            number,
+            start_line,
+            line_count,
+            image_count: 0,
+            ocr: false,
+        }
+    }
+
+    #[test]
+    fn rejoins_paragraphs_and_hyphenated_words_within_each_page() {
+        let text = [
+            "Annual Report",
+            "The committee reviewed the informa-",
+            "tion gathered during the well-known audit and the",
+            "findings were shared with every depart-",
+            "ment before the end of the year.",
+            "",
+            "Next Steps",
+            "1. Publish the summary",
+            "2. Schedule the well-",
+            "known review",
+            "3",
+            "A second page starts a fresh paragraph that runs on",
+            "to a second line here.",
+        ]
+        .join("\n");
+        let pages = [page(1, 0, 11), page(2, 11, 2)];
+        let furniture = [FurnitureLine {
+            line: 10,
+            kind: FurnitureKind::PageNumber,
+        }];
+
+        let reflowed = reflow(&pages, &text, &furniture);
+        let lines: Vec<&str> = reflowed.text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Annual Report",
+                "The committee reviewed the information gathered during the well-known audit and the findings were shared with every department before the end of the year.",
+                "",
+                "Next Steps",
+                "1. Publish the summary",
+                "2. Schedule the well-known review",
+                "3",
+                "A second page starts a fresh paragraph that runs on to a second line here.",
+            ]
+        );
+        assert_eq!(reflowed.pages[0].line_count, 7);
+        assert_eq!(reflowed.pages[1].start_line, 7);
+        assert_eq!(reflowed.furniture[0].line, 6, "the page number moved with its page");
+    }
+
+    #[test]
+    fn table_rows_and_short_lines_stay_apart() {
+        let text = "Region    Sales\nNorth     12\nSouth     9\nShort line\nAnother";
+        let reflowed = reflow(&[page(1, 0, 5)], text, &[]);
+        assert_eq!(reflowed.text, format!("{text}\n"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::app_structs::{AppState, Theme};
+use crate::pdf_doc::ExtractionMode;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -15,6 +16,13 @@ pub struct AppSettings {
     pub whole_word: bool,
     pub line_numbers: bool,
     pub wrap_lines: bool,
+    /// Added after the first release; files written before it lack the key.
+    #[serde(default)]
+    pub extraction: ExtractionMode,
+    #[serde(default)]
+    pub strip_furniture: bool,
+    #[serde(default)]
+    pub reflow: bool,
 }
 
 impl Default for AppSettings {
@@ -28,6 +36,9 @@ impl Default for AppSettings {
             whole_word: false,
             line_numbers: true,
             wrap_lines: false,
+            extraction: ExtractionMode::Flow,
+            strip_furniture: false,
+            reflow: false,
         }
     }
 }
@@ -55,7 +66,7 @@ impl AppSettings {
         }
 
         let contents = toml::to_string_pretty(self)
-            .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+            .map_err(io::Error::other)?;
         fs::write(path, contents)
     }
 
@@ -68,6 +79,9 @@ impl AppSettings {
         state.whole_word = self.whole_word;
         state.line_numbers = self.line_numbers;
         state.wrap_lines = self.wrap_lines;
+        state.extraction_mode = self.extraction;
+        state.strip_furniture = self.strip_furniture;
+        state.reflow = self.reflow;
     }
 
     pub fn from_state(state: &AppState) -> Self {
@@ -80,6 +94,9 @@ impl AppSettings {
             whole_word: state.whole_word,
             line_numbers: state.line_numbers,
             wrap_lines: state.wrap_lines,
+            extraction: state.extraction_mode,
+            strip_furniture: state.strip_furniture,
+            reflow: state.reflow,
         }
     }
 }
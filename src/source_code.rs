@@ -0,0 +1,782 @@
+//! Source files: which language they are in, what each stretch of a line is,
+//! and how much of them is code, comment and function.
+//!
+//! The lexer knows only what colouring and counting need: where comments and
+//! string literals start and end, which words the language reserves, and
+//! which words are called. It works a line at a time, carrying over only
+//! whether the line starts inside a block comment or a string, so the Viewer
+//! can colour just the lines on screen.
+
+use std::ops::Range;
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+
+/// The languages recognised by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    C,
+    Cpp,
+    Java,
+    Shell,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "rs" => Self::Rust,
+            "py" | "pyw" => Self::Python,
+            "js" | "mjs" | "cjs" | "jsx" => Self::JavaScript,
+            "ts" | "mts" | "cts" | "tsx" => Self::TypeScript,
+            "go" => Self::Go,
+            "c" | "h" => Self::C,
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Self::Cpp,
+            "java" => Self::Java,
+            "sh" | "bash" | "zsh" => Self::Shell,
+            _ => return None,
+        })
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::Python => "Python",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::Go => "Go",
+            Self::C => "C",
+            Self::Cpp => "C++",
+            Self::Java => "Java",
+            Self::Shell => "Shell",
+        }
+    }
+
+    fn syntax(self) -> &'static Syntax {
+        match self {
+            Self::Rust => &RUST,
+            Self::Python => &PYTHON,
+            Self::JavaScript => &JAVASCRIPT,
+            Self::TypeScript => &TYPESCRIPT,
+            Self::Go => &GO,
+            Self::C => &C,
+            Self::Cpp => &CPP,
+            Self::Java => &JAVA,
+            Self::Shell => &SHELL,
+        }
+    }
+}
+
+/// What a stretch of a line is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Identifiers, operators and punctuation.
+    Plain,
+    Keyword,
+    /// A word followed by `(`: a call or a definition.
+    Function,
+    Number,
+    String,
+    Comment,
+}
+
+/// A stretch of one line, by byte range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub range: Range<usize>,
+    pub kind: TokenKind,
+}
+
+/// A function definition, with the lines from its signature to its end.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Function {
+    pub name: String,
+    /// 1-based line the definition starts on.
+    pub line: usize,
+    pub lines: usize,
+}
+
+/// How a file divides into code, comments and blank lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeStats {
+    pub lines: usize,
+    /// Lines with any code on them, comments after it or not.
+    pub code_lines: usize,
+    /// Lines holding only a comment.
+    pub comment_lines: usize,
+    pub blank_lines: usize,
+    pub functions: Vec<Function>,
+}
+
+impl CodeStats {
+    /// Comment lines as a share of the lines that are not blank, 0 to 1.
+    pub fn comment_ratio(&self) -> f64 {
+        let written = self.code_lines + self.comment_lines;
+        if written == 0 {
+            0.0
+        } else {
+            self.comment_lines as f64 / written as f64
+        }
+    }
+
+    /// The function with the most lines; the first of them on a tie.
+    pub fn longest_function(&self) -> Option<&Function> {
+        self.functions.iter().reduce(|longest, function| {
+            if function.lines > longest.lines {
+                function
+            } else {
+                longest
+            }
+        })
+    }
+}
+
+/// A source file read for colouring and counting.
+#[derive(Debug, Clone)]
+pub struct SourceCode {
+    pub language: Language,
+    /// Whether each line starts in code, a comment or a string.
+    starts: Vec<LexState>,
+    pub stats: CodeStats,
+}
+
+impl SourceCode {
+    pub fn parse(text: &str, language: Language) -> Self {
+        let syntax = language.syntax();
+        let mut starts = Vec::new();
+        let mut stats = CodeStats::default();
+        let mut functions = FunctionFinder::new(syntax);
+        let mut state = LexState::Code;
+
+        for (index, line) in text.lines().enumerate() {
+            starts.push(state);
+            let (tokens, next) = lex_line(syntax, line, state);
+            let has_code = tokens.iter().any(|token| {
+                token.kind != TokenKind::Comment && !line[token.range.clone()].trim().is_empty()
+            });
+            if has_code {
+                stats.code_lines += 1;
+            } else if tokens.iter().any(|token| token.kind == TokenKind::Comment) {
+                stats.comment_lines += 1;
+            } else {
+                stats.blank_lines += 1;
+            }
+            functions.line(index, line, &tokens, state, has_code);
+            state = next;
+        }
+        stats.lines = starts.len();
+        stats.functions = functions.finish();
+
+        Self {
+            language,
+            starts,
+            stats,
+        }
+    }
+
+    /// The tokens of `line`, line `index` of the file, covering all of it.
+    /// The line may be cut short; it is read from the state the full line
+    /// starts in.
+    pub fn tokens(&self, index: usize, line: &str) -> Vec<Token> {
+        let state = self.starts.get(index).copied().unwrap_or_default();
+        lex_line(self.language.syntax(), line, state).0
+    }
+
+    /// The words the Analyze tab should weigh, a line of them per line of
+    /// `text`: comments as written, and identifiers split into their words,
+    /// so `parseHttpRequest` and `parse_http_request` both count as parse,
+    /// http and request. Keywords and numbers are left out, and so are
+    /// string literals, which hold paths and format strings as often as prose.
+    pub fn analysis_text(&self, text: &str) -> String {
+        let mut words = String::new();
+        for (index, line) in text.lines().enumerate() {
+            let mut first = true;
+            let mut push = |word: &str| {
+                if !first {
+                    words.push(' ');
+                }
+                words.push_str(word);
+                first = false;
+            };
+            for token in self.tokens(index, line) {
+                let piece = &line[token.range];
+                match token.kind {
+                    TokenKind::Comment => push(piece.trim()),
+                    TokenKind::Plain | TokenKind::Function => {
+                        for identifier in piece
+                            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                            .filter(|identifier| !identifier.is_empty())
+                        {
+                            for word in split_identifier(identifier) {
+                                push(&word);
+                            }
+                        }
+                    }
+                    TokenKind::Keyword | TokenKind::Number | TokenKind::String => {}
+                }
+            }
+            words.push('\n');
+        }
+        words
+    }
+}
+
+/// The words an identifier is made of, lowercased: `HTTPServer_v2` gives
+/// http, server and v2.
+pub fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in identifier.split('_').filter(|part| !part.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut start = 0;
+        for at in 1..chars.len() {
+            let (before, here) = (chars[at - 1], chars[at]);
+            let after = chars.get(at + 1).copied();
+            // fooBar splits before B; HTTPServer splits before the S.
+            let boundary = (before.is_lowercase() && here.is_uppercase())
+                || (before.is_uppercase()
+                    && here.is_uppercase()
+                    && after.is_some_and(char::is_lowercase));
+            if boundary {
+                words.push(chars[start..at].iter().collect::<String>().to_lowercase());
+                start = at;
+            }
+        }
+        words.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+    words
+}
+
+/// What a language's lines are made of, as far as the lexer is concerned.
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// String delimiters, longest first, and whether a string they open may
+    /// run on past the end of its line.
+    quotes: &'static [(&'static str, bool)],
+    /// `'` opens a string only when it closes again a character later, as
+    /// in Rust, where it also marks lifetimes.
+    char_quotes: bool,
+    /// Line comments start only at the start of a word, as `#` in a shell,
+    /// where `$#` is not one.
+    comments_after_space: bool,
+    /// Reserved words and built-in types, separated by spaces.
+    keywords: &'static str,
+    /// Finds a function's name in the code before its opening brace, or on
+    /// its first line where blocks are indented.
+    functions: &'static str,
+    /// Blocks end where their indentation does, as in Python.
+    indented: bool,
+}
+
+impl Syntax {
+    fn is_keyword(&self, word: &str) -> bool {
+        self.keywords
+            .split_whitespace()
+            .any(|keyword| keyword == word)
+    }
+}
+
+/// A name before a parameter list that ends the code before the brace.
+const C_FUNCTIONS: &str =
+    r"(\w+)\s*\([^;]*\)\s*(?:(?:const|noexcept|override|final|throws\s+[\w.,\s]+)\s*)*$";
+/// `function name`, `name = () =>` and `name = function`, and methods.
+const JS_FUNCTIONS: &str = concat!(
+    r"\bfunction\s*\*?\s*(\w+)",
+    r"|(\w+)\s*=\s*(?:async\s+)?(?:function\b[^{]*|\([^)]*\)\s*=>|\w+\s*=>)$",
+    r"|^(?:(?:async|static|get|set|public|private|protected|readonly)\s+)*",
+    r"(\w+)\s*(?:<[^>]*>)?\s*\([^)]*\)\s*(?::\s*[^{]+)?$",
+);
+
+const C_KEYWORDS: &str = "\
+    auto bool break case char const continue default define do double else endif \
+    enum extern false float for goto if ifdef ifndef include inline int long \
+    NULL pragma register restrict return short signed sizeof static struct \
+    switch true typedef union unsigned void volatile while";
+
+static RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("\"", true), ("'", false)],
+    char_quotes: true,
+    comments_after_space: false,
+    keywords: "\
+        as async await bool break char const continue crate dyn else enum extern f32 \
+        f64 false fn for i128 i16 i32 i64 i8 if impl in isize let loop match mod \
+        move mut pub ref return self Self static str struct super trait true type \
+        u128 u16 u32 u64 u8 unsafe use usize where while",
+    functions: r"\bfn\s+(\w+)",
+    indented: false,
+};
+
+static PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    char_quotes: false,
+    comments_after_space: false,
+    keywords: "\
+        False None True and as assert async await break class continue def del elif \
+        else except finally for from global if import in is lambda nonlocal not or \
+        pass raise return self try while with yield",
+    functions: r"^\s*(?:async\s+)?def\s+(\w+)",
+    indented: true,
+};
+
+static JAVASCRIPT: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("`", true), ("\"", false), ("'", false)],
+    char_quotes: false,
+    comments_after_space: false,
+    keywords: "\
+        async await break case catch class const continue debugger default delete do \
+        else export extends false finally for function if import in instanceof let \
+        new null of return static super switch this throw true try typeof undefined \
+        var void while with yield",
+    functions: JS_FUNCTIONS,
+    indented: false,
+};
+
+static TYPESCRIPT: Syntax = Syntax {
+    keywords: "\
+        abstract any as async await boolean break case catch class const continue \
+        debugger declare default delete do else enum export extends false finally \
+        for function if implements import in instanceof interface keyof let \
+        namespace never new null number of private protected public readonly return \
+        static string super switch this throw true try type typeof undefined unknown \
+        var void while with yield",
+    ..JAVASCRIPT
+};
+
+static GO: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("`", true), ("\"", false), ("'", false)],
+    char_quotes: false,
+    comments_after_space: false,
+    keywords: "\
+        bool break byte case chan const continue default defer else error \
+        fallthrough false float64 for func go goto if import int int64 interface map \
+        nil package range return rune select string struct switch true type uint var",
+    functions: r"\bfunc\s*(?:\([^)]*\)\s*)?(\w+)",
+    indented: false,
+};
+
+static C: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &[("\"", false), ("'", false)],
+    char_quotes: false,
+    comments_after_space: false,
+    keywords: C_KEYWORDS,
+    functions: C_FUNCTIONS,
+    indented: false,
+};
+
+static CPP: Syntax = Syntax {
+    keywords: "\
+        auto bool break case catch char class const const_cast constexpr continue \
+        decltype default define delete do double dynamic_cast else endif enum \
+        explicit extern false final float for friend goto if ifdef ifndef include \
+        inline int long mutable namespace new noexcept nullptr operator override \
+        pragma private protected public reinterpret_cast return short signed sizeof \
+        static static_cast struct switch template this throw true try typedef \
+        typename union unsigned using virtual void volatile while",
+    ..C
+};
+
+static JAVA: Syntax = Syntax {
+    quotes: &[("\"\"\"", true), ("\"", false), ("'", false)],
+    keywords: "\
+        abstract assert boolean break byte case catch char class const continue \
+        default do double else enum extends false final finally float for if \
+        implements import instanceof int interface long native new null package \
+        private protected public record return short static super switch \
+        synchronized this throw throws transient true try var void volatile while",
+    ..C
+};
+
+static SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &[("\"", true), ("'", true)],
+    char_quotes: false,
+    comments_after_space: true,
+    keywords: "\
+        break case continue declare do done elif else esac exit export fi for \
+        function if in local readonly return then until while",
+    functions: r"^(?:function\s+)?(\w+)\s*\(\s*\)|^function\s+(\w+)",
+    indented: false,
+};
+
+/// Where a line starts: in code, inside a block comment, or inside a string
+/// opened by the quote at that index of [`Syntax::quotes`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum LexState {
+    #[default]
+    Code,
+    Comment,
+    Quoted(usize),
+}
+
+/// Split `line` into tokens, starting in `state`, and say what state the
+/// next line starts in.
+fn lex_line(syntax: &Syntax, line: &str, mut state: LexState) -> (Vec<Token>, LexState) {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut push = |range: Range<usize>, kind: TokenKind| match tokens.last_mut() {
+        Some(last) if last.kind == kind && last.range.end == range.start => {
+            last.range.end = range.end;
+        }
+        _ => tokens.push(Token { range, kind }),
+    };
+
+    let mut at = 0;
+    while at < line.len() {
+        let rest = &line[at..];
+        match state {
+            LexState::Comment => {
+                let (_, close) = syntax.block_comment.expect("only block comments get here");
+                let end = match rest.find(close) {
+                    Some(offset) => {
+                        state = LexState::Code;
+                        at + offset + close.len()
+                    }
+                    None => line.len(),
+                };
+                push(at..end, TokenKind::Comment);
+                at = end;
+            }
+            LexState::Quoted(index) => {
+                let (quote, runs_on) = syntax.quotes[index];
+                let end = match string_end(rest, quote) {
+                    Some(offset) => {
+                        state = LexState::Code;
+                        at + offset
+                    }
+                    None => {
+                        if !runs_on {
+                            state = LexState::Code;
+                        }
+                        line.len()
+                    }
+                };
+                push(at..end, TokenKind::String);
+                at = end;
+            }
+            LexState::Code => {
+                let word_start =
+                    at == 0 || line[..at].ends_with(|c: char| c.is_whitespace() || c == ';');
+                if syntax
+                    .line_comments
+                    .iter()
+                    .any(|marker| rest.starts_with(marker))
+                    && (word_start || !syntax.comments_after_space)
+                {
+                    push(at..line.len(), TokenKind::Comment);
+                    break;
+                }
+                if let Some((open, _)) = syntax.block_comment {
+                    if rest.starts_with(open) {
+                        push(at..at + open.len(), TokenKind::Comment);
+                        at += open.len();
+                        state = LexState::Comment;
+                        continue;
+                    }
+                }
+                if let Some(index) = syntax
+                    .quotes
+                    .iter()
+                    .position(|(quote, _)| rest.starts_with(quote))
+                {
+                    let quote = syntax.quotes[index].0;
+                    if !(syntax.char_quotes && quote == "'") || is_char_literal(rest) {
+                        push(at..at + quote.len(), TokenKind::String);
+                        at += quote.len();
+                        state = LexState::Quoted(index);
+                        continue;
+                    }
+                }
+
+                let first = rest.chars().next().expect("not at the end");
+                let length = if first.is_ascii_digit() {
+                    number_length(rest)
+                } else if first.is_alphabetic() || first == '_' {
+                    rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len())
+                } else {
+                    first.len_utf8()
+                };
+                let word = &rest[..length];
+                let kind = if first.is_ascii_digit() {
+                    TokenKind::Number
+                } else if !(first.is_alphabetic() || first == '_') {
+                    TokenKind::Plain
+                } else if syntax.is_keyword(word) {
+                    TokenKind::Keyword
+                } else if rest[length..].trim_start().starts_with('(')
+                    || rest[length..].starts_with("!(")
+                {
+                    TokenKind::Function
+                } else {
+                    TokenKind::Plain
+                };
+                push(at..at + length, kind);
+                at += length;
+            }
+        }
+    }
+    (tokens, state)
+}
+
+/// Bytes of `rest` up to and including the `quote` that closes a string,
+/// skipping escaped characters.
+fn string_end(rest: &str, quote: &str) -> Option<usize> {
+    let mut chars = rest.char_indices();
+    while let Some((at, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if rest[at..].starts_with(quote) {
+            return Some(at + quote.len());
+        }
+    }
+    None
+}
+
+/// Whether `rest`, which starts with `'`, is a character literal such as
+/// `'a'` or `'\n'` rather than a lifetime such as `'a`.
+fn is_char_literal(rest: &str) -> bool {
+    let mut chars = rest.chars().skip(1);
+    match chars.next() {
+        Some('\\') => rest[2..].find('\'').is_some(),
+        Some(_) => chars.next() == Some('\''),
+        None => false,
+    }
+}
+
+/// Bytes of the number `rest` starts with: digits, letters for hex and
+/// suffixes, and a `.` only when a digit follows, so `0..10` is two.
+fn number_length(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut length = 0;
+    while length < bytes.len() {
+        let byte = bytes[length];
+        let decimal_point = byte == b'.'
+            && bytes
+                .get(length + 1)
+                .is_some_and(|next| next.is_ascii_digit());
+        if !(byte.is_ascii_alphanumeric() || byte == b'_' || decimal_point) {
+            break;
+        }
+        length += 1;
+    }
+    length
+}
+
+/// Follows a file line by line and records each function it defines, from
+/// the line its definition starts on to the line it ends on.
+struct FunctionFinder<'a> {
+    syntax: &'a Syntax,
+    pattern: Regex,
+    found: Vec<Function>,
+    /// Code since the last `;`, `{` or `}`, and the line it started on.
+    header: String,
+    header_line: usize,
+    /// The braces still open, with the function each one is the body of.
+    braces: Vec<Option<(String, usize)>>,
+    /// Python functions still open: indentation, name, first and last line.
+    indented: Vec<(usize, String, usize, usize)>,
+}
+
+impl<'a> FunctionFinder<'a> {
+    fn new(syntax: &'a Syntax) -> Self {
+        Self {
+            syntax,
+            pattern: Regex::new(syntax.functions).expect("static regex"),
+            found: Vec::new(),
+            header: String::new(),
+            header_line: 0,
+            braces: Vec::new(),
+            indented: Vec::new(),
+        }
+    }
+
+    fn line(&mut self, index: usize, line: &str, tokens: &[Token], start: LexState, code: bool) {
+        if self.syntax.indented {
+            self.indented_line(index, line, start, code);
+            return;
+        }
+        for token in tokens {
+            if matches!(token.kind, TokenKind::Comment | TokenKind::String) {
+                continue;
+            }
+            for c in line[token.range.clone()].chars() {
+                match c {
+                    '{' => {
+                        let name = self.name(self.header.trim());
+                        self.braces.push(name.map(|name| (name, self.header_line)));
+                        self.header.clear();
+                    }
+                    '}' => {
+                        if let Some(Some((name, first))) = self.braces.pop() {
+                            self.found.push(Function {
+                                name,
+                                line: first + 1,
+                                lines: index - first + 1,
+                            });
+                        }
+                        self.header.clear();
+                    }
+                    ';' => self.header.clear(),
+                    c if c.is_whitespace() && self.header.is_empty() => {}
+                    c => {
+                        if self.header.is_empty() {
+                            self.header_line = index;
+                        }
+                        self.header.push(c);
+                    }
+                }
+            }
+        }
+        if !self.header.is_empty() {
+            self.header.push(' ');
+        }
+    }
+
+    fn indented_line(&mut self, index: usize, line: &str, start: LexState, code: bool) {
+        // Lines inside a string or holding only a comment do not close a
+        // block, however far out they start.
+        if !code || start != LexState::Code {
+            return;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while self
+            .indented
+            .last()
+            .is_some_and(|(open, ..)| indent <= *open)
+        {
+            self.close_indented();
+        }
+        if let Some(name) = self.name(line) {
+            self.indented.push((indent, name, index, index));
+        }
+        for open in &mut self.indented {
+            open.3 = index;
+        }
+    }
+
+    fn close_indented(&mut self) {
+        if let Some((_, name, first, last)) = self.indented.pop() {
+            self.found.push(Function {
+                name,
+                line: first + 1,
+                lines: last - first + 1,
+            });
+        }
+    }
+
+    /// The function `code` defines, if it defines one.
+    fn name(&self, code: &str) -> Option<String> {
+        let captures = self.pattern.captures(code)?;
+        let name = captures.iter().skip(1).flatten().next()?.as_str();
+        (!self.syntax.is_keyword(name)).then(|| name.to_string())
+    }
+
+    /// Every function found, in the order they start.
+    fn finish(mut self) -> Vec<Function> {
+        while !self.indented.is_empty() {
+            self.close_indented();
+        }
+        self.found.sort_by_key(|function| function.line);
+        self.found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(language: Language, line: &str) -> Vec<(&str, TokenKind)> {
+        lex_line(language.syntax(), line, LexState::Code)
+            .0
+            .into_iter()
+            .filter(|token| !line[token.range.clone()].trim().is_empty())
+            .map(|token| (&line[token.range], token.kind))
+            .collect()
+    }
+
+    #[test]
+    fn lines_split_into_keywords_strings_and_comments() {
+        assert_eq!(
+            kinds(Language::Rust, "let s = parse(\"a // b\"); // note"),
+            [
+                ("let", TokenKind::Keyword),
+                (" s = ", TokenKind::Plain),
+                ("parse", TokenKind::Function),
+                ("(", TokenKind::Plain),
+                ("\"a // b\"", TokenKind::String),
+                ("); ", TokenKind::Plain),
+                ("// note", TokenKind::Comment),
+            ]
+        );
+        // A lifetime is not a character literal; 'x' is.
+        assert_eq!(
+            kinds(Language::Rust, "fn f<'a>(c: char) { c == 'x' }")
+                .iter()
+                .filter(|(_, kind)| *kind == TokenKind::String)
+                .count(),
+            1
+        );
+        assert_eq!(
+            kinds(Language::Shell, "echo $# # count"),
+            [
+                ("echo $# ", TokenKind::Plain),
+                ("# count", TokenKind::Comment)
+            ]
+        );
+    }
+
+    #[test]
+    fn identifiers_split_on_case_and_underscores() {
+        assert_eq!(
+            split_identifier("parseHTTPRequest"),
+            ["parse", "http", "request"]
+        );
+        assert_eq!(
+            split_identifier("MAX_LINE_width2"),
+            ["max", "line", "width2"]
+        );
+        assert_eq!(split_identifier("_private"), ["private"]);
+    }
+
+    #[test]
+    fn functions_are_measured_from_signature_to_end() {
+        let c = "/* header\n   comment */\nint add(int a, int b)\n{\n    if (a) {\n        return a + b;\n    }\n    return b;\n}\n";
+        let code = SourceCode::parse(c, Language::C);
+        assert_eq!(code.stats.comment_lines, 2);
+        assert_eq!(code.stats.code_lines, 7);
+        assert_eq!(
+            code.stats.functions,
+            [Function {
+                name: "add".into(),
+                line: 3,
+                lines: 7
+            }]
+        );
+
+        let python = "def short():\n    pass\n\ndef longer(x):\n    \"\"\"Doc\nstring.\"\"\"\n    # note\n    return x\n\nvalue = 1\n";
+        let code = SourceCode::parse(python, Language::Python);
+        let longest = code.stats.longest_function().unwrap();
+        assert_eq!(
+            (longest.name.as_str(), longest.line, longest.lines),
+            ("longer", 4, 5)
+        );
+        assert_eq!(code.stats.functions.len(), 2);
+    }
+}
@@ -0,0 +1,417 @@
+//! Delimited files read as tables.
+//!
+//! A CSV or TSV file is rows of cells under a header, not prose: read as text,
+//! its header names and numbers crowd the keywords and a search cannot say
+//! which column it means. Here the cells keep their columns. Each column gets
+//! a type guessed from its cells and a few statistics, and the rows are lined
+//! up so the Viewer reads like a spreadsheet.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::app_structs::SearchMatch;
+use crate::text_analysis::{Matcher, SearchOptions};
+
+/// Widest a column is drawn, in characters; longer cells are cut short.
+pub const MAX_COLUMN_WIDTH: usize = 32;
+/// Space between two columns.
+const COLUMN_GAP: &str = "  ";
+/// Delimiters tried when a file's name does not say which it uses.
+const DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+/// Cells that stand for a missing value. Only markers that mean nothing else:
+/// `NA` is also Namibia and sodium, and `None` a real answer.
+const NULLS: [&str; 4] = ["", "n/a", "null", "nan"];
+
+/// What a column holds, guessed from its cells. Missing values do not count
+/// against a type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnKind {
+    /// Nothing but missing values.
+    Empty,
+    Integer,
+    Number,
+    Boolean,
+    /// `2024-03-05`, with or without a time after it.
+    Date,
+    Text,
+}
+
+impl ColumnKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::Text => "text",
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::Integer | Self::Number)
+    }
+}
+
+/// One column's type and statistics.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    pub kind: ColumnKind,
+    /// Cells holding a missing value.
+    pub nulls: usize,
+    /// Different values among the rest.
+    pub distinct: usize,
+    /// Smallest and largest value as written, for numbers and dates.
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Average of a numeric column.
+    pub mean: Option<f64>,
+}
+
+/// A delimited file: a header, rows of cells and what is known of each column.
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub delimiter: char,
+    pub headers: Vec<String>,
+    /// Every row has a cell per header; short rows are padded with empty ones.
+    pub rows: Vec<Vec<String>>,
+    pub columns: Vec<ColumnStats>,
+    /// Characters each column is drawn in.
+    widths: Vec<usize>,
+}
+
+impl Table {
+    /// Read `text`, taking its first record as the header.
+    pub fn parse(text: &str, delimiter: u8) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+        let mut records = reader.records();
+        let mut headers: Vec<String> = match records.next() {
+            Some(record) => record
+                .map_err(|error| error.to_string())?
+                .iter()
+                .map(|name| name.trim().to_string())
+                .collect(),
+            None => return Err("the table has no header row".to_string()),
+        };
+        let mut rows = Vec::new();
+        for record in records {
+            let record = record.map_err(|error| error.to_string())?;
+            rows.push(record.iter().map(str::to_string).collect::<Vec<_>>());
+        }
+
+        let width = rows.iter().map(Vec::len).fold(headers.len(), usize::max);
+        headers.resize(width, String::new());
+        for (index, name) in headers.iter_mut().enumerate() {
+            if name.is_empty() {
+                *name = format!("column {}", index + 1);
+            }
+        }
+        for row in &mut rows {
+            row.resize(width, String::new());
+        }
+
+        let columns = (0..width)
+            .map(|index| column_stats(&headers[index], rows.iter().map(|row| row[index].as_str())))
+            .collect();
+        let widths = (0..width)
+            .map(|index| {
+                rows.iter()
+                    .map(|row| &row[index])
+                    .chain([&headers[index]])
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .min(MAX_COLUMN_WIDTH)
+            })
+            .collect();
+
+        Ok(Self {
+            delimiter: char::from(delimiter),
+            headers,
+            rows,
+            columns,
+            widths,
+        })
+    }
+
+    /// The header, lined up over the columns.
+    pub fn header_line(&self) -> String {
+        self.line(&self.headers)
+    }
+
+    /// Every row lined up under the header, one per line.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            text.push_str(&self.line(row));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The cells of the text columns, one row per line: what there is to read
+    /// in the table, without the numbers and dates between it.
+    pub fn text_cells(&self) -> String {
+        let text_columns: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.kind == ColumnKind::Text)
+            .map(|(index, _)| index)
+            .collect();
+        let mut text = String::new();
+        for row in &self.rows {
+            let cells: Vec<&str> = text_columns
+                .iter()
+                .map(|index| row[*index].trim())
+                .filter(|cell| !is_null(cell))
+                .collect();
+            text.push_str(&cells.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Where `column` sits in a lined-up row, in characters.
+    pub fn column_span(&self, column: usize) -> Range<usize> {
+        let start: usize = self.widths[..column.min(self.widths.len())]
+            .iter()
+            .map(|width| width + COLUMN_GAP.len())
+            .sum();
+        start..start + self.widths.get(column).copied().unwrap_or(0)
+    }
+
+    /// Characters to cut from the left of each row so that `column` fits in
+    /// `width`: none while it already does, else up to where it starts.
+    pub fn offset_showing(&self, column: Option<usize>, width: usize) -> usize {
+        match column.map(|column| self.column_span(column)) {
+            Some(span) if span.end > width => span.start,
+            _ => 0,
+        }
+    }
+
+    /// Rows matching `query`, in `options.column` alone when it is set. Each
+    /// match is reported as its lined-up row, numbered from 1 like the lines
+    /// of [`Table::text`].
+    pub fn search(&self, query: &str, options: SearchOptions) -> Result<Vec<SearchMatch>, String> {
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let matcher = Matcher::new(query, options)?;
+        Ok(self
+            .rows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, row)| {
+                let match_count = match options.column {
+                    Some(column) => row.get(column).map_or(0, |cell| matcher.count(cell)),
+                    None => row.iter().map(|cell| matcher.count(cell)).sum(),
+                };
+                (match_count > 0).then(|| SearchMatch {
+                    line_number: index + 1,
+                    line: self.line(row),
+                    match_count,
+                })
+            })
+            .collect())
+    }
+
+    fn line(&self, cells: &[String]) -> String {
+        let mut line = String::new();
+        for (index, (cell, width)) in cells.iter().zip(&self.widths).enumerate() {
+            if index > 0 {
+                line.push_str(COLUMN_GAP);
+            }
+            let cell = cell.replace(['\n', '\r', '\t'], " ");
+            let length = cell.chars().count();
+            if length > *width {
+                line.extend(cell.chars().take(width.saturating_sub(1)));
+                line.push('…');
+            } else if self.columns[index].kind.is_numeric() {
+                line.push_str(&" ".repeat(width - length));
+                line.push_str(&cell);
+            } else {
+                line.push_str(&cell);
+                line.push_str(&" ".repeat(width - length));
+            }
+        }
+        line.trim_end().to_string()
+    }
+}
+
+/// The delimiter a file called `path` starting with `head` uses: a tab for
+/// `.tsv` and `.tab`, otherwise whichever of `,` `;` tab and `|` splits the
+/// first lines into the same number of cells, commas when none does.
+pub fn delimiter_for(path: &Path, head: &str) -> u8 {
+    let extension = path
+        .extension()
+        .and_then(|value| value.to_str())
+        .unwrap_or_default();
+    if ["tsv", "tab"]
+        .iter()
+        .any(|tab| extension.eq_ignore_ascii_case(tab))
+    {
+        return b'\t';
+    }
+    let lines: Vec<&str> = head
+        .lines()
+        .filter(|line| !line.is_empty())
+        .take(5)
+        .collect();
+    DELIMITERS
+        .into_iter()
+        .filter_map(|delimiter| {
+            let counts: Vec<usize> = lines
+                .iter()
+                .map(|line| line.bytes().filter(|byte| *byte == delimiter).count())
+                .collect();
+            let first = *counts.first()?;
+            (first > 0 && counts.iter().all(|count| *count == first)).then_some((delimiter, first))
+        })
+        .max_by_key(|(_, count)| *count)
+        .map_or(b',', |(delimiter, _)| delimiter)
+}
+
+fn is_null(cell: &str) -> bool {
+    NULLS.iter().any(|null| cell.eq_ignore_ascii_case(null))
+}
+
+fn is_boolean(cell: &str) -> bool {
+    ["true", "false", "yes", "no"]
+        .iter()
+        .any(|word| cell.eq_ignore_ascii_case(word))
+}
+
+fn is_date(cell: &str) -> bool {
+    let bytes = cell.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10]
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| match index {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            })
+        && bytes.get(10).is_none_or(|byte| matches!(byte, b'T' | b' '))
+}
+
+fn column_stats<'a>(name: &str, cells: impl Iterator<Item = &'a str>) -> ColumnStats {
+    let mut nulls = 0;
+    let mut values = Vec::new();
+    for cell in cells.map(str::trim) {
+        if is_null(cell) {
+            nulls += 1;
+        } else {
+            values.push(cell);
+        }
+    }
+
+    let kind = if values.is_empty() {
+        ColumnKind::Empty
+    } else if values.iter().all(|value| value.parse::<i64>().is_ok()) {
+        ColumnKind::Integer
+    } else if values
+        .iter()
+        .all(|value| value.parse::<f64>().is_ok_and(f64::is_finite))
+    {
+        ColumnKind::Number
+    } else if values.iter().all(|value| is_boolean(value)) {
+        ColumnKind::Boolean
+    } else if values.iter().all(|value| is_date(value)) {
+        ColumnKind::Date
+    } else {
+        ColumnKind::Text
+    };
+
+    let (min, max, mean) = match kind {
+        kind if kind.is_numeric() => {
+            let numbers: Vec<(f64, &str)> = values
+                .iter()
+                .filter_map(|value| Some((value.parse::<f64>().ok()?, *value)))
+                .collect();
+            let min = numbers.iter().min_by(|a, b| a.0.total_cmp(&b.0));
+            let max = numbers.iter().max_by(|a, b| a.0.total_cmp(&b.0));
+            let mean = numbers.iter().map(|(number, _)| number).sum::<f64>() / numbers.len() as f64;
+            (
+                min.map(|(_, text)| text.to_string()),
+                max.map(|(_, text)| text.to_string()),
+                Some(mean),
+            )
+        }
+        // ISO dates sort as text.
+        ColumnKind::Date => (
+            values.iter().min().map(|value| value.to_string()),
+            values.iter().max().map(|value| value.to_string()),
+            None,
+        ),
+        _ => (None, None, None),
+    };
+
+    ColumnStats {
+        name: name.to_string(),
+        kind,
+        nulls,
+        distinct: values.iter().collect::<HashSet<_>>().len(),
+        min,
+        max,
+        mean,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_typed_and_measured() {
+        let table = Table::parse(
+            "id,price,in stock,added,name\n1,2.50,yes,2024-01-05,tea\n2,,no,2024-03-01,\n3,10,yes,2023-12-31,tea\n",
+            b',',
+        )
+        .unwrap();
+        let kinds: Vec<ColumnKind> = table.columns.iter().map(|column| column.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ColumnKind::Integer,
+                ColumnKind::Number,
+                ColumnKind::Boolean,
+                ColumnKind::Date,
+                ColumnKind::Text
+            ]
+        );
+        let price = &table.columns[1];
+        assert_eq!(price.nulls, 1);
+        assert_eq!(price.min.as_deref(), Some("2.50"));
+        assert_eq!(price.max.as_deref(), Some("10"));
+        assert_eq!(price.mean, Some(6.25));
+        assert_eq!(table.columns[3].min.as_deref(), Some("2023-12-31"));
+        assert_eq!(table.columns[4].distinct, 1);
+    }
+
+    #[test]
+    fn delimiters_are_guessed_from_the_first_lines() {
+        let path = Path::new("export.csv");
+        assert_eq!(delimiter_for(path, "a;b;c\n1;2,5;3\n"), b';');
+        assert_eq!(delimiter_for(path, "a,b\n1,2\n"), b',');
+        assert_eq!(delimiter_for(Path::new("data.tsv"), "a,b\n"), b'\t');
+    }
+
+    #[test]
+    fn ragged_rows_are_padded_and_lined_up() {
+        let table = Table::parse("name,count\nlong name here,7\nx\n", b',').unwrap();
+        assert_eq!(table.rows[1], ["x", ""]);
+        assert_eq!(table.header_line(), "name            count");
+        assert_eq!(table.text(), "long name here      7\nx\n");
+        assert_eq!(table.column_span(1), 16..21);
+    }
+}
@@ -1,15 +1,69 @@
 use crate::app_structs::{SearchMatch, StructuralAnalysisResults};
+use crate::log_file::Timestamps;
 use crate::stopwords::{self, Language};
-use regex::RegexBuilder;
+use regex::{Regex, RegexBuilder};
 use serde::Serialize;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct SearchOptions {
     pub case_sensitive: bool,
     pub regex_mode: bool,
     pub whole_word: bool,
+    /// Only look in this column, counting from 0, when searching a table.
+    /// Text without columns ignores it.
+    pub column: Option<usize>,
+}
+
+/// A query compiled once and counted against many lines or cells.
+pub enum Matcher {
+    Plain { query: String, case_sensitive: bool },
+    Pattern(Regex),
+}
+
+impl Matcher {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, String> {
+        if !options.regex_mode && !options.whole_word {
+            return Ok(Self::Plain {
+                query: query.to_string(),
+                case_sensitive: options.case_sensitive,
+            });
+        }
+
+        let pattern = if options.regex_mode {
+            if options.whole_word {
+                format!(r"\b(?:{})\b", query)
+            } else {
+                query.to_string()
+            }
+        } else {
+            let escaped = regex::escape(query);
+            if options.whole_word {
+                format!(r"\b{}\b", escaped)
+            } else {
+                escaped
+            }
+        };
+
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map(Self::Pattern)
+            .map_err(|error| error.to_string())
+    }
+
+    /// How many times the query occurs in `text`.
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Plain {
+                query,
+                case_sensitive: true,
+            } => text.match_indices(query.as_str()).count(),
+            Self::Plain { query, .. } => count_case_insensitive_matches(text, query),
+            Self::Pattern(regex) => regex.find_iter(text).count(),
+        }
+    }
 }
 
 pub fn search(query: &str, contents: &str) -> Vec<String> {
@@ -38,77 +92,17 @@ pub fn search_with_options(
         return Ok(Vec::new());
     }
 
-    if !options.regex_mode && !options.whole_word {
-        let matched_lines = if options.case_sensitive {
-            search(query, contents)
-        } else {
-            search_case_insensitive(query, contents)
-        };
-
-        let mut remaining_matches = HashMap::new();
-        for line in matched_lines {
-            *remaining_matches.entry(line).or_insert(0usize) += 1;
-        }
-
-        return Ok(contents
-            .lines()
-            .enumerate()
-            .filter_map(|(index, line)| {
-                let remaining = remaining_matches.get_mut(line)?;
-                if *remaining == 0 {
-                    return None;
-                }
-                *remaining -= 1;
-
-                let match_count = if options.case_sensitive {
-                    line.match_indices(query).count()
-                } else {
-                    count_case_insensitive_matches(line, query)
-                };
-
-                Some(SearchMatch {
-                    line_number: index + 1,
-                    line: line.to_string(),
-                    match_count,
-                })
-            })
-            .collect());
-    }
-
-    let pattern = if options.regex_mode {
-        if options.whole_word {
-            format!(r"\b(?:{})\b", query)
-        } else {
-            query.to_string()
-        }
-    } else {
-        let escaped = regex::escape(query);
-        if options.whole_word {
-            format!(r"\b{}\b", escaped)
-        } else {
-            escaped
-        }
-    };
-
-    let regex = RegexBuilder::new(&pattern)
-        .case_insensitive(!options.case_sensitive)
-        .build()
-        .map_err(|error| error.to_string())?;
-
+    let matcher = Matcher::new(query, options)?;
     Ok(contents
         .lines()
         .enumerate()
         .filter_map(|(index, line)| {
-            let match_count = regex.find_iter(line).count();
-            if match_count == 0 {
-                None
-            } else {
-                Some(SearchMatch {
-                    line_number: index + 1,
-                    line: line.to_string(),
-                    match_count,
-                })
-            }
+            let match_count = matcher.count(line);
+            (match_count > 0).then(|| SearchMatch {
+                line_number: index + 1,
+                line: line.to_string(),
+                match_count,
+            })
         })
         .collect())
 }
@@ -466,10 +460,17 @@ fn count_sentences(contents: &str) -> usize {
 }
 
 pub fn extract_repeated_lines(contents: &str, top_n: usize) -> Vec<(String, usize)> {
+    // Log lines repeat the same message at different times; the timestamp is
+    // left out so they count as one line.
+    let timestamps = Timestamps::new();
     let mut counts = HashMap::new();
 
-    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty()) {
-        *counts.entry(line.to_string()).or_insert(0) += 1;
+    for line in contents.lines() {
+        let line = timestamps.strip(line);
+        let line = line.trim();
+        if !line.is_empty() {
+            *counts.entry(line.to_string()).or_insert(0) += 1;
+        }
     }
 
     let mut repeated: Vec<(String, usize)> = counts.into_iter().filter(|(_, count)| *count > 1).collect();
@@ -0,0 +1,156 @@
+//! What two texts are about, set against each other.
+//!
+//! [`crate::text_diff`] says which lines changed; this says how the subject
+//! moved. Both texts go through [`analyze_content`], and the keyword lists and
+//! readability that come out are compared: keywords only one text uses, the
+//! ones both use with how their weight shifted, the LIX of each, and how much
+//! of the two vocabularies is shared.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::text_analysis::{analyze_content, Keyword, Readability};
+
+/// A keyword both texts use, with its weight in each.
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedKeyword {
+    pub word: String,
+    pub old_count: usize,
+    pub new_count: usize,
+    /// The word's share of its text's total keyword weight, from 0 to 1.
+    /// tf-idf scores only rank words within one text, so shares are what get
+    /// compared across the two.
+    pub old_weight: f64,
+    pub new_weight: f64,
+}
+
+impl SharedKeyword {
+    /// How far the word's weight moved from the old text to the new one.
+    pub fn delta(&self) -> f64 {
+        self.new_weight - self.old_weight
+    }
+}
+
+/// The keyword and readability profiles of two texts, side by side.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileComparison {
+    pub old_readability: Readability,
+    pub new_readability: Readability,
+    /// The old text's strongest keywords that the new one never uses.
+    pub only_old: Vec<Keyword>,
+    /// The new text's strongest keywords that the old one never uses.
+    pub only_new: Vec<Keyword>,
+    /// Keywords at the top of either text that the other uses too, heaviest
+    /// first across both.
+    pub shared: Vec<SharedKeyword>,
+    /// Distinct content words in each text, and in both.
+    pub old_vocabulary: usize,
+    pub new_vocabulary: usize,
+    pub shared_vocabulary: usize,
+}
+
+impl ProfileComparison {
+    /// Compare `old` and `new`, keeping up to `limit` keywords in each list.
+    pub fn new(old: &str, new: &str, limit: usize) -> Self {
+        // Every content word, not just the top ones: whether a word is unique
+        // to one side depends on all of the other side's vocabulary.
+        let before = analyze_content(old, usize::MAX);
+        let after = analyze_content(new, usize::MAX);
+        let old_words = weights(&before.keywords);
+        let new_words = weights(&after.keywords);
+
+        let only = |keywords: &[Keyword], other: &HashMap<&str, (usize, f64)>| -> Vec<Keyword> {
+            keywords
+                .iter()
+                .filter(|keyword| !other.contains_key(keyword.word.as_str()))
+                .take(limit)
+                .cloned()
+                .collect()
+        };
+        let only_old = only(&before.keywords, &new_words);
+        let only_new = only(&after.keywords, &old_words);
+
+        let mut shared: Vec<SharedKeyword> = Vec::new();
+        let tops = before
+            .keywords
+            .iter()
+            .take(limit)
+            .chain(after.keywords.iter().take(limit));
+        for keyword in tops {
+            let word = keyword.word.as_str();
+            if shared.iter().any(|seen| seen.word == word) {
+                continue;
+            }
+            let (Some(&(old_count, old_weight)), Some(&(new_count, new_weight))) =
+                (old_words.get(word), new_words.get(word))
+            else {
+                continue;
+            };
+            shared.push(SharedKeyword {
+                word: word.to_string(),
+                old_count,
+                new_count,
+                old_weight,
+                new_weight,
+            });
+        }
+        shared.sort_by(|a, b| {
+            (b.old_weight + b.new_weight)
+                .partial_cmp(&(a.old_weight + a.new_weight))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        shared.truncate(limit);
+
+        let shared_vocabulary = old_words
+            .keys()
+            .filter(|word| new_words.contains_key(*word))
+            .count();
+        Self {
+            old_readability: before.readability,
+            new_readability: after.readability,
+            only_old,
+            only_new,
+            shared,
+            old_vocabulary: old_words.len(),
+            new_vocabulary: new_words.len(),
+            shared_vocabulary,
+        }
+    }
+
+    /// The new text's LIX less the old one's: positive when it got harder
+    /// to read.
+    pub fn lix_delta(&self) -> f64 {
+        self.new_readability.lix - self.old_readability.lix
+    }
+
+    /// Jaccard index of the two vocabularies, from 0 to 1. Two texts without
+    /// content words count as the same, as [`crate::text_diff::DiffStats`]
+    /// does for two empty files.
+    pub fn jaccard(&self) -> f64 {
+        let union = self.old_vocabulary + self.new_vocabulary - self.shared_vocabulary;
+        if union == 0 {
+            1.0
+        } else {
+            self.shared_vocabulary as f64 / union as f64
+        }
+    }
+}
+
+/// Count and share of the total score for each keyword.
+fn weights(keywords: &[Keyword]) -> HashMap<&str, (usize, f64)> {
+    let total: f64 = keywords.iter().map(|keyword| keyword.score).sum();
+    keywords
+        .iter()
+        .map(|keyword| {
+            let weight = if total > 0.0 {
+                keyword.score / total
+            } else {
+                0.0
+            };
+            (keyword.word.as_str(), (keyword.count, weight))
+        })
+        .collect()
+}
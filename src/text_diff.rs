@@ -0,0 +1,726 @@
+//! What changed between two texts, line by line and word by word.
+//!
+//! Lines are matched with Myers' algorithm, in its linear-space form so a
+//! long PDF costs no more memory than its text. Within each run of changes,
+//! removed lines are paired with added ones in order and compared again word
+//! by word, so a reworded sentence is highlighted where it was reworded
+//! rather than as two whole lines.
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+/// Unchanged lines shown around each change in a hunk, as `diff -u` does.
+const CONTEXT: usize = 3;
+
+/// Edit distance past which the search for the shortest edit gives up and
+/// calls what is left one change. Two unrelated texts would otherwise take
+/// time proportional to the product of their lengths.
+const MAX_COST: usize = 4096;
+
+/// How a line fared between the old text and the new.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineKind {
+    Same,
+    Removed,
+    Added,
+}
+
+/// One line of the comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    /// 0-based line in the old text, unless the line was added.
+    pub old: Option<usize>,
+    /// 0-based line in the new text, unless the line was removed.
+    pub new: Option<usize>,
+    /// Byte ranges of the words that changed, when the line was paired with
+    /// one on the other side. An unpaired line changed as a whole.
+    pub changed: Vec<Range<usize>>,
+}
+
+/// A run of changes with the unchanged lines around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The lines of [`TextDiff::lines`] it covers, context included.
+    pub lines: Range<usize>,
+    /// 1-based first old line, or the line before the hunk when it has no old
+    /// lines, as in a unified diff header.
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+}
+
+/// How much changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DiffStats {
+    pub added: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    pub words_added: usize,
+    pub words_removed: usize,
+}
+
+impl DiffStats {
+    /// Share of the two texts' lines that are unchanged, from 0 to 1. Two
+    /// empty texts are the same.
+    pub fn similarity(&self) -> f64 {
+        let total = 2 * self.unchanged + self.added + self.removed;
+        if total == 0 {
+            1.0
+        } else {
+            (2 * self.unchanged) as f64 / total as f64
+        }
+    }
+}
+
+/// How the Viewer lays a diff out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffLayout {
+    /// Old text on the left, new on the right, changed lines facing each
+    /// other.
+    #[default]
+    SideBySide,
+    /// One column, removed lines above the ones that replaced them.
+    Unified,
+}
+
+impl DiffLayout {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SideBySide => "side by side",
+            Self::Unified => "unified",
+        }
+    }
+}
+
+/// The lines of [`TextDiff::lines`] one Viewer row shows. Side by side, the
+/// old line is on the left and the new on the right, and an unchanged line
+/// on both; unified, a row shows one line, on the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRow {
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+impl DiffRow {
+    /// The first line of the diff the row shows. Rows are in this order.
+    pub fn first(&self) -> usize {
+        self.left.or(self.right).unwrap_or(0)
+    }
+}
+
+/// Two texts and every difference between them.
+#[derive(Debug, Clone)]
+pub struct TextDiff {
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+    /// Every line of both texts, in order; in each run of changes the
+    /// removed lines come before the added ones.
+    pub lines: Vec<DiffLine>,
+    pub hunks: Vec<Hunk>,
+    pub stats: DiffStats,
+}
+
+impl TextDiff {
+    pub fn new(old: &str, new: &str) -> Self {
+        let old: Vec<String> = old.lines().map(str::to_string).collect();
+        let new: Vec<String> = new.lines().map(str::to_string).collect();
+        let ops = diff(&old, &new);
+
+        let mut lines = Vec::with_capacity(ops.len());
+        let mut stats = DiffStats::default();
+        let mut at = 0;
+        while at < ops.len() {
+            if let Op::Same(old_line, new_line) = ops[at] {
+                lines.push(DiffLine {
+                    kind: LineKind::Same,
+                    old: Some(old_line),
+                    new: Some(new_line),
+                    changed: Vec::new(),
+                });
+                stats.unchanged += 1;
+                at += 1;
+                continue;
+            }
+
+            let end = ops[at..]
+                .iter()
+                .position(|op| matches!(op, Op::Same(..)))
+                .map_or(ops.len(), |length| at + length);
+            let removed: Vec<usize> = ops[at..end]
+                .iter()
+                .filter_map(|op| match op {
+                    Op::Removed(line) => Some(*line),
+                    _ => None,
+                })
+                .collect();
+            let added: Vec<usize> = ops[at..end]
+                .iter()
+                .filter_map(|op| match op {
+                    Op::Added(line) => Some(*line),
+                    _ => None,
+                })
+                .collect();
+
+            let mut removed_changes = vec![Vec::new(); removed.len()];
+            let mut added_changes = vec![Vec::new(); added.len()];
+            for (pair, (old_line, new_line)) in removed.iter().zip(&added).enumerate() {
+                let (before, after) = changed_words(&old[*old_line], &new[*new_line]);
+                removed_changes[pair] = before;
+                added_changes[pair] = after;
+            }
+            for (index, line) in removed.iter().enumerate() {
+                stats.words_removed += changed_word_count(&old[*line], &removed_changes[index]);
+            }
+            for (index, line) in added.iter().enumerate() {
+                stats.words_added += changed_word_count(&new[*line], &added_changes[index]);
+            }
+            stats.removed += removed.len();
+            stats.added += added.len();
+
+            lines.extend(
+                removed
+                    .into_iter()
+                    .zip(removed_changes)
+                    .map(|(line, changed)| DiffLine {
+                        kind: LineKind::Removed,
+                        old: Some(line),
+                        new: None,
+                        changed,
+                    }),
+            );
+            lines.extend(
+                added
+                    .into_iter()
+                    .zip(added_changes)
+                    .map(|(line, changed)| DiffLine {
+                        kind: LineKind::Added,
+                        old: None,
+                        new: Some(line),
+                        changed,
+                    }),
+            );
+            at = end;
+        }
+
+        let hunks = hunks(&lines);
+        Self {
+            old,
+            new,
+            lines,
+            hunks,
+            stats,
+        }
+    }
+
+    /// The text of diff line `index`, from whichever side has it.
+    pub fn text(&self, index: usize) -> &str {
+        let line = &self.lines[index];
+        match (line.old, line.new) {
+            (Some(old), _) => &self.old[old],
+            (None, Some(new)) => &self.new[new],
+            (None, None) => "",
+        }
+    }
+
+    /// The rows of `layout`, and their text: unified, each line behind a
+    /// `-`, `+` or space; side by side, both sides split by a bar.
+    pub fn render(&self, layout: DiffLayout) -> (String, Vec<DiffRow>) {
+        let rows = match layout {
+            DiffLayout::Unified => (0..self.lines.len())
+                .map(|index| DiffRow {
+                    left: Some(index),
+                    right: None,
+                })
+                .collect(),
+            DiffLayout::SideBySide => self.side_by_side(),
+        };
+
+        let mut text = String::new();
+        for row in &rows {
+            match (layout, row.left, row.right) {
+                (DiffLayout::Unified, Some(index), _) => {
+                    text.push_str(self.lines[index].kind.marker());
+                    text.push(' ');
+                    text.push_str(self.text(index));
+                }
+                (_, left, right) => {
+                    text.push_str(left.map_or("", |index| self.text(index)));
+                    text.push_str(" │ ");
+                    text.push_str(right.map_or("", |index| self.text(index)));
+                }
+            }
+            text.push('\n');
+        }
+        (text, rows)
+    }
+
+    fn side_by_side(&self) -> Vec<DiffRow> {
+        let mut rows = Vec::new();
+        let mut at = 0;
+        while at < self.lines.len() {
+            if self.lines[at].kind == LineKind::Same {
+                rows.push(DiffRow {
+                    left: Some(at),
+                    right: Some(at),
+                });
+                at += 1;
+                continue;
+            }
+            let added = at
+                + self.lines[at..]
+                    .iter()
+                    .take_while(|line| line.kind == LineKind::Removed)
+                    .count();
+            let end = added
+                + self.lines[added..]
+                    .iter()
+                    .take_while(|line| line.kind == LineKind::Added)
+                    .count();
+            let (removed, added) = (at..added, added..end);
+            for pair in 0..removed.len().max(added.len()) {
+                rows.push(DiffRow {
+                    left: Some(removed.start + pair).filter(|index| removed.contains(index)),
+                    right: Some(added.start + pair).filter(|index| added.contains(index)),
+                });
+            }
+            at = end;
+        }
+        rows
+    }
+
+    /// The first changed line of hunk `hunk`.
+    pub fn first_change(&self, hunk: usize) -> usize {
+        let lines = self.hunks[hunk].lines.clone();
+        lines
+            .clone()
+            .find(|index| self.lines[*index].kind != LineKind::Same)
+            .unwrap_or(lines.start)
+    }
+
+    /// The removed and added lines, for measuring what the change is about.
+    pub fn changed_text(&self) -> String {
+        let mut text = String::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            if line.kind != LineKind::Same {
+                text.push_str(self.text(index));
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// The hunks as a unified diff, with `old_name` and `new_name` in its
+    /// header.
+    pub fn unified(&self, old_name: &str, new_name: &str) -> String {
+        let mut text = format!("--- {old_name}\n+++ {new_name}\n");
+        for hunk in &self.hunks {
+            text.push_str(&format!(
+                "@@ -{} +{} @@\n",
+                hunk_range(hunk.old_start, hunk.old_lines),
+                hunk_range(hunk.new_start, hunk.new_lines)
+            ));
+            for index in hunk.lines.clone() {
+                text.push_str(self.lines[index].kind.marker());
+                text.push_str(self.text(index));
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+impl LineKind {
+    /// What a unified diff puts in front of such a line.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Self::Same => " ",
+            Self::Removed => "-",
+            Self::Added => "+",
+        }
+    }
+}
+
+/// `start,count` of a unified diff header, with the count left out when it
+/// is 1.
+fn hunk_range(start: usize, count: usize) -> String {
+    if count == 1 {
+        start.to_string()
+    } else {
+        format!("{start},{count}")
+    }
+}
+
+/// Group the changes in `lines` into hunks, merging two whose context would
+/// overlap.
+fn hunks(lines: &[DiffLine]) -> Vec<Hunk> {
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if line.kind == LineKind::Same {
+            continue;
+        }
+        let span = index.saturating_sub(CONTEXT)..(index + 1 + CONTEXT).min(lines.len());
+        match spans.last_mut() {
+            Some(last) if span.start <= last.end => last.end = span.end,
+            _ => spans.push(span),
+        }
+    }
+
+    spans
+        .into_iter()
+        .map(|span| {
+            let old_before = lines[..span.start]
+                .iter()
+                .filter(|line| line.old.is_some())
+                .count();
+            let new_before = lines[..span.start]
+                .iter()
+                .filter(|line| line.new.is_some())
+                .count();
+            let old_lines = lines[span.clone()]
+                .iter()
+                .filter(|line| line.old.is_some())
+                .count();
+            let new_lines = lines[span.clone()]
+                .iter()
+                .filter(|line| line.new.is_some())
+                .count();
+            Hunk {
+                lines: span,
+                old_start: old_before + usize::from(old_lines > 0),
+                old_lines,
+                new_start: new_before + usize::from(new_lines > 0),
+                new_lines,
+            }
+        })
+        .collect()
+}
+
+/// The byte ranges of `old` and of `new` that differ, a word at a time.
+/// Changed words separated only by spaces are joined into one range.
+fn changed_words(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_words = words(old);
+    let new_words = words(new);
+    let old_text: Vec<&str> = old_words.iter().map(|range| &old[range.clone()]).collect();
+    let new_text: Vec<&str> = new_words.iter().map(|range| &new[range.clone()]).collect();
+
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for op in diff(&old_text, &new_text) {
+        match op {
+            Op::Same(..) => {}
+            Op::Removed(word) => join(&mut removed, old, old_words[word].clone()),
+            Op::Added(word) => join(&mut added, new, new_words[word].clone()),
+        }
+    }
+    (removed, added)
+}
+
+/// Add `range` to `ranges`, extending the last one when only whitespace lies
+/// between them.
+fn join(ranges: &mut Vec<Range<usize>>, line: &str, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if line[last.end..range.start].trim().is_empty() => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+/// A line cut into words, runs of whitespace and single punctuation marks,
+/// which between them cover all of it.
+fn words(line: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let same_class = |other: char| {
+            if c.is_alphanumeric() || c == '_' {
+                other.is_alphanumeric() || other == '_'
+            } else if c.is_whitespace() {
+                other.is_whitespace()
+            } else {
+                false
+            }
+        };
+        while let Some(&(at, next)) = chars.peek() {
+            if !same_class(next) {
+                break;
+            }
+            end = at + next.len_utf8();
+            chars.next();
+        }
+        words.push(start..end);
+    }
+    words
+}
+
+/// Words in the changed parts of `line`, or in all of it when nothing marks
+/// what changed.
+fn changed_word_count(line: &str, changed: &[Range<usize>]) -> usize {
+    if changed.is_empty() {
+        return line.split_whitespace().count();
+    }
+    changed
+        .iter()
+        .map(|range| line[range.clone()].split_whitespace().count())
+        .sum()
+}
+
+/// One step of an edit from the old sequence to the new.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// A shortest edit turning `old` into `new`.
+fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Op> {
+    let max = (old.len() + new.len()).div_ceil(2) + 1;
+    let mut forward = Diagonals::new(max);
+    let mut backward = Diagonals::new(max);
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    conquer(
+        old,
+        0..old.len(),
+        new,
+        0..new.len(),
+        &mut forward,
+        &mut backward,
+        &mut ops,
+    );
+    ops
+}
+
+/// The furthest x reached on each diagonal k = x - y, for k from -max to max.
+struct Diagonals {
+    offset: isize,
+    x: Vec<usize>,
+}
+
+impl Diagonals {
+    fn new(max: usize) -> Self {
+        Self {
+            offset: max as isize,
+            x: vec![0; 2 * max + 1],
+        }
+    }
+
+    fn get(&self, k: isize) -> usize {
+        self.x[(k + self.offset) as usize]
+    }
+
+    fn set(&mut self, k: isize, x: usize) {
+        self.x[(k + self.offset) as usize] = x;
+    }
+}
+
+/// Diff `old[old_range]` against `new[new_range]`: strip what they share at
+/// either end, then split the rest at the middle of its shortest edit and
+/// diff both halves.
+fn conquer<T: PartialEq>(
+    old: &[T],
+    mut old_range: Range<usize>,
+    new: &[T],
+    mut new_range: Range<usize>,
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+    ops: &mut Vec<Op>,
+) {
+    let prefix = common_prefix(&old[old_range.clone()], &new[new_range.clone()]);
+    for offset in 0..prefix {
+        ops.push(Op::Same(old_range.start + offset, new_range.start + offset));
+    }
+    old_range.start += prefix;
+    new_range.start += prefix;
+    let suffix = common_suffix(&old[old_range.clone()], &new[new_range.clone()]);
+    old_range.end -= suffix;
+    new_range.end -= suffix;
+
+    if old_range.is_empty() || new_range.is_empty() {
+        ops.extend(old_range.clone().map(Op::Removed));
+        ops.extend(new_range.clone().map(Op::Added));
+    } else if let Some((x, y)) = middle_snake(
+        old,
+        old_range.clone(),
+        new,
+        new_range.clone(),
+        forward,
+        backward,
+    ) {
+        conquer(
+            old,
+            old_range.start..x,
+            new,
+            new_range.start..y,
+            forward,
+            backward,
+            ops,
+        );
+        conquer(
+            old,
+            x..old_range.end,
+            new,
+            y..new_range.end,
+            forward,
+            backward,
+            ops,
+        );
+    } else {
+        ops.extend(old_range.clone().map(Op::Removed));
+        ops.extend(new_range.clone().map(Op::Added));
+    }
+
+    for offset in 0..suffix {
+        ops.push(Op::Same(old_range.end + offset, new_range.end + offset));
+    }
+}
+
+/// Where the forward and backward searches for the shortest edit meet, as a
+/// point to split both ranges at. `None` past [`MAX_COST`].
+fn middle_snake<T: PartialEq>(
+    old: &[T],
+    old_range: Range<usize>,
+    new: &[T],
+    new_range: Range<usize>,
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+) -> Option<(usize, usize)> {
+    let n = old_range.len();
+    let m = new_range.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    let old = &old[old_range.clone()];
+    let new = &new[new_range.clone()];
+    forward.set(1, 0);
+    backward.set(1, 0);
+
+    let max = (n + m).div_ceil(2) + 1;
+    for d in 0..max.min(MAX_COST) as isize {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward.get(k - 1) < forward.get(k + 1)) {
+                forward.get(k + 1)
+            } else {
+                forward.get(k - 1) + 1
+            };
+            let y = (x as isize - k) as usize;
+            let start = (x, y);
+            if x < n && y < m {
+                x += common_prefix(&old[x..], &new[y..]);
+            }
+            forward.set(k, x);
+            if odd && (k - delta).abs() < d && x + backward.get(-(k - delta)) >= n {
+                return Some((start.0 + old_range.start, start.1 + new_range.start));
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward.get(k - 1) < backward.get(k + 1)) {
+                backward.get(k + 1)
+            } else {
+                backward.get(k - 1) + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let shared = common_suffix(&old[..n - x], &new[..m - y]);
+                x += shared;
+                y += shared;
+            }
+            backward.set(k, x);
+            if !odd && (k - delta).abs() <= d && x + forward.get(-(k - delta)) >= n {
+                return Some((n - x + old_range.start, m - y + new_range.start));
+            }
+        }
+    }
+    None
+}
+
+fn common_prefix<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    old.iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rebuild both sides from the edit, to check it is a real one.
+    fn apply(ops: &[Op], old: &[&str], new: &[&str]) -> (Vec<String>, Vec<String>) {
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for op in ops {
+            match *op {
+                Op::Same(a, b) => {
+                    assert_eq!(old[a], new[b]);
+                    before.push(old[a].to_string());
+                    after.push(new[b].to_string());
+                }
+                Op::Removed(a) => before.push(old[a].to_string()),
+                Op::Added(b) => after.push(new[b].to_string()),
+            }
+        }
+        (before, after)
+    }
+
+    #[test]
+    fn finds_a_shortest_edit() {
+        let old: Vec<&str> = "abcabba".split("").filter(|s| !s.is_empty()).collect();
+        let new: Vec<&str> = "cbabac".split("").filter(|s| !s.is_empty()).collect();
+        let ops = diff(&old, &new);
+        let (before, after) = apply(&ops, &old, &new);
+        assert_eq!(before.concat(), "abcabba");
+        assert_eq!(after.concat(), "cbabac");
+        // The classic example from Myers' paper has an edit of 5.
+        let edits = ops.iter().filter(|op| !matches!(op, Op::Same(..))).count();
+        assert_eq!(edits, 5);
+    }
+
+    #[test]
+    fn pairs_changed_lines_and_marks_the_words() {
+        let diff = TextDiff::new(
+            "one\nthe quick brown fox\nthree\n",
+            "one\nthe slow brown fox\nthree\nfour\n",
+        );
+        let kinds: Vec<LineKind> = diff.lines.iter().map(|line| line.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LineKind::Same,
+                LineKind::Removed,
+                LineKind::Added,
+                LineKind::Same,
+                LineKind::Added
+            ]
+        );
+        assert_eq!(diff.lines[1].changed, vec![Range { start: 4, end: 9 }]);
+        assert_eq!(diff.lines[2].changed, vec![Range { start: 4, end: 8 }]);
+        assert_eq!(diff.stats.words_removed, 1);
+        assert_eq!(diff.stats.words_added, 2);
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(
+            diff.unified("a", "b"),
+            "--- a\n+++ b\n@@ -1,3 +1,4 @@\n one\n-the quick brown fox\n+the slow brown fox\n three\n+four\n"
+        );
+    }
+
+    #[test]
+    fn side_by_side_rows_face_each_other() {
+        let diff = TextDiff::new("a\nb\nc\nd\n", "a\nB\nd\ne\n");
+        let (text, rows) = diff.render(DiffLayout::SideBySide);
+        assert_eq!(text, "a │ a\nb │ B\nc │ \nd │ d\n │ e\n");
+        assert!(rows
+            .windows(2)
+            .all(|pair| pair[0].first() < pair[1].first()));
+        let (text, _) = diff.render(DiffLayout::Unified);
+        assert_eq!(text, "  a\n- b\n- c\n+ B\n  d\n+ e\n");
+    }
+}
@@ -0,0 +1,229 @@
+//! Headings in plain text, found by how they are typeset.
+//!
+//! A text file has no markup to say where its sections start, but writers
+//! mark them in a handful of familiar ways: a line underlined with `===` or
+//! `---`, a numbered line such as `2.3 Results`, a line in capitals, or a
+//! short line with a blank line after it. Each is a guess, so the
+//! rules lean towards missing a heading rather than inventing one.
+
+use crate::outline::{Outline, Segment, SegmentKind};
+
+/// Longest line taken for a heading, in characters.
+const MAX_HEADING_CHARS: usize = 60;
+/// Most words in a heading found only by standing alone.
+const MAX_LONE_WORDS: usize = 8;
+/// Deepest section number followed, as in `1.2.3.4`.
+const MAX_NUMBER_DEPTH: usize = 4;
+
+/// One heading: its line, nesting level and title.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Heading {
+    line: usize,
+    level: usize,
+    title: String,
+}
+
+/// The headings of `content` as an outline, each running until the next one
+/// at its own or an outer level. Empty when nothing looks like a heading.
+pub fn detect(content: &str) -> Outline {
+    let lines: Vec<&str> = content.lines().collect();
+    let headings = find_headings(&lines);
+
+    let mut numbers: Vec<usize> = Vec::new();
+    let segments = headings
+        .iter()
+        .enumerate()
+        .map(|(index, heading)| {
+            let end = headings[index + 1..]
+                .iter()
+                .find(|next| next.level <= heading.level)
+                .map_or(lines.len(), |next| next.line);
+            if numbers.len() <= heading.level {
+                numbers.resize(heading.level + 1, 0);
+            }
+            numbers[heading.level] += 1;
+            Segment {
+                kind: SegmentKind::Heading,
+                title: heading.title.clone(),
+                level: heading.level,
+                number: numbers[heading.level],
+                start_line: heading.line,
+                line_count: end - heading.line,
+            }
+        })
+        .collect();
+    Outline::new(segments)
+}
+
+fn find_headings(lines: &[&str]) -> Vec<Heading> {
+    let blank = |index: usize| lines.get(index).is_none_or(|line| line.trim().is_empty());
+    let mut headings = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let text = lines[index].trim();
+        let starts_block = index == 0 || blank(index - 1);
+        if text.is_empty() || text.chars().count() > MAX_HEADING_CHARS {
+            index += 1;
+            continue;
+        }
+
+        // `===` under a title makes it outermost, `---` one level in.
+        if let Some(level) = lines.get(index + 1).and_then(|next| underline(text, next)) {
+            if starts_block && !is_rule(text) {
+                headings.push(Heading {
+                    line: index,
+                    level,
+                    title: text.to_string(),
+                });
+                index += 2;
+                continue;
+            }
+        }
+
+        if starts_block {
+            let level = section_number(text)
+                .map(|depth| depth - 1)
+                .or_else(|| is_capitals(text).then_some(0))
+                .or_else(|| is_lone_heading(text, lines, index).then_some(1));
+            if let Some(level) = level {
+                headings.push(Heading {
+                    line: index,
+                    level,
+                    title: text.to_string(),
+                });
+            }
+        }
+        index += 1;
+    }
+    headings
+}
+
+/// The level a setext underline gives `title`: 0 for `=`, 1 for `-`. The
+/// underline has to be at least three characters and roughly as long as the
+/// title, so a stray `--` is not one.
+fn underline(title: &str, next: &str) -> Option<usize> {
+    let next = next.trim();
+    let length = next.chars().count();
+    if length < 3 || length * 2 < title.chars().count() {
+        return None;
+    }
+    if next.chars().all(|c| c == '=') {
+        Some(0)
+    } else if next.chars().all(|c| c == '-') {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// A line of nothing but rule characters, which an underline below it would
+/// otherwise turn into a heading.
+fn is_rule(text: &str) -> bool {
+    text.chars()
+        .all(|c| matches!(c, '=' | '-' | '*' | '_' | ' '))
+}
+
+/// How many parts the section number at the start of `text` has: 1 for
+/// `3 Methods` or `3. Methods`, 2 for `3.1 Sampling`. The title after it must
+/// start with a capital and not end like a sentence, which keeps numbered
+/// list items and sums out.
+fn section_number(text: &str) -> Option<usize> {
+    let (number, title) = text.split_once(char::is_whitespace)?;
+    let number = number.strip_suffix('.').unwrap_or(number);
+    let parts: Vec<&str> = number.split('.').collect();
+    let numeric = parts.iter().all(|part| {
+        !part.is_empty() && part.len() <= 3 && part.bytes().all(|b| b.is_ascii_digit())
+    });
+    let title = title.trim();
+    let capitalised = title.chars().next().is_some_and(char::is_uppercase);
+    (numeric && parts.len() <= MAX_NUMBER_DEPTH && capitalised && !ends_like_sentence(title))
+        .then_some(parts.len())
+}
+
+/// Every letter a capital, with at least three of them: `RESULTS`,
+/// `PART TWO: THE RETURN`.
+fn is_capitals(text: &str) -> bool {
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    letters >= 3 && !text.chars().any(char::is_lowercase) && !ends_like_sentence(text)
+}
+
+/// A short capitalised line with a blank line after it and more text further
+/// on, whatever that text is: prose, a list or more short lines.
+fn is_lone_heading(text: &str, lines: &[&str], index: usize) -> bool {
+    let alone = lines
+        .get(index + 1)
+        .is_some_and(|next| next.trim().is_empty());
+    let more_follows = lines[index + 1..]
+        .iter()
+        .any(|line| !line.trim().is_empty());
+    alone
+        && more_follows
+        && text.split_whitespace().count() <= MAX_LONE_WORDS
+        && text.chars().next().is_some_and(char::is_uppercase)
+        && !ends_like_sentence(text)
+}
+
+fn ends_like_sentence(text: &str) -> bool {
+    text.ends_with(['.', ',', ';', ':', '!', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(content: &str) -> Vec<(String, usize)> {
+        detect(content)
+            .segments()
+            .iter()
+            .map(|segment| (segment.title.clone(), segment.level))
+            .collect()
+    }
+
+    #[test]
+    fn each_style_of_heading_is_found() {
+        let content = "Field Notes\n===========\n\nINTRODUCTION\n\nWe walked the shore at dawn and counted every bird we saw.\n\n\
+2 Methods\nCounts were taken hourly.\n\n2.1 Equipment\nBinoculars and a notebook.\n\n\
+Weather\n-------\nCold.\n\nA Quiet Afternoon\n\nThe tide went out and the gulls settled on the flats to feed.\n";
+        assert_eq!(
+            titles(content),
+            [
+                ("Field Notes".to_string(), 0),
+                ("INTRODUCTION".to_string(), 0),
+                ("2 Methods".to_string(), 0),
+                ("2.1 Equipment".to_string(), 1),
+                ("Weather".to_string(), 1),
+                ("A Quiet Afternoon".to_string(), 1),
+            ]
+        );
+        let outline = detect(content);
+        let methods = &outline.segments()[2];
+        assert_eq!(methods.start_line, 7);
+        assert_eq!(
+            methods.end_line(),
+            content.lines().count(),
+            "runs to the end"
+        );
+    }
+
+    #[test]
+    fn prose_and_lists_are_left_alone() {
+        let content = "The list had three items.\n\n1. buy bread\n2. post the letter\n\n\
+That floats on high o'er vales and hills,\n\nTotal: 3 + 4 = 7.\n----\n\nThe End\n";
+        assert!(titles(content).is_empty(), "{:?}", titles(content));
+    }
+
+    #[test]
+    fn a_short_line_before_a_blank_one_is_a_heading_whatever_follows() {
+        let content = "Shopping\n\nbread\nmilk\n\nErrands\n\n- post the letter\n- fetch the parcel\n\n\
+Notes\n\nthe shop shuts early on thursdays\n";
+        assert_eq!(
+            titles(content),
+            [
+                ("Shopping".to_string(), 1),
+                ("Errands".to_string(), 1),
+                ("Notes".to_string(), 1),
+            ]
+        );
+    }
+}
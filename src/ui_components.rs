@@ -1,15 +1,23 @@
 use crate::app_structs::{
-    AppState, InputMode, Theme, TAB_ANALYZE, TAB_DASHBOARD, TAB_MEDIA, TAB_SEARCH, TAB_SETTINGS,
+    AnalysisScope, AppState, ImageAnalysis, InputMode, Theme, TAB_ANALYZE, TAB_DASHBOARD, TAB_MEDIA, TAB_SEARCH, TAB_SETTINGS,
     TAB_VIEWER,
 };
-use crate::media::MediaRenderer;
+use crate::log_file::{LogLevel, LogSummary};
+use crate::media::{visible_region, MediaRenderer, GRID_TILE};
+use crate::outline::SegmentKind;
+use crate::source_code::{SourceCode, Token, TokenKind};
+use crate::table::Table;
+use crate::text_analysis::{Keyword, Readability};
+use crate::text_compare::ProfileComparison;
+use crate::text_diff::{DiffLayout, LineKind, TextDiff};
+use std::ops::Range;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{
         Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Tabs, Wrap,
+        ScrollbarState, Sparkline, Tabs, Wrap,
     },
     Frame,
 };
@@ -48,8 +56,11 @@ pub fn ui(f: &mut Frame, state: &mut AppState, media: &mut MediaRenderer) {
     // this, leaving a mode would leave its panes catching clicks and wheel
     // events aimed at whatever replaced them.
     state.hit.viewer = Rect::ZERO;
+    state.hit.outline = Rect::ZERO;
     state.hit.search_results = Rect::ZERO;
     state.hit.media_list = Rect::ZERO;
+    state.hit.media_preview = Rect::ZERO;
+    state.hit.media_grid = Rect::ZERO;
     state.hit.settings_list = Rect::ZERO;
 
     render_header(f, chunks[0], state, &palette);
@@ -70,6 +81,10 @@ pub fn ui(f: &mut Frame, state: &mut AppState, media: &mut MediaRenderer) {
     match state.input_mode {
         InputMode::Search => render_search_input(f, state, &palette),
         InputMode::Goto => render_goto_input(f, state, &palette),
+        InputMode::Password => render_password_input(f, state, &palette),
+        InputMode::TimeRange => render_time_range_input(f, state, &palette),
+        InputMode::PathQuery => render_path_input(f, state, &palette),
+        InputMode::PageRange => render_page_range_input(f, state, &palette),
         InputMode::Normal => {}
     }
 }
@@ -359,11 +374,130 @@ fn render_footer(f: &mut Frame, area: Rect, state: &AppState, palette: &Palette)
             "Enter jump".into(),
             "Esc cancel".into(),
         ],
+        InputMode::Password => vec![
+            "Type the PDF password".into(),
+            "Enter unlock".into(),
+            "Esc cancel".into(),
+        ],
+        InputMode::TimeRange => vec![
+            "Type since..until".into(),
+            "Enter apply".into(),
+            "Esc cancel".into(),
+        ],
+        InputMode::PathQuery => vec![
+            "Type a path".into(),
+            "Enter go".into(),
+            "Esc cancel".into(),
+        ],
+        InputMode::PageRange => vec![
+            "Type pages like 3-7".into(),
+            "Enter measure".into(),
+            "Esc cancel".into(),
+        ],
+        InputMode::Normal if state.current_tab == TAB_ANALYZE => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            match (&state.diff, state.analysis_scope) {
+                (Some(_), _) if state.compare_profiles => "s changed lines".into(),
+                (Some(_), _) => "s compare profiles".into(),
+                (None, AnalysisScope::Document) => "s this page only".into(),
+                (None, _) => "s whole document".into(),
+            },
+            "S page range".into(),
+            "[ ] page".into(),
+            format!("x skip headers {}", on_off(state.strip_furniture)),
+            "/ search".into(),
+        ],
+        InputMode::Normal if state.current_tab == TAB_MEDIA && state.media_grid => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            "arrows or click pick image".into(),
+            "Enter view it".into(),
+            "v list".into(),
+        ],
         InputMode::Normal if state.current_tab == TAB_MEDIA => vec![
             "q quit".into(),
             "Tab mode".into(),
             "j/k or click pick image".into(),
             "Enter jump to its page".into(),
+            "+/- zoom".into(),
+            "R rotate".into(),
+            "v grid".into(),
+            "a/A pick attachment".into(),
+            "o open it".into(),
+            "Backspace back".into(),
+        ],
+        InputMode::Normal if state.diff.is_some() => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            "n/N hunk".into(),
+            match state.diff_layout {
+                DiffLayout::SideBySide => "u unified".into(),
+                DiffLayout::Unified => "u side by side".into(),
+            },
+            "/ search".into(),
+            ": line".into(),
+            "j/k g/G ^d/^u scroll".into(),
+            format!("c case {}", on_off(state.case_sensitive)),
+            format!("r regex {}", on_off(state.regex_mode)),
+            format!("w whole-word {}", on_off(state.whole_word)),
+            format!("l line nums {}", on_off(state.line_numbers)),
+        ],
+        InputMode::Normal if state.table.is_some() => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            "/ search".into(),
+            "n/N match".into(),
+            "Left/Right search column".into(),
+            ": row".into(),
+            "j/k g/G ^d/^u scroll".into(),
+            format!("c case {}", on_off(state.case_sensitive)),
+            format!("r regex {}", on_off(state.regex_mode)),
+            format!("w whole-word {}", on_off(state.whole_word)),
+            format!("l line nums {}", on_off(state.line_numbers)),
+        ],
+        InputMode::Normal if state.tree.is_some() => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            "/ search keys and values".into(),
+            "n/N match".into(),
+            "Enter or click fold".into(),
+            "e/E unfold/fold all".into(),
+            "P path".into(),
+            "j/k g/G ^d/^u scroll".into(),
+            format!("c case {}", on_off(state.case_sensitive)),
+            format!("r regex {}", on_off(state.regex_mode)),
+            format!("l line nums {}", on_off(state.line_numbers)),
+        ],
+        InputMode::Normal if state.code.is_some() => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            "/ search".into(),
+            "n/N match".into(),
+            ": line".into(),
+            "j/k g/G ^d/^u scroll".into(),
+            format!("c case {}", on_off(state.case_sensitive)),
+            format!("r regex {}", on_off(state.regex_mode)),
+            format!("w whole-word {}", on_off(state.whole_word)),
+            format!("l line nums {}", on_off(state.line_numbers)),
+            format!("z wrap {}", on_off(state.wrap_lines)),
+        ],
+        InputMode::Normal if state.log.is_some() => vec![
+            "q quit".into(),
+            "Tab mode".into(),
+            "/ search".into(),
+            "n/N match".into(),
+            format!(
+                "L level {}",
+                state.log_filter.min_level.map_or("all", |level| level.label())
+            ),
+            "T time range".into(),
+            ": line".into(),
+            "j/k g/G ^d/^u scroll".into(),
+            format!("c case {}", on_off(state.case_sensitive)),
+            format!("r regex {}", on_off(state.regex_mode)),
+            format!("l line nums {}", on_off(state.line_numbers)),
+            format!("z wrap {}", on_off(state.wrap_lines)),
         ],
         InputMode::Normal => vec![
             "q quit".into(),
@@ -373,11 +507,14 @@ fn render_footer(f: &mut Frame, area: Rect, state: &AppState, palette: &Palette)
             ": line".into(),
             "[ ] page".into(),
             "j/k g/G ^d/^u scroll".into(),
+            format!("t outline {}", on_off(state.outline_pane)),
             format!("c case {}", on_off(state.case_sensitive)),
             format!("r regex {}", on_off(state.regex_mode)),
             format!("w whole-word {}", on_off(state.whole_word)),
             format!("l line nums {}", on_off(state.line_numbers)),
             format!("z wrap {}", on_off(state.wrap_lines)),
+            format!("x skip headers {}", on_off(state.strip_furniture)),
+            format!("p reflow {}", on_off(state.reflow)),
         ],
     };
 
@@ -434,7 +571,13 @@ fn render_dashboard(f: &mut Frame, area: Rect, state: &AppState, palette: &Palet
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
         .split(rows[1]);
 
-    let preview = build_viewer_text(state, palette, state.preview_line_count, true);
+    let preview = build_viewer_text(
+        state,
+        palette,
+        state.preview_line_count,
+        true,
+        lower[0].width.saturating_sub(2),
+    );
     let viewer = Paragraph::new(preview)
         .wrap(Wrap { trim: false })
         .block(panel_block("Content Preview", palette.accent, palette));
@@ -453,19 +596,28 @@ fn render_search(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Pale
         .constraints([Constraint::Length(4), Constraint::Min(10)])
         .split(area);
 
+    let mut toggles = vec![
+        Span::styled("Case ", Style::default().fg(palette.muted)),
+        Span::styled(on_off(state.case_sensitive), Style::default().fg(palette.text)),
+        Span::styled("   Regex ", Style::default().fg(palette.muted)),
+        Span::styled(on_off(state.regex_mode), Style::default().fg(palette.text)),
+        Span::styled("   Whole word ", Style::default().fg(palette.muted)),
+        Span::styled(on_off(state.whole_word), Style::default().fg(palette.text)),
+    ];
+    if let Some(table) = &state.table {
+        let column = state
+            .table_column
+            .and_then(|column| table.headers.get(column))
+            .map_or("all", String::as_str);
+        toggles.push(Span::styled("   Column ", Style::default().fg(palette.muted)));
+        toggles.push(Span::styled(column.to_string(), Style::default().fg(palette.accent_alt)));
+    }
     let mode = Paragraph::new(vec![
         Line::from(vec![
             Span::styled("Query ", Style::default().fg(palette.muted)),
             Span::styled(if state.search_query.is_empty() { "(empty)" } else { &state.search_query }, Style::default().fg(palette.accent)),
         ]),
-        Line::from(vec![
-            Span::styled("Case ", Style::default().fg(palette.muted)),
-            Span::styled(on_off(state.case_sensitive), Style::default().fg(palette.text)),
-            Span::styled("   Regex ", Style::default().fg(palette.muted)),
-            Span::styled(on_off(state.regex_mode), Style::default().fg(palette.text)),
-            Span::styled("   Whole word ", Style::default().fg(palette.muted)),
-            Span::styled(on_off(state.whole_word), Style::default().fg(palette.text)),
-        ]),
+        Line::from(toggles),
     ])
     .block(panel_block("Search Model", palette.accent, palette));
     f.render_widget(mode, rows[0]);
@@ -525,21 +677,88 @@ fn render_search(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Pale
         f.render_stateful_widget(list, cols[0], &mut state.result_list_state);
     }
 
+    // Only PDFs that carry notes give up half the selection panel for them.
+    let has_notes = state
+        .document
+        .as_ref()
+        .is_some_and(|document| !document.annotations.is_empty() || !document.form_fields.is_empty());
+    let right = if has_notes {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(cols[1])
+    } else {
+        Layout::default().constraints([Constraint::Min(0)]).split(cols[1])
+    };
+
     let detail = Paragraph::new(selected_match_text(state, palette))
         .wrap(Wrap { trim: true })
         .block(panel_block("Selection", palette.warning, palette));
-    f.render_widget(detail, cols[1]);
+    f.render_widget(detail, right[0]);
+
+    if has_notes {
+        render_notes(f, right[1], state, palette);
+    }
+}
+
+fn render_notes(f: &mut Frame, area: Rect, state: &AppState, palette: &Palette) {
+    let lines: Vec<Line> = if state.note_matches.is_empty() {
+        vec![Line::from(Span::styled(
+            "No annotation or form field matches.",
+            Style::default().fg(palette.muted),
+        ))]
+    } else {
+        state
+            .note_matches
+            .iter()
+            .flat_map(|note| {
+                let page = match note.page {
+                    Some(page) => format!("p{page:<3} "),
+                    None => "     ".to_string(),
+                };
+                let mut lines = vec![Line::from(vec![
+                    Span::styled(page, Style::default().fg(palette.warning)),
+                    Span::styled(note.label.clone(), Style::default().fg(palette.accent_alt)),
+                ])];
+                lines.extend(note.text.lines().map(|line| {
+                    Line::from(Span::styled(format!("     {line}"), Style::default().fg(palette.text)))
+                }));
+                lines
+            })
+            .collect()
+    };
+
+    let title = format!("Notes & Fields ({})", state.note_matches.len());
+    let panel = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(panel_block(&title, palette.accent, palette));
+    f.render_widget(panel, area);
 }
 
 fn render_viewer(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Palette) {
-    let rows = Layout::default()
+    let mut rows = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(4), Constraint::Min(10)])
-        .split(area);
+        .split(area)
+        .to_vec();
+
+    let mut outline_area = None;
+    if state.outline_pane && !state.outline.is_empty() {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(OUTLINE_PANE_WIDTH), Constraint::Min(20)])
+            .split(rows[1]);
+        outline_area = Some(columns[0]);
+        rows[1] = columns[1];
+    }
 
     // Fill the pane rather than a fixed line budget, and remember the height so
-    // PageUp/PageDown move by exactly one screenful.
-    let visible = usize::from(rows[1].height.saturating_sub(2)).max(1);
+    // PageUp/PageDown move by exactly one screenful. A table's header takes
+    // the first row and stays there.
+    let frozen = usize::from(state.table.is_some());
+    let visible = usize::from(rows[1].height.saturating_sub(2))
+        .saturating_sub(frozen)
+        .max(1);
     state.viewer_height = visible;
 
     let total_lines = state.file_content.lines().count();
@@ -561,43 +780,100 @@ fn render_viewer(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Pale
             Style::default().fg(palette.text),
         ),
     ];
-    if let Some(document) = &state.document {
-        let index = document.page_of_line(state.content_scroll);
-        let images_here = document
-            .pages
-            .get(index)
-            .map(|page| page.image_count)
-            .unwrap_or(0);
-
-        position.push(Span::styled("   Page ", Style::default().fg(palette.muted)));
+    let top = state.outline.top_level();
+    if let Some(index) = state.outline.top_level_at(state.content_scroll) {
+        let segment = top[index];
+        position.push(Span::styled(
+            format!("   {} ", segment.kind.label()),
+            Style::default().fg(palette.muted),
+        ));
         position.push(Span::styled(
-            format!("{} of {}", index + 1, document.page_count()),
+            format!("{} of {}", index + 1, top.len()),
             Style::default().fg(palette.accent_alt),
         ));
+        if segment.kind != SegmentKind::Page {
+            position.push(Span::styled(
+                format!(" · {}", segment.title),
+                Style::default().fg(palette.text),
+            ));
+        }
+    }
+    if let Some(document) = &state.document {
+        let index = document.page_of_line(state.content_scroll);
+        let page = document.pages.get(index);
+        let images_here = page.map(|page| page.image_count).unwrap_or(0);
+
         position.push(Span::styled("   Images here ", Style::default().fg(palette.muted)));
         position.push(Span::styled(
             images_here.to_string(),
             Style::default().fg(palette.warning),
         ));
+        if page.is_some_and(|page| page.ocr) {
+            position.push(Span::styled(
+                "   OCR text",
+                Style::default().fg(palette.accent_alt),
+            ));
+        }
     }
 
-    let info = Paragraph::new(vec![
-        Line::from(position),
-        Line::from(vec![
-            Span::styled("Line numbers ", Style::default().fg(palette.muted)),
-            Span::styled(on_off(state.line_numbers), Style::default().fg(palette.text)),
-            Span::styled("   Wrap ", Style::default().fg(palette.muted)),
-            Span::styled(on_off(state.wrap_lines), Style::default().fg(palette.text)),
-            Span::styled("   [ ] page step", Style::default().fg(palette.muted)),
-        ]),
-    ])
+    let mut toggles = vec![
+        Span::styled("Line numbers ", Style::default().fg(palette.muted)),
+        Span::styled(on_off(state.line_numbers), Style::default().fg(palette.text)),
+        Span::styled("   Wrap ", Style::default().fg(palette.muted)),
+        Span::styled(on_off(state.wrap_lines), Style::default().fg(palette.text)),
+        Span::styled("   Outline ", Style::default().fg(palette.muted)),
+        Span::styled(on_off(state.outline_pane), Style::default().fg(palette.text)),
+    ];
+    if let Some(diff) = &state.diff {
+        toggles.push(Span::styled("   Layout ", Style::default().fg(palette.muted)));
+        toggles.push(Span::styled(
+            state.diff_layout.label(),
+            Style::default().fg(palette.accent_alt),
+        ));
+        toggles.push(Span::styled("   Hunk ", Style::default().fg(palette.muted)));
+        toggles.push(Span::styled(
+            match state.diff_hunk {
+                Some(hunk) => format!("{} of {}", hunk + 1, diff.hunks.len()),
+                None => format!("- of {}", diff.hunks.len()),
+            },
+            Style::default().fg(palette.text),
+        ));
+    } else if let Some(tree) = &state.tree {
+        let node = state.tree_rows.get(state.content_scroll).copied().unwrap_or(0);
+        toggles.push(Span::styled("   Path ", Style::default().fg(palette.muted)));
+        toggles.push(Span::styled(tree.path(node), Style::default().fg(palette.accent_alt)));
+    } else if let Some(code) = &state.code {
+        toggles.push(Span::styled("   Language ", Style::default().fg(palette.muted)));
+        toggles.push(Span::styled(
+            code.language.label(),
+            Style::default().fg(palette.accent_alt),
+        ));
+    } else if state.log.is_some() {
+        toggles.push(Span::styled("   Filter ", Style::default().fg(palette.muted)));
+        toggles.push(Span::styled(
+            state.log_filter.describe(),
+            Style::default().fg(palette.accent_alt),
+        ));
+    } else {
+        toggles.push(Span::styled("   [ ] page step", Style::default().fg(palette.muted)));
+    }
+
+    let info = Paragraph::new(vec![Line::from(position), Line::from(toggles)])
     .block(panel_block("Viewer", palette.accent, palette));
     f.render_widget(info, rows[0]);
 
     let block = panel_block("Content", palette.accent_soft, palette);
     state.hit.viewer = block.inner(rows[1]);
 
-    let content = build_viewer_text(state, palette, visible, false);
+    let gutter = if state.line_numbers { LINE_NUMBER_WIDTH } else { 0 };
+    if let Some(table) = &state.table {
+        let width = usize::from(state.hit.viewer.width).saturating_sub(gutter);
+        state.table_offset = table.offset_showing(state.table_column, width);
+    }
+    let mut content = build_viewer_text(state, palette, visible, false, state.hit.viewer.width);
+    if let Some(table) = &state.table {
+        content.lines.insert(0, table_header(state, table, gutter, palette));
+    }
     let mut viewer = Paragraph::new(content).block(block);
     // Wrapping reflows long lines onto extra rows, which would push the tail of
     // the window off-screen; only apply it when the user asked for it.
@@ -607,6 +883,51 @@ fn render_viewer(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Pale
     f.render_widget(viewer, rows[1]);
 
     render_content_scrollbar(f, rows[1], state, palette, total_lines, visible);
+
+    // Drawn last, so it highlights the section the clamped scroll lands in.
+    if let Some(outline_area) = outline_area {
+        render_outline_pane(f, outline_area, state, palette);
+    }
+}
+
+/// Columns the outline pane takes from the viewer, borders included.
+const OUTLINE_PANE_WIDTH: u16 = 30;
+
+/// List the outline beside the text, nested by level, with the section the
+/// top of the viewer is in highlighted. The list scrolls to keep it in view.
+fn render_outline_pane(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Palette) {
+    let entries = state.outline.contents();
+    let outer = entries.iter().map(|segment| segment.level).min().unwrap_or(0);
+    // Nested entries start later than the ones holding them, so the last
+    // entry holding the line is the innermost.
+    let current = entries
+        .iter()
+        .rposition(|segment| segment.contains(state.content_scroll));
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|segment| {
+            let indent = "  ".repeat(segment.level - outer);
+            ListItem::new(Line::from(Span::styled(
+                format!("{indent}{}", segment.title),
+                Style::default().fg(palette.text),
+            )))
+        })
+        .collect();
+    let count = items.len();
+
+    let title = format!("Outline ({count})");
+    let block = panel_block(&title, palette.accent_soft, palette);
+    state.hit.outline = block.inner(area);
+    state.outline_list_state.select(current);
+
+    let list = List::new(items).block(block).highlight_style(
+        Style::default()
+            .fg(palette.accent_alt)
+            .bg(palette.highlight_bg)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_stateful_widget(list, area, &mut state.outline_list_state);
 }
 
 /// Draw the viewer's position indicator, with the search matches marked on its
@@ -655,7 +976,9 @@ fn render_content_scrollbar(
     let column = track.right().saturating_sub(1);
     let buffer = f.buffer_mut();
     for entry in &state.search_results {
-        let line = entry.line_number.saturating_sub(1);
+        let Some(line) = state.match_line(entry) else {
+            continue;
+        };
         let row = track.y + (line * track.height as usize / total_lines.max(1)) as u16;
         if row >= track.bottom() {
             continue;
@@ -679,14 +1002,48 @@ fn render_media(
     media: &mut MediaRenderer,
     palette: &Palette,
 ) {
+    if state.media_grid && !state.media.is_empty() {
+        render_media_grid(f, area, state, media, palette);
+        return;
+    }
+
     let columns = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(32), Constraint::Percentage(68)])
         .split(area);
 
+    let attachments = state
+        .document
+        .as_ref()
+        .map(|document| document.attachments.as_slice())
+        .unwrap_or_default();
+    // Two rows per attachment plus the border, but never crowding out the
+    // image list the tab is named for.
+    let attachment_rows = if attachments.is_empty() {
+        0
+    } else {
+        (attachments.len() as u16 * 2 + 2).min(columns[0].height / 3)
+    };
+
+    let metadata = state
+        .selected_media_item()
+        .and_then(|item| item.metadata.as_ref())
+        .map(|metadata| metadata.rows())
+        .unwrap_or_default();
+    let metadata_rows = if metadata.is_empty() {
+        0
+    } else {
+        (metadata.len() as u16 + 2).min(columns[0].height / 3)
+    };
+
     let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(6), Constraint::Length(5)])
+        .constraints([
+            Constraint::Min(6),
+            Constraint::Length(metadata_rows),
+            Constraint::Length(attachment_rows),
+            Constraint::Length(5),
+        ])
         .split(columns[0]);
 
     let items: Vec<ListItem> = if state.media.is_empty() {
@@ -702,9 +1059,13 @@ fn render_media(
             .map(|(index, item)| {
                 let selected = index == state.selected_media;
                 let marker = if selected { "▸ " } else { "  " };
+                let title = match item.copies_label() {
+                    Some(copies) => format!("{marker}{} · {copies}", item.title),
+                    None => format!("{marker}{}", item.title),
+                };
                 ListItem::new(vec![
                     Line::from(Span::styled(
-                        format!("{marker}{}", item.title),
+                        title,
                         Style::default()
                             .fg(if selected { palette.accent_alt } else { palette.text })
                             .add_modifier(if selected {
@@ -726,6 +1087,52 @@ fn render_media(
     state.hit.media_list = block.inner(rows[0]);
     f.render_widget(List::new(items).block(block), rows[0]);
 
+    if !metadata.is_empty() {
+        let lines: Vec<Line> = metadata
+            .into_iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{label:<12}"), Style::default().fg(palette.muted)),
+                    Span::styled(value, Style::default().fg(palette.text)),
+                ])
+            })
+            .collect();
+        f.render_widget(
+            Paragraph::new(lines).block(panel_block("Metadata", palette.accent_soft, palette)),
+            rows[1],
+        );
+    }
+
+    if !attachments.is_empty() {
+        let items: Vec<ListItem> = attachments
+            .iter()
+            .enumerate()
+            .map(|(index, attachment)| {
+                let selected = index == state.selected_attachment;
+                let marker = if selected { "▸ " } else { "  " };
+                let mut detail = format!(
+                    "  {} · {}",
+                    attachment.mime_type.as_deref().unwrap_or("unknown type"),
+                    format_size(attachment.size)
+                );
+                if let Some(page) = attachment.page {
+                    detail.push_str(&format!(" · page {page}"));
+                }
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        format!("{marker}{}", attachment.name),
+                        Style::default().fg(if selected { palette.accent_alt } else { palette.text }),
+                    )),
+                    Line::from(Span::styled(detail, Style::default().fg(palette.muted))),
+                ])
+            })
+            .collect();
+        f.render_widget(
+            List::new(items).block(panel_block("Attachments", palette.warning, palette)),
+            rows[2],
+        );
+    }
+
     let mut status = vec![Line::from(vec![
         Span::styled("Renderer ", Style::default().fg(palette.muted)),
         Span::styled(
@@ -764,12 +1171,16 @@ fn render_media(
         Paragraph::new(status)
             .wrap(Wrap { trim: true })
             .block(panel_block("Output", palette.accent_soft, palette)),
-        rows[1],
+        rows[3],
     );
 
+    let view = state.media_view;
     let title = state
         .selected_media_item()
-        .map(|item| item.title.clone())
+        .map(|item| match view.label() {
+            label if label.is_empty() => item.title.clone(),
+            label => format!("{} · {label}", item.title),
+        })
         .unwrap_or_else(|| "Preview".to_string());
     let frame_block = panel_block("Preview", palette.accent_soft, palette).title(Span::styled(
         format!(" {title} "),
@@ -778,9 +1189,20 @@ fn render_media(
     let inner = frame_block.inner(columns[1]);
     f.render_widget(frame_block, columns[1]);
 
+    state.hit.media_preview = inner;
+    if let Some(item) = state.selected_media_item() {
+        let turned = if view.quarter_turns % 2 == 1 {
+            (item.image.height(), item.image.width())
+        } else {
+            (item.image.width(), item.image.height())
+        };
+        let region = visible_region(turned, &view, media.area_pixels(inner));
+        state.media_visible = region.fraction(turned);
+    }
+
     match state.selected_media_item() {
         Some(item) => {
-            media.select(item);
+            media.select(item, &view, inner);
             if !media.render(f, inner) {
                 f.render_widget(
                     Paragraph::new(placeholder_text(state, media, palette))
@@ -799,6 +1221,70 @@ fn render_media(
     }
 }
 
+/// Every image as a thumbnail, as many to a row as fit, scrolled to keep the
+/// selected one on screen.
+fn render_media_grid(
+    f: &mut Frame,
+    area: Rect,
+    state: &mut AppState,
+    media: &mut MediaRenderer,
+    palette: &Palette,
+) {
+    let title = format!(
+        "Images · {} of {}",
+        state.selected_media + 1,
+        state.media.len()
+    );
+    let block = panel_block(&title, palette.accent, palette);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = usize::from((inner.width / GRID_TILE.0).max(1));
+    let rows = usize::from((inner.height / GRID_TILE.1).max(1));
+    let selected_row = state.selected_media / columns;
+    let last_row = (state.media.len() - 1) / columns;
+    let mut scroll = state.media_grid_scroll.min(last_row);
+    if selected_row < scroll {
+        scroll = selected_row;
+    } else if selected_row >= scroll + rows {
+        scroll = selected_row + 1 - rows;
+    }
+    state.media_grid_columns = columns;
+    state.media_grid_scroll = scroll;
+    state.hit.media_grid = inner;
+
+    let first = scroll * columns;
+    let last = (first + rows * columns).min(state.media.len());
+    for (index, item) in state.media.iter().enumerate().take(last).skip(first) {
+        let (row, column) = ((index - first) / columns, (index - first) % columns);
+        let tile = Rect {
+            x: inner.x + column as u16 * GRID_TILE.0,
+            y: inner.y + row as u16 * GRID_TILE.1,
+            width: GRID_TILE.0,
+            height: GRID_TILE.1,
+        }
+        .intersection(inner);
+
+        let selected = index == state.selected_media;
+        let color = if selected { palette.accent_alt } else { palette.muted };
+        let tile_block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color))
+            .title(Span::styled(
+                item.title.clone(),
+                Style::default().fg(color).add_modifier(if selected {
+                    Modifier::BOLD
+                } else {
+                    Modifier::empty()
+                }),
+            ));
+        let thumbnail = tile_block.inner(tile);
+        f.render_widget(tile_block, tile);
+        media.render_thumbnail(f, item, thumbnail);
+    }
+    media.retain_thumbnails(&state.media);
+}
+
 fn placeholder_text(state: &AppState, media: &MediaRenderer, palette: &Palette) -> Text<'static> {
     let mut lines = Vec::new();
 
@@ -829,7 +1315,105 @@ fn placeholder_text(state: &AppState, media: &MediaRenderer, palette: &Palette)
     Text::from(lines)
 }
 
+/// Names the page or pages the numbers below are for, and says so when
+/// they leave out page furniture, since they will not match a count taken in
+/// the viewer.
+fn metrics_title(state: &AppState) -> String {
+    let top = state.outline.top_level();
+    let measured = state.page_analysis.as_ref().and_then(|analysis| {
+        Some((top.get(analysis.index).copied()?, top.get(analysis.last).copied()?))
+    });
+
+    let mut title = match measured {
+        Some((page, last)) if page.kind == SegmentKind::Page && last.number > page.number => {
+            format!("Metrics · pages {}-{} of {}", page.number, last.number, top.len())
+        }
+        Some((page, _)) if page.kind == SegmentKind::Page => {
+            format!("Metrics · page {} of {}", page.number, top.len())
+        }
+        Some((section, last)) if last.start_line > section.start_line => {
+            format!("Metrics · {} to {}", section.title, last.title)
+        }
+        Some((section, _)) => format!("Metrics · {}", section.title),
+        None => "Metrics".to_string(),
+    };
+    let Some(document) = &state.document else {
+        return title;
+    };
+    let skipped = document
+        .furniture
+        .iter()
+        .filter(|_| state.strip_furniture)
+        .filter(|entry| {
+            measured.is_none_or(|(first, last)| {
+                (first.start_line..last.end_line()).contains(&entry.line)
+            })
+        })
+        .count();
+    if skipped > 0 {
+        title.push_str(&format!(" · {skipped} header/footer lines skipped"));
+    }
+    title
+}
+
 fn render_analysis(f: &mut Frame, area: Rect, state: &AppState, palette: &Palette) {
+    // Comparing two files' profiles takes the whole tab.
+    if let (true, Some(diff), Some(profiles)) = (state.compare_profiles, &state.diff, &state.profiles) {
+        render_profile_comparison(f, area, diff, profiles, palette);
+        return;
+    }
+
+    // Files with images get their measurements below the text's, logs their
+    // event counts, tables their columns, source files their line counts and
+    // diffs what changed.
+    let area = match (&state.image_analysis, &state.log_summary, &state.table, &state.code) {
+        (Some(analysis), _, _, _) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(10), Constraint::Length(11)])
+                .split(area);
+            render_image_analysis(f, rows[1], analysis, palette);
+            rows[0]
+        }
+        (None, Some(summary), _, _) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(10), Constraint::Length(10)])
+                .split(area);
+            render_log_analysis(f, rows[1], summary, palette);
+            rows[0]
+        }
+        (None, None, Some(table), _) => {
+            // A row per column, up to half the tab.
+            let height = (table.columns.len() as u16 + 3).min(area.height / 2);
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(10), Constraint::Length(height)])
+                .split(area);
+            render_table_analysis(f, rows[1], table, palette);
+            rows[0]
+        }
+        (None, None, None, Some(code)) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(10), Constraint::Length(6)])
+                .split(area);
+            render_code_analysis(f, rows[1], code, palette);
+            rows[0]
+        }
+        (None, None, None, None) => match &state.diff {
+            Some(diff) => {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(10), Constraint::Length(6)])
+                    .split(area);
+                render_diff_analysis(f, rows[1], diff, palette);
+                rows[0]
+            }
+            None => area,
+        },
+    };
+
     let cols = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
@@ -840,25 +1424,34 @@ fn render_analysis(f: &mut Frame, area: Rect, state: &AppState, palette: &Palett
         .constraints([Constraint::Length(10), Constraint::Min(8)])
         .split(cols[0]);
 
+    // Page scope swaps in the page's numbers; the layout stays put so the two
+    // are easy to compare by toggling.
+    let page = state.page_analysis.as_ref();
+    let structure = page.map_or(&state.structural_analysis, |page| &page.structure);
+    let repeated_lines = page.map_or(&state.repeated_lines, |page| &page.repeated_lines);
+    let keywords = page.map_or(&state.keywords, |page| &page.keywords);
+    let phrases = page.map_or(&state.phrases, |page| &page.phrases);
+    let readability = page.map_or(&state.readability, |page| &page.readability);
+
+    let title = metrics_title(state);
     let metrics = Paragraph::new(vec![
-        Line::from(format!("Lines: {}", state.structural_analysis.lines)),
-        Line::from(format!("Words: {}", state.structural_analysis.words)),
-        Line::from(format!("Characters: {}", state.structural_analysis.characters)),
-        Line::from(format!("Stanzas: {}", state.structural_analysis.stanzas)),
-        Line::from(format!("Unique words: {}", state.structural_analysis.unique_words)),
-        Line::from(format!("Empty lines: {}", state.structural_analysis.empty_lines)),
-        Line::from(format!("Longest line: {}", state.structural_analysis.longest_line)),
-        Line::from(format!("Avg word length: {:.2}", state.structural_analysis.average_word_length)),
+        Line::from(format!("Lines: {}", structure.lines)),
+        Line::from(format!("Words: {}", structure.words)),
+        Line::from(format!("Characters: {}", structure.characters)),
+        Line::from(format!("Stanzas: {}", structure.stanzas)),
+        Line::from(format!("Unique words: {}", structure.unique_words)),
+        Line::from(format!("Empty lines: {}", structure.empty_lines)),
+        Line::from(format!("Longest line: {}", structure.longest_line)),
+        Line::from(format!("Avg word length: {:.2}", structure.average_word_length)),
     ])
     .style(Style::default().fg(palette.text))
-    .block(panel_block("Metrics", palette.accent, palette));
+    .block(panel_block(&title, palette.accent, palette));
     f.render_widget(metrics, left[0]);
 
-    let repeated_items: Vec<ListItem> = if state.repeated_lines.is_empty() {
+    let repeated_items: Vec<ListItem> = if repeated_lines.is_empty() {
         vec![ListItem::new(Line::from("No repeated non-empty lines."))]
     } else {
-        state
-            .repeated_lines
+        repeated_lines
             .iter()
             .map(|(line, count)| {
                 ListItem::new(Line::from(vec![
@@ -880,16 +1473,15 @@ fn render_analysis(f: &mut Frame, area: Rect, state: &AppState, palette: &Palett
         ])
         .split(cols[1]);
 
-    render_readability(f, right[0], state, palette);
+    render_readability(f, right[0], readability, palette);
 
-    let keyword_items: Vec<ListItem> = if state.keywords.is_empty() {
+    let keyword_items: Vec<ListItem> = if keywords.is_empty() {
         vec![ListItem::new(Line::from(Span::styled(
             "No keywords available.",
             Style::default().fg(palette.muted),
         )))]
     } else {
-        state
-            .keywords
+        keywords
             .iter()
             .enumerate()
             .map(|(index, keyword)| {
@@ -927,14 +1519,13 @@ fn render_analysis(f: &mut Frame, area: Rect, state: &AppState, palette: &Palett
         right[1],
     );
 
-    let phrase_items: Vec<ListItem> = if state.phrases.is_empty() {
+    let phrase_items: Vec<ListItem> = if phrases.is_empty() {
         vec![ListItem::new(Line::from(Span::styled(
             "No phrase repeats itself in this text.",
             Style::default().fg(palette.muted),
         )))]
     } else {
-        state
-            .phrases
+        phrases
             .iter()
             .map(|phrase| {
                 ListItem::new(Line::from(vec![
@@ -953,44 +1544,505 @@ fn render_analysis(f: &mut Frame, area: Rect, state: &AppState, palette: &Palett
     );
 }
 
-fn render_readability(f: &mut Frame, area: Rect, state: &AppState, palette: &Palette) {
-    let readability = &state.readability;
-    let band_color = match readability.lix {
-        _ if readability.sentences == 0 => palette.muted,
-        value if value < 35.0 => palette.success,
-        value if value < 45.0 => palette.accent_alt,
-        value if value < 55.0 => palette.warning,
-        _ => palette.danger,
-    };
+fn render_image_analysis(f: &mut Frame, area: Rect, analysis: &ImageAnalysis, palette: &Palette) {
+    let stats = &analysis.stats;
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(34),
+            Constraint::Min(20),
+            Constraint::Length(30),
+        ])
+        .split(area);
 
-    let lines = vec![
-        Line::from(vec![
-            Span::styled("Language  ", Style::default().fg(palette.muted)),
-            Span::styled(
-                readability.language.label(),
-                Style::default().fg(palette.accent),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled("LIX       ", Style::default().fg(palette.muted)),
-            Span::styled(
-                format!("{:.1}", readability.lix),
-                Style::default().fg(band_color).add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                format!("  {}", readability.band),
-                Style::default().fg(band_color),
-            ),
-        ]),
+    let row = |label: &'static str, value: String| {
         Line::from(vec![
-            Span::styled("Sentences ", Style::default().fg(palette.muted)),
-            Span::styled(
-                readability.sentences.to_string(),
-                Style::default().fg(palette.text),
+            Span::styled(format!("{label:<13}"), Style::default().fg(palette.muted)),
+            Span::styled(value, Style::default().fg(palette.text)),
+        ])
+    };
+    let transparency = match stats.transparency {
+        None => "none (no alpha)".to_string(),
+        Some(0.0) => "alpha, all opaque".to_string(),
+        Some(share) => format!("{:.0}% see-through", share * 100.0),
+    };
+    let title = format!("Image · {}", analysis.title);
+    f.render_widget(
+        Paragraph::new(vec![
+            row("Brightness", format!("{:.0} of 255", stats.mean_brightness)),
+            row("Contrast", format!("{:.0}", stats.contrast)),
+            row(
+                "Colour",
+                if stats.grayscale { "greyscale" } else { "colour" }.to_string(),
             ),
-            Span::styled("   words each ", Style::default().fg(palette.muted)),
-            Span::styled(
-                format!("{:.1}", readability.words_per_sentence),
+            row("Transparency", transparency),
+        ])
+        .block(panel_block(&title, palette.accent, palette)),
+        cols[0],
+    );
+
+    let block = panel_block("Histogram  R · G · B", palette.accent_soft, palette);
+    let inner = block.inner(cols[1]);
+    f.render_widget(block, cols[1]);
+    let bands = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Ratio(1, 3); 3])
+        .split(inner);
+    let histogram = &stats.histogram;
+    for ((channel, color), band) in [
+        (&histogram.red, Color::Red),
+        (&histogram.green, Color::Green),
+        (&histogram.blue, Color::Blue),
+    ]
+    .into_iter()
+    .zip(bands.iter())
+    {
+        let data: Vec<u64> = channel.iter().map(|count| u64::from(*count)).collect();
+        f.render_widget(
+            Sparkline::default()
+                .data(&data)
+                .style(Style::default().fg(color)),
+            *band,
+        );
+    }
+
+    let swatches: Vec<Line> = stats
+        .palette
+        .iter()
+        .map(|color| {
+            let [red, green, blue] = color.rgb;
+            Line::from(vec![
+                Span::styled("██████ ", Style::default().fg(Color::Rgb(red, green, blue))),
+                Span::styled(color.hex.clone(), Style::default().fg(palette.text)),
+                Span::styled(
+                    format!(" {:>4.0}%", color.share * 100.0),
+                    Style::default().fg(palette.muted),
+                ),
+            ])
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(swatches).block(panel_block("Dominant Colours", palette.success, palette)),
+        cols[2],
+    );
+}
+
+fn render_log_analysis(f: &mut Frame, area: Rect, summary: &LogSummary, palette: &Palette) {
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(24), Constraint::Min(20)])
+        .split(area);
+
+    let levels: Vec<Line> = summary
+        .levels
+        .iter()
+        .map(|(level, count)| {
+            let colour = level_colour(*level, palette).unwrap_or(palette.text);
+            Line::from(vec![
+                Span::styled(format!("{:<7}", level.label()), Style::default().fg(colour)),
+                Span::styled(format!("{count:>8}"), Style::default().fg(palette.text)),
+            ])
+        })
+        .collect();
+    let title = format!("Log · {} events", summary.events);
+    f.render_widget(
+        Paragraph::new(levels).block(panel_block(&title, palette.accent, palette)),
+        cols[0],
+    );
+
+    let block = panel_block("Events", palette.accent_soft, palette);
+    let inner = block.inner(cols[1]);
+    let (bars, minutes) = summary.binned(usize::from(inner.width));
+    let title = if minutes == 1 {
+        "Events per minute".to_string()
+    } else {
+        format!("Events per {minutes} minutes")
+    };
+    let block = panel_block(&title, palette.accent_soft, palette);
+    f.render_widget(block, cols[1]);
+    if bars.is_empty() {
+        f.render_widget(
+            Paragraph::new(Span::styled(
+                "No timestamped events shown.",
+                Style::default().fg(palette.muted),
+            )),
+            inner,
+        );
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+    f.render_widget(
+        Sparkline::default()
+            .data(&bars)
+            .style(Style::default().fg(palette.accent_alt)),
+        rows[0],
+    );
+    // The first and last minute under the ends of the bars.
+    let first = summary.per_minute.first().map(|(minute, _)| minute.to_string());
+    let last = summary.per_minute.last().map(|(minute, _)| minute.to_string());
+    let first = first.unwrap_or_default();
+    let last = last.unwrap_or_default();
+    let gap = usize::from(rows[1].width).saturating_sub(first.chars().count() + last.chars().count());
+    f.render_widget(
+        Paragraph::new(Span::styled(
+            format!("{first}{}{last}", " ".repeat(gap)),
+            Style::default().fg(palette.muted),
+        )),
+        rows[1],
+    );
+}
+
+fn render_table_analysis(f: &mut Frame, area: Rect, table: &Table, palette: &Palette) {
+    let name_width = table
+        .columns
+        .iter()
+        .map(|column| column.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(6, 24);
+    let heading = format!(
+        "{:<name_width$}  {:<8} {:>6} {:>8}  {:<12} {:<12} {:>10}",
+        "Column", "Type", "Nulls", "Distinct", "Min", "Max", "Mean"
+    );
+    let clip = |text: &str, width: usize| -> String {
+        if text.chars().count() > width {
+            let mut clipped: String = text.chars().take(width.saturating_sub(1)).collect();
+            clipped.push('…');
+            clipped
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut lines = vec![Line::from(Span::styled(
+        heading,
+        Style::default().fg(palette.muted),
+    ))];
+    for column in &table.columns {
+        let mean = column.mean.map_or(String::new(), |mean| format!("{mean:.2}"));
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<name_width$}  ", clip(&column.name, name_width)),
+                Style::default().fg(palette.text),
+            ),
+            Span::styled(
+                format!("{:<8} ", column.kind.label()),
+                Style::default().fg(palette.accent_alt),
+            ),
+            Span::styled(
+                format!("{:>6} {:>8}  ", column.nulls, column.distinct),
+                Style::default().fg(palette.warning),
+            ),
+            Span::styled(
+                format!(
+                    "{:<12} {:<12} {:>10}",
+                    clip(column.min.as_deref().unwrap_or(""), 12),
+                    clip(column.max.as_deref().unwrap_or(""), 12),
+                    clip(&mean, 10)
+                ),
+                Style::default().fg(palette.text),
+            ),
+        ]));
+    }
+
+    let title = format!(
+        "Table · {} rows · {} columns",
+        table.rows.len(),
+        table.columns.len()
+    );
+    f.render_widget(
+        Paragraph::new(lines).block(panel_block(&title, palette.accent, palette)),
+        area,
+    );
+}
+
+fn render_code_analysis(f: &mut Frame, area: Rect, code: &SourceCode, palette: &Palette) {
+    let stats = &code.stats;
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(palette.muted));
+    let value = |text: String| Span::styled(text, Style::default().fg(palette.text));
+    let longest = match stats.longest_function() {
+        Some(function) => vec![
+            Span::styled(function.name.clone(), Style::default().fg(palette.accent_alt)),
+            value(format!(", {} lines from line {}", function.lines, function.line)),
+        ],
+        None => vec![Span::styled("none found", Style::default().fg(palette.muted))],
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            label("Code "),
+            value(format!("{:<8}", stats.code_lines)),
+            label("Comments "),
+            value(format!("{:<8}", stats.comment_lines)),
+            label("Blank "),
+            value(stats.blank_lines.to_string()),
+        ]),
+        Line::from(vec![
+            label("Comment ratio "),
+            Span::styled(
+                format!("{:.1}%", stats.comment_ratio() * 100.0),
+                Style::default().fg(palette.warning),
+            ),
+        ]),
+        Line::from(vec![label("Functions "), value(stats.functions.len().to_string())]),
+        Line::from([vec![label("Longest function ")], longest].concat()),
+    ];
+
+    let title = format!("Code · {} · {} lines", code.language.label(), stats.lines);
+    f.render_widget(
+        Paragraph::new(lines).block(panel_block(&title, palette.accent, palette)),
+        area,
+    );
+}
+
+fn render_diff_analysis(f: &mut Frame, area: Rect, diff: &TextDiff, palette: &Palette) {
+    let stats = &diff.stats;
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(palette.muted));
+    let value = |text: String, colour: Color| Span::styled(text, Style::default().fg(colour));
+
+    let lines = vec![
+        Line::from(vec![
+            label("Lines added "),
+            value(format!("{:<8}", stats.added), palette.success),
+            label("removed "),
+            value(format!("{:<8}", stats.removed), palette.danger),
+            label("unchanged "),
+            value(stats.unchanged.to_string(), palette.text),
+        ]),
+        Line::from(vec![
+            label("Words added "),
+            value(format!("{:<8}", stats.words_added), palette.success),
+            label("removed "),
+            value(stats.words_removed.to_string(), palette.danger),
+        ]),
+        Line::from(vec![
+            label("Similarity "),
+            value(format!("{:.1}%", stats.similarity() * 100.0), palette.warning),
+        ]),
+        Line::from(vec![
+            label("Old "),
+            value(format!("{} lines", diff.old.len()), palette.text),
+            label("   New "),
+            value(format!("{} lines", diff.new.len()), palette.text),
+        ]),
+    ];
+
+    let title = format!("Diff · {} hunk(s)", diff.hunks.len());
+    f.render_widget(
+        Paragraph::new(lines).block(panel_block(&title, palette.accent, palette)),
+        area,
+    );
+}
+
+/// Analyze for a diff with `s` pressed: the two files' readability and
+/// keywords side by side, under the diff's own counts.
+fn render_profile_comparison(
+    f: &mut Frame,
+    area: Rect,
+    diff: &TextDiff,
+    profiles: &ProfileComparison,
+    palette: &Palette,
+) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(6), Constraint::Length(7), Constraint::Min(6)])
+        .split(area);
+    render_diff_analysis(f, rows[0], diff, palette);
+
+    let label = |text: String| Span::styled(text, Style::default().fg(palette.muted));
+    let value = |text: String, colour: Color| Span::styled(text, Style::default().fg(colour));
+    // Green for a change in the better direction, red for the worse one.
+    let change = |delta: f64, lower_is_better: bool| {
+        let colour = match delta {
+            _ if delta.abs() < 0.05 => palette.muted,
+            _ if (delta < 0.0) == lower_is_better => palette.success,
+            _ => palette.danger,
+        };
+        value(format!("{delta:+.1}"), colour)
+    };
+    let (old, new) = (&profiles.old_readability, &profiles.new_readability);
+
+    let strip = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(rows[1]);
+    let readability = vec![
+        Line::from(label(format!("{:<11}{:<16}{:<16}Change", "", "Old", "New"))),
+        Line::from(vec![
+            label(format!("{:<11}", "LIX")),
+            Span::styled(
+                format!("{:<16.1}", old.lix),
+                Style::default().fg(lix_color(old, palette)).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{:<16.1}", new.lix),
+                Style::default().fg(lix_color(new, palette)).add_modifier(Modifier::BOLD),
+            ),
+            change(profiles.lix_delta(), true),
+        ]),
+        Line::from(vec![
+            label(format!("{:<11}", "Band")),
+            value(format!("{:<16}", old.band), lix_color(old, palette)),
+            value(new.band.to_string(), lix_color(new, palette)),
+        ]),
+        Line::from(vec![
+            label(format!("{:<11}", "Sentences")),
+            value(format!("{:<16}", old.sentences), palette.text),
+            value(format!("{:<16}", new.sentences), palette.text),
+            change(new.sentences as f64 - old.sentences as f64, false),
+        ]),
+        Line::from(vec![
+            label(format!("{:<11}", "Words each")),
+            value(format!("{:<16.1}", old.words_per_sentence), palette.text),
+            value(format!("{:<16.1}", new.words_per_sentence), palette.text),
+            change(new.words_per_sentence - old.words_per_sentence, true),
+        ]),
+    ];
+    f.render_widget(
+        Paragraph::new(readability).block(panel_block("Readability", palette.warning, palette)),
+        strip[0],
+    );
+
+    let vocabulary = vec![
+        Line::from(vec![
+            label("Old     ".into()),
+            value(profiles.old_vocabulary.to_string(), palette.text),
+            label(" content words".into()),
+        ]),
+        Line::from(vec![
+            label("New     ".into()),
+            value(profiles.new_vocabulary.to_string(), palette.text),
+            label(" content words".into()),
+        ]),
+        Line::from(vec![
+            label("Shared  ".into()),
+            value(profiles.shared_vocabulary.to_string(), palette.text),
+        ]),
+        Line::from(vec![
+            label("Overlap ".into()),
+            value(format!("{:.1}%", profiles.jaccard() * 100.0), palette.warning),
+            label("  (Jaccard)".into()),
+        ]),
+    ];
+    f.render_widget(
+        Paragraph::new(vocabulary).block(panel_block("Vocabulary", palette.accent_soft, palette)),
+        strip[1],
+    );
+
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+        ])
+        .split(rows[2]);
+    let empty = |text: &'static str| vec![ListItem::new(Line::from(label(text.into())))];
+    let unique = |keywords: &[Keyword], colour: Color| -> Vec<ListItem<'static>> {
+        if keywords.is_empty() {
+            return empty("No keyword of its own.");
+        }
+        keywords
+            .iter()
+            .map(|keyword| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<18}", keyword.word),
+                        Style::default().fg(colour).add_modifier(Modifier::BOLD),
+                    ),
+                    value(format!("{:>4}", keyword.count), palette.warning),
+                ]))
+            })
+            .collect()
+    };
+    f.render_widget(
+        List::new(unique(&profiles.only_old, palette.danger))
+            .block(panel_block("Only in old", palette.danger, palette)),
+        cols[0],
+    );
+    let shared: Vec<ListItem> = if profiles.shared.is_empty() {
+        empty("The two share no keywords.")
+    } else {
+        profiles
+            .shared
+            .iter()
+            .map(|keyword| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<16}", keyword.word),
+                        Style::default().fg(palette.accent_alt).add_modifier(Modifier::BOLD),
+                    ),
+                    value(
+                        format!("{:>5.1}% → {:>5.1}%  ", keyword.old_weight * 100.0, keyword.new_weight * 100.0),
+                        palette.text,
+                    ),
+                    // A heavier keyword is neither better nor worse; only
+                    // the direction is shown.
+                    value(
+                        format!("{:+.1}", keyword.delta() * 100.0),
+                        if keyword.delta() < 0.0 { palette.danger } else { palette.success },
+                    ),
+                ]))
+            })
+            .collect()
+    };
+    f.render_widget(
+        List::new(shared).block(panel_block("Shared  (weight old → new)", palette.accent, palette)),
+        cols[1],
+    );
+    f.render_widget(
+        List::new(unique(&profiles.only_new, palette.success))
+            .block(panel_block("Only in new", palette.success, palette)),
+        cols[2],
+    );
+}
+
+/// Colour of a LIX value, from easy green to difficult red.
+fn lix_color(readability: &Readability, palette: &Palette) -> Color {
+    match readability.lix {
+        _ if readability.sentences == 0 => palette.muted,
+        value if value < 35.0 => palette.success,
+        value if value < 45.0 => palette.accent_alt,
+        value if value < 55.0 => palette.warning,
+        _ => palette.danger,
+    }
+}
+
+fn render_readability(f: &mut Frame, area: Rect, readability: &Readability, palette: &Palette) {
+    let band_color = lix_color(readability, palette);
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("Language  ", Style::default().fg(palette.muted)),
+            Span::styled(
+                readability.language.label(),
+                Style::default().fg(palette.accent),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("LIX       ", Style::default().fg(palette.muted)),
+            Span::styled(
+                format!("{:.1}", readability.lix),
+                Style::default().fg(band_color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  {}", readability.band),
+                Style::default().fg(band_color),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Sentences ", Style::default().fg(palette.muted)),
+            Span::styled(
+                readability.sentences.to_string(),
+                Style::default().fg(palette.text),
+            ),
+            Span::styled("   words each ", Style::default().fg(palette.muted)),
+            Span::styled(
+                format!("{:.1}", readability.words_per_sentence),
                 Style::default().fg(palette.text),
             ),
         ]),
@@ -1025,6 +2077,9 @@ fn render_settings(f: &mut Frame, area: Rect, state: &mut AppState, palette: &Pa
         ("Whole word", on_off(state.whole_word).to_string()),
         ("Line numbers", on_off(state.line_numbers).to_string()),
         ("Wrap lines", on_off(state.wrap_lines).to_string()),
+        ("PDF text", state.extraction_mode.label().to_string()),
+        ("Skip headers", on_off(state.strip_furniture).to_string()),
+        ("PDF lines", if state.reflow { "Reflowed" } else { "As typeset" }.to_string()),
     ];
 
     let items: Vec<ListItem> = settings
@@ -1117,7 +2172,114 @@ fn render_goto_input(f: &mut Frame, state: &AppState, palette: &Palette) {
     f.render_widget(input, popup_area);
 }
 
-fn build_viewer_text(state: &AppState, palette: &Palette, line_limit: usize, compact: bool) -> Text<'static> {
+fn render_time_range_input(f: &mut Frame, state: &AppState, palette: &Palette) {
+    let popup_area = centered_rect(60, 20, f.area());
+    f.render_widget(Clear, popup_area);
+
+    let input = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("Range ", Style::default().fg(palette.muted)),
+            Span::styled(
+                format!("{}_", state.time_range_buffer),
+                Style::default().fg(palette.text),
+            ),
+        ]),
+        Line::from(Span::styled(
+            "10:00..10:30 or 2024-03-01..   empty clears   Enter apply   Esc cancel",
+            Style::default().fg(palette.muted),
+        )),
+    ])
+    .alignment(Alignment::Left)
+    .style(Style::default().fg(palette.text).bg(palette.surface))
+    .block(panel_block("Log Time Range", palette.accent, palette));
+
+    f.render_widget(input, popup_area);
+}
+
+fn render_page_range_input(f: &mut Frame, state: &AppState, palette: &Palette) {
+    let popup_area = centered_rect(60, 20, f.area());
+    f.render_widget(Clear, popup_area);
+
+    let input = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("Pages ", Style::default().fg(palette.muted)),
+            Span::styled(
+                format!("{}_", state.page_range_buffer),
+                Style::default().fg(palette.text),
+            ),
+        ]),
+        Line::from(Span::styled(
+            "3-7 or 4   empty measures the whole document   Enter apply   Esc cancel",
+            Style::default().fg(palette.muted),
+        )),
+    ])
+    .alignment(Alignment::Left)
+    .style(Style::default().fg(palette.text).bg(palette.surface))
+    .block(panel_block("Analyze Pages", palette.accent, palette));
+
+    f.render_widget(input, popup_area);
+}
+
+fn render_path_input(f: &mut Frame, state: &AppState, palette: &Palette) {
+    let popup_area = centered_rect(60, 20, f.area());
+    f.render_widget(Clear, popup_area);
+
+    let input = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("Path ", Style::default().fg(palette.muted)),
+            Span::styled(format!("{}_", state.path_buffer), Style::default().fg(palette.text)),
+        ]),
+        Line::from(Span::styled(
+            "server.ports[0] or [\"two words\"]   Enter go   Esc cancel",
+            Style::default().fg(palette.muted),
+        )),
+    ])
+    .alignment(Alignment::Left)
+    .style(Style::default().fg(palette.text).bg(palette.surface))
+    .block(panel_block("Go To Path", palette.accent, palette));
+
+    f.render_widget(input, popup_area);
+}
+
+fn render_password_input(f: &mut Frame, state: &AppState, palette: &Palette) {
+    let popup_area = centered_rect(60, 20, f.area());
+    f.render_widget(Clear, popup_area);
+
+    let file = state.locked_file.as_deref().unwrap_or("this file");
+    // One mask character per typed character, so the user can tell a keystroke
+    // landed without the password itself reaching the screen.
+    let masked = "•".repeat(state.password_buffer.chars().count());
+    let input = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("Password ", Style::default().fg(palette.muted)),
+            Span::styled(format!("{masked}_"), Style::default().fg(palette.text)),
+        ]),
+        Line::from(Span::styled(
+            format!("{file} is encrypted   Enter unlock   Esc cancel"),
+            Style::default().fg(palette.muted),
+        )),
+        Line::from(Span::styled(
+            state.status_message.as_str(),
+            Style::default().fg(palette.warning),
+        )),
+    ])
+    .alignment(Alignment::Left)
+    .style(Style::default().fg(palette.text).bg(palette.surface))
+    .block(panel_block("Unlock PDF", palette.warning, palette));
+
+    f.render_widget(input, popup_area);
+}
+
+fn build_viewer_text(
+    state: &AppState,
+    palette: &Palette,
+    line_limit: usize,
+    compact: bool,
+    width: u16,
+) -> Text<'static> {
+    if let Some(diff) = &state.diff {
+        return diff_viewer_text(state, diff, palette, line_limit, usize::from(width));
+    }
     if state.file_content.is_empty() {
         return Text::from(Line::from(Span::styled(
             "No file loaded. Launch flerp with a file path.",
@@ -1129,7 +2291,7 @@ fn build_viewer_text(state: &AppState, palette: &Palette, line_limit: usize, com
     let selected_line = state
         .search_results
         .get(state.selected_result)
-        .map(|entry| entry.line_number);
+        .and_then(|entry| state.match_line(entry));
 
     let lines: Vec<Line<'static>> = state
         .file_content
@@ -1139,7 +2301,7 @@ fn build_viewer_text(state: &AppState, palette: &Palette, line_limit: usize, com
         .take(line_limit)
         .map(|(index, line)| {
             let line_number = index + 1;
-            let is_selected = selected_line == Some(line_number);
+            let is_selected = selected_line == Some(index);
             let base_style = if is_selected {
                 Style::default().fg(palette.text).bg(palette.highlight_bg)
             } else {
@@ -1154,23 +2316,56 @@ fn build_viewer_text(state: &AppState, palette: &Palette, line_limit: usize, com
                 ));
             }
 
+            // Table rows scroll sideways to keep the chosen column in view.
+            let line: String = line.chars().skip(state.table_offset).collect();
             let display_line = if compact && line.chars().count() > 88 {
                 format!("{}...", line.chars().take(88).collect::<String>())
             } else {
-                line.to_string()
+                line
             };
-            spans.extend(highlighted_line_spans(
-                display_line.as_str(),
-                state.search_query.as_str(),
-                state.case_sensitive,
-                state.regex_mode,
-                state.whole_word,
-                palette,
-                is_selected,
-            ));
+            match &state.code {
+                Some(code) => spans.extend(code_line_spans(
+                    &display_line,
+                    &code.tokens(index, &display_line),
+                    state,
+                    palette,
+                    is_selected,
+                )),
+                None => spans.extend(highlighted_line_spans(
+                    display_line.as_str(),
+                    state.search_query.as_str(),
+                    state.case_sensitive,
+                    state.regex_mode,
+                    state.whole_word,
+                    palette,
+                    is_selected,
+                )),
+            }
             if spans.len() == usize::from(state.line_numbers) {
                 spans.push(Span::styled(display_line, base_style));
             }
+            // Running headers and page numbers stay visible but recede, leaving
+            // search highlights (which carry a background) as they are.
+            if state
+                .document
+                .as_ref()
+                .is_some_and(|document| document.furniture_at(index).is_some())
+            {
+                let dim = Style::default().fg(palette.muted).add_modifier(Modifier::DIM);
+                for span in spans.iter_mut().skip(usize::from(state.line_numbers)) {
+                    if span.style.bg.is_none() {
+                        span.style = span.style.patch(dim);
+                    }
+                }
+            }
+            // Log lines take the colour of their severity, the same way.
+            if let Some(colour) = state.log_level_at(index).and_then(|level| level_colour(level, palette)) {
+                for span in spans.iter_mut().skip(usize::from(state.line_numbers)) {
+                    if span.style.bg.is_none() {
+                        span.style = span.style.fg(colour);
+                    }
+                }
+            }
             Line::from(spans)
         })
         .collect();
@@ -1178,6 +2373,50 @@ fn build_viewer_text(state: &AppState, palette: &Palette, line_limit: usize, com
     Text::from(lines)
 }
 
+/// Columns the line numbers take, with the bar after them.
+const LINE_NUMBER_WIDTH: usize = 7;
+
+/// A table's header row, scrolled like the rows under it, with the column
+/// searches are limited to picked out.
+fn table_header(state: &AppState, table: &Table, gutter: usize, palette: &Palette) -> Line<'static> {
+    let header: Vec<char> = table.header_line().chars().collect();
+    let style = Style::default().fg(palette.accent).add_modifier(Modifier::BOLD);
+    let span = state
+        .table_column
+        .map_or(0..0, |column| table.column_span(column));
+    let piece = |range: std::ops::Range<usize>| -> String {
+        header
+            .get(range.start.min(header.len())..range.end.min(header.len()))
+            .unwrap_or_default()
+            .iter()
+            .collect()
+    };
+
+    let offset = state.table_offset;
+    let mut spans = vec![Span::raw(" ".repeat(gutter))];
+    if span.is_empty() {
+        spans.push(Span::styled(piece(offset..header.len()), style));
+    } else {
+        spans.push(Span::styled(piece(offset..span.start.max(offset)), style));
+        spans.push(Span::styled(
+            format!("{:<width$}", piece(span.clone()), width = span.len()),
+            style.fg(palette.background).bg(palette.accent),
+        ));
+        spans.push(Span::styled(piece(span.end..header.len()), style));
+    }
+    Line::from(spans)
+}
+
+/// The colour a log line at `level` is drawn in; `None` leaves it plain.
+fn level_colour(level: LogLevel, palette: &Palette) -> Option<Color> {
+    match level {
+        LogLevel::Error | LogLevel::Fatal => Some(palette.danger),
+        LogLevel::Warn => Some(palette.warning),
+        LogLevel::Info => None,
+        LogLevel::Debug | LogLevel::Trace => Some(palette.muted),
+    }
+}
+
 fn selected_match_text(state: &AppState, palette: &Palette) -> Text<'static> {
     if let Some(error) = &state.search_error {
         return Text::from(Line::from(Span::styled(error.clone(), Style::default().fg(palette.danger))));
@@ -1256,6 +2495,214 @@ fn highlighted_line_spans(
     spans
 }
 
+/// A line of source code coloured by `tokens`, with search matches
+/// highlighted over the top as [`highlighted_line_spans`] does.
+fn code_line_spans(
+    line: &str,
+    tokens: &[Token],
+    state: &AppState,
+    palette: &Palette,
+    selected_line: bool,
+) -> Vec<Span<'static>> {
+    let base_style = if selected_line {
+        Style::default().fg(palette.text).bg(palette.highlight_bg)
+    } else {
+        Style::default().fg(palette.text)
+    };
+    let pieces: Vec<(Range<usize>, Style)> = tokens
+        .iter()
+        .map(|token| (token.range.clone(), base_style.patch(token_style(token.kind, palette))))
+        .collect();
+    match_spans(line, &pieces, state, palette)
+}
+
+/// `line` drawn in `pieces`, byte ranges that cover it each with their own
+/// style, and the search matches highlighted over the top.
+fn match_spans(
+    line: &str,
+    pieces: &[(Range<usize>, Style)],
+    state: &AppState,
+    palette: &Palette,
+) -> Vec<Span<'static>> {
+    let highlight_style = Style::default()
+        .fg(palette.background)
+        .bg(palette.warning)
+        .add_modifier(Modifier::BOLD);
+    let matches: Vec<Range<usize>> = build_search_regex(
+        &state.search_query,
+        state.case_sensitive,
+        state.regex_mode,
+        state.whole_word,
+    )
+    .map(|regex| {
+        regex
+            .find_iter(line)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    })
+    .unwrap_or_default();
+
+    let mut spans = Vec::new();
+    for (range, style) in pieces {
+        let mut at = range.start;
+        while at < range.end {
+            let next = matches
+                .iter()
+                .find(|found| found.end > at && found.start < range.end);
+            let (end, style) = match next {
+                Some(found) if found.start <= at => (found.end.min(range.end), highlight_style),
+                Some(found) => (found.start, *style),
+                None => (range.end, *style),
+            };
+            spans.push(Span::styled(line[at..end].to_string(), style));
+            at = end;
+        }
+    }
+    spans
+}
+
+/// The rows of a diff on screen, laid out as `state.diff_layout` says in
+/// `width` columns. Removed lines are drawn in the danger colour and added
+/// ones in the success colour, with the words that changed set on it.
+fn diff_viewer_text(
+    state: &AppState,
+    diff: &TextDiff,
+    palette: &Palette,
+    line_limit: usize,
+    width: usize,
+) -> Text<'static> {
+    let selected_line = state
+        .search_results
+        .get(state.selected_result)
+        .and_then(|entry| state.match_line(entry));
+    let muted = Style::default().fg(palette.muted);
+
+    let lines: Vec<Line<'static>> = state
+        .diff_rows
+        .iter()
+        .enumerate()
+        .skip(state.content_scroll)
+        .take(line_limit)
+        .map(|(index, row)| {
+            let base = if selected_line == Some(index) {
+                Style::default().fg(palette.text).bg(palette.highlight_bg)
+            } else {
+                Style::default().fg(palette.text)
+            };
+            let mut spans = Vec::new();
+            match (state.diff_layout, row.left) {
+                (DiffLayout::Unified, Some(line)) => {
+                    let entry = &diff.lines[line];
+                    if state.line_numbers {
+                        let number = entry.new.or(entry.old).map_or(0, |number| number + 1);
+                        spans.push(Span::styled(format!("{number:>4} | "), muted));
+                    }
+                    spans.push(Span::styled(
+                        format!("{} ", entry.kind.marker()),
+                        diff_style(entry.kind, base, palette),
+                    ));
+                    spans.extend(diff_side_spans(state, diff, line, usize::MAX, base, palette).0);
+                }
+                _ => {
+                    let half = width.saturating_sub(3) / 2;
+                    let sides = [(row.left, true), (row.right, false)];
+                    for (position, (line, old)) in sides.into_iter().enumerate() {
+                        if position == 1 {
+                            spans.push(Span::styled(" │ ", muted));
+                        }
+                        let mut used = 0;
+                        if let Some(line) = line {
+                            let entry = &diff.lines[line];
+                            if state.line_numbers {
+                                let number = if old { entry.old } else { entry.new };
+                                let number = number.map_or(0, |number| number + 1);
+                                spans.push(Span::styled(format!("{number:>4} "), muted));
+                                used += 5;
+                            }
+                            let (text, chars) = diff_side_spans(
+                                state,
+                                diff,
+                                line,
+                                half.saturating_sub(used),
+                                base,
+                                palette,
+                            );
+                            spans.extend(text);
+                            used += chars;
+                        }
+                        if position == 0 {
+                            spans.push(Span::styled(" ".repeat(half.saturating_sub(used)), base));
+                        }
+                    }
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    Text::from(lines)
+}
+
+/// Diff line `line` cut to `max_chars` characters, coloured by how it
+/// changed, with the number of characters it takes.
+fn diff_side_spans(
+    state: &AppState,
+    diff: &TextDiff,
+    line: usize,
+    max_chars: usize,
+    base: Style,
+    palette: &Palette,
+) -> (Vec<Span<'static>>, usize) {
+    let text = diff.text(line);
+    let cut = text.char_indices().nth(max_chars).map_or(text.len(), |(at, _)| at);
+    let text = &text[..cut];
+    let entry = &diff.lines[line];
+    let style = diff_style(entry.kind, base, palette);
+    let changed = match entry.kind {
+        LineKind::Removed => style.fg(palette.background).bg(palette.danger),
+        _ => style.fg(palette.background).bg(palette.success),
+    };
+
+    let mut pieces = Vec::new();
+    let mut at = 0;
+    for range in &entry.changed {
+        let range = range.start.min(cut)..range.end.min(cut);
+        if range.start > at {
+            pieces.push((at..range.start, style));
+        }
+        if !range.is_empty() {
+            pieces.push((range.clone(), changed));
+        }
+        at = range.end;
+    }
+    if at < cut {
+        pieces.push((at..cut, style));
+    }
+    (match_spans(text, &pieces, state, palette), text.chars().count())
+}
+
+/// How a diff line of `kind` is drawn over `base`.
+fn diff_style(kind: LineKind, base: Style, palette: &Palette) -> Style {
+    match kind {
+        LineKind::Same => base,
+        LineKind::Removed => base.fg(palette.danger),
+        LineKind::Added => base.fg(palette.success),
+    }
+}
+
+/// How each kind of source token is drawn, in the active theme's colours.
+fn token_style(kind: TokenKind, palette: &Palette) -> Style {
+    match kind {
+        TokenKind::Plain => Style::default(),
+        TokenKind::Keyword => Style::default().fg(palette.accent).add_modifier(Modifier::BOLD),
+        TokenKind::Function => Style::default().fg(palette.accent_alt),
+        TokenKind::Number => Style::default().fg(palette.warning),
+        TokenKind::String => Style::default().fg(palette.success),
+        TokenKind::Comment => Style::default().fg(palette.muted).add_modifier(Modifier::ITALIC),
+    }
+}
+
 fn build_search_regex(
     query: &str,
     case_sensitive: bool,
@@ -1325,6 +2772,14 @@ fn on_off(value: bool) -> &'static str {
     }
 }
 
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, rect: Rect) -> Rect {
     let vertical = Layout::default()
         .direction(Direction::Vertical)
@@ -0,0 +1,248 @@
+//! PDF bookmarks: read from the `/Outlines` tree however each one names its
+//! page, nested inside the pages of the outline, and listed in the viewer's
+//! outline pane.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{screen_at, Fixture};
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use flerp::app::App;
+use flerp::app_structs::TAB_VIEWER;
+use flerp::outline::SegmentKind;
+use flerp::pdf_bookmarks::Bookmark;
+use flerp::pdf_doc;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+
+const TOPICS: [&str; 4] = ["Welcome", "Approach", "Fieldwork", "Findings"];
+const LINES_PER_PAGE: usize = 12;
+
+/// Four pages of twelve lines. The bookmarks reach their pages each a
+/// different way, and the last one's `/Next` loops back to the first.
+fn write_fixture(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+
+    let mut kids = Vec::new();
+    for topic in TOPICS {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+        ];
+        for line in 0..LINES_PER_PAGE {
+            let y = 740 - 16 * line as i64;
+            operations.push(Operation::new(
+                "Tm",
+                vec![1.into(), 0.into(), 0.into(), 1.into(), 72.into(), y.into()],
+            ));
+            operations.push(Operation::new(
+                "Tj",
+                vec![Object::string_literal(format!("{topic} note {}", line + 1))],
+            ));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        let content = Content { operations };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        kids.push(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            },
+        }));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids.iter().copied().map(Object::Reference).collect::<Vec<_>>(),
+        }),
+    );
+
+    let outlines_id = doc.new_object_id();
+    let ids: Vec<ObjectId> = (0..4).map(|_| doc.new_object_id()).collect();
+    let fit = |page: ObjectId| Object::Array(vec![page.into(), "Fit".into()]);
+    // Welcome and Approach are top level, Fieldwork nests under Approach.
+    let items = [
+        dictionary! {
+            "Title" => Object::string_literal("Welcome"),
+            "Parent" => outlines_id,
+            "Next" => ids[1],
+            "Dest" => fit(kids[0]),
+        },
+        dictionary! {
+            "Title" => Object::string_literal("Approach"),
+            "Parent" => outlines_id,
+            "Prev" => ids[0],
+            "Next" => ids[3],
+            "First" => ids[2],
+            "Last" => ids[2],
+            "A" => dictionary! { "S" => "GoTo", "D" => fit(kids[1]) },
+        },
+        dictionary! {
+            "Title" => Object::string_literal("Fieldwork"),
+            "Parent" => ids[1],
+            "Dest" => Object::string_literal("fieldwork"),
+        },
+        dictionary! {
+            "Title" => Object::string_literal("Findings"),
+            "Parent" => outlines_id,
+            "Prev" => ids[1],
+            "Next" => ids[0],
+            "Dest" => fit(kids[3]),
+        },
+    ];
+    for (id, item) in ids.iter().zip(items) {
+        doc.objects.insert(*id, Object::Dictionary(item));
+    }
+    doc.objects.insert(
+        outlines_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Outlines",
+            "First" => ids[0],
+            "Last" => ids[3],
+        }),
+    );
+
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+        "Outlines" => outlines_id,
+        "Names" => dictionary! {
+            "Dests" => dictionary! {
+                "Names" => vec![
+                    Object::string_literal("fieldwork"),
+                    dictionary! { "D" => fit(kids[2]) }.into(),
+                ],
+            },
+        },
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+/// The clicks below are placed for a terminal of this size.
+fn screen(app: &mut App) -> String {
+    screen_at(app, 110, 30)
+}
+
+fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+    MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    }
+}
+
+#[test]
+fn bookmarks_are_read_whichever_way_they_name_their_page() {
+    let fixture = Fixture::with("read.pdf", write_fixture);
+    let document = pdf_doc::load(fixture.as_str()).expect("the PDF loads");
+    assert_eq!(
+        document.bookmarks,
+        [
+            Bookmark {
+                title: "Welcome".into(),
+                level: 0,
+                page: 1
+            },
+            Bookmark {
+                title: "Approach".into(),
+                level: 0,
+                page: 2
+            },
+            Bookmark {
+                title: "Fieldwork".into(),
+                level: 1,
+                page: 3
+            },
+            Bookmark {
+                title: "Findings".into(),
+                level: 0,
+                page: 4
+            },
+        ],
+        "each is read once despite the loop"
+    );
+
+    let outline = document.outline();
+    assert_eq!(outline.top_level().len(), 4, "pages stay the top level");
+    let approach = outline
+        .segments()
+        .iter()
+        .find(|segment| segment.title == "Approach")
+        .expect("a heading per bookmark");
+    assert_eq!(approach.kind, SegmentKind::Heading);
+    assert_eq!(approach.level, 1);
+    assert_eq!(approach.start_line, LINES_PER_PAGE);
+    assert_eq!(
+        approach.line_count,
+        2 * LINES_PER_PAGE,
+        "through its child's page"
+    );
+    assert_eq!(
+        outline
+            .innermost_at(2 * LINES_PER_PAGE + 1)
+            .map(|segment| segment.title.as_str()),
+        Some("Fieldwork")
+    );
+}
+
+#[test]
+fn the_outline_pane_follows_the_text_and_jumps_on_click() {
+    let fixture = Fixture::with("pane.pdf", write_fixture);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the PDF loads");
+    app.state.current_tab = TAB_VIEWER;
+
+    let shown = screen(&mut app);
+    assert!(!shown.contains("Outline (4)"), "hidden until asked for");
+    app.handle_key(KeyCode::Char('t'));
+    assert!(app.state.outline_pane);
+    let shown = screen(&mut app);
+    assert!(
+        shown.contains("Outline (4)"),
+        "bookmarks rather than pages\n{shown}"
+    );
+    assert!(
+        shown.contains("│  Fieldwork"),
+        "nested one step in\n{shown}"
+    );
+    assert_eq!(app.state.outline_list_state.selected(), Some(0));
+
+    // Reading on into the third page highlights the bookmark nested there.
+    app.state.content_scroll = 2 * LINES_PER_PAGE + 3;
+    screen(&mut app);
+    assert_eq!(app.state.outline_list_state.selected(), Some(2));
+
+    // The pane's fourth row is Findings.
+    let pane = app.state.hit.outline;
+    app.handle_mouse(mouse(
+        MouseEventKind::Down(MouseButton::Left),
+        pane.x + 2,
+        pane.y + 3,
+    ));
+    assert_eq!(app.state.content_scroll, 3 * LINES_PER_PAGE);
+    assert_eq!(app.state.status_message, "Findings · 12 lines");
+    screen(&mut app);
+    assert_eq!(app.state.outline_list_state.selected(), Some(3));
+
+    // The wheel over the pane steps between its entries.
+    app.handle_mouse(mouse(MouseEventKind::ScrollUp, pane.x + 2, pane.y));
+    assert_eq!(app.state.content_scroll, 2 * LINES_PER_PAGE);
+
+    app.handle_key(KeyCode::Char('t'));
+    assert!(!screen(&mut app).contains("Outline (4)"));
+    assert_eq!(app.state.hit.outline.area(), 0, "no stale click target");
+}
@@ -0,0 +1,140 @@
+//! Helpers shared by the integration tests. Every test binary compiles its own
+//! copy of this module and most use only part of it.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use flerp::app::App;
+use flerp::headless::HeadlessRequest;
+use flerp::media::MediaRenderer;
+use flerp::text_analysis::SearchOptions;
+use flerp::ui_components::ui;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::Terminal;
+
+/// The terminal the UI tests draw on, unless a test needs another size.
+pub const WIDTH: u16 = 120;
+pub const HEIGHT: u16 = 40;
+
+/// A file in the system temp directory that is removed when dropped.
+///
+/// The name is prefixed with the process id, so parallel test binaries never
+/// share a file; within one binary each test picks its own `name`. Keep the
+/// extension on `name` when the loader goes by it.
+pub struct Fixture {
+    pub path: PathBuf,
+}
+
+impl Fixture {
+    /// A fixture holding `contents`.
+    pub fn write(name: &str, contents: impl AsRef<[u8]>) -> Self {
+        Self::with(name, |path| {
+            fs::write(path, contents).expect("fixture should write");
+        })
+    }
+
+    /// A fixture that `write` puts on disk.
+    pub fn with(name: &str, write: impl FnOnce(&PathBuf)) -> Self {
+        let path = std::env::temp_dir().join(format!("flerp-{}-{name}", std::process::id()));
+        write(&path);
+        Self { path }
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.path.to_str().expect("utf-8 fixture path")
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// An output directory in the system temp directory, removed with everything
+/// in it when dropped. It starts out missing, for the code under test to
+/// create.
+pub struct TempDir {
+    pub path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("flerp-out-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        Self { path }
+    }
+
+    /// Names of the files written into the directory, sorted.
+    pub fn files(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.path)
+            .expect("output directory exists")
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A headless run over `file` as the command line asks for it with no other
+/// flags, so searches are case-sensitive.
+pub fn request(file: &str) -> HeadlessRequest {
+    HeadlessRequest {
+        file: file.to_string(),
+        search_options: SearchOptions {
+            case_sensitive: true,
+            ..SearchOptions::default()
+        },
+        ..HeadlessRequest::default()
+    }
+}
+
+/// [`request`] with `--json`.
+pub fn json_request(file: &str) -> HeadlessRequest {
+    HeadlessRequest {
+        json: true,
+        ..request(file)
+    }
+}
+
+/// The whole UI drawn once on a `width` by `height` test terminal.
+pub fn draw_at(app: &mut App, width: u16, height: u16) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let mut media = MediaRenderer::halfblocks();
+    terminal
+        .draw(|frame| ui(frame, &mut app.state, &mut media))
+        .unwrap();
+    terminal.backend().buffer().clone()
+}
+
+pub fn draw(app: &mut App) -> Buffer {
+    draw_at(app, WIDTH, HEIGHT)
+}
+
+/// Each row of `buffer` as text, with the cells it was drawn from.
+pub fn rows(buffer: &Buffer) -> impl Iterator<Item = (String, &[Cell])> {
+    buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| (row.iter().map(|cell| cell.symbol()).collect(), row))
+}
+
+/// The UI as text, one line per terminal row.
+pub fn screen_at(app: &mut App, width: u16, height: u16) -> String {
+    rows(&draw_at(app, width, height))
+        .map(|(text, _)| text)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn screen(app: &mut App) -> String {
+    screen_at(app, WIDTH, HEIGHT)
+}
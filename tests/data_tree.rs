@@ -0,0 +1,220 @@
+//! JSON, YAML and TOML files: shown as a tree that folds, named by paths,
+//! searched by key or value, and still printed as written by `--text`.
+
+mod common;
+
+use common::{screen, Fixture};
+use crossterm::event::KeyModifiers;
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use flerp::app::App;
+use flerp::app_structs::{InputMode, TAB_SEARCH, TAB_VIEWER};
+use flerp::headless::{run, HeadlessRequest};
+
+const CONFIG: &str = r#"{
+  "name": "flerp",
+  "server": {
+    "host": "localhost",
+    "ports": [8080, 8443],
+    "tls": {"cert": "/etc/flerp.pem"}
+  },
+  "users": [
+    {"name": "ada", "admin": true},
+    {"name": "brian", "admin": false}
+  ]
+}
+"#;
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        app.handle_key(KeyCode::Char(c));
+    }
+}
+
+fn lines(app: &App) -> Vec<&str> {
+    app.state.file_content.lines().collect()
+}
+
+#[test]
+fn the_viewer_folds_and_unfolds_nodes() {
+    let fixture = Fixture::write("fold.json", CONFIG);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the file loads");
+    assert!(
+        app.state
+            .status_message
+            .contains("JSON with 15 keys and values"),
+        "{}",
+        app.state.status_message
+    );
+    assert_eq!(
+        lines(&app)[..3],
+        [
+            "  name: \"flerp\"",
+            "▾ server: {3 keys}",
+            "    host: \"localhost\"",
+        ]
+    );
+
+    // Enter folds the node at the top of the viewer.
+    app.state.current_tab = TAB_VIEWER;
+    app.state.content_scroll = 1;
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(app.state.status_message, "Folded server.");
+    assert_eq!(
+        lines(&app)[1..3],
+        ["▸ server: {3 keys}", "▾ users: [2 items]"]
+    );
+    // The file fits on screen, so the viewer settles back at the top.
+    assert!(screen(&mut app).contains("Path name"));
+
+    // A click on a row folds that row.
+    let viewer = app.state.hit.viewer;
+    app.handle_mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: viewer.x + 10,
+        row: viewer.y + 2,
+        modifiers: KeyModifiers::NONE,
+    });
+    assert_eq!(app.state.status_message, "Folded users.");
+    assert_eq!(lines(&app).len(), 3);
+
+    app.handle_key(KeyCode::Char('e'));
+    assert_eq!(lines(&app).len(), 15);
+    app.handle_key(KeyCode::Char('E'));
+    assert_eq!(
+        lines(&app),
+        [
+            "  name: \"flerp\"",
+            "▸ server: {3 keys}",
+            "▸ users: [2 items]"
+        ]
+    );
+}
+
+#[test]
+fn a_path_query_opens_the_way_to_its_node() {
+    let fixture = Fixture::write("path.json", CONFIG);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the file loads");
+    app.handle_key(KeyCode::Char('E'));
+
+    app.handle_key(KeyCode::Char('P'));
+    assert_eq!(app.state.input_mode, InputMode::PathQuery);
+    type_text(&mut app, "users[1].admin");
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(app.state.status_message, "users[1].admin · false");
+    assert_eq!(app.state.current_tab, TAB_VIEWER);
+    assert!(lines(&app).contains(&"      admin: false"));
+    assert!(
+        lines(&app).contains(&"▸ server: {3 keys}"),
+        "other folds stay"
+    );
+
+    app.handle_key(KeyCode::Char('P'));
+    type_text(&mut app, "server.ports[5]");
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(
+        app.state.status_message,
+        "Nothing at server.ports[5] in this file."
+    );
+}
+
+#[test]
+fn searches_reach_keys_and_values_under_folds() {
+    let fixture = Fixture::write(
+        "search.yaml",
+        "server:\n  host: localhost\n  tls:\n    cert: /etc/flerp.pem\nname: flerp\n",
+    );
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the file loads");
+    assert_eq!(
+        app.state.tree.as_ref().map(|tree| tree.format.label()),
+        Some("YAML")
+    );
+    app.handle_key(KeyCode::Char('E'));
+
+    app.state.case_sensitive = false;
+    app.set_search_query("cert");
+    let found: Vec<&str> = app
+        .state
+        .search_results
+        .iter()
+        .map(|entry| entry.line.as_str())
+        .collect();
+    assert_eq!(found, ["server.tls.cert = \"/etc/flerp.pem\""]);
+    assert_eq!(
+        app.state.match_line(&app.state.search_results[0]),
+        None,
+        "folded away"
+    );
+
+    app.state.current_tab = TAB_SEARCH;
+    app.handle_key(KeyCode::Char('n'));
+    assert_eq!(
+        app.state.status_message,
+        "Jumped to server.tls.cert from search results."
+    );
+    assert_eq!(app.state.match_line(&app.state.search_results[0]), Some(3));
+    assert_eq!(lines(&app)[3], "      cert: \"/etc/flerp.pem\"");
+
+    app.set_search_query("flerp");
+    assert_eq!(app.state.search_results.len(), 2, "values match too");
+}
+
+#[test]
+fn a_file_that_does_not_parse_is_shown_as_text() {
+    let fixture = Fixture::write("broken.toml", "[server\nhost = 1\n");
+    let mut app = App::new();
+    app.load_file(fixture.as_str())
+        .expect("the file still loads");
+    assert!(app.state.tree.is_none());
+    assert_eq!(app.state.file_content, "[server\nhost = 1\n");
+    assert!(
+        app.state.status_message.contains("Could not read as TOML"),
+        "{}",
+        app.state.status_message
+    );
+    app.handle_key(KeyCode::Char('P'));
+    assert_eq!(app.state.input_mode, InputMode::Normal);
+}
+
+#[test]
+fn large_trees_open_folded() {
+    let items: Vec<String> = (0..300).map(|n| format!("{{\"id\": {n}}}")).collect();
+    let fixture = Fixture::write(
+        "large.json",
+        format!("{{\"items\": [{}]}}", items.join(", ")),
+    );
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the file loads");
+    assert_eq!(lines(&app), ["▸ items: [300 items]"]);
+}
+
+#[test]
+fn headless_prints_the_source_and_describes_the_tree() {
+    let fixture = Fixture::write(
+        "headless.toml",
+        "title = \"demo\"\n\n[owner]\nname = \"ada\"\nborn = 1815-12-10\n",
+    );
+    let mut request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        text: true,
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    assert!(String::from_utf8(buffer)
+        .unwrap()
+        .starts_with("title = \"demo\"\n\n[owner]"));
+
+    request.text = false;
+    request.json = true;
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+    assert_eq!(value["kind"], "toml");
+    assert_eq!(value["tree"]["format"], "toml");
+    assert_eq!(value["tree"]["nodes"], 4);
+    assert_eq!(value["tree"]["depth"], 2);
+    assert_eq!(value["tree"]["keys"], serde_json::json!(["title", "owner"]));
+}
@@ -3,82 +3,21 @@
 //! These run without a terminal on purpose: the point of headless mode is that
 //! it works where the TUI cannot.
 
-use std::fs;
-use std::path::PathBuf;
+mod common;
 
+use common::{request, Fixture, TempDir};
 use flerp::headless::{run, HeadlessRequest};
-use flerp::text_analysis::SearchOptions;
 use image::{DynamicImage, RgbImage};
 use serde_json::Value;
 
 const SAMPLE: &str = "alpha beta gamma\nbeta gamma\nrepeated line\nrepeated line\n";
 
-/// A temp file that cleans up after itself, so a failing assertion cannot leave
-/// litter behind in the temp directory.
-struct Fixture {
-    path: PathBuf,
-}
-
-impl Fixture {
-    fn text(name: &str, contents: &str) -> Self {
-        let path =
-            std::env::temp_dir().join(format!("flerp-headless-{name}-{}.txt", std::process::id()));
-        fs::write(&path, contents).expect("write fixture");
-        Self { path }
-    }
-
-    fn image(name: &str) -> Self {
-        let path =
-            std::env::temp_dir().join(format!("flerp-headless-{name}-{}.png", std::process::id()));
+/// A 4x3 PNG, for the paths that need an image rather than text.
+fn image_fixture(name: &str) -> Fixture {
+    Fixture::with(&format!("{name}.png"), |path| {
         let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 3, image::Rgb([10, 20, 30])));
-        image.save(&path).expect("write image fixture");
-        Self { path }
-    }
-
-    fn as_str(&self) -> &str {
-        self.path.to_str().expect("utf-8 fixture path")
-    }
-}
-
-impl Drop for Fixture {
-    fn drop(&mut self) {
-        let _ = fs::remove_file(&self.path);
-    }
-}
-
-struct TempDir {
-    path: PathBuf,
-}
-
-impl TempDir {
-    fn new(name: &str) -> Self {
-        let path =
-            std::env::temp_dir().join(format!("flerp-headless-{name}-{}", std::process::id()));
-        let _ = fs::remove_dir_all(&path);
-        Self { path }
-    }
-}
-
-impl Drop for TempDir {
-    fn drop(&mut self) {
-        let _ = fs::remove_dir_all(&self.path);
-    }
-}
-
-fn request(file: &str) -> HeadlessRequest {
-    HeadlessRequest {
-        file: file.to_string(),
-        json: false,
-        text: false,
-        extract_images: None,
-        search: None,
-        search_options: SearchOptions {
-            case_sensitive: true,
-            regex_mode: false,
-            whole_word: false,
-        },
-        keyword_limit: 10,
-    }
+        image.save(path).expect("write image fixture");
+    })
 }
 
 fn capture(request: &HeadlessRequest) -> String {
@@ -89,7 +28,7 @@ fn capture(request: &HeadlessRequest) -> String {
 
 #[test]
 fn json_mode_emits_one_parseable_object() {
-    let fixture = Fixture::text("json", SAMPLE);
+    let fixture = Fixture::write("json.txt", SAMPLE);
     let mut request = request(fixture.as_str());
     request.json = true;
 
@@ -107,7 +46,7 @@ fn json_mode_emits_one_parseable_object() {
 
 #[test]
 fn json_mode_reports_search_matches_with_counts() {
-    let fixture = Fixture::text("search", SAMPLE);
+    let fixture = Fixture::write("search.txt", SAMPLE);
     let mut request = request(fixture.as_str());
     request.json = true;
     request.search = Some("beta".to_string());
@@ -125,7 +64,7 @@ fn json_mode_reports_search_matches_with_counts() {
 
 #[test]
 fn text_mode_writes_the_content_verbatim() {
-    let fixture = Fixture::text("text", SAMPLE);
+    let fixture = Fixture::write("text.txt", SAMPLE);
     let mut request = request(fixture.as_str());
     request.text = true;
 
@@ -134,7 +73,7 @@ fn text_mode_writes_the_content_verbatim() {
 
 #[test]
 fn plain_mode_prints_grep_style_match_lines() {
-    let fixture = Fixture::text("grep", SAMPLE);
+    let fixture = Fixture::write("grep.txt", SAMPLE);
     let mut request = request(fixture.as_str());
     request.search = Some("gamma".to_string());
 
@@ -148,7 +87,7 @@ fn plain_mode_prints_grep_style_match_lines() {
 
 #[test]
 fn search_options_are_honoured() {
-    let fixture = Fixture::text("options", "Alpha\nalpha\n");
+    let fixture = Fixture::write("options.txt", "Alpha\nalpha\n");
     let mut request = request(fixture.as_str());
     request.search = Some("alpha".to_string());
 
@@ -160,7 +99,7 @@ fn search_options_are_honoured() {
 
 #[test]
 fn an_invalid_regex_surfaces_as_an_error_not_a_panic() {
-    let fixture = Fixture::text("badregex", SAMPLE);
+    let fixture = Fixture::write("badregex.txt", SAMPLE);
     let mut request = request(fixture.as_str());
     request.search = Some("(unclosed".to_string());
     request.search_options.regex_mode = true;
@@ -171,7 +110,7 @@ fn an_invalid_regex_surfaces_as_an_error_not_a_panic() {
 
 #[test]
 fn extract_images_writes_png_files_and_reports_them() {
-    let fixture = Fixture::image("extract");
+    let fixture = image_fixture("extract");
     let directory = TempDir::new("extract-out");
     let mut request = request(fixture.as_str());
     request.extract_images = Some(directory.path.clone());
@@ -189,7 +128,7 @@ fn extract_images_writes_png_files_and_reports_them() {
 
 #[test]
 fn extracting_from_an_image_file_also_lands_in_json() {
-    let fixture = Fixture::image("json-extract");
+    let fixture = image_fixture("json-extract");
     let directory = TempDir::new("json-extract-out");
     let mut request = request(fixture.as_str());
     request.json = true;
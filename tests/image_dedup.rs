@@ -0,0 +1,293 @@
+//! The same picture embedded as separate objects: recognised by its pixels,
+//! listed once in the Media tab with the pages it is on, and written once by
+//! `--extract-images --unique-images`.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{json_request, Fixture, TempDir};
+use flerp::app::App;
+use flerp::headless::run;
+use flerp::pdf_doc;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// A two-tone banner with a diagonal edge, drawn at any size, so a rescaled
+/// copy has the same picture in different samples.
+fn logo(width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let navy = x * height < y * width * 2;
+            pixels.extend_from_slice(if navy {
+                &[20, 30, 110]
+            } else {
+                &[240, 200, 40]
+            });
+        }
+    }
+    pixels
+}
+
+/// Vertical stripes in other colours: a different picture altogether.
+fn photo(width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = Vec::new();
+    for _ in 0..height {
+        for x in 0..width {
+            pixels.extend_from_slice(if x % 8 < 4 {
+                &[10, 160, 60]
+            } else {
+                &[200, 60, 160]
+            });
+        }
+    }
+    pixels
+}
+
+/// A scanned page of text, lit unevenly the way a flatbed scan is: the same
+/// margins, line spacing and shading whatever the seed, with words of
+/// different lengths on each line. Greyscale, `SCAN_WIDTH` by `SCAN_HEIGHT`.
+fn scanned_page(seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    let mut next = move |bound: u32| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % u64::from(bound)) as u32
+    };
+    let mut pixels: Vec<u8> = (0..SCAN_WIDTH * SCAN_HEIGHT)
+        .map(|offset| (150 + offset % SCAN_WIDTH * 100 / SCAN_WIDTH) as u8)
+        .collect();
+    for line in 0..30 {
+        let top = 50 + line * 15;
+        let mut x = 45;
+        while x < 380 {
+            let word = 8 + next(35);
+            for dx in 0..word.min(380 - x) {
+                // Letters: two dark columns and a gap.
+                if dx % 3 == 2 {
+                    continue;
+                }
+                for dy in 0..7 {
+                    if next(5) > 0 {
+                        pixels[((top + dy) * SCAN_WIDTH + x + dx) as usize] = 30;
+                    }
+                }
+            }
+            x += word + 6;
+        }
+    }
+    pixels
+}
+
+const SCAN_WIDTH: u32 = 425;
+const SCAN_HEIGHT: u32 = 550;
+
+fn image_object(doc: &mut Document, width: u32, height: u32, pixels: Vec<u8>) -> Object {
+    let colour_space = if pixels.len() == (width * height) as usize {
+        "DeviceGray"
+    } else {
+        "DeviceRGB"
+    };
+    let mut stream = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width as i64,
+            "Height" => height as i64,
+            "ColorSpace" => colour_space,
+            "BitsPerComponent" => 8,
+        },
+        pixels,
+    );
+    stream.compress().expect("image stream should compress");
+    Object::Reference(doc.add_object(stream))
+}
+
+/// Three pages, each embedding its own copy of the logo (page two's at twice
+/// the resolution); page three also has a photo.
+fn write_fixture(path: &PathBuf) {
+    write_pages(path, 3, |doc, number| {
+        let scale = if number == 2 { 2 } else { 1 };
+        let mut xobjects = dictionary! {
+            "Logo" => image_object(doc, 32 * scale, 16 * scale, logo(32 * scale, 16 * scale)),
+        };
+        if number == 3 {
+            xobjects.set("Photo", image_object(doc, 24, 24, photo(24, 24)));
+        }
+        xobjects
+    });
+}
+
+/// Two pages, each a scan of the same form with different text on it.
+fn write_scans(path: &PathBuf) {
+    write_pages(path, 2, |doc, number| {
+        dictionary! {
+            "Scan" => image_object(doc, SCAN_WIDTH, SCAN_HEIGHT, scanned_page(number as u64)),
+        }
+    });
+}
+
+/// `count` pages with a line of text each, and the images `xobjects` makes
+/// for page `number`.
+fn write_pages(
+    path: &PathBuf,
+    count: usize,
+    xobjects: impl Fn(&mut Document, usize) -> lopdf::Dictionary,
+) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+
+    let mut page_ids = Vec::new();
+    for number in 1..=count {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 700.into()]),
+                Operation::new(
+                    "Tj",
+                    vec![Object::string_literal(format!("Page {number} body"))],
+                ),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+        let xobjects = xobjects(&mut doc, number);
+
+        page_ids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+                "XObject" => xobjects,
+            },
+        })));
+    }
+
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => page_ids,
+            "Count" => count as i64,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+#[test]
+fn separate_copies_of_a_picture_point_at_the_first_one() {
+    let fixture = Fixture::with("document.pdf", write_fixture);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    assert_eq!(document.images.len(), 4, "every copy is still decoded");
+    let logos: Vec<&pdf_doc::PdfImageAsset> = document
+        .images
+        .iter()
+        .filter(|asset| asset.width != 24)
+        .collect();
+    assert_eq!(logos[0].duplicate_of, None);
+    assert!(logos[1..].iter().all(|asset| asset.duplicate_of == Some(0)));
+
+    let photo = document
+        .images
+        .iter()
+        .position(|asset| asset.width == 24)
+        .unwrap();
+    assert_eq!(
+        document.images[photo].duplicate_of, None,
+        "a different picture"
+    );
+    assert_eq!(document.image_copy_pages(photo), [3]);
+    assert_eq!(document.image_copy_pages(1), [1, 2, 3]);
+}
+
+#[test]
+fn scans_of_one_form_with_different_text_are_both_listed() {
+    let fixture = Fixture::with("scans.pdf", write_scans);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+    assert_eq!(
+        document.images[0].hash, document.images[1].hash,
+        "the small hash cannot tell them apart"
+    );
+    assert_eq!(document.images[1].duplicate_of, None);
+
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+    let pages: Vec<Option<usize>> = app.state.media.iter().map(|item| item.page).collect();
+    assert_eq!(pages, [Some(1), Some(2)]);
+}
+
+#[test]
+fn the_media_tab_lists_each_picture_once_with_its_pages() {
+    let fixture = Fixture::with("tui.pdf", write_fixture);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+
+    assert_eq!(app.state.media.len(), 2);
+    let logo = &app.state.media[0];
+    assert_eq!(logo.copies, [1, 2, 3]);
+    assert_eq!(logo.copies_label().as_deref(), Some("×3 on pages 1, 2, 3"));
+    assert_eq!(app.state.media[1].copies_label(), None);
+}
+
+#[test]
+fn extraction_writes_every_copy_unless_asked_for_unique_images() {
+    let fixture = Fixture::with("extract.pdf", write_fixture);
+
+    let all = TempDir::new("all");
+    let mut every = json_request(fixture.as_str());
+    every.extract_images = Some(all.path.clone());
+    let mut buffer = Vec::new();
+    run(&every, &mut buffer).expect("headless run");
+    assert_eq!(all.files().len(), 4);
+
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+    let images = value["images"].as_array().expect("images array");
+    assert_eq!(images.len(), 2, "the JSON lists pictures, not copies");
+    assert_eq!(images[0]["copies"], 3);
+    assert_eq!(images[0]["pages"], serde_json::json!([1, 2, 3]));
+    assert_eq!(images[1]["copies"], 1);
+    assert_eq!(images[1]["pages"], serde_json::json!([3]));
+
+    // The photo is the fourth image in the document, and both the JSON and
+    // the file name number it so.
+    assert_eq!(images[1]["index"], 3);
+    let written = value["extracted_images"].as_array().expect("written files");
+    let photo = written
+        .iter()
+        .find(|entry| entry["index"] == 3)
+        .expect("the photo was written");
+    assert!(
+        photo["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("page-003-img-04.png"),
+        "{photo}"
+    );
+
+    let unique = TempDir::new("unique");
+    let mut once = json_request(fixture.as_str());
+    once.extract_images = Some(unique.path.clone());
+    once.image_export.unique = true;
+    run(&once, &mut Vec::new()).expect("headless run");
+    let names = unique.files();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].starts_with("page-001-"), "{names:?}");
+    assert!(names[1].starts_with("page-003-"), "{names:?}");
+}
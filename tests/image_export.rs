@@ -0,0 +1,287 @@
+//! `--extract-images` options: the format images are saved in, skipping small
+//! ones, limiting them to a page range, naming the files, and writing JPEGs
+//! exactly as the PDF stores them.
+
+mod common;
+
+use std::fs;
+use std::io::Cursor;
+
+use common::{json_request, Fixture, TempDir};
+use flerp::cli_values::{parse_min_size, parse_name_template, ImageFileFormat};
+use flerp::headless::{run, HeadlessRequest, ImageExport};
+use image::codecs::jpeg::JpegEncoder;
+use image::{ImageEncoder, Rgb, RgbImage};
+use lopdf::{dictionary, Document, Object, Stream};
+
+fn jpeg() -> Vec<u8> {
+    let pixels = RgbImage::from_fn(40, 20, |x, _| {
+        if x < 20 {
+            Rgb([200, 30, 30])
+        } else {
+            Rgb([30, 30, 200])
+        }
+    });
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(Cursor::new(&mut bytes), 90)
+        .write_image(&pixels, 40, 20, image::ExtendedColorType::Rgb8)
+        .expect("fixture JPEG should encode");
+    bytes
+}
+
+fn raw_image(doc: &mut Document, size: u32, rgb: [u8; 3], mask: Option<Object>) -> Object {
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => size as i64,
+        "Height" => size as i64,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+    };
+    if let Some(mask) = mask {
+        dict.set("SMask", mask);
+    }
+    let pixels = rgb.repeat((size * size) as usize);
+    Object::Reference(doc.add_object(Stream::new(dict, pixels)))
+}
+
+/// A mask hiding the top half of a `size` square.
+fn top_hidden(doc: &mut Document, size: u32) -> Object {
+    let mask: Vec<u8> = (0..size)
+        .flat_map(|y| (0..size).map(move |_| if y < size / 2 { 0 } else { 255 }))
+        .collect();
+    Object::Reference(doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => size as i64,
+            "Height" => size as i64,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+        },
+        mask,
+    )))
+}
+
+/// Page one has a JPEG photo and a 4x4 icon; page two a green square whose
+/// top half is see-through.
+fn pdf() -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let photo = Object::Reference(doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 40,
+            "Height" => 20,
+            "ColorSpace" => "DeviceRGB",
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+        },
+        jpeg(),
+    )));
+    let icon = raw_image(&mut doc, 4, [0, 0, 0], None);
+    let mask = top_hidden(&mut doc, 30);
+    let square = raw_image(&mut doc, 30, [0, 160, 0], Some(mask));
+
+    let pages: Vec<Object> = [
+        (
+            b"q 40 0 0 20 100 100 cm /Photo Do Q q 4 0 0 4 10 10 cm /Icon Do Q".to_vec(),
+            dictionary! { "Photo" => photo, "Icon" => icon },
+        ),
+        (
+            b"q 30 0 0 30 100 100 cm /Square Do Q".to_vec(),
+            dictionary! { "Square" => square },
+        ),
+    ]
+    .into_iter()
+    .map(|(content, xobjects)| {
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! { "XObject" => xobjects },
+        }))
+    })
+    .collect();
+
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => pages.len() as i64,
+            "Kids" => pages,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).expect("fixture PDF should save");
+    bytes
+}
+
+fn request(file: &str, directory: &TempDir, image_export: ImageExport) -> HeadlessRequest {
+    HeadlessRequest {
+        extract_images: Some(directory.path.clone()),
+        image_export,
+        ..json_request(file)
+    }
+}
+
+fn extract(request: &HeadlessRequest) -> serde_json::Value {
+    let mut buffer = Vec::new();
+    run(request, &mut buffer).expect("headless run");
+    serde_json::from_slice(&buffer).expect("valid JSON")
+}
+
+#[test]
+fn images_are_re_encoded_in_the_chosen_format() {
+    let fixture = Fixture::write("formats.pdf", pdf());
+
+    let out = TempDir::new("jpeg");
+    let export = ImageExport {
+        format: ImageFileFormat::Jpeg,
+        ..ImageExport::default()
+    };
+    extract(&request(fixture.as_str(), &out, export));
+    assert_eq!(
+        out.files(),
+        [
+            "page-001-img-01.jpg",
+            "page-001-img-02.jpg",
+            "page-002-img-03.jpg"
+        ]
+    );
+    // JPEG has no alpha: the hidden half of the square turns white.
+    let square = image::open(out.path.join("page-002-img-03.jpg"))
+        .expect("a readable JPEG")
+        .to_rgb8();
+    let (hidden, shown) = (square.get_pixel(15, 5).0, square.get_pixel(15, 25).0);
+    assert!(hidden.iter().all(|channel| *channel > 240), "{hidden:?}");
+    assert!(shown[1] > 120 && shown[0] < 40, "{shown:?}");
+
+    let out = TempDir::new("webp");
+    let export = ImageExport {
+        format: ImageFileFormat::Webp,
+        ..ImageExport::default()
+    };
+    extract(&request(fixture.as_str(), &out, export));
+    let square = image::open(out.path.join("page-002-img-03.webp")).expect("a readable WebP");
+    assert_eq!(
+        square.to_rgba8().get_pixel(15, 5).0[3],
+        0,
+        "WebP keeps alpha"
+    );
+}
+
+#[test]
+fn small_images_and_other_pages_are_left_out() {
+    let fixture = Fixture::write("filters.pdf", pdf());
+
+    let out = TempDir::new("min-size");
+    let export = ImageExport {
+        min_size: parse_min_size("16").unwrap(),
+        ..ImageExport::default()
+    };
+    extract(&request(fixture.as_str(), &out, export));
+    assert_eq!(
+        out.files(),
+        ["page-001-img-01.png", "page-002-img-03.png"],
+        "the 4x4 icon is skipped; the numbering is unchanged"
+    );
+
+    let out = TempDir::new("wide");
+    let export = ImageExport {
+        min_size: parse_min_size("35x10").unwrap(),
+        ..ImageExport::default()
+    };
+    extract(&request(fixture.as_str(), &out, export));
+    assert_eq!(out.files(), ["page-001-img-01.png"]);
+
+    let out = TempDir::new("range");
+    let mut second_page = request(fixture.as_str(), &out, ImageExport::default());
+    second_page.page_range = Some(2..=2);
+    let value = extract(&second_page);
+    assert_eq!(out.files(), ["page-002-img-03.png"]);
+    assert_eq!(value["extracted_images"][0]["page"], 2);
+}
+
+#[test]
+fn jpegs_can_be_written_as_stored_and_files_named_by_template() {
+    let fixture = Fixture::write("original.pdf", pdf());
+    let out = TempDir::new("original");
+    let export = ImageExport {
+        format: ImageFileFormat::Webp,
+        name_template: Some(
+            parse_name_template("{name}-p{page}-{index}-{width}x{height}.{ext}").unwrap(),
+        ),
+        original: true,
+        ..ImageExport::default()
+    };
+    let value = extract(&request(fixture.as_str(), &out, export));
+
+    let stem = fixture
+        .path
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let photo = format!("{stem}-p001-01-40x20.jpg");
+    assert_eq!(
+        out.files(),
+        [
+            photo.clone(),
+            format!("{stem}-p001-02-4x4.webp"),
+            format!("{stem}-p002-03-30x30.webp"),
+        ]
+    );
+    assert_eq!(
+        fs::read(out.path.join(&photo)).unwrap(),
+        jpeg(),
+        "byte for byte"
+    );
+
+    let written = value["extracted_images"]
+        .as_array()
+        .expect("written images");
+    assert_eq!(written[0]["format"], "jpg");
+    assert_eq!(written[0]["original"], true);
+    assert_eq!(
+        written[1]["original"], false,
+        "raw samples have no file format of their own"
+    );
+    assert_eq!(written[1]["format"], "webp");
+}
+
+#[test]
+fn an_image_file_is_copied_as_is() {
+    let fixture = Fixture::write("photo.jpg", jpeg());
+    let out = TempDir::new("file");
+    let export = ImageExport {
+        original: true,
+        ..ImageExport::default()
+    };
+    extract(&request(fixture.as_str(), &out, export));
+    assert_eq!(out.files(), ["image-01.jpg"]);
+    assert_eq!(fs::read(out.path.join("image-01.jpg")).unwrap(), jpeg());
+}
+
+#[test]
+fn bad_templates_and_sizes_are_rejected() {
+    assert!(
+        parse_name_template("page-{page}.{ext}").is_err(),
+        "no {{index}}"
+    );
+    assert!(parse_name_template("../{index}.png").is_err());
+    assert!(parse_name_template("{index}-{colour}.png").is_err());
+    assert!(parse_name_template("{index}.{ext}").is_ok());
+    assert_eq!(parse_min_size("64x32"), Ok((64, 32)));
+    assert_eq!(parse_min_size("48"), Ok((48, 48)));
+    assert!(parse_min_size("big").is_err());
+}
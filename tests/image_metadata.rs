@@ -0,0 +1,266 @@
+//! Photo metadata: EXIF camera, capture time, GPS and orientation plus the ICC
+//! profile name, read from JPEG files and from JPEGs embedded in PDFs, shown
+//! in the Media tab and reported by `--json`. Sideways photos are turned
+//! upright before they are drawn.
+
+mod common;
+
+use std::io::Cursor;
+
+use common::Fixture;
+use flerp::app::App;
+use flerp::headless::{run, HeadlessRequest};
+use flerp::pdf_doc;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ImageEncoder, Rgb, RgbImage};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// Stored 40x20 with red on the left, blue on the right, and tagged to be
+/// turned a quarter clockwise for display.
+const STORED: (u32, u32) = (40, 20);
+
+/// Tag, TIFF type, count and big-endian value bytes.
+type Field = (u16, u16, u32, Vec<u8>);
+
+fn ascii(tag: u16, text: &str) -> Field {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.push(0);
+    (tag, 2, bytes.len() as u32, bytes)
+}
+
+fn rationals(tag: u16, values: &[(u32, u32)]) -> Field {
+    let bytes = values
+        .iter()
+        .flat_map(|(numerator, denominator)| {
+            [numerator.to_be_bytes(), denominator.to_be_bytes()].concat()
+        })
+        .collect();
+    (tag, 5, values.len() as u32, bytes)
+}
+
+fn long(tag: u16, value: u32) -> Field {
+    (tag, 4, 1, value.to_be_bytes().to_vec())
+}
+
+/// Append one directory, with the values that do not fit in an entry right
+/// after it, and return where it starts.
+fn write_directory(out: &mut Vec<u8>, fields: &[Field]) -> u32 {
+    let start = out.len();
+    let mut data_offset = start + 2 + fields.len() * 12 + 4;
+    let mut data = Vec::new();
+    out.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+    for (tag, kind, count, bytes) in fields {
+        out.extend_from_slice(&tag.to_be_bytes());
+        out.extend_from_slice(&kind.to_be_bytes());
+        out.extend_from_slice(&count.to_be_bytes());
+        if bytes.len() <= 4 {
+            let mut inline = bytes.clone();
+            inline.resize(4, 0);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&(data_offset as u32).to_be_bytes());
+            data.extend_from_slice(bytes);
+            data_offset += bytes.len();
+        }
+    }
+    out.extend_from_slice(&0u32.to_be_bytes());
+    out.extend_from_slice(&data);
+    start as u32
+}
+
+/// A big-endian EXIF block. The sub-directories are written first so their
+/// offsets are known when the first directory points at them.
+fn exif() -> Vec<u8> {
+    let mut out = b"MM\0\x2a\0\0\0\0".to_vec();
+    let exif_ifd = write_directory(
+        &mut out,
+        &[
+            rationals(0x829a, &[(1, 250)]),
+            rationals(0x829d, &[(28, 10)]),
+            (0x8827, 3, 1, 400u16.to_be_bytes().to_vec()),
+            ascii(0x9003, "2024:05:01 10:22:03"),
+            rationals(0x920a, &[(35, 1)]),
+        ],
+    );
+    let gps_ifd = write_directory(
+        &mut out,
+        &[
+            ascii(1, "N"),
+            rationals(2, &[(52, 1), (22, 1), (2290, 100)]),
+            ascii(3, "W"),
+            rationals(4, &[(4, 1), (53, 1), (2820, 100)]),
+        ],
+    );
+    let first = write_directory(
+        &mut out,
+        &[
+            ascii(0x010f, "Fujifilm"),
+            ascii(0x0110, "X-T30"),
+            (0x0112, 3, 1, 6u16.to_be_bytes().to_vec()),
+            long(0x8769, exif_ifd),
+            long(0x8825, gps_ifd),
+        ],
+    );
+    out[4..8].copy_from_slice(&first.to_be_bytes());
+    out
+}
+
+/// A version 4 ICC profile holding only its description.
+fn icc_profile(description: &str) -> Vec<u8> {
+    let text: Vec<u8> = description
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes)
+        .collect();
+    let mut tag = b"mluc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&1u32.to_be_bytes());
+    tag.extend_from_slice(&12u32.to_be_bytes());
+    tag.extend_from_slice(b"enUS");
+    tag.extend_from_slice(&(text.len() as u32).to_be_bytes());
+    tag.extend_from_slice(&28u32.to_be_bytes());
+    tag.extend_from_slice(&text);
+
+    let mut profile = vec![0u8; 128];
+    profile.extend_from_slice(&1u32.to_be_bytes());
+    profile.extend_from_slice(b"desc");
+    profile.extend_from_slice(&144u32.to_be_bytes());
+    profile.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+    profile.extend_from_slice(&tag);
+    let size = profile.len() as u32;
+    profile[0..4].copy_from_slice(&size.to_be_bytes());
+    profile
+}
+
+fn jpeg() -> Vec<u8> {
+    let pixels = RgbImage::from_fn(STORED.0, STORED.1, |x, _| {
+        if x < STORED.0 / 2 {
+            Rgb([230, 20, 20])
+        } else {
+            Rgb([20, 20, 230])
+        }
+    });
+    let mut bytes = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(Cursor::new(&mut bytes), 95);
+    encoder.set_exif_metadata(exif()).expect("JPEG takes EXIF");
+    encoder
+        .set_icc_profile(icc_profile("Display P3"))
+        .expect("JPEG takes ICC profiles");
+    encoder
+        .write_image(&pixels, STORED.0, STORED.1, image::ExtendedColorType::Rgb8)
+        .expect("fixture JPEG should encode");
+    bytes
+}
+
+/// The same photo embedded as a `DCTDecode` image on one PDF page.
+fn pdf() -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let photo = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => STORED.0 as i64,
+            "Height" => STORED.1 as i64,
+            "ColorSpace" => "DeviceRGB",
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+        },
+        jpeg(),
+    ));
+    let content_id = doc.add_object(Stream::new(
+        dictionary! {},
+        b"q 40 0 0 20 100 100 cm /Photo Do Q".to_vec(),
+    ));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Resources" => dictionary! {
+            "XObject" => dictionary! { "Photo" => Object::Reference(photo) },
+        },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).expect("fixture PDF should save");
+    bytes
+}
+
+#[test]
+fn a_sideways_photo_is_turned_upright_and_its_metadata_listed() {
+    let fixture = Fixture::write("photo.jpg", jpeg());
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+
+    let item = &app.state.media[0];
+    assert_eq!((item.image.width(), item.image.height()), (20, 40));
+    // The left of the stored picture is now the top.
+    let upright = item.image.to_rgb8();
+    let (top, bottom) = (upright.get_pixel(10, 2).0, upright.get_pixel(10, 37).0);
+    assert!(top[0] > 180 && top[2] < 80, "top is {top:?}");
+    assert!(bottom[2] > 180 && bottom[0] < 80, "bottom is {bottom:?}");
+
+    let metadata = item.metadata.as_ref().expect("the photo has metadata");
+    assert_eq!(metadata.camera.as_deref(), Some("Fujifilm X-T30"));
+    assert_eq!(
+        metadata.exposure.as_deref(),
+        Some("1/250 s · f/2.8 · ISO 400 · 35 mm")
+    );
+    assert_eq!(metadata.taken.as_deref(), Some("2024-05-01 10:22:03"));
+    assert_eq!(metadata.orientation, Some(6));
+    assert_eq!(metadata.color_profile.as_deref(), Some("Display P3"));
+    let gps = metadata.gps.expect("the photo is geotagged");
+    assert!((gps.latitude - 52.373028).abs() < 1e-5, "{gps:?}");
+    assert!((gps.longitude + 4.891167).abs() < 1e-5, "{gps:?}");
+
+    assert!(app.state.file_content.contains("Dimensions: 20x40"));
+    assert!(app.state.file_content.contains("Camera: Fujifilm X-T30"));
+}
+
+#[test]
+fn jpegs_inside_a_pdf_keep_their_metadata_but_not_their_rotation() {
+    let fixture = Fixture::write("document.pdf", pdf());
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    let asset = &document.images[0];
+    assert_eq!(
+        (asset.width, asset.height),
+        STORED,
+        "the page's transform decides which way up it is drawn"
+    );
+    let metadata = asset.metadata.as_ref().expect("the JPEG has metadata");
+    assert_eq!(metadata.camera.as_deref(), Some("Fujifilm X-T30"));
+    assert_eq!(metadata.orientation, Some(6));
+}
+
+#[test]
+fn the_json_includes_each_images_metadata() {
+    let fixture = Fixture::write("json.jpg", jpeg());
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    let image = &value["images"][0];
+    assert_eq!(image["width"], 20);
+    assert_eq!(image["metadata"]["camera"], "Fujifilm X-T30");
+    assert_eq!(image["metadata"]["orientation"], 6);
+    assert_eq!(image["metadata"]["color_profile"], "Display P3");
+    assert!(image["metadata"]["gps"]["longitude"].as_f64().unwrap() < 0.0);
+    assert!(image["metadata"]["lens"].is_null());
+}
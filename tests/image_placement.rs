@@ -0,0 +1,227 @@
+//! Where images are drawn: the box, resolution and rotation each `Do` gives
+//! them, through nested transforms and form XObjects, and inline images that
+//! never appear among the page's resources.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::Fixture;
+use flerp::headless::{run, HeadlessRequest};
+use flerp::pdf_doc::{self, PdfImageAsset};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// Drawn at 144x72 points, so the 200x100 photo prints at 100 dpi.
+const PHOTO: (u32, u32) = (200, 100);
+
+fn rgb_image(
+    doc: &mut Document,
+    (width, height): (u32, u32),
+    pixel: fn(u32, u32) -> [u8; 3],
+) -> Object {
+    let mut pixels = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            pixels.extend_from_slice(&pixel(x, y));
+        }
+    }
+    let mut stream = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width as i64,
+            "Height" => height as i64,
+            "ColorSpace" => "DeviceRGB",
+            "BitsPerComponent" => 8,
+        },
+        pixels,
+    );
+    stream.compress().expect("image stream should compress");
+    Object::Reference(doc.add_object(stream))
+}
+
+fn flate(bytes: &[u8]) -> Vec<u8> {
+    let mut stream = Stream::new(dictionary! {}, bytes.to_vec());
+    stream.compress().expect("data should compress");
+    assert!(
+        stream.dict.has(b"Filter"),
+        "too small to be worth compressing"
+    );
+    stream.content
+}
+
+/// 40x2, red on the left and green on the right.
+fn inline_pixels() -> Vec<u8> {
+    (0..2)
+        .flat_map(|_| (0..40).flat_map(|x| if x < 20 { [255, 0, 0] } else { [0, 255, 0] }))
+        .collect()
+}
+
+/// One page: a photo placed by nested `cm`s, a stamp drawn turned a quarter
+/// from inside a form XObject, and a compressed inline image.
+fn write_fixture(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let photo = rgb_image(&mut doc, PHOTO, |x, y| [(x % 256) as u8, (y * 2) as u8, 90]);
+    let stamp = rgb_image(&mut doc, (10, 4), |_, _| [20, 40, 220]);
+
+    // No /Resources of its own: the page's are used.
+    let form = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+            "Matrix" => vec![0.into(), 1.into(), (-1).into(), 0.into(), 0.into(), 0.into()],
+        },
+        b"q 50 0 0 20 0 0 cm /Stamp Do Q".to_vec(),
+    ));
+
+    let mut content = Vec::new();
+    content.extend_from_slice(b"q 1 0 0 1 100 450 cm q 144 0 0 72 0 50 cm /Photo Do Q Q\n");
+    content.extend_from_slice(b"q 1 0 0 1 300 300 cm /Seal Do Q\n");
+    content.extend_from_slice(b"q 20 0 0 10 400 100 cm\nBI /W 40 /H 2 /CS /RGB /BPC 8 /F /Fl ID ");
+    content.extend_from_slice(&flate(&inline_pixels()));
+    content.extend_from_slice(b"\nEI Q\n");
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Resources" => dictionary! {
+            "XObject" => dictionary! {
+                "Photo" => photo,
+                "Stamp" => stamp,
+                "Seal" => Object::Reference(form),
+            },
+        },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn find(images: &[PdfImageAsset], width: u32) -> &PdfImageAsset {
+    images
+        .iter()
+        .find(|asset| asset.width == width)
+        .unwrap_or_else(|| panic!("no {width}-pixel-wide image"))
+}
+
+#[test]
+fn each_image_reports_its_box_resolution_and_rotation() {
+    let fixture = Fixture::with("document.pdf", write_fixture);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+    assert!(
+        document.skipped.is_empty(),
+        "{:?}",
+        document
+            .skipped
+            .iter()
+            .map(|s| &s.reason)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(document.images.len(), 3);
+
+    let photo = find(&document.images, PHOTO.0)
+        .placement
+        .expect("the photo is drawn");
+    assert_eq!(
+        (photo.left, photo.bottom, photo.right, photo.top),
+        (100.0, 500.0, 244.0, 572.0)
+    );
+    assert_eq!(photo.dpi(PHOTO.0, PHOTO.1), (100.0, 100.0));
+    assert_eq!(photo.rotation, 0);
+
+    // 50x20 points inside the form, turned a quarter by its /Matrix.
+    let stamp = find(&document.images, 10)
+        .placement
+        .expect("the stamp is drawn");
+    assert_eq!(
+        (stamp.left, stamp.bottom, stamp.right, stamp.top),
+        (280.0, 300.0, 300.0, 350.0)
+    );
+    assert_eq!((stamp.drawn_width, stamp.drawn_height), (50.0, 20.0));
+    assert_eq!(stamp.rotation, 90);
+}
+
+#[test]
+fn compressed_inline_images_are_extracted_with_their_placement() {
+    let fixture = Fixture::with("inline.pdf", write_fixture);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    // The text extractor cannot parse past the compressed image, so the page
+    // reads as empty instead of failing the whole document.
+    assert_eq!(document.unreadable_pages, [1]);
+
+    let inline = find(&document.images, 40);
+    assert!(inline.inline);
+    assert_eq!(inline.color_space, "DeviceRGB");
+    assert_eq!(inline.encoding, "FlateDecode samples");
+    let pixels = inline.image.to_rgb8();
+    assert_eq!(pixels.get_pixel(0, 1).0, [255, 0, 0]);
+    assert_eq!(pixels.get_pixel(39, 1).0, [0, 255, 0]);
+
+    let placement = inline
+        .placement
+        .expect("inline images are placed where they are drawn");
+    assert_eq!((placement.left, placement.bottom), (400.0, 100.0));
+    assert_eq!(placement.dpi(40, 2), (144.0, 14.4));
+}
+
+#[test]
+fn the_json_and_media_detail_carry_the_placement() {
+    let fixture = Fixture::with("json.pdf", write_fixture);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    let images = value["images"].as_array().expect("images array");
+    let photo = images
+        .iter()
+        .find(|image| image["width"] == PHOTO.0)
+        .expect("photo listed");
+    assert_eq!(photo["inline"], false);
+    assert_eq!(photo["placements"][0]["page"], 1);
+    assert_eq!(photo["placements"][0]["left"], 100.0);
+    assert_eq!(photo["placements"][0]["dpi"]["x"], 100.0);
+    assert!(
+        photo["detail"]
+            .as_str()
+            .unwrap()
+            .ends_with("144x72 pt at 100,500 · 100 dpi"),
+        "{}",
+        photo["detail"]
+    );
+
+    let stamp = images
+        .iter()
+        .find(|image| image["width"] == 10)
+        .expect("stamp listed");
+    assert_eq!(stamp["placements"][0]["rotation"], 90);
+    assert!(stamp["detail"].as_str().unwrap().ends_with("rotated 90°"));
+
+    let inline = images
+        .iter()
+        .find(|image| image["width"] == 40)
+        .expect("inline image listed");
+    assert_eq!(inline["inline"], true);
+}
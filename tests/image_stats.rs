@@ -0,0 +1,144 @@
+//! Image analysis: histograms, dominant colours, brightness, transparency and
+//! greyscale detection, shown in the Analyze tab for the selected image and
+//! reported under each image in `--json`.
+
+mod common;
+
+use common::{screen, Fixture};
+use flerp::app::App;
+use flerp::app_structs::TAB_ANALYZE;
+use flerp::headless::{run, HeadlessRequest};
+use image::{Rgba, RgbaImage};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// An orange badge on a see-through background: the top quarter is clear.
+fn badge_png() -> Vec<u8> {
+    let badge = RgbaImage::from_fn(40, 40, |_, y| {
+        if y < 10 {
+            Rgba([0, 0, 0, 0])
+        } else {
+            Rgba([240, 120, 20, 255])
+        }
+    });
+    let mut bytes = Vec::new();
+    badge
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .expect("fixture PNG should encode");
+    bytes
+}
+
+/// One page with a grey ramp whose `/SMask` hides its right half.
+fn masked_pdf() -> Vec<u8> {
+    let (width, height) = (32u32, 8u32);
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let ramp: Vec<u8> = (0..height)
+        .flat_map(|_| (0..width).flat_map(|x| [(x * 8) as u8; 3]))
+        .collect();
+    let mask: Vec<u8> = (0..height)
+        .flat_map(|_| (0..width).map(|x| if x < width / 2 { 255 } else { 0 }))
+        .collect();
+    let mask_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width as i64,
+            "Height" => height as i64,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+        },
+        mask,
+    ));
+    let image_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width as i64,
+            "Height" => height as i64,
+            "ColorSpace" => "DeviceRGB",
+            "BitsPerComponent" => 8,
+            "SMask" => Object::Reference(mask_id),
+        },
+        ramp,
+    ));
+    let content_id = doc.add_object(Stream::new(
+        dictionary! {},
+        b"q 128 0 0 32 100 600 cm /Ramp Do Q".to_vec(),
+    ));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Resources" => dictionary! {
+            "XObject" => dictionary! { "Ramp" => Object::Reference(image_id) },
+        },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).expect("fixture PDF should save");
+    bytes
+}
+
+#[test]
+fn the_analyze_tab_measures_the_selected_image() {
+    let fixture = Fixture::write("badge.png", badge_png());
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+
+    let analysis = app
+        .state
+        .image_analysis
+        .as_ref()
+        .expect("an image file is measured as soon as it loads");
+    let stats = &analysis.stats;
+    assert_eq!(stats.transparency, Some(0.25));
+    assert!(!stats.grayscale);
+    assert_eq!(stats.palette.len(), 1, "clear pixels have no colour");
+    assert_eq!(stats.palette[0].hex, "#f07814");
+
+    app.state.current_tab = TAB_ANALYZE;
+    let screen = screen(&mut app);
+    assert!(screen.contains("Image ·"));
+    assert!(screen.contains("25% see-through"));
+    assert!(screen.contains("#f07814  100%"));
+    assert!(screen.contains("Dominant Colours"));
+}
+
+#[test]
+fn the_json_reports_each_images_analysis() {
+    let fixture = Fixture::write("masked.pdf", masked_pdf());
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    let analysis = &value["images"][0]["analysis"];
+    assert_eq!(analysis["transparency"], 0.5, "the soft mask hides half");
+    assert_eq!(analysis["grayscale"], true);
+    assert_eq!(analysis["histogram"]["red"].as_array().unwrap().len(), 32);
+    // Only the visible left half counts: levels 0 to 120.
+    let brightness = analysis["mean_brightness"].as_f64().unwrap();
+    assert!((brightness - 60.0).abs() < 1.0, "{brightness}");
+    assert!(!analysis["palette"].as_array().unwrap().is_empty());
+}
@@ -322,6 +322,9 @@ fn clicking_an_image_row_picks_that_image() {
             detail: "8x8 · raw samples · DeviceRGB".to_string(),
             page: Some(index + 1),
             image: DynamicImage::ImageRgb8(RgbImage::new(8, 8)),
+            copies: Vec::new(),
+            source: None,
+            metadata: None,
         })
         .collect();
     app.state.current_tab = TAB_MEDIA;
@@ -0,0 +1,193 @@
+//! Loaders: formats registered from outside the crate, with pages of their
+//! own, and the built-in ones recognising files by content as well as name.
+
+mod common;
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use common::{json_request, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::TAB_VIEWER;
+use flerp::file_utils::LoadedFile;
+use flerp::headless::run_with_loaders;
+use flerp::loader::{LoadOptions, Loader, LoaderRegistry};
+use flerp::outline::Outline;
+use image::{Rgb, RgbImage};
+
+/// Slide decks: plain text with a `---` line between slides.
+struct SlidesLoader;
+
+impl Loader for SlidesLoader {
+    fn name(&self) -> &'static str {
+        "slides"
+    }
+
+    fn sniff(&self, path: &Path, _head: &[u8]) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension == "slides")
+    }
+
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let slides: Vec<&str> = text
+            .split("\n---\n")
+            .map(|slide| slide.trim_end())
+            .collect();
+        Ok(LoadedFile {
+            content: slides.join("\n"),
+            outline: Outline::pages(slides.iter().map(|slide| slide.lines().count())),
+            ..LoadedFile::default()
+        })
+    }
+}
+
+/// Claims every `.txt` file, to show a registration wins over a built-in.
+struct ShoutingLoader;
+
+/// How loud [`ShoutingLoader`] is, passed among the load options.
+#[derive(Clone, Copy, Default)]
+struct Volume {
+    exclamations: usize,
+}
+
+/// What [`ShoutingLoader`] learns about a file besides its text.
+struct WordCount(usize);
+
+impl Loader for ShoutingLoader {
+    fn name(&self) -> &'static str {
+        "shouting"
+    }
+
+    fn sniff(&self, path: &Path, _head: &[u8]) -> bool {
+        path.extension().is_some_and(|extension| extension == "txt")
+    }
+
+    fn load(&self, path: &Path, options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let volume = options.extensions.get::<Volume>().copied().unwrap_or_default();
+        let words = text.split_whitespace().count();
+        Ok(LoadedFile {
+            content: format!(
+                "{}{}\n",
+                text.trim_end().to_uppercase(),
+                "!".repeat(volume.exclamations)
+            ),
+            ..LoadedFile::default()
+        }
+        .with(WordCount(words)))
+    }
+}
+
+fn with_slides() -> LoaderRegistry {
+    let mut loaders = LoaderRegistry::default();
+    loaders.register(SlidesLoader);
+    loaders
+}
+
+const DECK: &[u8] = b"Welcome\nAgenda\n---\nNumbers\nRevenue grew\nCosts fell\n---\nQuestions\n";
+
+#[test]
+fn a_registered_format_opens_with_its_own_pages() {
+    let fixture = Fixture::write("deck.slides", DECK);
+    let mut app = App::with_loaders(with_slides());
+    app.load_file(fixture.as_str()).expect("the deck loads");
+
+    assert!(app.state.document.is_none(), "slides are not a PDF");
+    let pages = app.state.outline.top_level();
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[1].start_line, 2);
+    assert_eq!(pages[1].line_count, 3);
+
+    // Page jumps work as they do in a PDF.
+    app.state.current_tab = TAB_VIEWER;
+    app.handle_key(KeyCode::Char(']'));
+    app.handle_key(KeyCode::Char(']'));
+    assert_eq!(app.state.content_scroll, 5);
+    assert!(app.state.status_message.contains("Page 3 of 3"));
+}
+
+#[test]
+fn headless_runs_report_the_loader_as_the_kind() {
+    let fixture = Fixture::write("report.slides", DECK);
+    let mut buffer = Vec::new();
+    run_with_loaders(&json_request(fixture.as_str()), &with_slides(), &mut buffer)
+        .expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+    assert_eq!(value["kind"], "slides");
+    assert_eq!(value["outline"].as_array().map(Vec::len), Some(3));
+    assert_eq!(value["outline"][2]["title"], "Page 3");
+
+    // Without the registration the deck is only text.
+    let plain = LoaderRegistry::default()
+        .load(fixture.as_str(), &LoadOptions::default())
+        .expect("text loads");
+    assert_eq!(plain.kind, "text");
+    assert!(plain.outline.is_empty());
+}
+
+#[test]
+fn later_registrations_are_asked_first() {
+    let fixture = Fixture::write("notes.txt", b"quiet words\n");
+    let mut loaders = LoaderRegistry::default();
+    loaders.register(ShoutingLoader);
+    assert_eq!(
+        loaders.names(),
+        [
+            "shouting", "pdf", "image", "json", "yaml", "toml", "code", "table", "log", "text"
+        ]
+    );
+
+    let loaded = loaders
+        .load(fixture.as_str(), &LoadOptions::default())
+        .expect("loads");
+    assert_eq!(loaded.kind, "shouting");
+    assert_eq!(loaded.content, "QUIET WORDS\n");
+}
+
+#[test]
+fn loaders_take_their_own_options_and_report_their_own_findings() {
+    let fixture = Fixture::write("notes.txt", b"quiet words\n");
+    let mut loaders = LoaderRegistry::default();
+    loaders.register(ShoutingLoader);
+    let options = LoadOptions::default().with(Volume { exclamations: 3 });
+
+    let loaded = loaders.load(fixture.as_str(), &options).expect("loads");
+    assert_eq!(loaded.content, "QUIET WORDS!!!\n");
+    assert_eq!(loaded.get::<WordCount>().map(|count| count.0), Some(2));
+
+    // The built-in text loader knows nothing of either.
+    let plain = LoaderRegistry::default()
+        .load(fixture.as_str(), &options)
+        .expect("text loads");
+    assert_eq!(plain.content, "quiet words\n");
+    assert!(plain.get::<WordCount>().is_none());
+}
+
+#[test]
+fn built_in_formats_are_recognised_by_content() {
+    let mut png = Vec::new();
+    RgbImage::from_pixel(8, 4, Rgb([10, 200, 10]))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .expect("fixture PNG should encode");
+    let fixture = Fixture::write("picture.dat", png);
+    let loaded = LoaderRegistry::default()
+        .load(fixture.as_str(), &LoadOptions::default())
+        .expect("a PNG loads whatever its name");
+    assert_eq!(loaded.kind, "image");
+    assert_eq!(loaded.media.len(), 1);
+
+    let binary = Fixture::write("blob.bin", [0xff, 0xfe, 0x00, 0x9c, 0x80]);
+    let error = LoaderRegistry::default()
+        .load(binary.as_str(), &LoadOptions::default())
+        .err()
+        .expect("nothing reads it");
+    assert!(error.to_string().contains("not in a format flerp can open"));
+
+    let empty = LoaderRegistry::empty();
+    assert!(empty
+        .load(fixture.as_str(), &LoadOptions::default())
+        .is_err());
+}
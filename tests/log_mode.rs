@@ -0,0 +1,226 @@
+//! Log files: recognised by their timestamps, coloured and filtered by level
+//! and time, counted per minute in Analyze and in `--json`.
+
+mod common;
+
+use std::fs;
+
+use common::{draw, rows, screen, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::{InputMode, TAB_ANALYZE, TAB_VIEWER};
+use flerp::headless::{run, HeadlessRequest};
+use flerp::log_file::LogLevel;
+use flerp::text_analysis::extract_repeated_lines;
+use ratatui::style::Color;
+
+const LOG: &str = "\
+2024-03-05 10:00:01 INFO server started
+2024-03-05 10:00:30 DEBUG cache warmed
+2024-03-05 10:01:02 WARN disk at 85%
+2024-03-05 10:01:40 ERROR upstream timed out
+    at fetch (client.rs:42)
+2024-03-05 10:02:05 INFO request served
+2024-03-05 10:03:10 INFO request served
+2024-03-05 10:03:50 ERROR upstream timed out
+";
+
+/// Colour of the first character of the row showing `needle`.
+fn colour_of(app: &mut App, needle: &str) -> Color {
+    let buffer = draw(app);
+    let colour = rows(&buffer)
+        .find_map(|(text, cells)| {
+            let column = text[..text.find(needle)?].chars().count();
+            Some(cells[column].fg)
+        })
+        .expect("the line is on screen");
+    colour
+}
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        app.handle_key(KeyCode::Char(c));
+    }
+}
+
+#[test]
+fn levels_colour_and_filter_the_lines() {
+    let fixture = Fixture::write("levels.log", LOG);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the log loads");
+    assert!(app.state.log.is_some(), "recognised as a log");
+    assert!(
+        app.state.status_message.contains("log of 7 events"),
+        "{}",
+        app.state.status_message
+    );
+    assert_eq!(app.state.log_level_at(1), Some(LogLevel::Debug));
+    assert_eq!(
+        app.state.log_level_at(4),
+        Some(LogLevel::Error),
+        "the stack line belongs to its error"
+    );
+
+    app.state.current_tab = TAB_VIEWER;
+    let error = colour_of(&mut app, "2024-03-05 10:01:40");
+    let info = colour_of(&mut app, "2024-03-05 10:00:01");
+    assert_ne!(error, info, "errors stand out");
+    assert_eq!(colour_of(&mut app, "    at fetch"), error);
+    assert_ne!(
+        colour_of(&mut app, "2024-03-05 10:00:30"),
+        info,
+        "debug recedes"
+    );
+
+    // Debug, Info, Warn: each press hides one more level.
+    for _ in 0..3 {
+        app.handle_key(KeyCode::Char('L'));
+    }
+    assert_eq!(app.state.log_filter.min_level, Some(LogLevel::Warn));
+    assert_eq!(
+        app.state.file_content.lines().collect::<Vec<_>>(),
+        [
+            "2024-03-05 10:01:02 WARN disk at 85%",
+            "2024-03-05 10:01:40 ERROR upstream timed out",
+            "    at fetch (client.rs:42)",
+            "2024-03-05 10:03:50 ERROR upstream timed out",
+        ]
+    );
+    assert_eq!(app.state.log_lines, [2, 3, 4, 7]);
+    assert_eq!(app.state.log_level_at(0), Some(LogLevel::Warn));
+    assert!(
+        app.state
+            .status_message
+            .starts_with("Showing 4 of 8 log lines · WARN and above"),
+        "{}",
+        app.state.status_message
+    );
+    assert!(screen(&mut app).contains("Filter WARN and above"));
+
+    // Past Error the filter wraps back to every line.
+    app.handle_key(KeyCode::Char('L'));
+    app.handle_key(KeyCode::Char('L'));
+    assert_eq!(app.state.log_filter.min_level, None);
+    assert_eq!(app.state.file_content, LOG);
+}
+
+#[test]
+fn a_time_range_keeps_the_minutes_it_names() {
+    let fixture = Fixture::write("range.log", LOG);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the log loads");
+    app.state.current_tab = TAB_VIEWER;
+
+    app.handle_key(KeyCode::Char('T'));
+    assert_eq!(app.state.input_mode, InputMode::TimeRange);
+    type_text(&mut app, "10:01..10:02");
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(app.state.input_mode, InputMode::Normal);
+    assert_eq!(
+        app.state.log_lines,
+        [2, 3, 4, 5],
+        "through the end of 10:02"
+    );
+
+    app.handle_key(KeyCode::Char('T'));
+    type_text(&mut app, "soon..later");
+    app.handle_key(KeyCode::Enter);
+    assert!(
+        app.state.status_message.contains("is not a time"),
+        "{}",
+        app.state.status_message
+    );
+    assert_eq!(
+        app.state.log_lines,
+        [2, 3, 4, 5],
+        "a bad range changes nothing"
+    );
+
+    // An empty range shows everything again.
+    app.handle_key(KeyCode::Char('T'));
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(app.state.log_lines.len(), 8);
+}
+
+#[test]
+fn plain_text_has_no_log_keys() {
+    let path = std::env::temp_dir().join(format!("flerp-log-{}-plain.txt", std::process::id()));
+    fs::write(&path, "Just a note.\nNothing timed here.\n").unwrap();
+    let mut app = App::new();
+    app.load_file(path.to_str().unwrap())
+        .expect("the note loads");
+    let _ = fs::remove_file(&path);
+
+    assert!(app.state.log.is_none());
+    app.handle_key(KeyCode::Char('T'));
+    assert_eq!(app.state.input_mode, InputMode::Normal);
+    assert_eq!(app.state.status_message, "Time filters need a log file.");
+}
+
+#[test]
+fn repeated_messages_are_grouped_without_their_timestamps() {
+    let repeated = extract_repeated_lines(LOG, 8);
+    assert_eq!(
+        repeated,
+        [
+            ("ERROR upstream timed out".to_string(), 2),
+            ("INFO request served".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn analyze_shows_events_per_minute() {
+    let fixture = Fixture::write("analyze.log", LOG);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the log loads");
+    app.state.current_tab = TAB_ANALYZE;
+
+    let summary = app.state.log_summary.as_ref().expect("a log summary");
+    assert_eq!(summary.events, 7);
+    let minutes: Vec<(String, usize)> = summary
+        .per_minute
+        .iter()
+        .map(|(minute, count)| (minute.to_string(), *count))
+        .collect();
+    assert_eq!(
+        minutes,
+        [
+            ("2024-03-05 10:00:00".to_string(), 2),
+            ("2024-03-05 10:01:00".to_string(), 2),
+            ("2024-03-05 10:02:00".to_string(), 1),
+            ("2024-03-05 10:03:00".to_string(), 2),
+        ]
+    );
+
+    let shown = screen(&mut app);
+    assert!(shown.contains("Log · 7 events"), "{shown}");
+    assert!(shown.contains("Events per minute"), "{shown}");
+    assert!(
+        shown.contains("2024-03-05 10:03:00"),
+        "the last minute is labelled"
+    );
+    assert!(shown.contains("x 2 ERROR upstream timed out"), "{shown}");
+}
+
+#[test]
+fn the_json_counts_levels_and_minutes() {
+    let fixture = Fixture::write("json.log", LOG);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    assert_eq!(value["kind"], "log");
+    let log = &value["log"];
+    assert_eq!(log["events"], 7);
+    assert_eq!(log["levels"]["error"], 2);
+    assert_eq!(log["levels"]["info"], 3);
+    assert_eq!(log["levels"]["fatal"], 0);
+    assert_eq!(log["events_per_minute"][1]["minute"], "2024-03-05 10:01:00");
+    assert_eq!(log["events_per_minute"][1]["count"], 2);
+}
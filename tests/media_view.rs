@@ -0,0 +1,268 @@
+//! Zooming, panning and turning the Media preview: which part of the image is
+//! visible, what gets handed to the terminal, and the keys and mouse drags
+//! that move it. Also the thumbnail grid and how it is navigated.
+
+mod common;
+
+use common::screen_at;
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use flerp::app::App;
+use flerp::app_structs::TAB_MEDIA;
+use flerp::media::{visible_pixels, visible_region, MediaItem, MediaView, GRID_TILE};
+use image::{DynamicImage, Rgb, RgbImage};
+
+fn zoomed(zoom: usize) -> MediaView {
+    MediaView {
+        zoom,
+        ..MediaView::default()
+    }
+}
+
+/// Red in the left half, blue in the right.
+fn halves(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, _| {
+        if x < width / 2 {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([0, 0, 255])
+        }
+    }))
+}
+
+fn app_with_images(count: usize) -> App {
+    let mut app = App::new();
+    app.state.file_name = "scan.pdf".to_string();
+    app.state.media = (0..count)
+        .map(|index| MediaItem {
+            key: format!("scan#{index}"),
+            title: format!("Page {} · image 1", index + 1),
+            detail: "800x400".to_string(),
+            page: Some(index + 1),
+            image: halves(800, 400),
+            copies: Vec::new(),
+            source: None,
+            metadata: None,
+        })
+        .collect();
+    app.state.current_tab = TAB_MEDIA;
+    app
+}
+
+/// The clicks below are placed for a terminal of this size.
+fn draw(app: &mut App) -> String {
+    screen_at(app, 100, 40)
+}
+
+fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+    MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    }
+}
+
+#[test]
+fn zooming_narrows_the_region_around_the_center() {
+    // Fitted, a 400x200 image in a 200x100 preview is drawn at half size.
+    let fitted = visible_region((400, 200), &MediaView::default(), (200, 100));
+    assert_eq!((fitted.x, fitted.width, fitted.scale), (0, 400, 0.5));
+
+    // Twice that is one pixel per pixel: the middle 200x100.
+    let region = visible_region((400, 200), &zoomed(2), (200, 100));
+    assert_eq!(
+        (region.x, region.y, region.width, region.height),
+        (100, 50, 200, 100)
+    );
+    assert_eq!(region.scale, 1.0);
+
+    let actual = MediaView {
+        actual_size: true,
+        ..MediaView::default()
+    };
+    assert_eq!(visible_region((400, 200), &actual, (200, 100)), region);
+
+    // Panned past the edge, the region stops at it.
+    let panned = MediaView {
+        center: (1.0, 0.0),
+        ..zoomed(2)
+    };
+    let region = visible_region((400, 200), &panned, (200, 100));
+    assert_eq!((region.x, region.y), (200, 0));
+}
+
+#[test]
+fn only_the_visible_pixels_are_encoded() {
+    let image = halves(800, 400);
+
+    // Zoomed in on the left edge: nothing from the blue half is sent.
+    let view = MediaView {
+        center: (0.0, 0.5),
+        ..zoomed(4)
+    };
+    let pixels = visible_pixels(&image, &view, (400, 200)).to_rgb8();
+    assert!(pixels.width() <= 400 && pixels.height() <= 200);
+    assert!(pixels.pixels().all(|pixel| pixel.0 == [255, 0, 0]));
+
+    // A small image zoomed past its resolution is enlarged pixel for pixel.
+    let small = halves(40, 20);
+    let pixels = visible_pixels(&small, &zoomed(4), (400, 200)).to_rgb8();
+    assert_eq!(pixels.dimensions(), (160, 80));
+    assert_eq!(pixels.get_pixel(79, 0).0, [255, 0, 0]);
+    assert_eq!(pixels.get_pixel(80, 0).0, [0, 0, 255]);
+}
+
+#[test]
+fn turning_swaps_the_sides_and_keeps_the_spot_in_the_middle() {
+    let mut view = MediaView {
+        center: (0.25, 0.5),
+        ..zoomed(2)
+    };
+    view.rotate();
+    assert_eq!(view.quarter_turns, 1);
+    assert_eq!(view.center, (0.5, 0.25));
+    assert_eq!(view.label(), "200% · 90°");
+
+    // Turned clockwise, the left half ends up on top.
+    let pixels = visible_pixels(
+        &halves(40, 20),
+        &MediaView {
+            quarter_turns: 1,
+            ..MediaView::default()
+        },
+        (400, 400),
+    )
+    .to_rgb8();
+    assert_eq!(pixels.dimensions(), (20, 40));
+    assert_eq!(pixels.get_pixel(10, 0).0, [255, 0, 0]);
+    assert_eq!(pixels.get_pixel(10, 39).0, [0, 0, 255]);
+}
+
+#[test]
+fn arrows_pan_only_while_zoomed_and_a_new_image_starts_fitted() {
+    let mut app = app_with_images(2);
+    draw(&mut app);
+
+    app.handle_key(KeyCode::Char('+'));
+    app.handle_key(KeyCode::Char('+'));
+    assert_eq!(app.state.media_view.zoom, 2);
+    draw(&mut app);
+    let (visible_width, _) = app.state.media_visible;
+    assert!(
+        visible_width < 1.0,
+        "zoomed in, part of the image is hidden"
+    );
+
+    app.handle_key(KeyCode::Right);
+    assert!(app.state.media_view.center.0 > 0.5);
+    assert_eq!(app.state.selected_media, 0, "the arrow panned");
+
+    app.handle_key(KeyCode::Char('R'));
+    assert!(app.state.status_message.contains("90°"));
+
+    // `j` still moves through the list, and the next image is seen whole.
+    app.handle_key(KeyCode::Char('j'));
+    assert_eq!(app.state.selected_media, 1);
+    assert_eq!(app.state.media_view, MediaView::default());
+
+    app.handle_key(KeyCode::Char('1'));
+    assert!(app.state.media_view.actual_size);
+    app.handle_key(KeyCode::Char('0'));
+    assert_eq!(app.state.media_view, MediaView::default());
+    app.handle_key(KeyCode::Up);
+    assert_eq!(app.state.selected_media, 0, "fitted, the arrows select");
+}
+
+#[test]
+fn dragging_the_preview_pulls_the_image_along() {
+    let mut app = app_with_images(1);
+    app.state.media_view = zoomed(3);
+    draw(&mut app);
+    let preview = app.state.hit.media_preview;
+    assert!(preview.width > 0);
+
+    let (column, row) = (
+        preview.x + preview.width / 2,
+        preview.y + preview.height / 2,
+    );
+    app.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left), column, row));
+    app.handle_mouse(mouse(
+        MouseEventKind::Drag(MouseButton::Left),
+        column - 10,
+        row,
+    ));
+    app.handle_mouse(mouse(
+        MouseEventKind::Up(MouseButton::Left),
+        column - 10,
+        row,
+    ));
+
+    // Dragging leftwards brings more of the right side into view.
+    assert!(app.state.media_view.center.0 > 0.5);
+    assert_eq!(app.state.media_view.center.1, 0.5);
+
+    // The wheel over the preview zooms rather than changing the selection.
+    app.handle_mouse(mouse(MouseEventKind::ScrollUp, column, row));
+    assert_eq!(app.state.media_view.zoom, 4);
+    app.handle_mouse(mouse(MouseEventKind::ScrollDown, column, row));
+    assert_eq!(app.state.media_view.zoom, 3);
+}
+
+#[test]
+fn the_grid_moves_in_two_dimensions_and_enter_opens_the_image() {
+    let mut app = app_with_images(10);
+    app.handle_key(KeyCode::Char('v'));
+    assert!(app.state.media_grid);
+    let screen = draw(&mut app);
+    assert!(screen.contains("Images · 1 of 10"));
+    assert!(
+        screen.contains("Page 2 · image 1"),
+        "neighbours are shown too"
+    );
+
+    let columns = app.state.media_grid_columns;
+    assert!(columns > 1, "a 100-column terminal fits several thumbnails");
+
+    app.handle_key(KeyCode::Right);
+    assert_eq!(app.state.selected_media, 1);
+    app.handle_key(KeyCode::Down);
+    assert_eq!(app.state.selected_media, 1 + columns);
+    app.handle_key(KeyCode::Char('k'));
+    assert_eq!(app.state.selected_media, 1);
+
+    // Zoom keys mean nothing on the grid.
+    app.handle_key(KeyCode::Char('+'));
+    assert_eq!(app.state.media_view, MediaView::default());
+
+    app.handle_key(KeyCode::Enter);
+    assert!(!app.state.media_grid);
+    assert_eq!(app.state.selected_media, 1);
+    assert!(draw(&mut app).contains("Page 2 · image 1"));
+}
+
+#[test]
+fn the_grid_scrolls_to_the_selection_and_clicks_pick_thumbnails() {
+    let mut app = app_with_images(30);
+    app.state.media_grid = true;
+    draw(&mut app);
+
+    app.handle_key(KeyCode::End);
+    assert_eq!(app.state.selected_media, 29);
+    let screen = draw(&mut app);
+    assert!(app.state.media_grid_scroll > 0);
+    assert!(screen.contains("Page 30 · image 1"));
+    assert!(!screen.contains("Page 1 · image 1 "));
+
+    app.handle_key(KeyCode::Home);
+    draw(&mut app);
+    assert_eq!(app.state.media_grid_scroll, 0);
+
+    // The second tile of the second row, then the same one again to open it.
+    let grid = app.state.hit.media_grid;
+    let (column, row) = (grid.x + GRID_TILE.0 + 3, grid.y + GRID_TILE.1 + 3);
+    app.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left), column, row));
+    assert_eq!(app.state.selected_media, app.state.media_grid_columns + 1);
+    assert!(app.state.media_grid);
+    app.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left), column, row));
+    assert!(!app.state.media_grid);
+}
@@ -9,6 +9,7 @@ use crossterm::event::KeyCode;
 use flerp::app::App;
 use flerp::app_structs::{TAB_DASHBOARD, TAB_MEDIA, TAB_SETTINGS, TAB_VIEWER};
 use flerp::media::MediaItem;
+use flerp::outline::Outline;
 use flerp::pdf_doc::{PdfDocument, PdfPage};
 use image::{DynamicImage, RgbImage};
 
@@ -18,6 +19,7 @@ fn page(number: usize, start_line: usize, line_count: usize, image_count: usize)
         start_line,
         line_count,
         image_count,
+        ocr: false,
     }
 }
 
@@ -30,19 +32,23 @@ fn paged_app() -> App {
         .join("\n");
     app.state.file_name = "paged.pdf".to_string();
     app.state.viewer_height = 10;
-    app.state.document = Some(Arc::new(PdfDocument {
-        text: app.state.file_content.clone(),
-        pages: vec![page(1, 0, 20, 0), page(2, 20, 20, 0), page(3, 40, 20, 1)],
-        images: Vec::new(),
-        skipped: Vec::new(),
-        image_error: None,
-    }));
+    app.state.set_document(
+        Some(Arc::new(PdfDocument {
+            text: app.state.file_content.clone(),
+            pages: vec![page(1, 0, 20, 0), page(2, 20, 20, 0), page(3, 40, 20, 1)],
+            ..PdfDocument::default()
+        })),
+        Outline::default(),
+    );
     app.state.media = vec![MediaItem {
         key: "paged#0".to_string(),
         title: "Page 3 · image 1".to_string(),
         detail: "4x4".to_string(),
         page: Some(3),
         image: DynamicImage::ImageRgb8(RgbImage::new(4, 4)),
+        copies: Vec::new(),
+        source: None,
+        metadata: None,
     }];
     app
 }
@@ -121,6 +127,9 @@ fn media_selection_stays_in_bounds_and_jumps_to_the_right_page() {
         detail: "4x4".to_string(),
         page: Some(1),
         image: DynamicImage::ImageRgb8(RgbImage::new(4, 4)),
+        copies: Vec::new(),
+        source: None,
+        metadata: None,
     });
     app.state.current_tab = TAB_MEDIA;
 
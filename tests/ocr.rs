@@ -0,0 +1,274 @@
+//! OCR of scanned pages and images, with a stand-in for Tesseract so the
+//! tests say what flerp does with recognised text rather than how well a real
+//! engine reads.
+#![cfg(all(feature = "ocr", unix))]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use flerp::file_utils::{load_file, load_file_with_options};
+use flerp::headless::{run, HeadlessRequest};
+use flerp::loader::LoadOptions;
+use flerp::pdf_doc::{self, ExtractionMode, PdfDocument, PdfOptions};
+use image::{GrayImage, Luma};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// The engine is picked through the environment, which every test in this
+/// binary shares.
+static ENGINE: Mutex<()> = Mutex::new(());
+
+/// Echoes the language it was asked for, so the tests see what flerp passed.
+const FAKE_TESSERACT: &str = "#!/bin/sh\ncat > /dev/null\necho run >> \"$0.runs\"\nprintf 'SCANNED INVOICE 42  \\n\\nlanguage %s\\n\\f' \"$4\"\n";
+
+struct Scratch {
+    dir: PathBuf,
+}
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("flerp-ocr-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    fn engine(&self) -> PathBuf {
+        let path = self.dir.join("tesseract");
+        fs::write(&path, FAKE_TESSERACT).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// How many times the engine from [`Scratch::engine`] has run.
+    fn runs(&self) -> usize {
+        fs::read_to_string(self.dir.join("tesseract.runs"))
+            .map(|runs| runs.lines().count())
+            .unwrap_or(0)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.dir.join(name).to_str().expect("utf-8 temp path").to_string()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn use_engine(path: &Path) {
+    std::env::set_var("FLERP_TESSERACT", path);
+    std::env::set_var("FLERP_OCR_LANG", "eng+tur");
+}
+
+/// What `--ocr` asks for.
+fn with_ocr() -> LoadOptions<'static> {
+    LoadOptions {
+        ocr: true,
+        ..LoadOptions::default()
+    }
+}
+
+fn scan() -> GrayImage {
+    GrayImage::from_fn(16, 8, |x, _| Luma([if x % 2 == 0 { 0 } else { 255 }]))
+}
+
+/// Page 1 has a text layer; page 2 is nothing but a scanned image.
+fn write_scanned_pdf(path: &str) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let scan = scan();
+    let image_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => scan.width() as i64,
+            "Height" => scan.height() as i64,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+        },
+        scan.into_raw(),
+    ));
+
+    let text_page = Content {
+        operations: vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            Operation::new("Td", vec![72.into(), 700.into()]),
+            Operation::new("Tj", vec![Object::string_literal("Cover letter")]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    let scanned_page = Content {
+        operations: vec![
+            Operation::new("q", vec![]),
+            Operation::new(
+                "cm",
+                vec![612.into(), 0.into(), 0.into(), 792.into(), 0.into(), 0.into()],
+            ),
+            Operation::new("Do", vec!["Im1".into()]),
+            Operation::new("Q", vec![]),
+        ],
+    };
+
+    let mut kids = Vec::new();
+    let text_resources = dictionary! {
+        "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+    };
+    let scan_resources = dictionary! {
+        "XObject" => dictionary! { "Im1" => Object::Reference(image_id) },
+    };
+    for (content, resources) in [(text_page, text_resources), (scanned_page, scan_resources)] {
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        kids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => resources,
+        })));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+#[test]
+fn scanned_pages_get_recognised_text_and_are_flagged() {
+    let _engine = ENGINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let scratch = Scratch::new("pdf");
+    use_engine(&scratch.engine());
+    let pdf = scratch.path("scanned.pdf");
+    write_scanned_pdf(&pdf);
+
+    let document = pdf_doc::load_with_options(&pdf, &with_ocr()).expect("fixture loads");
+    assert_eq!(document.ocr_error, None);
+    let flags: Vec<bool> = document.pages.iter().map(|page| page.ocr).collect();
+    assert_eq!(flags, [false, true], "the page with a text layer is left alone");
+
+    let lines: Vec<&str> = document.text.lines().collect();
+    let second = &document.pages[1];
+    assert_eq!(
+        &lines[second.start_line..second.start_line + second.line_count],
+        ["SCANNED INVOICE 42", "", "language eng+tur"]
+    );
+    assert!(document.text.starts_with("Cover letter"));
+
+    let mut buffer = Vec::new();
+    let request = HeadlessRequest {
+        file: pdf.clone(),
+        json: true,
+        search: Some("invoice".to_string()),
+        ocr: true,
+        ..HeadlessRequest::default()
+    };
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+    assert_eq!(value["ocr"], true);
+    assert_eq!(value["pages"][1]["ocr"], true);
+    assert_eq!(value["search"]["match_count"], 1);
+    assert_eq!(value["search"]["matches"][0]["page"], 2);
+}
+
+#[test]
+fn reading_a_scan_again_reuses_the_recognised_text() {
+    let _engine = ENGINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let scratch = Scratch::new("again");
+    use_engine(&scratch.engine());
+    let pdf = scratch.path("scanned.pdf");
+    write_scanned_pdf(&pdf);
+
+    let first = pdf_doc::load_with_options(&pdf, &with_ocr()).expect("fixture loads");
+    assert_eq!(scratch.runs(), 1);
+    // What the TUI does on `p` and on an extraction-mode change.
+    let reflowed = pdf_doc::load_with_options(
+        &pdf,
+        &with_ocr().with(PdfOptions {
+            extraction: ExtractionMode::Layout,
+            reflow: true,
+        }),
+    )
+    .expect("fixture loads again");
+    assert_eq!(scratch.runs(), 1, "Tesseract ran once for the same scan");
+    assert!(reflowed.pages[1].ocr);
+    assert!(first.text.contains("SCANNED INVOICE 42"));
+    assert!(reflowed.text.contains("SCANNED INVOICE 42"));
+}
+
+#[test]
+fn an_image_file_is_searchable_by_the_text_in_it() {
+    let _engine = ENGINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let scratch = Scratch::new("image");
+    use_engine(&scratch.engine());
+    let png = scratch.path("receipt.png");
+    scan().save(&png).unwrap();
+
+    let loaded = load_file_with_options(&png, &with_ocr()).expect("image loads");
+    assert!(loaded.ocr);
+    assert!(loaded.content.starts_with("Image file loaded."), "{}", loaded.content);
+    assert!(loaded.content.contains("Dimensions: 16x8"), "{}", loaded.content);
+    assert!(
+        loaded.content.trim_end().ends_with("SCANNED INVOICE 42\n\nlanguage eng+tur"),
+        "{}",
+        loaded.content
+    );
+    assert_eq!(loaded.media.len(), 1, "the image is still shown");
+    assert!(loaded.notice.unwrap().contains("OCR"));
+}
+
+#[test]
+fn a_missing_engine_leaves_the_scan_empty_and_says_why() {
+    let _engine = ENGINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let scratch = Scratch::new("missing");
+    use_engine(&scratch.dir.join("no-such-tesseract"));
+    let pdf = scratch.path("scanned.pdf");
+    write_scanned_pdf(&pdf);
+
+    let loaded = load_file_with_options(&pdf, &with_ocr()).expect("the PDF still opens");
+    assert!(!loaded.ocr);
+    let document = loaded.get::<PdfDocument>().unwrap();
+    assert!(document.pages.iter().all(|page| !page.ocr));
+    let error = document.ocr_error.as_deref().expect("the failure is kept");
+    assert!(error.contains("not installed"), "{error}");
+    assert!(loaded.notice.unwrap().contains("OCR unavailable"));
+}
+
+#[test]
+fn nothing_is_recognised_unless_asked_for() {
+    let _engine = ENGINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let scratch = Scratch::new("off");
+    use_engine(&scratch.engine());
+    let pdf = scratch.path("scanned.pdf");
+    write_scanned_pdf(&pdf);
+    let png = scratch.path("receipt.png");
+    scan().save(&png).unwrap();
+
+    let loaded = load_file(&pdf).expect("the PDF opens");
+    assert!(!loaded.ocr);
+    assert!(loaded.get::<PdfDocument>().unwrap().pages.iter().all(|page| !page.ocr));
+    let loaded = load_file(&png).expect("the image opens");
+    assert!(!loaded.ocr);
+    assert!(!loaded.content.contains("SCANNED"), "{}", loaded.content);
+    assert_eq!(loaded.notice, None);
+    assert_eq!(scratch.runs(), 0, "Tesseract never ran");
+}
@@ -0,0 +1,183 @@
+//! The format-neutral outline: a loader's chapters and headings drive page
+//! jumps, the viewer's position line, per-section analysis and the JSON, just
+//! as a PDF's pages do.
+
+mod common;
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use common::{screen, screen_at, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::{AnalysisScope, TAB_ANALYZE, TAB_VIEWER};
+use flerp::file_utils::LoadedFile;
+use flerp::headless::{run_with_loaders, HeadlessRequest};
+use flerp::loader::{LoadOptions, Loader, LoaderRegistry};
+use flerp::outline::{Outline, Segment, SegmentKind};
+
+/// `# ` lines start chapters and `## ` lines headings within them.
+struct BookLoader;
+
+impl Loader for BookLoader {
+    fn name(&self) -> &'static str {
+        "book"
+    }
+
+    fn sniff(&self, path: &Path, _head: &[u8]) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension == "book")
+    }
+
+    fn load(&self, path: &Path, _options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let total = content.lines().count();
+        let marks: Vec<(usize, usize, &str)> = content
+            .lines()
+            .enumerate()
+            .filter_map(|(line, text)| match text.strip_prefix("## ") {
+                Some(title) => Some((line, 1, title)),
+                None => text.strip_prefix("# ").map(|title| (line, 0, title)),
+            })
+            .collect();
+        let segments = marks
+            .iter()
+            .enumerate()
+            .map(|(index, (line, level, title))| {
+                // Runs until the next mark at the same or an outer level.
+                let end = marks[index + 1..]
+                    .iter()
+                    .find(|(_, other, _)| other <= level)
+                    .map_or(total, |(next, _, _)| *next);
+                Segment {
+                    kind: if *level == 0 {
+                        SegmentKind::Chapter
+                    } else {
+                        SegmentKind::Heading
+                    },
+                    title: title.to_string(),
+                    level: *level,
+                    number: index + 1,
+                    start_line: *line,
+                    line_count: end - line,
+                }
+            })
+            .collect();
+        Ok(LoadedFile {
+            content,
+            outline: Outline::new(segments),
+            ..LoadedFile::default()
+        })
+    }
+}
+
+/// Long enough that the viewer can scroll to the first chapter.
+const BOOK: &str = "Preface line\n\
+# Harbours\n\
+Boats in the harbour.\n\
+## Tides\n\
+The tide turns twice a day.\n\
+# Glaciers\n\
+Ice moves slowly.\n\
+Glacier ice is old.\n\
+Glaciers carve valleys.\n\
+Glaciers feed rivers.\n\
+Glaciers retreat in summer.\n\
+Glaciers advance in winter.\n\
+Glaciers hold fresh water.\n\
+Glaciers grind rock to flour.\n\
+Glaciers leave moraines behind.\n\
+Glaciers calve into the sea.\n\
+Glaciers sing as they crack.\n\
+Glaciers are measured each year.\n\
+Glaciers shrink.\n\
+Glaciers end.\n";
+
+fn loaders() -> LoaderRegistry {
+    let mut loaders = LoaderRegistry::default();
+    loaders.register(BookLoader);
+    loaders
+}
+
+#[test]
+fn chapters_are_stepped_through_like_pages() {
+    let fixture = Fixture::write("steps.book", BOOK);
+    let mut app = App::with_loaders(loaders());
+    app.load_file(fixture.as_str()).expect("the book loads");
+    assert_eq!(app.state.outline.top_level().len(), 2);
+    assert!(app.state.document.is_none(), "no PDF behind it");
+    app.state.current_tab = TAB_VIEWER;
+
+    app.handle_key(KeyCode::Char(']'));
+    assert_eq!(app.state.content_scroll, 1);
+    assert!(app.state.status_message.starts_with("Harbours · 1 of 2"));
+    let shown = screen_at(&mut app, 100, 30);
+    assert!(shown.contains("Chapter 1 of 2 · Harbours"), "{shown}");
+
+    app.handle_key(KeyCode::Char(']'));
+    assert_eq!(app.state.content_scroll, 5);
+    app.handle_key(KeyCode::Char('['));
+    assert_eq!(app.state.content_scroll, 1);
+
+    // Chapters are not pages.
+    assert_eq!(app.state.current_page(), None);
+    app.goto_page(2);
+    assert!(app.state.status_message.contains("paged document"));
+}
+
+#[test]
+fn analysis_can_follow_the_current_chapter() {
+    let fixture = Fixture::write("analysis.book", BOOK);
+    let mut app = App::with_loaders(loaders());
+    app.load_file(fixture.as_str()).expect("the book loads");
+    app.state.current_tab = TAB_ANALYZE;
+
+    app.handle_key(KeyCode::Char('s'));
+    assert_eq!(app.state.analysis_scope, AnalysisScope::Page);
+    let analysis = app.state.page_analysis.as_ref().expect("chapter analysis");
+    assert_eq!(analysis.index, 0);
+    assert!(screen(&mut app).contains("Metrics · Harbours"));
+
+    app.handle_key(KeyCode::Char(']'));
+    app.handle_key(KeyCode::Char(']'));
+    app.state.current_tab = TAB_ANALYZE;
+    let analysis = app.state.page_analysis.as_ref().expect("chapter analysis");
+    assert_eq!(analysis.index, 1);
+    let words: Vec<&str> = analysis
+        .keywords
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect();
+    assert!(words.contains(&"ice"), "{words:?}");
+    assert!(
+        !words.contains(&"harbour"),
+        "only the second chapter counts"
+    );
+}
+
+#[test]
+fn the_json_lists_the_outline_and_places_matches_in_it() {
+    let fixture = Fixture::write("json.book", BOOK);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        search: Some("tide".to_string()),
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run_with_loaders(&request, &loaders(), &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    let outline = value["outline"].as_array().expect("an outline");
+    assert_eq!(outline.len(), 3);
+    assert_eq!(outline[1]["kind"], "heading");
+    assert_eq!(outline[1]["title"], "Tides");
+    assert_eq!(outline[1]["level"], 1);
+    assert_eq!(outline[0]["line_count"], 4);
+
+    let found = &value["search"]["matches"][0];
+    assert_eq!(found["line_number"], 4, "the heading itself");
+    assert_eq!(found["section"], "Tides", "the innermost section");
+    assert!(found["page"].is_null());
+}
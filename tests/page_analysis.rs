@@ -0,0 +1,198 @@
+//! Per-page analysis: each PDF page measured on its own, in the Analyze tab
+//! for the page under the viewer and in the JSON for every page and a range.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{json_request, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::{AnalysisScope, InputMode};
+use flerp::cli_values::parse_page_range;
+use flerp::headless::{run, HeadlessRequest};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// One topic per page, so each page's top keyword is known in advance.
+const PAGES: [&str; 3] = [
+    "Harbour cranes lift harbour cargo. The harbour never sleeps.",
+    "Orchard apples ripen. Orchard workers pick orchard fruit.",
+    "Glacier ice moves. The glacier carves valleys as glacier melt flows.",
+];
+
+fn write_topics_pdf(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+
+    let mut kids = Vec::new();
+    for text in PAGES {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 700.into()]),
+                Operation::new("Tj", vec![Object::string_literal(text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        kids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            },
+        })));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn request(file: &str) -> HeadlessRequest {
+    HeadlessRequest {
+        keyword_limit: 5,
+        ..json_request(file)
+    }
+}
+
+fn capture(request: &HeadlessRequest) -> Result<String, String> {
+    let mut buffer = Vec::new();
+    run(request, &mut buffer).map_err(|error| error.to_string())?;
+    Ok(String::from_utf8(buffer).expect("utf-8 output"))
+}
+
+fn top_keyword(analysis: &serde_json::Value) -> &str {
+    analysis["keywords"][0]["word"].as_str().unwrap_or_default()
+}
+
+#[test]
+fn json_pages_each_carry_their_own_analysis() {
+    let fixture = Fixture::with("json.pdf", write_topics_pdf);
+    let mut request = request(fixture.as_str());
+    request.page_range = Some(2..=3);
+
+    let value: serde_json::Value =
+        serde_json::from_str(&capture(&request).unwrap()).expect("valid JSON");
+    let pages = value["pages"].as_array().expect("page table");
+    let tops: Vec<&str> = pages.iter().map(|page| top_keyword(&page["analysis"])).collect();
+    assert_eq!(tops, ["harbour", "orchard", "glacier"]);
+    assert_eq!(pages[1]["analysis"]["readability"]["sentences"], 2);
+    assert_eq!(pages[1]["analysis"]["stats"]["words"], 8);
+
+    let range = &value["page_range"];
+    assert_eq!((range["first"].as_u64(), range["last"].as_u64()), (Some(2), Some(3)));
+    let words: Vec<&str> = range["analysis"]["keywords"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|keyword| keyword["word"].as_str())
+        .collect();
+    assert!(words.contains(&"orchard") && words.contains(&"glacier"), "{words:?}");
+    assert!(!words.contains(&"harbour"), "page 1 is outside the range");
+}
+
+#[test]
+fn text_mode_writes_only_the_requested_pages() {
+    let fixture = Fixture::with("text.pdf", write_topics_pdf);
+    let mut request = request(fixture.as_str());
+    request.json = false;
+    request.text = true;
+    request.page_range = Some(3..=3);
+
+    let text = capture(&request).unwrap();
+    assert!(text.contains("Glacier"), "{text}");
+    assert!(!text.contains("Orchard"), "{text}");
+}
+
+#[test]
+fn page_ranges_are_checked() {
+    assert_eq!(parse_page_range("3-7"), Ok(3..=7));
+    assert_eq!(parse_page_range("4"), Ok(4..=4));
+    assert!(parse_page_range("0-2").is_err(), "pages count from 1");
+    assert!(parse_page_range("5-2").is_err());
+    assert!(parse_page_range("two").is_err());
+
+    let fixture = Fixture::with("range.pdf", write_topics_pdf);
+    let mut request = request(fixture.as_str());
+    request.page_range = Some(2..=9);
+    let error = capture(&request).unwrap_err();
+    assert!(error.contains("3 page(s)"), "{error}");
+}
+
+#[test]
+fn the_analyze_tab_follows_the_viewer_page_by_page() {
+    let fixture = Fixture::with("tui.pdf", write_topics_pdf);
+    let mut app = App::new();
+    app.state.keyword_limit = 5;
+    app.load_file(fixture.as_str()).expect("fixture loads");
+    assert_eq!(app.state.keywords.len(), 5, "the document scope mixes every page");
+
+    app.handle_key(KeyCode::Char('s'));
+    assert_eq!(app.state.analysis_scope, AnalysisScope::Page);
+    let page = app.state.page_analysis.as_ref().expect("page analysis");
+    assert_eq!((page.index, page.keywords[0].word.as_str()), (0, "harbour"));
+
+    app.handle_key(KeyCode::Char(']'));
+    app.handle_key(KeyCode::Char(']'));
+    let page = app.state.page_analysis.as_ref().expect("page analysis");
+    assert_eq!((page.index, page.keywords[0].word.as_str()), (2, "glacier"));
+
+    app.handle_key(KeyCode::Char('s'));
+    assert!(app.state.page_analysis.is_none());
+}
+
+#[test]
+fn the_analyze_tab_measures_a_typed_page_range() {
+    let fixture = Fixture::with("tui-range.pdf", write_topics_pdf);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+
+    let type_range = |app: &mut App, range: &str| {
+        app.handle_key(KeyCode::Char('S'));
+        assert_eq!(app.state.input_mode, InputMode::PageRange);
+        for c in range.chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        app.handle_key(KeyCode::Enter);
+    };
+
+    type_range(&mut app, "2-3");
+    assert_eq!(app.state.analysis_scope, AnalysisScope::Pages { first: 2, last: 3 });
+    let range = app.state.page_analysis.as_ref().expect("range analysis");
+    assert_eq!((range.index, range.last), (1, 2));
+    let words: Vec<&str> = range.keywords.iter().map(|keyword| keyword.word.as_str()).collect();
+    assert!(words.contains(&"orchard") && words.contains(&"glacier"), "{words:?}");
+    assert!(!words.contains(&"harbour"), "page 1 is outside the range");
+
+    // Scrolling does not move a range the way it moves the current page.
+    app.handle_key(KeyCode::Char(']'));
+    assert_eq!(app.state.page_analysis.as_ref().map(|range| range.index), Some(1));
+
+    type_range(&mut app, "2-9");
+    assert!(app.state.status_message.contains("3 page(s)"), "{}", app.state.status_message);
+    assert_eq!(app.state.analysis_scope, AnalysisScope::Pages { first: 2, last: 3 });
+
+    type_range(&mut app, "");
+    assert_eq!(app.state.analysis_scope, AnalysisScope::Document);
+    assert!(app.state.page_analysis.is_none());
+}
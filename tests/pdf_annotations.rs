@@ -0,0 +1,188 @@
+//! Review marks and form data: sticky notes, highlights over page text and
+//! filled-in AcroForm fields are collected beside the text, searchable from the
+//! TUI and reported by the headless JSON.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{screen, Fixture};
+use flerp::app::App;
+use flerp::app_structs::TAB_SEARCH;
+use flerp::headless::{run, HeadlessRequest};
+use flerp::pdf_doc;
+use flerp::text_analysis::SearchOptions;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+fn write_reviewed_pdf(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let page_id = doc.new_object_id();
+
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let content = Content {
+        operations: vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 24.into()]),
+            Operation::new("Td", vec![72.into(), 700.into()]),
+            Operation::new("Tj", vec![Object::string_literal("Payment terms")]),
+            Operation::new("Td", vec![0.into(), (-50).into()]),
+            Operation::new("Tj", vec![Object::string_literal("Net thirty days")]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+    let note_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Text",
+        "Rect" => vec![20.into(), 700.into(), 40.into(), 720.into()],
+        "T" => Object::string_literal("Alice"),
+        "Contents" => Object::string_literal("Check with legal before signing"),
+    });
+    // One quad over the whole second line, corners in the spec's order.
+    let highlight_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Highlight",
+        "Rect" => vec![60.into(), 645.into(), 400.into(), 675.into()],
+        "QuadPoints" => vec![
+            60.into(), 675.into(), 400.into(), 675.into(),
+            60.into(), 645.into(), 400.into(), 645.into(),
+        ],
+        "T" => Object::string_literal("Bob"),
+    });
+    let link_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Link",
+        "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+    });
+    let field_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Widget",
+        "FT" => "Tx",
+        "T" => Object::string_literal("name"),
+        "V" => Object::string_literal("Ada Lovelace"),
+        "P" => page_id,
+        "Rect" => vec![72.into(), 500.into(), 300.into(), 520.into()],
+    });
+    let client_id = doc.add_object(dictionary! {
+        "T" => Object::string_literal("client"),
+        "Kids" => vec![Object::Reference(field_id)],
+    });
+
+    doc.objects.insert(
+        page_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            },
+            "Annots" => vec![
+                Object::Reference(note_id),
+                Object::Reference(highlight_id),
+                Object::Reference(link_id),
+                Object::Reference(field_id),
+            ],
+        }),
+    );
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+        "AcroForm" => dictionary! { "Fields" => vec![Object::Reference(client_id)] },
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+#[test]
+fn notes_highlights_and_fields_are_collected_apart_from_the_text() {
+    let fixture = Fixture::with("collect.pdf", write_reviewed_pdf);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    // The page text stays the document's own words.
+    assert!(!document.text.contains("legal"), "{:?}", document.text);
+
+    let kinds: Vec<&str> = document.annotations.iter().map(|note| note.kind.as_str()).collect();
+    assert_eq!(kinds, ["Text", "Highlight"], "links and widgets are not review notes");
+
+    let note = &document.annotations[0];
+    assert_eq!(note.page, 1);
+    assert_eq!(note.author.as_deref(), Some("Alice"));
+    assert_eq!(note.contents.as_deref(), Some("Check with legal before signing"));
+
+    let highlight = &document.annotations[1];
+    assert_eq!(highlight.highlighted.as_deref(), Some("Net thirty days"));
+
+    assert_eq!(document.form_fields.len(), 1);
+    let field = &document.form_fields[0];
+    assert_eq!(field.name, "client.name");
+    assert_eq!(field.kind, "Tx");
+    assert_eq!(field.value.as_deref(), Some("Ada Lovelace"));
+    assert_eq!(field.page, Some(1));
+}
+
+#[test]
+fn search_filters_the_notes_panel() {
+    let fixture = Fixture::with("search.pdf", write_reviewed_pdf);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+    assert_eq!(app.state.note_matches.len(), 3, "an empty query lists every note");
+
+    app.state.search_query = "lovelace".to_string();
+    app.state.case_sensitive = false;
+    app.update_search();
+    assert!(app.state.search_results.is_empty(), "field values are not page text");
+    assert_eq!(app.state.note_matches.len(), 1);
+    assert_eq!(app.state.note_matches[0].label, "Field client.name");
+
+    app.state.current_tab = TAB_SEARCH;
+    let rendered = screen(&mut app);
+    assert!(rendered.contains("Notes & Fields (1)"));
+    assert!(rendered.contains("Ada Lovelace"));
+}
+
+#[test]
+fn headless_json_lists_notes_and_their_search_matches() {
+    let fixture = Fixture::with("json.pdf", write_reviewed_pdf);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        search: Some("legal".to_string()),
+        search_options: SearchOptions {
+            case_sensitive: true,
+            ..SearchOptions::default()
+        },
+        ..HeadlessRequest::default()
+    };
+
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    assert_eq!(value["annotations"].as_array().map(Vec::len), Some(2));
+    assert_eq!(value["annotations"][1]["highlighted"], "Net thirty days");
+    assert_eq!(value["form_fields"][0]["name"], "client.name");
+    assert_eq!(value["form_fields"][0]["value"], "Ada Lovelace");
+
+    let notes = value["search"]["note_matches"].as_array().expect("note matches");
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["label"], "Text · Alice");
+    assert_eq!(notes[0]["page"], 1);
+}
@@ -0,0 +1,282 @@
+//! Files embedded in a PDF: listed with name, size and type, openable in the
+//! TUI when flerp can read them, and written out by `--extract-attachments`.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::{request, Fixture, TempDir};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::InputMode;
+use flerp::headless::run;
+use flerp::pdf_attachments::{write_attachments, Attachment};
+use flerp::pdf_doc;
+use lopdf::content::{Content, Operation};
+use lopdf::{
+    dictionary, Document, EncryptionState, EncryptionVersion, Object, Permissions, Stream,
+    StringFormat,
+};
+
+const INVOICE_XML: &str = "<Invoice>\n  <Total>42.00</Total>\n</Invoice>\n";
+const BINARY: [u8; 6] = [0x00, 0xff, 0xfe, 0x80, 0x81, 0x00];
+const PASSWORD: &str = "open sesame";
+
+fn portfolio() -> Document {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let content = Content {
+        operations: vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 24.into()]),
+            Operation::new("Td", vec![72.into(), 700.into()]),
+            Operation::new("Tj", vec![Object::string_literal("Invoice 2024-001")]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+    // Compressed, as real invoices ship their XML.
+    let mut xml = Stream::new(
+        dictionary! {
+            "Type" => "EmbeddedFile",
+            "Subtype" => Object::Name(b"text/xml".to_vec()),
+        },
+        INVOICE_XML.as_bytes().to_vec(),
+    );
+    xml.compress().unwrap();
+    let xml_id = doc.add_object(xml);
+    let xml_spec_id = doc.add_object(dictionary! {
+        "Type" => "Filespec",
+        "F" => Object::string_literal("factur-x.xml"),
+        "UF" => Object::String(
+            // UTF-16BE with BOM, as `/UF` is meant to be written.
+            [0xfe, 0xff]
+                .into_iter()
+                .chain("factur-x.xml".encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+            StringFormat::Literal,
+        ),
+        "Desc" => Object::string_literal("Machine-readable invoice"),
+        "EF" => dictionary! { "F" => Object::Reference(xml_id) },
+    });
+
+    let blob_id = doc.add_object(Stream::new(dictionary! { "Type" => "EmbeddedFile" }, BINARY.to_vec()));
+    let blob_spec_id = doc.add_object(dictionary! {
+        "Type" => "Filespec",
+        // A hostile name must not steer where extraction writes.
+        "F" => Object::string_literal("../../payload.bin"),
+        "EF" => dictionary! { "F" => Object::Reference(blob_id) },
+    });
+    let annot_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "FileAttachment",
+        "Rect" => vec![0.into(), 0.into(), 20.into(), 20.into()],
+        "FS" => Object::Reference(blob_spec_id),
+    });
+    // The same XML attached to the page as well: listed once.
+    let duplicate_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "FileAttachment",
+        "Rect" => vec![20.into(), 0.into(), 40.into(), 20.into()],
+        "FS" => Object::Reference(xml_spec_id),
+    });
+
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Resources" => dictionary! {
+            "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+        },
+        "Annots" => vec![Object::Reference(annot_id), Object::Reference(duplicate_id)],
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+        "Names" => dictionary! {
+            "EmbeddedFiles" => dictionary! {
+                "Names" => vec![
+                    Object::string_literal("factur-x.xml"),
+                    Object::Reference(xml_spec_id),
+                ],
+            },
+        },
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc
+}
+
+fn write_portfolio(path: &PathBuf) {
+    portfolio().save(path).expect("fixture PDF should save");
+}
+
+/// The portfolio behind [`PASSWORD`].
+fn write_locked_portfolio(path: &PathBuf) {
+    let mut doc = portfolio();
+    // The file identifier is an input to the key derivation.
+    let id = Object::string_literal(b"flerp-portfolio-id".to_vec());
+    doc.trailer.set("ID", Object::Array(vec![id.clone(), id]));
+    let state = EncryptionState::try_from(EncryptionVersion::V2 {
+        document: &doc,
+        owner_password: "owner",
+        user_password: PASSWORD,
+        key_length: 128,
+        permissions: Permissions::all(),
+    })
+    .expect("encryption state");
+    doc.encrypt(&state).expect("encrypt fixture");
+    doc.save(path).expect("fixture PDF should save");
+}
+
+#[test]
+fn embedded_files_are_listed_with_name_size_and_type() {
+    let fixture = Fixture::with("list.pdf", write_portfolio);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    assert_eq!(document.attachments.len(), 2, "the XML is listed once");
+
+    let xml = &document.attachments[0];
+    assert_eq!(xml.name, "factur-x.xml");
+    assert_eq!(xml.description.as_deref(), Some("Machine-readable invoice"));
+    assert_eq!(xml.mime_type.as_deref(), Some("text/xml"));
+    assert_eq!(xml.size, INVOICE_XML.len(), "size is of the decompressed file");
+    assert_eq!(xml.page, None);
+    assert_eq!(xml.data, INVOICE_XML.as_bytes());
+
+    let blob = &document.attachments[1];
+    assert_eq!(blob.page, Some(1));
+    assert_eq!(blob.mime_type, None, "no /Subtype and no extension to guess from");
+    assert_eq!(blob.size, BINARY.len());
+}
+
+#[test]
+fn extract_attachments_writes_each_file_inside_the_directory() {
+    let fixture = Fixture::with("extract.pdf", write_portfolio);
+    let out = TempDir::new("extract");
+    let mut request = request(fixture.as_str());
+    request.extract_attachments = Some(out.path.clone());
+    request.json = true;
+
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    assert_eq!(value["attachments"][0]["name"], "factur-x.xml");
+    assert_eq!(value["attachments"][0]["mime_type"], "text/xml");
+    assert_eq!(value["attachments"][1]["page"], 1);
+    assert!(value["attachments"][0].get("data").is_none(), "bytes stay out of the JSON");
+    assert_eq!(value["extracted_attachments"].as_array().map(Vec::len), Some(2));
+
+    assert_eq!(fs::read_to_string(out.path.join("factur-x.xml")).unwrap(), INVOICE_XML);
+    assert_eq!(fs::read(out.path.join("payload.bin")).unwrap(), BINARY);
+}
+
+#[test]
+fn extract_attachments_refuses_a_file_that_is_not_a_pdf() {
+    let path = std::env::temp_dir().join(format!("flerp-attachments-{}.txt", std::process::id()));
+    fs::write(&path, "plain text").unwrap();
+    let out = TempDir::new("text");
+    let mut request = request(path.to_str().unwrap());
+    request.extract_attachments = Some(out.path.clone());
+
+    let result = run(&request, &mut Vec::new());
+    let _ = fs::remove_file(&path);
+    assert!(result.is_err());
+}
+
+#[test]
+fn extracting_never_overwrites_a_file() {
+    let out = TempDir::new("clash");
+    fs::create_dir_all(&out.path).unwrap();
+    fs::write(out.path.join("notes.txt"), "already here").unwrap();
+    let attachment = |name: &str, data: &str| Attachment {
+        name: name.to_string(),
+        description: None,
+        mime_type: None,
+        size: data.len(),
+        page: None,
+        data: data.as_bytes().to_vec(),
+    };
+    let attachments = [
+        attachment("03-a.txt", "first"),
+        attachment("a.txt", "second"),
+        attachment("a.txt", "third"),
+        attachment("notes.txt", "fourth"),
+    ];
+
+    let written = write_attachments(&attachments, &out.path).expect("all written");
+    let names: Vec<String> = written
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, ["03-a.txt", "a.txt", "02-a.txt", "02-notes.txt"]);
+    for (path, attachment) in written.iter().zip(&attachments) {
+        assert_eq!(fs::read(path).unwrap(), attachment.data);
+    }
+    assert_eq!(fs::read_to_string(out.path.join("notes.txt")).unwrap(), "already here");
+}
+
+#[test]
+fn the_tui_opens_a_readable_attachment_and_returns_to_the_pdf() {
+    let fixture = Fixture::with("open.pdf", write_portfolio);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("fixture loads");
+    assert!(app.state.file_content.contains("Invoice 2024-001"));
+
+    app.handle_key(KeyCode::Char('o'));
+    assert!(app.state.file_content.contains("<Total>42.00</Total>"), "{}", app.state.status_message);
+    assert!(app.state.status_message.contains("factur-x.xml"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let staged = PathBuf::from(&app.state.file_name);
+        let directory = fs::metadata(staged.parent().unwrap()).unwrap();
+        assert_eq!(directory.permissions().mode() & 0o777, 0o700, "only we can look inside");
+    }
+
+    app.handle_key(KeyCode::Backspace);
+    assert!(app.state.file_content.contains("Invoice 2024-001"));
+    assert!(app.state.attachment_parents.is_empty());
+
+    // The binary attachment is listed but not something flerp can show.
+    app.handle_key(KeyCode::Char('a'));
+    assert_eq!(app.state.selected_attachment, 1);
+    app.handle_key(KeyCode::Char('o'));
+    assert!(app.state.file_content.contains("Invoice 2024-001"));
+    assert!(app.state.status_message.contains("--extract-attachments"));
+}
+
+#[test]
+fn returning_from_an_attachment_reopens_a_locked_pdf_without_asking_again() {
+    let fixture = Fixture::with("locked.pdf", write_locked_portfolio);
+    let mut app = App::new();
+    app.load_file_with_password(fixture.as_str(), Some(PASSWORD))
+        .expect("fixture loads");
+    assert!(app.state.file_content.contains("Invoice 2024-001"));
+
+    app.handle_key(KeyCode::Char('o'));
+    assert!(app.state.file_content.contains("<Total>42.00</Total>"), "{}", app.state.status_message);
+
+    app.handle_key(KeyCode::Backspace);
+    assert_eq!(app.state.input_mode, InputMode::Normal, "{}", app.state.status_message);
+    assert!(app.state.file_content.contains("Invoice 2024-001"));
+    assert_eq!(app.state.file_name, fixture.as_str());
+}
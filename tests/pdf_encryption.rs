@@ -0,0 +1,195 @@
+//! Password-protected PDFs: decrypted for text and images alike, and a clear
+//! answer when the password is missing or wrong.
+//!
+//! The fixtures are encrypted here with `lopdf` rather than checked in, so both
+//! cipher families are covered without shipping binary files.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use common::Fixture;
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::InputMode;
+use flerp::pdf_doc::{self, PasswordError};
+use lopdf::content::{Content, Operation};
+use lopdf::encryption::crypt_filters::{Aes128CryptFilter, CryptFilter};
+use lopdf::{
+    dictionary, Document, EncryptionState, EncryptionVersion, Object, Permissions, Stream,
+};
+
+const USER_PASSWORD: &str = "open sesame";
+const OWNER_PASSWORD: &str = "owner";
+
+/// 1x1 red pixel, so the image path is exercised through decryption too.
+const RED: [u8; 3] = [255, 0, 0];
+
+#[derive(Clone, Copy)]
+enum Cipher {
+    Rc4,
+    Aes128,
+}
+
+fn plain_document() -> Document {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let image_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 1,
+            "Height" => 1,
+            "ColorSpace" => "DeviceRGB",
+            "BitsPerComponent" => 8,
+        },
+        RED.to_vec(),
+    ));
+
+    let content = Content {
+        operations: vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 24.into()]),
+            Operation::new("Td", vec![72.into(), 700.into()]),
+            Operation::new("Tj", vec![Object::string_literal("Secret page text")]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Resources" => dictionary! {
+            "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            "XObject" => dictionary! { "Im0" => Object::Reference(image_id) },
+        },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    // The file identifier is an input to the key derivation.
+    doc.trailer.set(
+        "ID",
+        Object::Array(vec![
+            Object::string_literal(b"flerp-fixture-id".to_vec()),
+            Object::string_literal(b"flerp-fixture-id".to_vec()),
+        ]),
+    );
+    doc
+}
+
+fn write_encrypted(path: &PathBuf, cipher: Cipher) {
+    let mut doc = plain_document();
+
+    let version = match cipher {
+        Cipher::Rc4 => EncryptionVersion::V2 {
+            document: &doc,
+            owner_password: OWNER_PASSWORD,
+            user_password: USER_PASSWORD,
+            key_length: 128,
+            permissions: Permissions::all(),
+        },
+        Cipher::Aes128 => {
+            let filter: Arc<dyn CryptFilter> = Arc::new(Aes128CryptFilter);
+            EncryptionVersion::V4 {
+                document: &doc,
+                encrypt_metadata: true,
+                crypt_filters: BTreeMap::from([(b"StdCF".to_vec(), filter)]),
+                stream_filter: b"StdCF".to_vec(),
+                string_filter: b"StdCF".to_vec(),
+                owner_password: OWNER_PASSWORD,
+                user_password: USER_PASSWORD,
+                permissions: Permissions::all(),
+            }
+        }
+    };
+    let state = EncryptionState::try_from(version).expect("encryption state");
+    doc.encrypt(&state).expect("encrypt fixture");
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn locked(name: &str, cipher: Cipher) -> Fixture {
+    Fixture::with(&format!("{name}.pdf"), |path| write_encrypted(path, cipher))
+}
+
+fn password_error(result: Result<pdf_doc::PdfDocument, Box<dyn std::error::Error>>) -> PasswordError {
+    match result {
+        Ok(_) => panic!("the locked PDF opened without the right password"),
+        Err(error) => *error
+            .downcast_ref::<PasswordError>()
+            .unwrap_or_else(|| panic!("expected a password error, got {error}")),
+    }
+}
+
+#[test]
+fn the_right_password_decrypts_text_and_images() {
+    for (name, cipher) in [("rc4", Cipher::Rc4), ("aes", Cipher::Aes128)] {
+        let fixture = locked(name, cipher);
+        let document = pdf_doc::load_with_password(fixture.as_str(), Some(USER_PASSWORD))
+            .unwrap_or_else(|error| panic!("{name}: {error}"));
+
+        assert!(document.text.contains("Secret page text"), "{name}: {:?}", document.text);
+        assert_eq!(document.images.len(), 1, "{name}: image not decrypted");
+        assert_eq!(document.images[0].image.to_rgb8().get_pixel(0, 0).0, RED);
+    }
+}
+
+#[test]
+fn a_missing_or_wrong_password_is_reported_as_such() {
+    let fixture = locked("wrong", Cipher::Aes128);
+
+    assert_eq!(password_error(pdf_doc::load(fixture.as_str())), PasswordError::Required);
+    assert_eq!(
+        password_error(pdf_doc::load_with_password(fixture.as_str(), Some("guess"))),
+        PasswordError::Incorrect
+    );
+}
+
+#[test]
+fn the_tui_prompts_for_the_password_and_retries_on_a_wrong_one() {
+    let fixture = locked("prompt", Cipher::Rc4);
+    let mut app = App::new();
+
+    app.load_file(fixture.as_str()).expect("a locked file opens the prompt");
+    assert_eq!(app.state.input_mode, InputMode::Password);
+    assert!(app.state.file_content.is_empty());
+
+    for c in "guess".chars() {
+        app.handle_key(KeyCode::Char(c));
+    }
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(app.state.input_mode, InputMode::Password, "wrong password re-prompts");
+    assert!(app.state.status_message.contains("Wrong password"));
+    assert!(app.state.password_buffer.is_empty());
+
+    for c in USER_PASSWORD.chars() {
+        app.handle_key(KeyCode::Char(c));
+    }
+    app.handle_key(KeyCode::Enter);
+    assert_eq!(app.state.input_mode, InputMode::Normal);
+    assert!(app.state.file_content.contains("Secret page text"));
+    assert_eq!(app.state.media.len(), 1);
+    assert!(app.state.locked_file.is_none());
+}
@@ -0,0 +1,164 @@
+//! Running headers, footers and page numbers: found on every page of a short
+//! report, dimmed in place, and left out of the analysis on request.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{json_request, Fixture};
+use flerp::app::App;
+use flerp::headless::{run, HeadlessRequest};
+use flerp::pdf_doc;
+use flerp::pdf_furniture::FurnitureKind;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+const HEADER: &str = "Northwind Quarterly Review";
+const BODIES: [&str; 3] = [
+    "Revenue grew in every region",
+    "Margins held despite freight costs",
+    "The outlook for spring is steady",
+];
+
+fn write_report(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+
+    let mut kids = Vec::new();
+    for (index, body) in BODIES.iter().enumerate() {
+        let footer = format!("Page {} of {}", index + 1, BODIES.len());
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+        ];
+        for (y, text) in [(750, HEADER), (600, body), (40, footer.as_str())] {
+            operations.push(Operation::new(
+                "Tm",
+                vec![1.into(), 0.into(), 0.into(), 1.into(), 72.into(), y.into()],
+            ));
+            operations.push(Operation::new("Tj", vec![Object::string_literal(text)]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        let content = Content { operations };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            },
+        });
+        kids.push(Object::Reference(page_id));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn headless_json(file: &str, strip_furniture: bool) -> serde_json::Value {
+    let request = HeadlessRequest {
+        strip_furniture,
+        ..json_request(file)
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    serde_json::from_slice(&buffer).expect("valid JSON")
+}
+
+#[test]
+fn every_page_has_its_header_and_page_number_found() {
+    let fixture = Fixture::with("detect.pdf", write_report);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    let lines: Vec<&str> = document.text.lines().collect();
+    let found: Vec<(&str, FurnitureKind)> = document
+        .furniture
+        .iter()
+        .map(|entry| (lines[entry.line].trim(), entry.kind))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (HEADER, FurnitureKind::Header),
+            ("Page 1 of 3", FurnitureKind::PageNumber),
+            (HEADER, FurnitureKind::Header),
+            ("Page 2 of 3", FurnitureKind::PageNumber),
+            (HEADER, FurnitureKind::Header),
+            ("Page 3 of 3", FurnitureKind::PageNumber),
+        ],
+        "{}",
+        document.text
+    );
+
+    let stripped = document.text_without_furniture();
+    assert!(!stripped.contains(HEADER) && !stripped.contains("Page 2"));
+    for body in BODIES {
+        assert!(stripped.contains(body), "{stripped}");
+    }
+}
+
+#[test]
+fn strip_headers_keeps_the_header_out_of_the_headless_analysis() {
+    let fixture = Fixture::with("json.pdf", write_report);
+
+    let kept = headless_json(fixture.as_str(), false);
+    assert_eq!(kept["furniture_excluded"], false);
+    assert_eq!(kept["repeated_lines"][0]["line"], HEADER);
+    assert_eq!(kept["furniture"].as_array().map(Vec::len), Some(6));
+    assert_eq!(kept["furniture"][1]["kind"], "page_number");
+    assert_eq!(kept["furniture"][1]["page"], 1);
+    assert_eq!(kept["furniture"][2]["page"], 2);
+
+    let stripped = headless_json(fixture.as_str(), true);
+    assert_eq!(stripped["furniture_excluded"], true);
+    assert_eq!(stripped["repeated_lines"].as_array().map(Vec::len), Some(0));
+    assert_eq!(stripped["furniture"], kept["furniture"], "still listed");
+    let boundaries = |value: &serde_json::Value| -> Vec<(serde_json::Value, serde_json::Value)> {
+        value["pages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|page| (page["start_line"].clone(), page["line_count"].clone()))
+            .collect()
+    };
+    assert_eq!(boundaries(&stripped), boundaries(&kept), "the text itself is untouched");
+    assert_eq!(
+        stripped["pages"][0]["analysis"]["stats"]["words"], 5,
+        "each page is measured without its header and number"
+    );
+}
+
+#[test]
+fn the_app_leaves_furniture_out_of_metrics_but_not_the_viewer() {
+    let fixture = Fixture::with("app.pdf", write_report);
+    let mut app = App::new();
+    app.state.strip_furniture = true;
+    app.load_file(fixture.as_str()).expect("fixture loads");
+
+    assert!(app.state.file_content.contains(HEADER));
+    assert!(app.state.repeated_lines.is_empty(), "{:?}", app.state.repeated_lines);
+    assert!(app
+        .state
+        .keywords
+        .iter()
+        .all(|keyword| !keyword.word.eq_ignore_ascii_case("northwind")));
+}
@@ -4,8 +4,11 @@
 //! The fixture PDF is built here with `lopdf` rather than checked in, so the
 //! expected pixels are stated next to the bytes that produce them.
 
+mod common;
+
 use std::path::PathBuf;
 
+use common::Fixture;
 use flerp::pdf_doc;
 use lopdf::content::{Content, Operation};
 use lopdf::{dictionary, Document, Object, Stream};
@@ -144,27 +147,9 @@ fn write_fixture(path: &PathBuf) {
     doc.save(path).expect("fixture PDF should save");
 }
 
-struct Fixture {
-    path: PathBuf,
-}
-
-impl Fixture {
-    fn new(name: &str) -> Self {
-        let path = std::env::temp_dir().join(format!("flerp-{name}-{}.pdf", std::process::id()));
-        write_fixture(&path);
-        Self { path }
-    }
-}
-
-impl Drop for Fixture {
-    fn drop(&mut self) {
-        let _ = std::fs::remove_file(&self.path);
-    }
-}
-
 #[test]
 fn reads_every_page_and_tracks_where_each_one_starts() {
-    let fixture = Fixture::new("pages");
+    let fixture = Fixture::with("pages.pdf", write_fixture);
     let document = pdf_doc::load(fixture.path.to_str().unwrap()).expect("PDF should load");
 
     assert_eq!(document.page_count(), 2);
@@ -193,7 +178,7 @@ fn reads_every_page_and_tracks_where_each_one_starts() {
 
 #[test]
 fn decodes_embedded_images_to_exact_pixels() {
-    let fixture = Fixture::new("images");
+    let fixture = Fixture::with("images.pdf", write_fixture);
     let document = pdf_doc::load(fixture.path.to_str().unwrap()).expect("PDF should load");
 
     assert!(
@@ -0,0 +1,138 @@
+//! Layout extraction: a two-column page read column by column,
+//! against the content-stream order the default mode keeps.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::Fixture;
+use flerp::app::App;
+use flerp::loader::LoadOptions;
+use flerp::pdf_doc::{self, ExtractionMode, PdfOptions};
+use flerp::settings::AppSettings;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+const LEFT: [&str; 4] = [
+    "Alpha paragraph opens the left column",
+    "and keeps going in the left column",
+    "until the left column is nearly done",
+    "with its final left column sentence",
+];
+const RIGHT: [&str; 4] = [
+    "Omega paragraph opens the right column",
+    "and it continues down the right side",
+    "with more of the right column prose",
+    "closing the right column for good",
+];
+
+/// Draws each row left then right, the way a typesetter working line by line
+/// would, so content-stream order interleaves the two columns.
+fn write_two_column_pdf(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+
+    let mut operations = vec![
+        Operation::new("BT", vec![]),
+        Operation::new("Tf", vec!["F1".into(), 10.into()]),
+    ];
+    for (row, (left, right)) in LEFT.iter().zip(RIGHT).enumerate() {
+        let y = 700 - row as i64 * 14;
+        for (x, text) in [(72, *left), (320, right)] {
+            operations.push(Operation::new(
+                "Tm",
+                vec![1.into(), 0.into(), 0.into(), 1.into(), x.into(), y.into()],
+            ));
+            operations.push(Operation::new("Tj", vec![Object::string_literal(text)]));
+        }
+    }
+    operations.push(Operation::new("ET", vec![]));
+    let content = Content { operations };
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Resources" => dictionary! {
+            "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+        },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![Object::Reference(page_id)],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn layout() -> LoadOptions<'static> {
+    LoadOptions::default().with(PdfOptions {
+        extraction: ExtractionMode::Layout,
+        ..PdfOptions::default()
+    })
+}
+
+#[test]
+fn layout_mode_reads_each_column_to_the_bottom() {
+    let fixture = Fixture::with("layout.pdf", write_two_column_pdf);
+    let document = pdf_doc::load_with_options(fixture.as_str(), &layout()).expect("fixture loads");
+
+    let lines: Vec<&str> = document.text.lines().filter(|line| !line.is_empty()).collect();
+    let expected: Vec<&str> = LEFT.iter().chain(RIGHT.iter()).copied().collect();
+    assert_eq!(lines, expected, "{}", document.text);
+}
+
+#[test]
+fn flow_mode_still_follows_the_content_stream() {
+    let fixture = Fixture::with("flow.pdf", write_two_column_pdf);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+
+    let alpha = document.text.find("Alpha").expect("left column text");
+    let omega = document.text.find("Omega").expect("right column text");
+    let keeps = document.text.find("and keeps going").expect("second left row");
+    assert!(alpha < omega && omega < keeps, "{}", document.text);
+}
+
+#[test]
+fn the_app_loads_with_its_extraction_setting() {
+    let fixture = Fixture::with("app.pdf", write_two_column_pdf);
+    let mut app = App::new();
+    app.state.extraction_mode = ExtractionMode::Layout;
+    app.load_file(fixture.as_str()).expect("fixture loads");
+
+    let left_end = app.state.file_content.find("final left column").unwrap();
+    let right_start = app.state.file_content.find("Omega").unwrap();
+    assert!(left_end < right_start, "{}", app.state.file_content);
+}
+
+#[test]
+fn settings_saved_before_the_extraction_row_still_load() {
+    let old = r#"
+        theme = "tokyo-night"
+        keyword_limit = 12
+        preview_line_count = 50
+        case_sensitive = false
+        regex_mode = false
+        whole_word = false
+        line_numbers = true
+        wrap_lines = false
+    "#;
+    let settings: AppSettings = toml::from_str(old).expect("old settings parse");
+    assert_eq!(settings.keyword_limit, 12, "the other settings survive");
+    assert_eq!(settings.extraction, ExtractionMode::Flow);
+}
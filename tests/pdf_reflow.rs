@@ -0,0 +1,190 @@
+//! Reflow: a PDF typeset with hard line breaks and hyphenated words read back
+//! as paragraphs, page by page, with its page numbers still on their own line.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::Fixture;
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::headless::{run, HeadlessRequest};
+use flerp::loader::LoadOptions;
+use flerp::pdf_doc::{self, PdfOptions};
+use flerp::pdf_furniture::FurnitureKind;
+use flerp::settings::AppSettings;
+use flerp::text_analysis::SearchOptions;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// Each page as typeset: a paragraph broken across lines, then its number.
+const PAGES: [&[&str]; 2] = [
+    &[
+        "The survey collected detailed informa-",
+        "tion about commuting habits in the region",
+        "and how they changed over the decade.",
+        "1",
+    ],
+    &[
+        "Most respondents now cycle at least once",
+        "a week when the weather allows it.",
+        "2",
+    ],
+];
+
+fn write_typeset_pdf(path: &PathBuf) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+
+    let mut kids = Vec::new();
+    for lines in PAGES {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 11.into()]),
+        ];
+        for (row, line) in lines.iter().enumerate() {
+            // The page number sits at the foot of the page.
+            let y = if row + 1 == lines.len() { 40 } else { 700 - row as i64 * 14 };
+            operations.push(Operation::new(
+                "Tm",
+                vec![1.into(), 0.into(), 0.into(), 1.into(), 72.into(), y.into()],
+            ));
+            operations.push(Operation::new("Tj", vec![Object::string_literal(*line)]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        let content = Content { operations };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        kids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            },
+        })));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn non_empty_lines(text: &str) -> Vec<&str> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty()).collect()
+}
+
+#[test]
+fn reflow_joins_paragraphs_and_keeps_pages_and_page_numbers_apart() {
+    let fixture = Fixture::with("load.pdf", write_typeset_pdf);
+    let options = LoadOptions::default().with(PdfOptions {
+        reflow: true,
+        ..PdfOptions::default()
+    });
+    let document = pdf_doc::load_with_options(fixture.as_str(), &options).expect("fixture loads");
+
+    assert_eq!(
+        non_empty_lines(&document.text),
+        [
+            "The survey collected detailed information about commuting habits in the region and how they changed over the decade.",
+            "1",
+            "Most respondents now cycle at least once a week when the weather allows it.",
+            "2",
+        ],
+        "{}",
+        document.text
+    );
+
+    let lines: Vec<&str> = document.text.lines().collect();
+    let second = &document.pages[1];
+    assert!(lines[second.start_line].starts_with("Most respondents"));
+    assert_eq!(document.page_of_line(second.start_line), 1);
+
+    let numbers: Vec<&str> = document
+        .furniture
+        .iter()
+        .filter(|entry| entry.kind == FurnitureKind::PageNumber)
+        .map(|entry| lines[entry.line].trim())
+        .collect();
+    assert_eq!(numbers, ["1", "2"], "furniture moved with the reflowed lines");
+}
+
+#[test]
+fn without_reflow_the_lines_stay_as_typeset() {
+    let fixture = Fixture::with("raw.pdf", write_typeset_pdf);
+    let document = pdf_doc::load(fixture.as_str()).expect("fixture loads");
+    assert!(document.text.contains("informa-"), "{}", document.text);
+}
+
+#[test]
+fn headless_whole_word_search_finds_a_word_split_across_lines() {
+    let fixture = Fixture::with("json.pdf", write_typeset_pdf);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        reflow: true,
+        search: Some("information".to_string()),
+        search_options: SearchOptions {
+            case_sensitive: true,
+            whole_word: true,
+            ..SearchOptions::default()
+        },
+        ..HeadlessRequest::default()
+    };
+
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+    assert_eq!(value["reflow"], true);
+    assert_eq!(value["search"]["match_count"], 1);
+    assert_eq!(value["search"]["matches"][0]["page"], 1);
+}
+
+#[test]
+fn the_app_reads_with_its_reflow_setting() {
+    let fixture = Fixture::with("app.pdf", write_typeset_pdf);
+    let mut app = App::new();
+    app.state.reflow = true;
+    app.load_file(fixture.as_str()).expect("fixture loads");
+    assert!(app.state.file_content.contains("detailed information about"));
+
+    let old = r#"
+        theme = "tokyo-night"
+        keyword_limit = 10
+        preview_line_count = 50
+        case_sensitive = true
+        regex_mode = false
+        whole_word = false
+        line_numbers = true
+        wrap_lines = false
+    "#;
+    let settings: AppSettings = toml::from_str(old).expect("old settings parse");
+    assert!(!settings.reflow, "files saved before the setting read as typeset");
+}
+
+#[test]
+fn p_leaves_the_setting_alone_without_a_pdf() {
+    let fixture = Fixture::write("notes.txt", "Plain words, no pages.\n");
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("text loads");
+    let reflow = app.state.reflow;
+
+    app.handle_key(KeyCode::Char('p'));
+    assert_eq!(app.state.reflow, reflow);
+    assert_eq!(app.state.status_message, "Reflow needs a PDF.");
+}
@@ -0,0 +1,168 @@
+//! Source files: coloured by syntax in the Viewer, measured as code in
+//! Analyze and `--json`, with keywords taken from comments and identifiers.
+
+mod common;
+
+use common::{draw, rows, screen, Fixture};
+use flerp::app::App;
+use flerp::app_structs::{TAB_ANALYZE, TAB_VIEWER};
+use flerp::headless::{run, HeadlessRequest};
+use flerp::source_code::Language;
+use ratatui::style::Color;
+
+const RUST: &str = r#"// Reads the request line of an HTTP message.
+use std::io;
+
+/* The verbs a parser accepts. */
+const VERBS: [&str; 2] = ["GET", "POST"];
+
+fn parse_request_line(line: &str) -> io::Result<String> {
+    let verb = line.split(' ').next().unwrap_or("");
+    if VERBS.contains(&verb) {
+        return Ok(verb.to_string());
+    }
+    Err(io::Error::other("unknown verb"))
+}
+
+fn main() {
+    let parsedRequest = parse_request_line("GET /");
+    println!("{parsedRequest:?}");
+}
+"#;
+
+/// Foreground and background of the first character of `needle`, on the
+/// first row showing it.
+fn colour_of(app: &mut App, needle: &str) -> (Color, Color) {
+    let buffer = draw(app);
+    let colour = rows(&buffer)
+        .find_map(|(text, cells)| {
+            let column = text[..text.find(needle)?].chars().count();
+            Some((cells[column].fg, cells[column].bg))
+        })
+        .expect("the text is on screen");
+    colour
+}
+
+fn load(name: &str, contents: &str) -> App {
+    let fixture = Fixture::write(name, contents);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the file loads");
+    app
+}
+
+#[test]
+fn the_viewer_colours_code_by_what_it_is() {
+    let mut app = load("colour.rs", RUST);
+    assert!(
+        app.state.status_message.contains("Rust, 13 lines of code"),
+        "{}",
+        app.state.status_message
+    );
+    app.state.current_tab = TAB_VIEWER;
+
+    let keyword = colour_of(&mut app, "fn parse_request_line").0;
+    let plain = colour_of(&mut app, "line: &str").0;
+    let comment = colour_of(&mut app, "// Reads").0;
+    let string = colour_of(&mut app, "\"unknown verb\"").0;
+    let call = colour_of(&mut app, "parse_request_line(\"GET").0;
+    let all = [keyword, plain, comment, string, call];
+    for (index, colour) in all.iter().enumerate() {
+        assert!(
+            !all[index + 1..].contains(colour),
+            "each kind has its own colour: {all:?}"
+        );
+    }
+    assert_eq!(
+        colour_of(&mut app, "The verbs").0,
+        comment,
+        "block comments too"
+    );
+
+    // Search matches still stand out, whatever they fall in.
+    app.state.case_sensitive = false;
+    app.set_search_query("unknown");
+    app.state.current_tab = TAB_VIEWER;
+    let (_, background) = colour_of(&mut app, "unknown verb");
+    assert_ne!(background, colour_of(&mut app, "Err(").1);
+    assert!(screen(&mut app).contains("Language Rust"));
+}
+
+#[test]
+fn keywords_come_from_identifiers_and_comments() {
+    let app = load("keywords.rs", RUST);
+    let words: Vec<&str> = app
+        .state
+        .keywords
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect();
+    assert!(words.contains(&"request"), "split identifiers: {words:?}");
+    assert!(words.contains(&"verbs"), "comments count: {words:?}");
+    assert!(!words.contains(&"unknown"), "strings do not: {words:?}");
+    assert!(
+        !words.iter().any(|word| word.contains('_')),
+        "no identifier is left whole: {words:?}"
+    );
+    assert!(!words.contains(&"parsedrequest"), "{words:?}");
+}
+
+#[test]
+fn analyze_counts_lines_and_finds_the_longest_function() {
+    let python = "\
+# Greeting helpers.
+
+def greet(name):
+    \"\"\"Say hello.\"\"\"
+    message = f\"Hello, {name}\"
+    # loud
+    return message.upper()
+
+
+def wave():
+    pass
+";
+    let mut app = load("analyze.py", python);
+    let code = app.state.code.as_ref().expect("read as code");
+    assert_eq!(code.language, Language::Python);
+    assert_eq!(
+        (
+            code.stats.code_lines,
+            code.stats.comment_lines,
+            code.stats.blank_lines
+        ),
+        (6, 2, 3)
+    );
+
+    app.state.current_tab = TAB_ANALYZE;
+    let shown = screen(&mut app);
+    assert!(shown.contains("Code · Python · 11 lines"), "{shown}");
+    assert!(shown.contains("Comment ratio 25.0%"), "{shown}");
+    assert!(
+        shown.contains("Longest function greet, 5 lines from line 3"),
+        "{shown}"
+    );
+}
+
+#[test]
+fn the_json_describes_the_code() {
+    let fixture = Fixture::write("headless.rs", RUST);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    assert_eq!(value["kind"], "code");
+    let code = &value["code"];
+    assert_eq!(code["language"], "rust");
+    assert_eq!(code["lines"], 18);
+    assert_eq!(code["code_lines"], 13);
+    assert_eq!(code["comment_lines"], 2);
+    assert_eq!(code["functions"], 2);
+    assert_eq!(code["longest_function"]["name"], "parse_request_line");
+    assert_eq!(code["longest_function"]["line"], 7);
+    assert_eq!(code["longest_function"]["lines"], 7);
+}
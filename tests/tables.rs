@@ -0,0 +1,227 @@
+//! CSV and TSV files: read as tables with a typed, measured schema, shown
+//! lined up under a header that stays put, and searchable one column at a time.
+
+mod common;
+
+use common::{json_request, screen_at, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::{TAB_ANALYZE, TAB_SEARCH, TAB_VIEWER};
+use flerp::file_utils::load_file;
+use flerp::headless::run;
+use flerp::table::{ColumnKind, Table};
+
+const STOCK: &str = "\
+id,product,price,in stock,added
+1,Green tea,4.50,yes,2024-01-05
+2,Black tea,3.75,no,2024-02-11
+3,Tea pot,,yes,2024-02-11
+4,\"Cup, large\",12,yes,2023-12-30
+";
+
+#[test]
+fn rows_line_up_under_a_header_that_stays_put() {
+    let mut csv = String::from("n,word\n");
+    for n in 1..=60 {
+        csv.push_str(&format!("{n},row {n}\n"));
+    }
+    let fixture = Fixture::write("long.csv", csv);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the table loads");
+    assert!(
+        app.state.status_message.contains("60 rows in 2 columns"),
+        "{}",
+        app.state.status_message
+    );
+    assert_eq!(app.state.file_content.lines().next(), Some(" 1  row 1"));
+
+    app.state.current_tab = TAB_VIEWER;
+    app.state.content_scroll = 40;
+    let shown = screen_at(&mut app, 100, 30);
+    let header = shown
+        .lines()
+        .position(|line| line.contains(" n  word"))
+        .expect("the header is on screen");
+    assert!(
+        shown
+            .lines()
+            .nth(header + 1)
+            .unwrap()
+            .contains("41  row 41"),
+        "the row under the header is the one scrolled to\n{shown}"
+    );
+}
+
+#[test]
+fn searches_can_be_limited_to_one_column() {
+    let fixture = Fixture::write("stock.csv", STOCK);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the table loads");
+    app.set_search_query("yes");
+    assert_eq!(app.state.search_results.len(), 3, "every column by default");
+
+    app.state.current_tab = TAB_SEARCH;
+    app.handle_key(KeyCode::Right);
+    app.handle_key(KeyCode::Right);
+    assert_eq!(app.state.table_column, Some(1));
+    assert_eq!(
+        app.state.status_message,
+        "Searching column 2 of 5: product."
+    );
+    assert!(
+        app.state.search_results.is_empty(),
+        "no product is called yes"
+    );
+    assert!(screen_at(&mut app, 100, 30).contains("Column product"));
+
+    app.state.case_sensitive = false;
+    app.set_search_query("tea");
+    let rows: Vec<usize> = app
+        .state
+        .search_results
+        .iter()
+        .map(|entry| entry.line_number)
+        .collect();
+    assert_eq!(rows, [1, 2, 3]);
+
+    // Left from the first column goes back to all of them, then wraps.
+    app.handle_key(KeyCode::Left);
+    app.handle_key(KeyCode::Left);
+    assert_eq!(app.state.table_column, None);
+    assert_eq!(app.state.status_message, "Searching every column.");
+    app.handle_key(KeyCode::Left);
+    assert_eq!(app.state.table_column, Some(4));
+}
+
+#[test]
+fn a_far_column_scrolls_into_view() {
+    let header: Vec<String> = (1..=12).map(|n| format!("heading{n:02}")).collect();
+    let row: Vec<String> = (1..=12).map(|n| format!("value{n:02}")).collect();
+    let csv = format!("{}\n{}\n", header.join(","), row.join(","));
+    let fixture = Fixture::write("wide.csv", csv);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the table loads");
+    app.state.current_tab = TAB_VIEWER;
+
+    assert!(!screen_at(&mut app, 80, 30).contains("heading12"));
+    app.handle_key(KeyCode::Left);
+    assert_eq!(app.state.table_column, Some(11));
+    let shown = screen_at(&mut app, 80, 30);
+    assert!(shown.contains("heading12"), "{shown}");
+    assert!(shown.contains("value12"), "the rows scroll with it");
+    assert!(app.state.table_offset > 0);
+}
+
+#[test]
+fn a_character_across_the_sniff_window_does_not_stop_the_load() {
+    // The delimiter is guessed from the first 512 bytes; put a two-byte
+    // character across that edge.
+    let mut csv = String::from("city,note\n");
+    while csv.len() < 500 {
+        csv.push_str("Oslo,fjord\n");
+    }
+    csv.push_str(&"x".repeat(511 - csv.len()));
+    csv.push_str("é,north\n");
+    assert!(!csv.is_char_boundary(512));
+    let fixture = Fixture::write("accents.csv", csv);
+
+    let loaded = load_file(fixture.as_str()).expect("the table loads");
+    let table = loaded.get::<Table>().expect("read as a table");
+    assert_eq!(table.columns.len(), 2);
+}
+
+#[test]
+fn analyze_shows_the_columns_not_the_headers_as_keywords() {
+    let fixture = Fixture::write("analyze.csv", STOCK);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the table loads");
+    let table = app.state.table.as_ref().expect("a table");
+    let kinds: Vec<ColumnKind> = table.columns.iter().map(|column| column.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            ColumnKind::Integer,
+            ColumnKind::Text,
+            ColumnKind::Number,
+            ColumnKind::Boolean,
+            ColumnKind::Date
+        ]
+    );
+    let words: Vec<&str> = app
+        .state
+        .keywords
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect();
+    assert!(words.contains(&"tea"), "{words:?}");
+    assert!(
+        !words.contains(&"product"),
+        "headers are not text: {words:?}"
+    );
+    assert!(!words.contains(&"yes"), "nor are other columns: {words:?}");
+
+    app.state.current_tab = TAB_ANALYZE;
+    let shown = screen_at(&mut app, 120, 30);
+    assert!(shown.contains("Table · 4 rows · 5 columns"), "{shown}");
+    let price = shown
+        .lines()
+        .find(|line| line.contains("price "))
+        .expect("a row for price");
+    assert!(price.contains("number"), "{price}");
+    assert!(price.contains("6.75"), "{price}");
+}
+
+#[test]
+fn the_json_describes_the_schema() {
+    let fixture = Fixture::write(
+        "schema.tsv",
+        STOCK.replace("\"Cup, large\"", "Cup").replace(',', "\t"),
+    );
+    let mut request = json_request(fixture.as_str());
+    request.search = Some("2024-02".to_string());
+    request.search_options.column = Some(4);
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    assert_eq!(value["kind"], "table");
+    let table = &value["table"];
+    assert_eq!(table["delimiter"], "\t");
+    assert_eq!(table["rows"], 4);
+    let price = &table["columns"][2];
+    assert_eq!(price["name"], "price");
+    assert_eq!(price["kind"], "number");
+    assert_eq!(price["nulls"], 1);
+    assert_eq!(price["min"], "3.75");
+    assert_eq!(price["max"], "12");
+    assert_eq!(price["mean"], 6.75);
+    assert_eq!(table["columns"][4]["distinct"], 3);
+
+    assert_eq!(value["search"]["column"], 5);
+    let rows: Vec<u64> = value["search"]["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["line_number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(rows, [2, 3]);
+
+    let mut text_request = common::request(fixture.as_str());
+    text_request.text = true;
+    let mut buffer = Vec::new();
+    run(&text_request, &mut buffer).expect("headless run");
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.starts_with("id  product"), "{text}");
+    assert_eq!(text.lines().count(), 5);
+}
+
+#[test]
+fn only_explicit_markers_count_as_missing() {
+    let csv = "country,element,score\nNA,Na,N/A\nZA,K,null\nNA,Na,\nBW,NaN,3\n";
+    let table = Table::parse(csv, b',').expect("parses");
+    let missing: Vec<usize> = table.columns.iter().map(|column| column.nulls).collect();
+    assert_eq!(missing, [0, 1, 3], "Namibia and sodium are values");
+    assert_eq!(table.columns[0].distinct, 3);
+    assert_eq!(table.columns[1].distinct, 2);
+    assert_eq!(table.columns[2].kind, ColumnKind::Integer);
+}
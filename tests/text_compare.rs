@@ -0,0 +1,164 @@
+//! What two files are about, compared: keywords each has to itself, the ones
+//! they share and how their weight moved, readability and vocabulary overlap,
+//! in Analyze with `s` and in `--diff --json`.
+
+mod common;
+
+use common::{screen, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::TAB_ANALYZE;
+use flerp::headless::{run_diff, DiffRequest};
+use flerp::pdf_doc::ExtractionMode;
+use flerp::text_compare::ProfileComparison;
+
+const OLD: &str = "\
+Harbour cranes lift cargo from the ships.
+
+The harbour cranes work through the night.
+
+Cargo waits on the quay for trucks.
+";
+
+const NEW: &str = "\
+Harbour boats carry cargo along the coast, and the boats sail at dawn long \
+before the fishermen wake up to mend their nets.
+
+The harbour boats return at noon.
+
+Cargo waits on the quay for trains.
+";
+
+fn words(keywords: &[flerp::text_analysis::Keyword]) -> Vec<&str> {
+    keywords
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect()
+}
+
+#[test]
+fn keywords_split_into_each_files_own_and_shared() {
+    let profiles = ProfileComparison::new(OLD, NEW, 10);
+
+    let only_old = words(&profiles.only_old);
+    assert!(only_old.contains(&"cranes"), "{only_old:?}");
+    assert!(!only_old.contains(&"cargo"), "{only_old:?}");
+    let only_new = words(&profiles.only_new);
+    assert_eq!(only_new[0], "boats", "strongest first: {only_new:?}");
+    assert!(!only_new.contains(&"harbour"), "{only_new:?}");
+
+    let shared: Vec<&str> = profiles
+        .shared
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect();
+    assert_eq!(shared.len(), 4, "{shared:?}");
+    for word in ["cargo", "harbour", "quay", "waits"] {
+        assert!(shared.contains(&word), "{word} in {shared:?}");
+    }
+    let cargo = profiles
+        .shared
+        .iter()
+        .find(|keyword| keyword.word == "cargo")
+        .unwrap();
+    assert_eq!((cargo.old_count, cargo.new_count), (2, 2));
+    assert!(
+        cargo.delta() < 0.0,
+        "a longer text spreads its weight thinner"
+    );
+
+    assert_eq!(
+        (
+            profiles.old_vocabulary,
+            profiles.new_vocabulary,
+            profiles.shared_vocabulary
+        ),
+        (10, 18, 4)
+    );
+    assert!((profiles.jaccard() - 4.0 / 24.0).abs() < 1e-9);
+    assert_eq!(
+        profiles.lix_delta(),
+        profiles.new_readability.lix - profiles.old_readability.lix
+    );
+
+    let same = ProfileComparison::new("", "", 10);
+    assert_eq!(same.jaccard(), 1.0, "nothing against nothing is a match");
+}
+
+#[test]
+fn s_switches_analyze_to_the_profiles() {
+    let old = Fixture::write("tui-old.txt", OLD);
+    let new = Fixture::write("tui-new.txt", NEW);
+    let mut app = App::new();
+    app.load_diff(old.as_str(), new.as_str(), None)
+        .expect("both files load");
+    app.state.current_tab = TAB_ANALYZE;
+    assert!(!screen(&mut app).contains("Only in old"));
+
+    app.handle_key(KeyCode::Char('s'));
+    assert_eq!(
+        app.state.status_message,
+        "Analyze compares what the two files are about."
+    );
+    let shown = screen(&mut app);
+    for needle in [
+        "Diff · ",
+        "Only in old",
+        "Only in new",
+        "cranes",
+        "boats",
+        "Overlap 16.7%",
+        "s changed lines",
+    ] {
+        assert!(shown.contains(needle), "{needle} in\n{shown}");
+    }
+    let cargo = shown
+        .lines()
+        .find(|line| line.contains("cargo"))
+        .expect("cargo is shared");
+    assert!(cargo.contains(" → "), "{cargo}");
+
+    app.handle_key(KeyCode::Char('s'));
+    assert_eq!(
+        app.state.status_message,
+        "Analyze measures the changed lines."
+    );
+    assert!(screen(&mut app).contains("Top Keywords"));
+}
+
+#[test]
+fn json_carries_the_profiles() {
+    let old = Fixture::write("json-old.txt", OLD);
+    let new = Fixture::write("json-new.txt", NEW);
+    let request = DiffRequest {
+        old: old.as_str().to_string(),
+        new: new.as_str().to_string(),
+        password: None,
+        json: true,
+        extraction: ExtractionMode::Flow,
+        reflow: false,
+        ocr: false,
+        keyword_limit: 3,
+    };
+    let mut buffer = Vec::new();
+    run_diff(&request, &mut buffer).expect("headless diff");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+    let profiles = &value["profiles"];
+
+    assert_eq!(profiles["vocabulary"]["shared"], 4);
+    assert_eq!(profiles["vocabulary"]["old"], 10);
+    assert_eq!(profiles["keywords"]["only_new"][0]["word"], "boats");
+    assert_eq!(
+        profiles["keywords"]["only_old"].as_array().unwrap().len(),
+        3
+    );
+    let shared = &profiles["keywords"]["shared"][0];
+    let delta = shared["new_weight"].as_f64().unwrap() - shared["old_weight"].as_f64().unwrap();
+    assert!((shared["delta"].as_f64().unwrap() - delta).abs() < 1e-9);
+    assert_eq!(profiles["readability"]["old"]["sentences"], 3);
+    let lix = |side: &str| profiles["readability"][side]["lix"].as_f64().unwrap();
+    assert!(
+        (profiles["readability"]["lix_delta"].as_f64().unwrap() - (lix("new") - lix("old"))).abs()
+            < 1e-9
+    );
+}
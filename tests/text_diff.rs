@@ -0,0 +1,265 @@
+//! Two files compared with `--diff`: side by side or unified in the Viewer,
+//! stepped through a hunk at a time, and printed as hunks or a unified diff.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{draw, screen, Fixture};
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::{TAB_ANALYZE, TAB_VIEWER};
+use flerp::headless::{run_diff, DiffRequest};
+use flerp::pdf_doc::ExtractionMode;
+use flerp::text_diff::DiffLayout;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+const OLD: &str = "\
+Shipping notes
+the quick brown fox
+crates leave at dawn
+crates arrive at noon
+the dock is quiet
+cranes rest
+gulls circle
+rain comes
+tides turn
+the closing line
+";
+
+const NEW: &str = "\
+Shipping notes
+the slow brown fox
+crates leave at dawn
+crates arrive at noon
+the dock is quiet
+cranes rest
+gulls circle
+rain comes
+tides turn
+the closing words
+an appendix
+";
+
+/// A PDF with one line of text per page.
+fn write_pdf(path: &PathBuf, pages: &[&str]) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let mut kids = Vec::new();
+    for text in pages {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 700.into()]),
+                Operation::new("Tj", vec![Object::string_literal(*text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        kids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => Object::Reference(font_id) },
+            },
+        })));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).expect("fixture PDF should save");
+}
+
+fn compare(old: &Fixture, new: &Fixture) -> App {
+    let mut app = App::new();
+    app.load_diff(old.as_str(), new.as_str(), None)
+        .expect("both files load");
+    app
+}
+
+fn request(old: &Fixture, new: &Fixture, json: bool) -> DiffRequest {
+    DiffRequest {
+        old: old.as_str().to_string(),
+        new: new.as_str().to_string(),
+        password: None,
+        json,
+        extraction: ExtractionMode::Flow,
+        reflow: false,
+        ocr: false,
+        keyword_limit: 10,
+    }
+}
+
+#[test]
+fn the_viewer_shows_both_sides_with_the_changed_words_marked() {
+    let old = Fixture::write("side-old.txt", OLD);
+    let new = Fixture::write("side-new.txt", NEW);
+    let mut app = compare(&old, &new);
+    assert!(
+        app.state
+            .status_message
+            .contains("2 hunk(s) · 3 lines added, 2 removed"),
+        "{}",
+        app.state.status_message
+    );
+    assert_eq!(app.state.diff_layout, DiffLayout::SideBySide);
+
+    app.state.current_tab = TAB_VIEWER;
+    let buffer = draw(&mut app);
+    let (row, text) = buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+        .enumerate()
+        .find(|(_, text)| text.contains("the quick brown fox"))
+        .expect("the old line is on screen");
+    assert!(
+        text.contains("the slow brown fox"),
+        "the two face each other: {text}"
+    );
+    let cell = |needle: &str| {
+        let column = text[..text.find(needle).unwrap()].chars().count();
+        buffer[(column as u16, row as u16)].bg
+    };
+    assert_ne!(
+        cell("quick"),
+        cell("brown"),
+        "only the changed word is marked"
+    );
+    assert_ne!(
+        cell("quick"),
+        cell("slow"),
+        "removed and added words differ"
+    );
+}
+
+#[test]
+fn n_steps_through_hunks_and_u_switches_layout() {
+    let old = Fixture::write("step-old.txt", OLD);
+    let new = Fixture::write("step-new.txt", NEW);
+    let mut app = compare(&old, &new);
+
+    app.handle_key(KeyCode::Char('n'));
+    assert!(
+        app.state.status_message.starts_with("Hunk 1 of 2"),
+        "{}",
+        app.state.status_message
+    );
+    app.handle_key(KeyCode::Char('n'));
+    assert!(app
+        .state
+        .status_message
+        .starts_with("Hunk 2 of 2 · old line 7"));
+    app.handle_key(KeyCode::Char('n'));
+    assert!(app.state.status_message.starts_with("Hunk 1 of 2"), "wraps");
+    app.handle_key(KeyCode::Char('N'));
+    assert!(app.state.status_message.starts_with("Hunk 2 of 2"));
+    assert!(screen(&mut app).contains("Hunk 2 of 2"));
+
+    app.handle_key(KeyCode::Char('u'));
+    assert_eq!(app.state.diff_layout, DiffLayout::Unified);
+    assert!(app
+        .state
+        .file_content
+        .contains("- the quick brown fox\n+ the slow brown fox\n"));
+    assert!(app
+        .state
+        .file_content
+        .ends_with("- the closing line\n+ the closing words\n+ an appendix\n"));
+
+    app.state.current_tab = TAB_ANALYZE;
+    let shown = screen(&mut app);
+    assert!(shown.contains("Diff · 2 hunk(s)"), "{shown}");
+    assert!(shown.contains("Similarity 76.2%"), "{shown}");
+    let words: Vec<&str> = app
+        .state
+        .keywords
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect();
+    assert!(words.contains(&"appendix"), "{words:?}");
+    assert!(
+        !words.contains(&"crates"),
+        "unchanged lines are left out: {words:?}"
+    );
+}
+
+#[test]
+fn json_lists_the_hunks_of_two_pdfs() {
+    let old = Fixture::with("old.pdf", |path| {
+        write_pdf(path, &["Harbour cranes lift cargo.", "The harbour never sleeps."])
+    });
+    let new = Fixture::with("new.pdf", |path| {
+        write_pdf(path, &["Harbour boats lift cargo.", "The harbour never sleeps."])
+    });
+    let mut buffer = Vec::new();
+    run_diff(&request(&old, &new, true), &mut buffer).expect("headless diff");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    assert_eq!(value["old"]["kind"], "pdf");
+    assert_eq!(value["new"]["file"], new.as_str());
+    assert_eq!(value["stats"]["hunks"], 1);
+    assert_eq!(value["stats"]["added"], 1);
+    assert_eq!(value["stats"]["removed"], 1);
+    assert_eq!(value["stats"]["words_added"], 1);
+
+    let lines = value["hunks"][0]["lines"].as_array().unwrap();
+    let removed = lines
+        .iter()
+        .find(|line| line["kind"] == "removed")
+        .expect("a removed line");
+    assert_eq!(removed["text"], "Harbour cranes lift cargo.");
+    assert_eq!(
+        removed["changed"],
+        serde_json::json!([{ "start": 8, "end": 14, "text": "cranes" }])
+    );
+    let added = lines.iter().find(|line| line["kind"] == "added").unwrap();
+    assert_eq!(added["old_line"], serde_json::Value::Null);
+    assert_eq!(added["changed"][0]["text"], "boats");
+}
+
+#[test]
+fn text_prints_a_unified_diff() {
+    let old = Fixture::write("unified-old.txt", "a\nb\nc\n");
+    let new = Fixture::write("unified-new.txt", "a\nB\nc\n");
+    let mut buffer = Vec::new();
+    run_diff(&request(&old, &new, false), &mut buffer).expect("headless diff");
+    let text = String::from_utf8(buffer).unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "--- {}\n+++ {}\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n",
+            old.as_str(),
+            new.as_str()
+        )
+    );
+
+    let missing = DiffRequest {
+        new: "/nonexistent/flerp.txt".to_string(),
+        ..request(&old, &new, false)
+    };
+    let error = run_diff(&missing, &mut Vec::new()).unwrap_err();
+    assert!(
+        error.to_string().starts_with("/nonexistent/flerp.txt: "),
+        "{error}"
+    );
+}
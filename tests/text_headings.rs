@@ -0,0 +1,115 @@
+//! Plain text files: headings found by how they look give the file an outline,
+//! so `[`/`]` step between sections and Analyze can measure one at a time.
+
+mod common;
+
+use common::Fixture;
+use crossterm::event::KeyCode;
+use flerp::app::App;
+use flerp::app_structs::{AnalysisScope, TAB_ANALYZE, TAB_VIEWER};
+use flerp::headless::{run, HeadlessRequest};
+
+const REPORT: &str = "\
+SURVEY OF THE NORTH SHORE
+
+This report covers a season of counts along the northern coast.
+
+1 Birds
+Terns nested on the spit. Terns fished the shallows. Terns fledged late.
+Gulls followed the boats.
+
+1.1 Waders
+Oystercatchers probed the mud at every low tide of the season.
+
+2 Plants
+Thrift flowered on the cliffs. Thrift covered the slopes in May.
+Sea kale grew above the strandline.
+
+Acknowledgements
+----------------
+Thanks to the wardens.
+";
+
+#[test]
+fn brackets_step_between_detected_sections() {
+    let fixture = Fixture::write("steps.txt", REPORT);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the report loads");
+    let titles: Vec<&str> = app
+        .state
+        .outline
+        .top_level()
+        .iter()
+        .map(|segment| segment.title.as_str())
+        .collect();
+    assert_eq!(titles, ["SURVEY OF THE NORTH SHORE", "1 Birds", "2 Plants"]);
+
+    app.state.current_tab = TAB_VIEWER;
+    app.handle_key(KeyCode::Char(']'));
+    assert_eq!(app.state.content_scroll, 4);
+    assert!(
+        app.state.status_message.starts_with("1 Birds · 2 of 3"),
+        "{}",
+        app.state.status_message
+    );
+    app.handle_key(KeyCode::Char(']'));
+    assert_eq!(app.state.content_scroll, 11);
+    app.handle_key(KeyCode::Char('['));
+    assert_eq!(app.state.content_scroll, 4);
+}
+
+#[test]
+fn analysis_can_measure_one_section() {
+    let fixture = Fixture::write("analysis.txt", REPORT);
+    let mut app = App::new();
+    app.load_file(fixture.as_str()).expect("the report loads");
+    app.state.current_tab = TAB_VIEWER;
+    app.handle_key(KeyCode::Char(']'));
+
+    app.state.current_tab = TAB_ANALYZE;
+    app.handle_key(KeyCode::Char('s'));
+    assert_eq!(app.state.analysis_scope, AnalysisScope::Page);
+    let analysis = app.state.page_analysis.as_ref().expect("section analysis");
+    let words: Vec<&str> = analysis
+        .keywords
+        .iter()
+        .map(|keyword| keyword.word.as_str())
+        .collect();
+    assert!(words.contains(&"terns"), "{words:?}");
+    assert!(
+        !words.contains(&"thrift"),
+        "the Plants section is not counted"
+    );
+}
+
+#[test]
+fn the_json_lists_the_detected_headings() {
+    let fixture = Fixture::write("json.txt", REPORT);
+    let request = HeadlessRequest {
+        file: fixture.as_str().to_string(),
+        json: true,
+        search: Some("oystercatchers".to_string()),
+        ..HeadlessRequest::default()
+    };
+    let mut buffer = Vec::new();
+    run(&request, &mut buffer).expect("headless run");
+    let value: serde_json::Value = serde_json::from_slice(&buffer).expect("valid JSON");
+
+    let outline = value["outline"].as_array().expect("an outline");
+    let titles: Vec<&str> = outline
+        .iter()
+        .map(|segment| segment["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            "SURVEY OF THE NORTH SHORE",
+            "1 Birds",
+            "1.1 Waders",
+            "2 Plants",
+            "Acknowledgements"
+        ]
+    );
+    assert_eq!(outline[2]["level"], 1);
+    assert_eq!(value["search"]["matches"][0]["section"], "1.1 Waders");
+}
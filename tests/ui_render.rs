@@ -10,6 +10,7 @@ use flerp::app_structs::{
     TAB_VIEWER,
 };
 use flerp::media::{MediaItem, MediaRenderer};
+use flerp::outline::Outline;
 use flerp::pdf_doc::{PdfDocument, PdfPage};
 use flerp::ui_components::ui;
 use image::{DynamicImage, Rgb, RgbImage};
@@ -43,6 +44,9 @@ fn state_with_media() -> AppState {
         detail: "8x8 · raw samples · DeviceRGB".to_string(),
         page: Some(1),
         image: swatch(),
+        copies: Vec::new(),
+        source: None,
+        metadata: None,
     }];
     state
 }
@@ -128,17 +132,18 @@ fn a_page_jump_made_before_the_viewer_was_drawn_still_lands_correctly() {
         .map(|n| format!("line {n}"))
         .collect::<Vec<_>>()
         .join("\n");
-    app.state.document = Some(Arc::new(PdfDocument {
-        text: app.state.file_content.clone(),
-        pages: vec![
-            PdfPage { number: 1, start_line: 0, line_count: 20, image_count: 0 },
-            PdfPage { number: 2, start_line: 20, line_count: 20, image_count: 0 },
-            PdfPage { number: 3, start_line: 40, line_count: 20, image_count: 0 },
-        ],
-        images: Vec::new(),
-        skipped: Vec::new(),
-        image_error: None,
-    }));
+    app.state.set_document(
+        Some(Arc::new(PdfDocument {
+            text: app.state.file_content.clone(),
+            pages: vec![
+                PdfPage { number: 1, start_line: 0, line_count: 20, image_count: 0, ocr: false },
+                PdfPage { number: 2, start_line: 20, line_count: 20, image_count: 0, ocr: false },
+                PdfPage { number: 3, start_line: 40, line_count: 20, image_count: 0, ocr: false },
+            ],
+            ..PdfDocument::default()
+        })),
+        Outline::default(),
+    );
 
     // Jump straight from the dashboard, before any viewer draw has happened.
     app.state.current_tab = TAB_DASHBOARD;